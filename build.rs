@@ -0,0 +1,98 @@
+use std::env;
+
+fn main() {
+	println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+
+	for version in &[5, 6, 7] {
+		println!("cargo:rustc-check-cfg=cfg(ffmpeg_{})", version);
+	}
+
+	let major = if env::var_os("CARGO_FEATURE_FFMPEG7").is_some() {
+		Some(7)
+	}
+	else if env::var_os("CARGO_FEATURE_FFMPEG6").is_some() {
+		Some(6)
+	}
+	else if env::var_os("CARGO_FEATURE_FFMPEG5").is_some() {
+		Some(5)
+	}
+	else if env::var_os("CARGO_FEATURE_STATIC_FFMPEG").is_some() {
+		// The vendored sources are whatever ffmpeg-sys bundles, not what the
+		// system happens to have installed.
+		exported()
+	}
+	else {
+		// The ffmpeg-sys in the graph knows no release past 4, whatever it
+		// linked against, so the installed library gets a say too.
+		exported().max(pkg_config())
+	};
+
+	// Each version implies the ones before it, so code can be gated on the
+	// oldest release that introduced a change.
+	for version in 5..=major.unwrap_or(4) {
+		println!("cargo:rustc-cfg=ffmpeg_{}", version);
+	}
+
+	#[cfg(feature = "capi")]
+	header();
+
+	if env::var_os("CARGO_FEATURE_CHROMAPRINT").is_some() {
+		pkg_config::probe_library("libchromaprint").expect("libchromaprint not found");
+	}
+}
+
+// Into `OUT_DIR`, the source tree may well be read-only. The copy under
+// `include` is updated by hand, see the README.
+#[cfg(feature = "capi")]
+fn header() {
+	let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+	let out = env::var("OUT_DIR").unwrap();
+
+	println!("cargo:rerun-if-changed=src/capi.rs");
+	println!("cargo:rerun-if-changed=cbindgen.toml");
+
+	let bindings = cbindgen::Config::from_file(format!("{}/cbindgen.toml", root))
+		.map_err(|error| error.to_string())
+		.and_then(|config| {
+			cbindgen::Builder::new()
+				.with_crate(&root)
+				.with_config(config)
+				.generate()
+				.map_err(|error| error.to_string())
+		});
+
+	// Not being able to regenerate the header is no reason to fail the build.
+	match bindings {
+		Ok(bindings) => {
+			bindings.write_to_file(format!("{}/avmetadata.h", out));
+		}
+		Err(error) => println!("cargo:warning=unable to generate C bindings: {}", error),
+	}
+}
+
+// ffmpeg-sys exports a `ffmpeg_<major>_<minor>` key for every release the
+// headers it compiled against are at least as recent as, which is the library
+// that ends up linked, vendored or not.
+fn exported() -> Option<u32> {
+	env::vars()
+		.filter_map(|(name, _)| {
+			let release = name.strip_prefix("DEP_FFMPEG_FFMPEG_")?;
+			release.split('_').next()?.parse::<u32>().ok()
+		})
+		.max()
+}
+
+// Maps the libavcodec major version to the FFmpeg release that shipped it.
+fn pkg_config() -> Option<u32> {
+	let library = pkg_config::Config::new()
+		.cargo_metadata(false)
+		.probe("libavcodec")
+		.ok()?;
+
+	match library.version.split('.').next()?.parse::<u32>().ok()? {
+		59 => Some(5),
+		60 => Some(6),
+		n if n >= 61 => Some(7),
+		_ => Some(4),
+	}
+}
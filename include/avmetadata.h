@@ -0,0 +1,39 @@
+#ifndef AVMETADATA_H
+#define AVMETADATA_H
+
+/* Generated with cbindgen, do not edit by hand. */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * Probes the file or URL at `path` and stores the metadata serialized as a
+ * NUL-terminated JSON string in `json_out`, to be released with
+ * `avmetadata_free`.
+ *
+ * Returns zero on success or a negative FFmpeg error code, `AVERROR_BUG` when
+ * the probe panicked.
+ *
+ * # Safety
+ *
+ * `path` must be a valid NUL-terminated string and `json_out` must point to
+ * writable memory.
+ */
+int avmetadata_probe(const char *path, char **json_out);
+
+/**
+ * Releases a string returned by `avmetadata_probe`.
+ *
+ * # Safety
+ *
+ * `json` must have been returned by `avmetadata_probe` and not freed
+ * already, or be NULL.
+ */
+void avmetadata_free(char *json);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif /* AVMETADATA_H */
@@ -0,0 +1,119 @@
+use ffmpeg::{
+	codec,
+	format::{context::Input, stream::Stream},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{bitstream::aac, probe, raw};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AacInfo {
+	// The audio object type of the core, 2 for LC.
+	pub object_type: u8,
+	pub profile: Option<String>,
+	pub sbr: Option<AacSignaling>,
+	pub ps: Option<AacSignaling>,
+	pub core_sample_rate: Option<u32>,
+	// What comes out of the decoder, after SBR and PS.
+	pub sample_rate: Option<u32>,
+	pub channels: Option<u16>,
+	pub source: AacConfigSource,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum AacSignaling {
+	// Declared in the configuration, hierarchically or backward compatibly.
+	Explicit,
+	// Only found by decoding, the configuration says plain LC.
+	Implicit,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum AacConfigSource {
+	AudioSpecificConfig,
+	Adts,
+}
+
+impl AacInfo {
+	pub fn find(input: &Input, stream: &Stream) -> Option<Self> {
+		Self::with_packets(stream, &probe::Packets::new(input))
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub(crate) fn with_packets(stream: &Stream, packets: &probe::Packets) -> Option<Self> {
+		let parameters = stream.parameters();
+
+		if parameters.id() != codec::Id::AAC {
+			return None;
+		}
+
+		// ADTS streams have no extradata, every frame repeats the header.
+		let (config, source) = match aac::Config::parse(raw::extradata(&parameters)) {
+			Some(config) => (config, AacConfigSource::AudioSpecificConfig),
+			None => {
+				let packets = packets.stream(stream.index());
+				let config = packets.first().and_then(|packet| aac::Config::adts(packet))?;
+
+				(config, AacConfigSource::Adts)
+			}
+		};
+
+		// The decoder finds implicit SBR and PS on the first frames, and says
+		// so in the profile.
+		let decoded = raw::profile_name(parameters.id(), raw::profile(&parameters));
+		let signaling = |explicit: bool, profiles: &[&str]| {
+			if explicit {
+				Some(AacSignaling::Explicit)
+			}
+			else if decoded.as_deref().map_or(false, |name| profiles.contains(&name)) {
+				Some(AacSignaling::Implicit)
+			}
+			else {
+				None
+			}
+		};
+
+		let sbr = signaling(config.sbr, &["HE-AAC", "HE-AACv2"]);
+		let ps = signaling(config.ps, &["HE-AACv2"]);
+
+		let sample_rate = match sbr {
+			Some(_) => config.extension_sample_rate.or_else(|| config.sample_rate.map(|rate| rate * 2)),
+			None => config.sample_rate,
+		};
+
+		let channels = match aac::channels(config.channels) {
+			Some(1) if ps.is_some() => Some(2),
+			channels => channels,
+		};
+
+		let profile = match (config.object_type, sbr, ps) {
+			(2, Some(_), Some(_)) => Some("HE-AACv2"),
+			(2, Some(_), None) => Some("HE-AAC"),
+			(1, ..) => Some("Main"),
+			(2, ..) => Some("LC"),
+			(3, ..) => Some("SSR"),
+			(4, ..) => Some("LTP"),
+			(17, ..) => Some("ER LC"),
+			(23, ..) => Some("LD"),
+			(39, ..) => Some("ELD"),
+			(42, ..) => Some("xHE-AAC"),
+			_ => None,
+		};
+
+		Some(AacInfo {
+			object_type: config.object_type,
+			profile: profile.map(String::from),
+			sbr,
+			ps,
+			core_sample_rate: config.sample_rate,
+			sample_rate,
+			channels,
+			source,
+		})
+	}
+}
@@ -0,0 +1,133 @@
+use ffmpeg::{codec, format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{Black, BlackThresholds, Interval, Options, Silence, SilenceThresholds};
+use crate::{bitstream::scte35, compat};
+
+// Evidence closer than this is considered to point at the same break.
+const TOLERANCE: f64 = 1.0;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AdBreaks {
+	pub candidates: Vec<Candidate>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Candidate {
+	pub time: f64,
+	pub duration: Option<f64>,
+	pub confidence: f64,
+	pub evidence: Vec<Evidence>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Evidence {
+	Black,
+	Silence,
+	Scte35,
+}
+
+impl AdBreaks {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, options: &Options) -> ffmpeg::Result<Self> {
+		let video = input.streams().best(media::Type::Video).map(|s| s.index());
+		let audio = input.streams().best(media::Type::Audio).map(|s| s.index());
+		let cues = input
+			.streams()
+			.filter(|s| compat::medium(s) == media::Type::Data && s.parameters().id() == codec::Id::SCTE_35)
+			.map(|s| s.index())
+			.collect::<Vec<_>>();
+
+		let mut findings = Vec::new();
+
+		// Breaks are framed by much shorter black and silence than the
+		// defaults, which are aimed at trimming.
+		if let Some(index) = video {
+			let thresholds = BlackThresholds {
+				duration: 0.1,
+				..Default::default()
+			};
+
+			for interval in Black::analyze(input, index, options, &thresholds)?.intervals {
+				findings.push((interval, Evidence::Black));
+			}
+		}
+
+		if let Some(index) = audio {
+			let thresholds = SilenceThresholds {
+				duration: 0.3,
+				..Default::default()
+			};
+
+			for interval in Silence::analyze(input, index, options, &thresholds)?.intervals {
+				findings.push((interval, Evidence::Silence));
+			}
+		}
+
+		for index in cues {
+			super::packets(input, index, options, |packet, _| {
+				let splice = match packet.data().and_then(scte35::parse) {
+					Some(splice) if splice.out => splice,
+					_ => return Ok(()),
+				};
+
+				if let Some(pts) = splice.pts {
+					let start = pts as f64 / 90_000.0;
+					let end = start + splice.duration.map_or(0.0, |d| d as f64 / 90_000.0);
+
+					findings.push((Interval { start, end }, Evidence::Scte35));
+				}
+
+				Ok(())
+			})?;
+		}
+
+		findings.sort_by(|a, b| a.0.start.total_cmp(&b.0.start));
+
+		let mut candidates: Vec<Candidate> = Vec::new();
+		for (interval, evidence) in findings {
+			match candidates.last_mut() {
+				Some(last) if interval.start - last.time <= TOLERANCE => {
+					if !last.evidence.contains(&evidence) {
+						last.evidence.push(evidence);
+					}
+
+					if evidence == Evidence::Scte35 {
+						last.time = interval.start;
+						last.duration = Some(interval.duration()).filter(|&d| d > 0.0);
+					}
+				}
+
+				_ => candidates.push(Candidate {
+					time: interval.start,
+					duration: Some(interval.duration()).filter(|&d| d > 0.0 && evidence == Evidence::Scte35),
+					confidence: 0.0,
+					evidence: vec![evidence],
+				}),
+			}
+		}
+
+		for candidate in &mut candidates {
+			let has = |evidence| candidate.evidence.contains(&evidence);
+			let found = (has(Evidence::Scte35), has(Evidence::Black), has(Evidence::Silence));
+
+			candidate.confidence = match found {
+				(true, true, true) => 1.0,
+				(true, true, false) | (true, false, true) => 0.97,
+				(true, false, false) => 0.9,
+				(false, true, true) => 0.8,
+				// Silence is all there is to go by for audio only content.
+				(false, false, true) if video.is_none() => 0.5,
+				(false, true, false) => 0.4,
+				(false, false, true) => 0.25,
+				(false, false, false) => 0.0,
+			};
+		}
+
+		Ok(AdBreaks { candidates })
+	}
+}
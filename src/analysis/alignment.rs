@@ -0,0 +1,185 @@
+use std::f64::consts::PI;
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{samples, Options};
+use crate::compat;
+
+// Envelopes are compared at millisecond resolution, which is well below what
+// anyone can perceive as out of sync.
+const RATE: f64 = 1000.0;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AlignmentThresholds {
+	pub length: f64,
+	pub max_offset: f64,
+}
+
+impl Default for AlignmentThresholds {
+	fn default() -> Self {
+		AlignmentThresholds {
+			length: 120.0,
+			max_offset: 30.0,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Alignment {
+	// How late the second stream is compared to the first, what happens at `t`
+	// in the first one happens at `t + offset` in the second one.
+	pub offset: f64,
+	pub confidence: f64,
+}
+
+impl Alignment {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		first: (&mut Input, usize),
+		second: (&mut Input, usize),
+		options: &Options,
+		thresholds: &AlignmentThresholds,
+	) -> ffmpeg::Result<Option<Self>> {
+		let a = envelope(first.0, first.1, options, thresholds.length + thresholds.max_offset)?;
+		let b = envelope(second.0, second.1, options, thresholds.length + thresholds.max_offset)?;
+
+		if a.is_empty() || b.is_empty() {
+			return Ok(None);
+		}
+
+		let size = (a.len() + b.len()).next_power_of_two();
+		let mut fa = a.iter().map(|&x| (x, 0.0)).collect::<Vec<_>>();
+		let mut fb = b.iter().map(|&x| (x, 0.0)).collect::<Vec<_>>();
+		fa.resize(size, (0.0, 0.0));
+		fb.resize(size, (0.0, 0.0));
+
+		fft(&mut fa, false);
+		fft(&mut fb, false);
+
+		let mut product = fa
+			.iter()
+			.zip(&fb)
+			.map(|(&(ar, ai), &(br, bi))| (ar * br + ai * bi, ar * bi - ai * br))
+			.collect::<Vec<_>>();
+
+		fft(&mut product, true);
+
+		// Negative lags wrap around to the end of the buffer.
+		let max = (thresholds.max_offset * RATE) as isize;
+		let (lag, peak) = (-max..=max)
+			.filter(|&lag| lag.unsigned_abs() < size)
+			.map(|lag| (lag, product[lag.rem_euclid(size as isize) as usize].0))
+			.fold((0, f64::MIN), |best, current| if current.1 > best.1 { current } else { best });
+
+		let energy = |x: &[f64]| x.iter().map(|x| x * x).sum::<f64>();
+		let norm = (energy(&a) * energy(&b)).sqrt();
+
+		Ok(Some(Alignment {
+			offset: lag as f64 / RATE,
+			confidence: if norm > 0.0 { (peak / norm).clamp(0.0, 1.0) } else { 0.0 },
+		}))
+	}
+}
+
+// Millisecond RMS of the mono downmix, differentiated so only onsets are left
+// and level or microphone differences between the recordings don't matter.
+fn envelope(
+	input: &mut Input,
+	index: usize,
+	options: &Options,
+	length: f64,
+) -> ffmpeg::Result<Vec<f64>> {
+	if compat::medium(&super::stream(input, index)?) != media::Type::Audio {
+		return Err(ffmpeg::Error::InvalidData);
+	}
+
+	let mut rms = Vec::new();
+	let mut sum = 0.0;
+	let mut count = 0;
+
+	let result = super::audio(input, index, options, |frame, _| {
+		let block = (f64::from(frame.rate()) / RATE).round().max(1.0) as usize;
+		let channels = samples::channels(frame);
+
+		for i in 0..frame.samples() {
+			let mono = channels.iter().map(|c| f64::from(c[i])).sum::<f64>()
+				/ channels.len().max(1) as f64;
+
+			sum += mono * mono;
+			count += 1;
+
+			if count == block {
+				rms.push((sum / block as f64).sqrt());
+				sum = 0.0;
+				count = 0;
+			}
+		}
+
+		// There's no way to stop decoding early other than failing.
+		if rms.len() as f64 >= length * RATE {
+			return Err(ffmpeg::Error::Eof);
+		}
+
+		Ok(())
+	});
+
+	match result {
+		Ok(()) | Err(ffmpeg::Error::Eof) => (),
+		Err(error) => return Err(error),
+	}
+
+	let mut onsets = rms.windows(2).map(|w| (w[1] - w[0]).max(0.0)).collect::<Vec<_>>();
+	let mean = onsets.iter().sum::<f64>() / onsets.len().max(1) as f64;
+	onsets.iter_mut().for_each(|x| *x -= mean);
+
+	Ok(onsets)
+}
+
+fn fft(data: &mut [(f64, f64)], inverse: bool) {
+	let n = data.len();
+
+	let mut j = 0;
+	for i in 1..n {
+		let mut bit = n >> 1;
+		while j & bit != 0 {
+			j ^= bit;
+			bit >>= 1;
+		}
+		j |= bit;
+
+		if i < j {
+			data.swap(i, j);
+		}
+	}
+
+	let mut length = 2;
+	while length <= n {
+		let angle = 2.0 * PI / length as f64 * if inverse { 1.0 } else { -1.0 };
+		let step = (angle.cos(), angle.sin());
+
+		for chunk in data.chunks_mut(length) {
+			let mut w = (1.0, 0.0);
+
+			for k in 0..length / 2 {
+				let (ur, ui) = chunk[k];
+				let (vr, vi) = chunk[k + length / 2];
+				let v = (vr * w.0 - vi * w.1, vr * w.1 + vi * w.0);
+
+				chunk[k] = (ur + v.0, ui + v.1);
+				chunk[k + length / 2] = (ur - v.0, ui - v.1);
+				w = (w.0 * step.0 - w.1 * step.1, w.0 * step.1 + w.1 * step.0);
+			}
+		}
+
+		length <<= 1;
+	}
+
+	if inverse {
+		for value in data.iter_mut() {
+			value.0 /= n as f64;
+			value.1 /= n as f64;
+		}
+	}
+}
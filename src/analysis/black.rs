@@ -0,0 +1,122 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{picture::Gray, Interval, Options};
+use crate::compat;
+
+// Detection works fine on heavily downscaled pictures and it keeps the cost
+// dominated by decoding.
+const WIDTH: u32 = 160;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BlackThresholds {
+	pub pixel: u8,
+	pub ratio: f64,
+	pub duration: f64,
+}
+
+impl Default for BlackThresholds {
+	fn default() -> Self {
+		BlackThresholds {
+			pixel: 32,
+			ratio: 0.98,
+			duration: 2.0,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Black {
+	pub frames: usize,
+	pub leading: Option<Interval>,
+	pub trailing: Option<Interval>,
+	pub intervals: Vec<Interval>,
+}
+
+impl Black {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		thresholds: &BlackThresholds,
+	) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut gray = Gray::new(WIDTH);
+		let mut frames = 0;
+		let mut first = None;
+		let mut last = 0.0;
+		let mut step = 0.0;
+		let mut start = None;
+		let mut intervals = Vec::new();
+
+		super::video(input, index, options, |frame, time_base| {
+			let time = match frame.timestamp().or_else(|| frame.pts()) {
+				Some(timestamp) => super::seconds(timestamp, time_base),
+				None => last + step,
+			};
+
+			if first.is_some() && time > last {
+				step = time - last;
+			}
+
+			first.get_or_insert(time);
+			last = time;
+
+			let plane = gray.convert(frame)?;
+			let dark = plane.pixels().filter(|&p| p <= thresholds.pixel).count();
+			let black = dark as f64 / plane.area().max(1) as f64 >= thresholds.ratio;
+
+			if black {
+				frames += 1;
+			}
+
+			match (black, start) {
+				(true, None) => start = Some(time),
+
+				(false, Some(from)) => {
+					if time - from >= thresholds.duration {
+						intervals.push(Interval { start: from, end: time });
+					}
+
+					start = None;
+				}
+
+				_ => (),
+			}
+
+			Ok(())
+		})?;
+
+		let mut black = Black {
+			frames,
+			leading: None,
+			trailing: None,
+			intervals,
+		};
+
+		if let Some(from) = start {
+			let end = last + step;
+
+			if end - from >= thresholds.duration {
+				let interval = Interval { start: from, end };
+
+				black.intervals.push(interval);
+				black.trailing = Some(interval);
+			}
+		}
+
+		if let (Some(first), Some(interval)) = (first, black.intervals.first()) {
+			if interval.start <= first {
+				black.leading = Some(*interval);
+			}
+		}
+
+		Ok(black)
+	}
+}
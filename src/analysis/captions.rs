@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+use ffmpeg::{codec, format::context::Input, media};
+
+use super::Options;
+use crate::{
+	bitstream::{nal, sei},
+	compat, raw, CaptionService, CaptionStandard, ClosedCaptionInfo,
+};
+
+// Captions are repeated continuously, a few seconds of packets are plenty.
+const PACKETS: usize = 300;
+
+impl ClosedCaptionInfo {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+	) -> ffmpeg::Result<Option<Self>> {
+		let (id, length) = {
+			let stream = super::stream(input, index)?;
+			let parameters = stream.parameters();
+
+			if compat::medium(&stream) != media::Type::Video {
+				return Err(ffmpeg::Error::InvalidData);
+			}
+
+			let id = parameters.id();
+			let length = nal::length_size(raw::extradata(&parameters), id == codec::Id::HEVC);
+
+			(id, length)
+		};
+
+		let mut options = options.clone();
+		options.max_frames.get_or_insert(PACKETS);
+
+		let mut scanner = Scanner::default();
+		super::packets(input, index, &options, |packet, _| {
+			let data = match packet.data() {
+				Some(data) => data,
+				None => return Ok(()),
+			};
+
+			match id {
+				codec::Id::H264 | codec::Id::HEVC => {
+					for payload in sei::payloads(data, length, id == codec::Id::HEVC) {
+						scanner.sei(&payload);
+					}
+				}
+
+				codec::Id::MPEG2VIDEO | codec::Id::MPEG1VIDEO => {
+					for unit in nal::units(data, None) {
+						if unit.first() == Some(&0xb2) {
+							scanner.a53(&unit[1..]);
+						}
+					}
+				}
+
+				_ => (),
+			}
+
+			Ok(())
+		})?;
+
+		Ok(scanner.finish())
+	}
+}
+
+#[derive(Default)]
+struct Scanner {
+	standards: BTreeSet<CaptionStandard>,
+	services: BTreeSet<CaptionService>,
+	channels: [u8; 2],
+	dtvcc: Vec<u8>,
+}
+
+impl Scanner {
+	fn sei(&mut self, data: &[u8]) {
+		for (kind, payload) in sei::messages(data) {
+			// Registered ITU-T T.35 user data, United States, ATSC provider.
+			if kind == 4 && payload.len() > 3 && payload[0] == 0xb5 && payload[1..3] == [0x00, 0x31] {
+				self.a53(&payload[3..]);
+			}
+		}
+	}
+
+	fn a53(&mut self, data: &[u8]) {
+		if data.len() < 7 || &data[..4] != b"GA94" || data[4] != 0x03 {
+			return;
+		}
+
+		let count = usize::from(data[5] & 0x1f);
+		let triplets = &data[7..];
+
+		for triplet in triplets.chunks_exact(3).take(count) {
+			if triplet[0] & 0x04 == 0 {
+				continue;
+			}
+
+			match triplet[0] & 0x03 {
+				field @ 0..=1 => self.eia608(usize::from(field), triplet[1] & 0x7f, triplet[2] & 0x7f),
+
+				2 => self.dtvcc.extend_from_slice(&triplet[1..]),
+
+				_ => {
+					self.packet();
+					self.dtvcc.extend_from_slice(&triplet[1..]);
+				}
+			}
+		}
+	}
+
+	fn eia608(&mut self, field: usize, first: u8, second: u8) {
+		if first == 0 && second == 0 {
+			return;
+		}
+
+		// Control codes select the data channel, which then holds until the
+		// next control code.
+		if (0x10..=0x1f).contains(&first) {
+			self.channels[field] = if first & 0x08 != 0 { 1 } else { 0 };
+		}
+
+		self.standards.insert(CaptionStandard::Eia608);
+		self.services.insert(CaptionService::Eia608(field as u8 * 2 + self.channels[field] + 1));
+	}
+
+	fn packet(&mut self) {
+		let packet = std::mem::take(&mut self.dtvcc);
+
+		if packet.is_empty() {
+			return;
+		}
+
+		let mut blocks = &packet[1..];
+		while let Some(&header) = blocks.first() {
+			let mut service = header >> 5;
+			let size = usize::from(header & 0x1f);
+			blocks = &blocks[1..];
+
+			if service == 0 || size == 0 {
+				break;
+			}
+
+			if service == 7 {
+				match blocks.first() {
+					Some(&extended) => service = extended & 0x3f,
+					None => break,
+				}
+
+				blocks = &blocks[1..];
+			}
+
+			if size > blocks.len() {
+				break;
+			}
+
+			self.standards.insert(CaptionStandard::Cea708);
+			self.services.insert(CaptionService::Cea708(service));
+			blocks = &blocks[size..];
+		}
+	}
+
+	fn finish(mut self) -> Option<ClosedCaptionInfo> {
+		self.packet();
+
+		if self.standards.is_empty() {
+			return None;
+		}
+
+		Some(ClosedCaptionInfo {
+			standards: self.standards.into_iter().collect(),
+			services: self.services.into_iter().collect(),
+		})
+	}
+}
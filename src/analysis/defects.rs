@@ -0,0 +1,145 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{samples, Interval, Options};
+use crate::compat;
+
+// Adaptation rate of the running average of the local curvature.
+const SMOOTHING: f32 = 0.001;
+
+// Clicks closer than this are reported as a single event.
+const MERGE: f64 = 0.01;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DefectThresholds {
+	pub click: f64,
+	pub factor: f64,
+	pub noise: f64,
+	pub dropout: Interval,
+}
+
+impl Default for DefectThresholds {
+	fn default() -> Self {
+		DefectThresholds {
+			click: 0.25,
+			factor: 20.0,
+			noise: -90.0,
+			dropout: Interval {
+				start: 0.001,
+				end: 0.5,
+			},
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Defects {
+	pub clicks: Vec<Click>,
+	pub dropouts: Vec<Interval>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Click {
+	pub time: f64,
+	pub channel: usize,
+	pub magnitude: f64,
+}
+
+impl Defects {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		thresholds: &DefectThresholds,
+	) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Audio {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let noise = 10f64.powf(thresholds.noise / 20.0) as f32;
+		let mut history: Vec<[f32; 2]> = Vec::new();
+		let mut average: Vec<f32> = Vec::new();
+		let mut position = 0.0;
+		let mut quiet = None;
+		let mut heard = false;
+		let mut defects = Defects {
+			clicks: Vec::new(),
+			dropouts: Vec::new(),
+		};
+
+		super::audio(input, index, options, |frame, time_base| {
+			let rate = f64::from(frame.rate());
+			if rate <= 0.0 {
+				return Ok(());
+			}
+
+			if let Some(timestamp) = frame.timestamp().or_else(|| frame.pts()) {
+				position = super::seconds(timestamp, time_base);
+			}
+
+			let channels = samples::channels(frame);
+			history.resize(channels.len(), [0.0; 2]);
+			average.resize(channels.len(), 0.0);
+
+			for i in 0..frame.samples() {
+				let time = position + i as f64 / rate;
+
+				for (c, channel) in channels.iter().enumerate() {
+					let sample = channel[i];
+					let [first, second] = history[c];
+					let curvature = (sample - 2.0 * first + second).abs();
+
+					if f64::from(curvature) > thresholds.click
+						&& f64::from(curvature) > f64::from(average[c]) * thresholds.factor
+					{
+						match defects.clicks.last_mut() {
+							Some(last) if time - last.time < MERGE => {
+								last.magnitude = last.magnitude.max(f64::from(curvature));
+							}
+
+							_ => defects.clicks.push(Click {
+								time,
+								channel: c,
+								magnitude: f64::from(curvature),
+							}),
+						}
+					}
+
+					average[c] += (curvature - average[c]) * SMOOTHING;
+					history[c] = [sample, first];
+				}
+
+				// Dropouts are short holes of digital silence in otherwise audible
+				// content, longer ones are just silence.
+				let silent = channels.iter().all(|channel| channel[i].abs() < noise);
+
+				match (silent, quiet) {
+					(true, None) if heard => quiet = Some(time),
+
+					(false, Some(from)) => {
+						let duration = time - from;
+
+						if duration >= thresholds.dropout.start && duration <= thresholds.dropout.end {
+							defects.dropouts.push(Interval { start: from, end: time });
+						}
+
+						quiet = None;
+					}
+
+					_ => (),
+				}
+
+				heard |= !silent;
+			}
+
+			position += frame.samples() as f64 / rate;
+			Ok(())
+		})?;
+
+		Ok(defects)
+	}
+}
@@ -0,0 +1,169 @@
+use ffmpeg::{format::context::Input, Packet, Rational};
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+use crate::raw;
+
+// Enough for a few seconds of video, or a minute or so of audio.
+const PACKETS: usize = 2000;
+// Read back from the end of the file for the last timestamps.
+const TAIL: u64 = 2 * 1024 * 1024;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum EstimateMethod {
+	// The last timestamps, read from the end of the file.
+	Tail,
+	// The file size over the bit rate of the packets at the start.
+	Extrapolated,
+}
+
+// Bit rates and durations from a bounded sample of packets, for the inputs
+// that don't declare them.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Estimate {
+	pub duration: Option<f64>,
+	pub bit_rate: Option<usize>,
+	pub method: Option<EstimateMethod>,
+	pub streams: Vec<StreamEstimate>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StreamEstimate {
+	pub index: usize,
+	pub bit_rate: Option<usize>,
+	pub duration: Option<f64>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Span {
+	bytes: usize,
+	first: Option<f64>,
+	last: Option<f64>,
+}
+
+impl Span {
+	fn add(&mut self, packet: &Packet, time_base: Rational) {
+		self.bytes += packet.size();
+
+		if let Some(time) = super::time(packet, time_base) {
+			let end = time + super::seconds(packet.duration(), time_base);
+
+			self.first = Some(self.first.map_or(time, |first| first.min(time)));
+			self.last = Some(self.last.map_or(end, |last| last.max(end)));
+		}
+	}
+
+	fn length(&self) -> Option<f64> {
+		Some(self.last? - self.first?).filter(|&length| length > 0.0)
+	}
+
+	fn bit_rate(&self) -> Option<usize> {
+		self.length().map(|length| (self.bytes as f64 * 8.0 / length).round() as usize)
+	}
+}
+
+impl Estimate {
+	// `max_frames` bounds the packets read from the start, and again from the
+	// tail.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, options: &Options) -> ffmpeg::Result<Self> {
+		let limit = options.max_frames.unwrap_or(PACKETS);
+		let count = input.streams().count();
+		let mut ticker = options.ticker(input);
+
+		let mut head = vec![Span::default(); count];
+		let mut total = Span::default();
+		let mut read = 0;
+
+		input.seek(0, ..)?;
+		for (stream, packet) in input.packets().take(limit) {
+			ticker.packet(&packet, stream.time_base())?;
+			read += 1;
+
+			if let Some(span) = head.get_mut(stream.index()) {
+				span.add(&packet, stream.time_base());
+				total.add(&packet, stream.time_base());
+			}
+		}
+
+		// The start already covers the whole file when it ran out of packets.
+		let size = raw::size(input);
+		let ends: Vec<Option<f64>> = if read < limit {
+			head.iter().map(|span| span.last).collect()
+		}
+		else if size.map_or(false, |size| size > TAIL) && raw::seek_bytes(input, size.unwrap() - TAIL) {
+			let mut tail = vec![Span::default(); count];
+
+			for (stream, packet) in input.packets().take(limit) {
+				ticker.packet(&packet, stream.time_base())?;
+
+				if let Some(span) = tail.get_mut(stream.index()) {
+					span.add(&packet, stream.time_base());
+				}
+			}
+
+			// Timestamps from the tail are only trusted past the ones at the
+			// start, demuxers that don't resync return garbage or nothing.
+			tail
+				.iter()
+				.zip(&head)
+				.map(|(tail, head)| match (tail.last, head.last) {
+					(Some(tail), Some(head)) if tail >= head => Some(tail),
+					_ => None,
+				})
+				.collect()
+		}
+		else {
+			vec![None; count]
+		};
+
+		input.seek(0, ..)?;
+
+		let start = extreme(head.iter().filter_map(|span| span.first), f64::min);
+		let last = extreme(ends.iter().flatten().copied(), f64::max);
+		let bit_rate = total.bit_rate();
+
+		let (duration, method) = match (start, last, size, bit_rate) {
+			(Some(start), Some(last), ..) if last > start => {
+				(Some(last - start), Some(EstimateMethod::Tail))
+			}
+			(.., Some(size), Some(rate)) if rate > 0 => {
+				(Some(size as f64 * 8.0 / rate as f64), Some(EstimateMethod::Extrapolated))
+			}
+			_ => (None, None),
+		};
+
+		let streams = head
+			.iter()
+			.zip(ends)
+			.enumerate()
+			.map(|(index, (span, end))| StreamEstimate {
+				index,
+				bit_rate: span.bit_rate(),
+				duration: end
+					.zip(span.first)
+					.map(|(end, first)| end - first)
+					.filter(|&duration| duration > 0.0),
+			})
+			.collect();
+
+		Ok(Estimate {
+			duration,
+			bit_rate,
+			method,
+			streams,
+		})
+	}
+
+	pub fn stream(&self, index: usize) -> Option<&StreamEstimate> {
+		self.streams.iter().find(|stream| stream.index == index)
+	}
+}
+
+fn extreme(values: impl Iterator<Item = f64>, pick: fn(f64, f64) -> f64) -> Option<f64> {
+	values.fold(None, |current, value| Some(current.map_or(value, |current| pick(current, value))))
+}
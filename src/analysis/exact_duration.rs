@@ -0,0 +1,87 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+use crate::{compat, raw, tags::itunes, SampleSource};
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+	Decode,
+	Headers,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExactDuration {
+	pub source: SampleSource,
+	pub sample_rate: u32,
+	pub samples: u64,
+	pub seconds: f64,
+}
+
+impl ExactDuration {
+	// Decoding is the only way to be exact for every format, the decoders
+	// already drop priming and padding wherever the container signals them.
+	// Headers are only trusted when a tag carries the actual sample count,
+	// otherwise the container duration is converted to samples.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		method: Method,
+	) -> ffmpeg::Result<Option<Self>> {
+		let (rate, smpb, duration) = {
+			let stream = super::stream(input, index)?;
+
+			if compat::medium(&stream) != media::Type::Audio {
+				return Err(ffmpeg::Error::InvalidData);
+			}
+
+			let smpb = itunes::find_smpb(input, &stream);
+			let duration = Some(stream.duration())
+				.filter(|&duration| duration > 0)
+				.map(|duration| super::seconds(duration, stream.time_base()));
+
+			(raw::sample_rate(&stream.parameters()), smpb, duration)
+		};
+
+		let (source, rate, samples) = match method {
+			Method::Decode => {
+				let mut samples = 0u64;
+				let mut decoded = rate;
+
+				super::audio(input, index, options, |frame, _| {
+					samples += frame.samples() as u64;
+					decoded = frame.rate();
+
+					Ok(())
+				})?;
+
+				(SampleSource::Decoded, decoded, samples)
+			}
+
+			Method::Headers => match (smpb, duration) {
+				(Some(smpb), _) if smpb.samples > 0 => (SampleSource::ITunSMPB, rate, smpb.samples),
+				(_, Some(duration)) => {
+					(SampleSource::Container, rate, (duration * f64::from(rate)).round() as u64)
+				}
+
+				_ => return Ok(None),
+			},
+		};
+
+		if rate == 0 {
+			return Ok(None);
+		}
+
+		Ok(Some(ExactDuration {
+			source,
+			sample_rate: rate,
+			samples,
+			seconds: samples as f64 / f64::from(rate),
+		}))
+	}
+}
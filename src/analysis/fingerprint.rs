@@ -0,0 +1,140 @@
+use std::{
+	ffi::CStr,
+	os::raw::{c_char, c_int, c_void},
+	ptr,
+};
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{samples, Options};
+use crate::{compat, Audio};
+
+// The same amount of audio `fpcalc` uses by default, AcoustID lookups expect
+// fingerprints of this length.
+const LENGTH: f64 = 120.0;
+
+const ALGORITHM_DEFAULT: c_int = 1;
+
+#[repr(C)]
+struct Context {
+	_private: [u8; 0],
+}
+
+extern "C" {
+	fn chromaprint_new(algorithm: c_int) -> *mut Context;
+	fn chromaprint_free(context: *mut Context);
+	fn chromaprint_start(context: *mut Context, sample_rate: c_int, channels: c_int) -> c_int;
+	fn chromaprint_feed(context: *mut Context, data: *const i16, size: c_int) -> c_int;
+	fn chromaprint_finish(context: *mut Context) -> c_int;
+	fn chromaprint_get_fingerprint(context: *mut Context, fingerprint: *mut *mut c_char) -> c_int;
+	fn chromaprint_dealloc(pointer: *mut c_void);
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Fingerprint {
+	pub fingerprint: String,
+	pub duration: f64,
+}
+
+struct Chromaprint(*mut Context);
+
+impl Chromaprint {
+	fn check(result: c_int) -> ffmpeg::Result<()> {
+		if result == 1 {
+			Ok(())
+		}
+		else {
+			Err(ffmpeg::Error::External)
+		}
+	}
+}
+
+impl Drop for Chromaprint {
+	fn drop(&mut self) {
+		unsafe { chromaprint_free(self.0) }
+	}
+}
+
+impl Audio {
+	pub fn fingerprint(input: &mut Input, index: usize) -> ffmpeg::Result<Fingerprint> {
+		let duration = {
+			let stream = super::stream(input, index)?;
+
+			if compat::medium(&stream) != media::Type::Audio {
+				return Err(ffmpeg::Error::InvalidData);
+			}
+
+			if stream.duration() > 0 {
+				super::seconds(stream.duration(), stream.time_base())
+			}
+			else {
+				input.duration().max(0) as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE)
+			}
+		};
+
+		let context = Chromaprint(unsafe { chromaprint_new(ALGORITHM_DEFAULT) });
+		if context.0.is_null() {
+			return Err(ffmpeg::Error::External);
+		}
+
+		let mut started = false;
+		let mut fed = 0.0;
+		let mut interleaved = Vec::new();
+
+		let result = super::audio(input, index, &Options::default(), |frame, _| {
+			let channels = samples::channels(frame);
+
+			if !started {
+				Chromaprint::check(unsafe {
+					chromaprint_start(context.0, frame.rate() as c_int, channels.len() as c_int)
+				})?;
+
+				started = true;
+			}
+
+			interleaved.clear();
+			for i in 0..frame.samples() {
+				for channel in &channels {
+					interleaved.push((channel[i].clamp(-1.0, 1.0) * 32_767.0) as i16);
+				}
+			}
+
+			Chromaprint::check(unsafe {
+				chromaprint_feed(context.0, interleaved.as_ptr(), interleaved.len() as c_int)
+			})?;
+
+			// There's no way to stop decoding early other than failing.
+			fed += frame.samples() as f64 / f64::from(frame.rate());
+			if fed >= LENGTH {
+				return Err(ffmpeg::Error::Eof);
+			}
+
+			Ok(())
+		});
+
+		match result {
+			Ok(()) | Err(ffmpeg::Error::Eof) => (),
+			Err(error) => return Err(error),
+		}
+
+		if !started {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		unsafe {
+			Chromaprint::check(chromaprint_finish(context.0))?;
+
+			let mut fingerprint = ptr::null_mut();
+			Chromaprint::check(chromaprint_get_fingerprint(context.0, &mut fingerprint))?;
+
+			let value = CStr::from_ptr(fingerprint).to_string_lossy().into_owned();
+			chromaprint_dealloc(fingerprint as *mut c_void);
+
+			Ok(Fingerprint {
+				fingerprint: value,
+				duration: if duration > 0.0 { duration } else { fed },
+			})
+		}
+	}
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use ffmpeg::{format::context::Input, media, Rational};
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+use crate::compat;
+
+// Fraction of frames allowed to be off the nominal rate before a stream is
+// considered variable, to account for the odd glitch at splice points.
+const VARIABLE_THRESHOLD: f64 = 0.01;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameRate {
+	pub mode: Mode,
+	#[cfg_attr(feature = "schemars", schemars(with = "crate::schema::Rational"))]
+	pub nominal: Rational,
+	pub durations: Vec<FrameDuration>,
+	pub off_nominal_percentage: f64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+	Constant,
+	Variable,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameDuration {
+	pub duration: i64,
+	pub seconds: f64,
+	pub count: usize,
+}
+
+impl FrameRate {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, index: usize, options: &Options) -> ffmpeg::Result<Self> {
+		let stream = super::stream(input, index)?;
+
+		if compat::medium(&stream) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let time_base = stream.time_base();
+		let nominal = stream.frame_rate();
+
+		let mut timestamps = Vec::new();
+		super::packets(input, index, options, |packet, _| {
+			if let Some(pts) = packet.pts() {
+				timestamps.push(pts);
+			}
+
+			Ok(())
+		})?;
+
+		// Packets come in decoding order, presentation order is what matters.
+		timestamps.sort_unstable();
+		timestamps.dedup();
+
+		let mut histogram = BTreeMap::new();
+		for pair in timestamps.windows(2) {
+			*histogram.entry(pair[1] - pair[0]).or_insert(0usize) += 1;
+		}
+
+		// Without a nominal rate the most common duration stands in for it.
+		let expected = if nominal.numerator() > 0 && time_base.numerator() > 0 {
+			Some(
+				(f64::from(time_base.denominator()) * f64::from(nominal.denominator()))
+					/ (f64::from(time_base.numerator()) * f64::from(nominal.numerator())),
+			)
+		}
+		else {
+			histogram
+				.iter()
+				.max_by_key(|(_, &count)| count)
+				.map(|(&duration, _)| duration as f64)
+		};
+
+		let total = histogram.values().sum::<usize>();
+		let off = histogram
+			.iter()
+			.filter(|(&duration, _)| expected.map_or(false, |e| (duration as f64 - e).abs() >= 1.0))
+			.map(|(_, &count)| count)
+			.sum::<usize>();
+
+		let off_nominal = if total > 0 { off as f64 / total as f64 } else { 0.0 };
+
+		Ok(FrameRate {
+			mode: if off_nominal > VARIABLE_THRESHOLD {
+				Mode::Variable
+			}
+			else {
+				Mode::Constant
+			},
+			nominal,
+			durations: histogram
+				.into_iter()
+				.map(|(duration, count)| FrameDuration {
+					duration,
+					seconds: duration as f64 * f64::from(time_base),
+					count,
+				})
+				.collect(),
+			off_nominal_percentage: off_nominal * 100.0,
+		})
+	}
+}
@@ -0,0 +1,133 @@
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OutlierThresholds {
+	pub factor: f64,
+	pub size: Option<usize>,
+}
+
+impl Default for OutlierThresholds {
+	fn default() -> Self {
+		OutlierThresholds {
+			factor: 10.0,
+			size: None,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameSizes {
+	pub count: usize,
+	pub total: usize,
+	pub all: Option<Percentiles>,
+	pub keyframes: Option<Percentiles>,
+	pub outliers: Vec<Outlier>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Percentiles {
+	pub min: usize,
+	pub p50: usize,
+	pub p90: usize,
+	pub p95: usize,
+	pub p99: usize,
+	pub max: usize,
+	pub mean: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Outlier {
+	pub timestamp: Option<f64>,
+	pub size: usize,
+	pub key: bool,
+}
+
+impl FrameSizes {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		thresholds: &OutlierThresholds,
+	) -> ffmpeg::Result<Self> {
+		let mut packets = Vec::new();
+		super::packets(input, index, options, |packet, time_base| {
+			packets.push((
+				packet.size(),
+				packet.is_key(),
+				packet.pts().map(|pts| super::seconds(pts, time_base)),
+			));
+
+			Ok(())
+		})?;
+
+		let mut all = packets.iter().map(|p| p.0).collect::<Vec<_>>();
+		let mut keyframes = packets.iter().filter(|p| p.1).map(|p| p.0).collect::<Vec<_>>();
+		let mut others = packets.iter().filter(|p| !p.1).map(|p| p.0).collect::<Vec<_>>();
+
+		let all = Percentiles::new(&mut all);
+		let keyframes = Percentiles::new(&mut keyframes);
+		let others = Percentiles::new(&mut others);
+
+		// Keyframes are naturally much bigger than the rest, so each kind is
+		// compared against its own median.
+		let limit = |median: Option<&Percentiles>| {
+			median.map(|p| (p.p50 as f64 * thresholds.factor) as usize)
+		};
+
+		let key_limit = limit(keyframes.as_ref());
+		let other_limit = limit(others.as_ref());
+
+		let outliers = packets
+			.iter()
+			.filter(|(size, key, _)| {
+				let relative = if *key { key_limit } else { other_limit };
+
+				relative.map_or(false, |limit| *size > limit)
+					|| thresholds.size.map_or(false, |limit| *size > limit)
+			})
+			.map(|&(size, key, timestamp)| Outlier {
+				timestamp,
+				size,
+				key,
+			})
+			.collect();
+
+		Ok(FrameSizes {
+			count: packets.len(),
+			total: packets.iter().map(|p| p.0).sum(),
+			all,
+			keyframes,
+			outliers,
+		})
+	}
+}
+
+impl Percentiles {
+	fn new(sizes: &mut [usize]) -> Option<Self> {
+		if sizes.is_empty() {
+			return None;
+		}
+
+		sizes.sort_unstable();
+
+		let at = |percentile: usize| sizes[((sizes.len() - 1) * percentile) / 100];
+
+		Some(Percentiles {
+			min: sizes[0],
+			p50: at(50),
+			p90: at(90),
+			p95: at(95),
+			p99: at(99),
+			max: sizes[sizes.len() - 1],
+			mean: sizes.iter().sum::<usize>() as f64 / sizes.len() as f64,
+		})
+	}
+}
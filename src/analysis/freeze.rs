@@ -0,0 +1,115 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{picture::Gray, Interval, Options};
+use crate::compat;
+
+// Detection works fine on heavily downscaled pictures and it keeps the cost
+// dominated by decoding.
+const WIDTH: u32 = 160;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FreezeThresholds {
+	pub noise: f64,
+	pub duration: f64,
+}
+
+impl Default for FreezeThresholds {
+	fn default() -> Self {
+		FreezeThresholds {
+			noise: 0.001,
+			duration: 2.0,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Freeze {
+	pub frames: usize,
+	pub intervals: Vec<Interval>,
+}
+
+impl Freeze {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		thresholds: &FreezeThresholds,
+	) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut gray = Gray::new(WIDTH);
+		let mut previous = Vec::new();
+		let mut frames = 0;
+		let mut last = 0.0;
+		let mut step = 0.0;
+		let mut start = None;
+		let mut intervals = Vec::new();
+
+		super::video(input, index, options, |frame, time_base| {
+			let time = match frame.timestamp().or_else(|| frame.pts()) {
+				Some(timestamp) => super::seconds(timestamp, time_base),
+				None => last + step,
+			};
+
+			if !previous.is_empty() && time > last {
+				step = time - last;
+			}
+
+			last = time;
+
+			let plane = gray.convert(frame)?;
+			let current = plane.pixels().collect::<Vec<_>>();
+
+			// A resolution change is never a freeze.
+			let frozen = previous.len() == current.len()
+				&& !current.is_empty()
+				&& current
+					.iter()
+					.zip(&previous)
+					.map(|(&a, &b)| u64::from((i16::from(a) - i16::from(b)).unsigned_abs()))
+					.sum::<u64>() as f64
+					/ (current.len() as f64 * 255.0)
+					<= thresholds.noise;
+
+			previous = current;
+
+			if frozen {
+				frames += 1;
+			}
+
+			match (frozen, start) {
+				// The run starts with the frame that got repeated, not the first
+				// repetition.
+				(true, None) => start = Some(time - step),
+
+				(false, Some(from)) => {
+					if time - from >= thresholds.duration {
+						intervals.push(Interval { start: from, end: time });
+					}
+
+					start = None;
+				}
+
+				_ => (),
+			}
+
+			Ok(())
+		})?;
+
+		if let Some(from) = start {
+			let end = last + step;
+
+			if end - from >= thresholds.duration {
+				intervals.push(Interval { start: from, end });
+			}
+		}
+
+		Ok(Freeze { frames, intervals })
+	}
+}
@@ -0,0 +1,263 @@
+use std::hash::Hasher as _;
+use ffmpeg::{
+	format::{context::Input, stream::Disposition},
+	frame, media,
+};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use twox_hash::XxHash64;
+
+use super::Options;
+use crate::{compat, raw, Content, Metadata};
+
+// Enough packets to tell apart encodes of the same source, few enough to only
+// touch the start of the file.
+const ASSET_PACKETS: usize = 32;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Algorithm {
+	Md5,
+	Sha256,
+	Xxh64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HashOptions {
+	pub algorithm: Algorithm,
+	pub decode: bool,
+	pub frames: bool,
+}
+
+impl Default for HashOptions {
+	fn default() -> Self {
+		HashOptions {
+			algorithm: Algorithm::Md5,
+			decode: true,
+			frames: false,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Hashes {
+	pub index: usize,
+	pub algorithm: Algorithm,
+	pub packets: usize,
+	pub packet_hash: String,
+	pub decoded_hash: Option<String>,
+	pub frames: Vec<FrameHash>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameHash {
+	pub time: Option<f64>,
+	pub hash: String,
+}
+
+enum Hasher {
+	Md5(Md5),
+	Sha256(Sha256),
+	Xxh64(XxHash64),
+}
+
+impl Hasher {
+	fn new(algorithm: Algorithm) -> Self {
+		match algorithm {
+			Algorithm::Md5 => Hasher::Md5(Md5::new()),
+			Algorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+			Algorithm::Xxh64 => Hasher::Xxh64(XxHash64::with_seed(0)),
+		}
+	}
+
+	fn update(&mut self, data: &[u8]) {
+		match self {
+			Hasher::Md5(hasher) => hasher.update(data),
+			Hasher::Sha256(hasher) => hasher.update(data),
+			Hasher::Xxh64(hasher) => hasher.write(data),
+		}
+	}
+
+	fn finish(self) -> String {
+		let bytes = match self {
+			Hasher::Md5(hasher) => hasher.finalize().to_vec(),
+			Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+			Hasher::Xxh64(hasher) => hasher.finish().to_be_bytes().to_vec(),
+		};
+
+		bytes.iter().map(|b| format!("{:02x}", b)).collect()
+	}
+}
+
+impl Hashes {
+	// Packet hashes only match between identical bitstreams, decoded hashes
+	// also survive a remux that rewrites the bitstream framing (e.g. Annex B to
+	// AVCC).
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		hashing: &HashOptions,
+	) -> ffmpeg::Result<Self> {
+		let medium = compat::medium(&super::stream(input, index)?);
+
+		let mut packets = 0;
+		let mut hasher = Hasher::new(hashing.algorithm);
+		super::packets(input, index, options, |packet, _| {
+			hasher.update(packet.data().unwrap_or_default());
+			packets += 1;
+
+			Ok(())
+		})?;
+
+		let mut frames = Vec::new();
+		let mut decoded = Hasher::new(hashing.algorithm);
+		let mut each = |planes: Vec<&[u8]>, time: Option<f64>| {
+			let mut frame = hashing.frames.then(|| Hasher::new(hashing.algorithm));
+
+			for plane in planes {
+				decoded.update(plane);
+				frame.iter_mut().for_each(|hasher| hasher.update(plane));
+			}
+
+			if let Some(hasher) = frame {
+				frames.push(FrameHash {
+					time,
+					hash: hasher.finish(),
+				});
+			}
+		};
+
+		let decoded = match medium {
+			media::Type::Audio if hashing.decode => {
+				super::audio(input, index, options, |frame, time_base| {
+					let time = frame.timestamp().map(|ts| super::seconds(ts, time_base));
+					each(audio(frame), time);
+
+					Ok(())
+				})?;
+
+				Some(decoded)
+			}
+
+			media::Type::Video if hashing.decode => {
+				super::video(input, index, options, |frame, time_base| {
+					let time = frame.timestamp().map(|ts| super::seconds(ts, time_base));
+					each(video(frame), time);
+
+					Ok(())
+				})?;
+
+				Some(decoded)
+			}
+
+			_ => None,
+		};
+
+		Ok(Hashes {
+			index,
+			algorithm: hashing.algorithm,
+			packets,
+			packet_hash: hasher.finish(),
+			decoded_hash: decoded.map(Hasher::finish),
+			frames,
+		})
+	}
+
+	pub fn all(
+		input: &mut Input,
+		options: &Options,
+		hashing: &HashOptions,
+	) -> ffmpeg::Result<Vec<Self>> {
+		let indices = input.streams().map(|s| s.index()).collect::<Vec<_>>();
+
+		indices
+			.into_iter()
+			.map(|index| Hashes::analyze(input, index, options, hashing))
+			.collect()
+	}
+}
+
+// Identifies the essence rather than the file: a hash over the first packets of
+// every audio, video and subtitle stream together with their codecs and
+// durations. Tags, cover art and the container are left out so renames, tag
+// edits and remuxes that keep the bitstream framing keep the identifier.
+pub fn asset_id(metadata: &Metadata, input: &mut Input) -> ffmpeg::Result<String> {
+	// Order by kind and position within the kind, remuxers renumber streams.
+	let mut streams = metadata
+		.streams
+		.iter()
+		.filter(|stream| {
+			matches!(stream.content, Content::Audio(_) | Content::Video(_) | Content::Subtitle(_))
+		})
+		.filter(|stream| !stream.disposition.contains(Disposition::ATTACHED_PIC))
+		.collect::<Vec<_>>();
+
+	streams.sort_by_key(|stream| (stream.content.kind(), stream.index));
+
+	let mut hashers = streams.iter().map(|_| (0, Sha256::new())).collect::<Vec<_>>();
+
+	input.seek(0, ..)?;
+	for (stream, packet) in input.packets() {
+		let position = match streams.iter().position(|s| s.index == stream.index()) {
+			Some(position) => position,
+			None => continue,
+		};
+
+		let (count, hasher) = &mut hashers[position];
+		if *count < ASSET_PACKETS {
+			hasher.update(packet.data().unwrap_or_default());
+			*count += 1;
+		}
+
+		if hashers.iter().all(|(count, _)| *count >= ASSET_PACKETS) {
+			break;
+		}
+	}
+
+	// Durations to the second, remuxing can move the last timestamp a frame.
+	let mut id = Sha256::new();
+	for (stream, (_, hasher)) in streams.iter().zip(hashers) {
+		id.update(stream.content.kind());
+		id.update(stream.content.codec().map_or("", |codec| codec.name.as_str()));
+		id.update(stream.duration_seconds().map_or(-1, |d| d.round() as i64).to_be_bytes());
+		id.update(hasher.finalize());
+	}
+
+	Ok(id.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Only the samples are hashed, the padding at the end of each plane is
+// whatever the decoder left there.
+fn audio(frame: &frame::Audio) -> Vec<&[u8]> {
+	let size = frame.format().bytes() * frame.samples();
+
+	if frame.is_planar() {
+		(0..frame.planes()).map(|i| &frame.data(i)[..size]).collect()
+	}
+	else {
+		vec![&frame.data(0)[..size * frame.channels() as usize]]
+	}
+}
+
+fn video(frame: &frame::Video) -> Vec<&[u8]> {
+	let mut rows = Vec::new();
+
+	for plane in 0..frame.planes() {
+		let stride = frame.stride(plane);
+		let width = raw::linesize(frame.format(), frame.width(), plane).min(stride);
+		let data = frame.data(plane);
+
+		for row in 0..frame.plane_height(plane) as usize {
+			rows.push(&data[row * stride..row * stride + width]);
+		}
+	}
+
+	rows
+}
@@ -0,0 +1,133 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{picture::Gray, Options};
+use crate::compat;
+
+// Number of frames sampled when no explicit limit is given.
+const SAMPLES: usize = 500;
+
+// Minimum luma difference, on both sides, for a pixel to count as combed.
+const COMB: i32 = 10 * 10;
+
+// Fraction of combed pixels for a frame to count as interlaced.
+const COMBED: f64 = 0.02;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Interlacing {
+	pub scan: Scan,
+	pub confidence: f64,
+	pub frames: usize,
+	pub combed: usize,
+	pub flagged: usize,
+	pub top_first: usize,
+	pub repeated: usize,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Scan {
+	Progressive,
+	TopFieldFirst,
+	BottomFieldFirst,
+	Telecined,
+}
+
+impl Interlacing {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, index: usize, options: &Options) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut options = options.clone();
+		options.max_frames.get_or_insert(SAMPLES);
+
+		// Combing only shows at full vertical resolution.
+		let mut gray = Gray::new(u32::MAX);
+		let mut report = Interlacing {
+			scan: Scan::Progressive,
+			confidence: 0.0,
+			frames: 0,
+			combed: 0,
+			flagged: 0,
+			top_first: 0,
+			repeated: 0,
+		};
+
+		super::video(input, index, &options, |frame, _| {
+			report.frames += 1;
+
+			if frame.is_interlaced() {
+				report.flagged += 1;
+
+				if frame.is_top_first() {
+					report.top_first += 1;
+				}
+			}
+
+			if unsafe { (*frame.as_ptr()).repeat_pict } > 0 {
+				report.repeated += 1;
+			}
+
+			let plane = gray.convert(frame)?;
+			let rows = plane.rows().collect::<Vec<_>>();
+			let mut combed = 0;
+
+			for y in 1..rows.len().saturating_sub(1) {
+				for x in 0..plane.width {
+					let pixel = i32::from(rows[y][x]);
+					let above = pixel - i32::from(rows[y - 1][x]);
+					let below = pixel - i32::from(rows[y + 1][x]);
+
+					if above * below > COMB {
+						combed += 1;
+					}
+				}
+			}
+
+			if combed as f64 / plane.area().max(1) as f64 > COMBED {
+				report.combed += 1;
+			}
+
+			Ok(())
+		})?;
+
+		if report.frames == 0 {
+			return Ok(report);
+		}
+
+		let frames = report.frames as f64;
+		let combed = report.combed as f64 / frames;
+		let repeated = report.repeated as f64 / frames;
+
+		// Hard telecine combs two frames out of five, soft telecine signals the
+		// repeated fields instead.
+		let (scan, confidence) = if repeated > 0.2 {
+			(Scan::Telecined, repeated.min(0.4) / 0.4)
+		}
+		else if combed > 0.25 && combed < 0.5 {
+			(Scan::Telecined, 1.0 - (combed - 0.4).abs() / 0.15)
+		}
+		else if combed >= 0.5 || report.flagged as f64 / frames > 0.5 {
+			let order = if report.top_first * 2 >= report.flagged {
+				Scan::TopFieldFirst
+			}
+			else {
+				Scan::BottomFieldFirst
+			};
+
+			(order, combed.max(report.flagged as f64 / frames))
+		}
+		else {
+			(Scan::Progressive, 1.0 - combed)
+		};
+
+		report.scan = scan;
+		report.confidence = confidence.clamp(0.0, 1.0);
+
+		Ok(report)
+	}
+}
@@ -0,0 +1,162 @@
+use ffmpeg::{
+	format::{context::Input, stream::Disposition},
+	media,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{seconds, Options};
+use crate::compat;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexSource {
+	// What the container declares, e.g. MP4 sample tables or Matroska cues.
+	Demuxer,
+	// Built from reading every packet.
+	Scan,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct KeyframeIndex {
+	pub streams: Vec<StreamKeyframes>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StreamKeyframes {
+	pub index: usize,
+	pub source: IndexSource,
+	pub keyframes: Vec<Keyframe>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Keyframe {
+	pub time: f64,
+	pub timestamp: i64,
+	pub position: Option<u64>,
+	pub size: Option<usize>,
+}
+
+impl KeyframeIndex {
+	// Video streams only, every audio packet is a keyframe. The demuxer's
+	// index is used where there is one, the rest are scanned.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, options: &Options) -> ffmpeg::Result<Self> {
+		let mut streams = Vec::new();
+		let mut missing = Vec::new();
+
+		for stream in input.streams().filter(video) {
+			let time_base = stream.time_base();
+			let keyframes = compat::index_entries(&stream)
+				.into_iter()
+				.filter(|&(_, _, _, key)| key)
+				.map(|(timestamp, position, size, _)| Keyframe {
+					time: seconds(timestamp, time_base),
+					timestamp,
+					position: Some(position).filter(|&p| p >= 0).map(|p| p as u64),
+					size: Some(size).filter(|&s| s > 0).map(|s| s as usize),
+				})
+				.collect::<Vec<_>>();
+
+			if keyframes.is_empty() {
+				missing.push(stream.index());
+			}
+			else {
+				streams.push(StreamKeyframes {
+					index: stream.index(),
+					source: IndexSource::Demuxer,
+					keyframes,
+				});
+			}
+		}
+
+		if !missing.is_empty() {
+			streams.extend(scan(input, &missing, options)?);
+			streams.sort_by_key(|stream| stream.index);
+		}
+
+		Ok(KeyframeIndex { streams })
+	}
+
+	// Ignores the demuxer's index, for when it can't be trusted.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn scan(input: &mut Input, options: &Options) -> ffmpeg::Result<Self> {
+		let indices = input.streams().filter(video).map(|s| s.index()).collect::<Vec<_>>();
+
+		Ok(KeyframeIndex {
+			streams: scan(input, &indices, options)?,
+		})
+	}
+
+	pub fn stream(&self, index: usize) -> Option<&StreamKeyframes> {
+		self.streams.iter().find(|stream| stream.index == index)
+	}
+}
+
+impl StreamKeyframes {
+	// The last keyframe at or before `time`, where seeking there lands.
+	pub fn before(&self, time: f64) -> Option<&Keyframe> {
+		self.keyframes.iter().take_while(|keyframe| keyframe.time <= time).last()
+	}
+}
+
+fn video(stream: &ffmpeg::format::stream::Stream) -> bool {
+	compat::medium(stream) == media::Type::Video
+		&& !stream.disposition().contains(Disposition::ATTACHED_PIC)
+}
+
+fn scan(
+	input: &mut Input,
+	indices: &[usize],
+	options: &Options,
+) -> ffmpeg::Result<Vec<StreamKeyframes>> {
+	let mut streams = indices
+		.iter()
+		.map(|&index| StreamKeyframes {
+			index,
+			source: IndexSource::Scan,
+			keyframes: Vec::new(),
+		})
+		.collect::<Vec<_>>();
+
+	let mut ticker = options.ticker(input);
+	let mut count = 0;
+
+	let window = options.window(input)?;
+	for (stream, packet) in input.packets() {
+		ticker.packet(&packet, stream.time_base())?;
+
+		let time = super::time(&packet, stream.time_base());
+		if window.before(time) {
+			continue;
+		}
+
+		if options.max_frames.map_or(false, |max| count >= max) || window.after(time) {
+			break;
+		}
+
+		let keyframes = match streams.iter_mut().find(|s| s.index == stream.index()) {
+			Some(stream) => &mut stream.keyframes,
+			None => continue,
+		};
+
+		count += 1;
+		if !packet.is_key() {
+			continue;
+		}
+
+		if let Some(timestamp) = packet.pts().or_else(|| packet.dts()) {
+			keyframes.push(Keyframe {
+				time: seconds(timestamp, stream.time_base()),
+				timestamp,
+				position: Some(packet.position()).filter(|&p| p >= 0).map(|p| p as u64),
+				size: Some(packet.size()),
+			});
+		}
+	}
+
+	Ok(streams)
+}
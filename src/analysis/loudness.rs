@@ -0,0 +1,256 @@
+use std::f64::consts::PI;
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{samples, Options};
+use crate::compat;
+
+// Loudness is measured on 100ms segments, momentary blocks are 4 of them and
+// short-term blocks 30, as in ITU-R BS.1770 and EBU Tech 3342.
+const MOMENTARY: usize = 4;
+const SHORT_TERM: usize = 30;
+const ABSOLUTE_GATE: f64 = -70.0;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Loudness {
+	pub integrated: Option<f64>,
+	pub range: Option<f64>,
+	pub sample_peak: Option<f64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Target {
+	pub integrated: f64,
+	pub peak: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Normalization {
+	pub gain: f64,
+	pub peak: Option<f64>,
+	pub headroom: Option<f64>,
+	pub needs_limiter: bool,
+}
+
+impl Target {
+	// What Spotify, YouTube and most streaming services normalize to.
+	pub fn streaming() -> Self {
+		Target {
+			integrated: -14.0,
+			peak: -1.0,
+		}
+	}
+
+	pub fn ebu_r128() -> Self {
+		Target {
+			integrated: -23.0,
+			peak: -1.0,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+	b: [f64; 3],
+	a: [f64; 2],
+	z: [f64; 2],
+}
+
+impl Biquad {
+	fn process(&mut self, x: f64) -> f64 {
+		let y = self.b[0] * x + self.z[0];
+		self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+		self.z[1] = self.b[2] * x - self.a[1] * y;
+
+		y
+	}
+}
+
+// The K-weighting pre-filter, coefficients derived for any sample rate the
+// same way libebur128 does.
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+	let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+	let k = (PI * f0 / rate).tan();
+	let vh = 10f64.powf(gain / 20.0);
+	let vb = vh.powf(0.4996667741545416);
+	let a0 = 1.0 + k / q + k * k;
+
+	let shelf = Biquad {
+		b: [
+			(vh + vb * k / q + k * k) / a0,
+			2.0 * (k * k - vh) / a0,
+			(vh - vb * k / q + k * k) / a0,
+		],
+		a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+		z: [0.0; 2],
+	};
+
+	let (f0, q) = (38.13547087602444, 0.5003270373238773);
+	let k = (PI * f0 / rate).tan();
+	let a0 = 1.0 + k / q + k * k;
+
+	let pass = Biquad {
+		b: [1.0, -2.0, 1.0],
+		a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+		z: [0.0; 2],
+	};
+
+	[shelf, pass]
+}
+
+// Surround channels count more, the LFE doesn't count at all. Only the usual
+// 5.1 and 7.1 orders are recognized, everything else is weighted equally.
+fn weight(channel: usize, channels: usize) -> f64 {
+	match (channels, channel) {
+		(6, 3) | (8, 3) => 0.0,
+		(6, 4..=5) | (8, 4..=7) => 1.41,
+		_ => 1.0,
+	}
+}
+
+fn loudness(power: f64) -> f64 {
+	-0.691 + 10.0 * power.log10()
+}
+
+fn blocks(segments: &[f64], length: usize) -> Vec<f64> {
+	segments
+		.windows(length)
+		.map(|window| window.iter().sum::<f64>() / length as f64)
+		.collect()
+}
+
+impl Loudness {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, index: usize, options: &Options) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Audio {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut filters = Vec::<[Biquad; 2]>::new();
+		let mut energy = Vec::<f64>::new();
+		let mut position = 0;
+		let mut segments = Vec::new();
+		let mut peak = 0f32;
+
+		super::audio(input, index, options, |frame, _| {
+			let rate = frame.rate() as usize;
+			let channels = samples::channels(frame);
+
+			if rate == 0 || channels.is_empty() {
+				return Ok(());
+			}
+
+			if filters.len() != channels.len() {
+				filters = vec![k_weighting(rate as f64); channels.len()];
+				energy = vec![0.0; channels.len()];
+			}
+
+			let length = rate / 10;
+
+			for i in 0..frame.samples() {
+				for (c, channel) in channels.iter().enumerate() {
+					peak = peak.max(channel[i].abs());
+
+					let [shelf, pass] = &mut filters[c];
+					let y = pass.process(shelf.process(f64::from(channel[i])));
+					energy[c] += y * y;
+				}
+
+				position += 1;
+
+				if position == length {
+					let total = channels.len();
+					let power = energy
+						.iter()
+						.enumerate()
+						.map(|(c, e)| weight(c, total) * e / length as f64)
+						.sum::<f64>();
+
+					segments.push(power);
+					energy.iter_mut().for_each(|e| *e = 0.0);
+					position = 0;
+				}
+			}
+
+			Ok(())
+		})?;
+
+		Ok(Loudness {
+			integrated: integrated(&blocks(&segments, MOMENTARY)),
+			range: range(&blocks(&segments, SHORT_TERM)),
+			sample_peak: Some(peak)
+				.filter(|&peak| peak > 0.0)
+				.map(|peak| 20.0 * f64::from(peak).log10()),
+		})
+	}
+
+	// The peak is a sample peak, inter-sample peaks can be up to a few dB
+	// higher so a limiter is suggested before the target is actually reached.
+	pub fn normalize(&self, target: &Target) -> Option<Normalization> {
+		let gain = target.integrated - self.integrated?;
+		let peak = self.sample_peak.map(|peak| peak + gain);
+		let headroom = peak.map(|peak| target.peak - peak);
+
+		Some(Normalization {
+			gain,
+			peak,
+			headroom,
+			needs_limiter: headroom.map_or(false, |headroom| headroom < 0.5),
+		})
+	}
+}
+
+fn integrated(blocks: &[f64]) -> Option<f64> {
+	let gated = blocks
+		.iter()
+		.copied()
+		.filter(|&power| loudness(power) > ABSOLUTE_GATE)
+		.collect::<Vec<_>>();
+
+	if gated.is_empty() {
+		return None;
+	}
+
+	let relative = loudness(gated.iter().sum::<f64>() / gated.len() as f64) - 10.0;
+	let gated = gated
+		.into_iter()
+		.filter(|&power| loudness(power) > relative)
+		.collect::<Vec<_>>();
+
+	if gated.is_empty() {
+		return None;
+	}
+
+	Some(loudness(gated.iter().sum::<f64>() / gated.len() as f64))
+}
+
+fn range(blocks: &[f64]) -> Option<f64> {
+	let gated = blocks
+		.iter()
+		.copied()
+		.filter(|&power| loudness(power) > ABSOLUTE_GATE)
+		.collect::<Vec<_>>();
+
+	if gated.is_empty() {
+		return None;
+	}
+
+	let relative = loudness(gated.iter().sum::<f64>() / gated.len() as f64) - 20.0;
+	let mut values = gated
+		.into_iter()
+		.map(loudness)
+		.filter(|&value| value > relative)
+		.collect::<Vec<_>>();
+
+	if values.is_empty() {
+		return None;
+	}
+
+	values.sort_by(f64::total_cmp);
+
+	let percentile = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+	Some(percentile(0.95) - percentile(0.10))
+}
@@ -0,0 +1,406 @@
+use std::{mem, ops::DerefMut};
+use ffmpeg::{decoder, ffi, format::context::Input, frame, Packet, Rational};
+use serde::{Deserialize, Serialize};
+
+use crate::compat;
+
+pub(crate) mod picture;
+mod samples;
+
+mod progress;
+pub use self::progress::{CancellationToken, Progress, ProgressCallback};
+pub(crate) use self::progress::Ticker;
+
+mod captions;
+
+pub mod frame_rate;
+pub use self::frame_rate::{FrameDuration, FrameRate, Mode};
+
+pub mod silence;
+pub use self::silence::{Silence, SilenceThresholds};
+
+pub mod black;
+pub use self::black::{Black, BlackThresholds};
+
+pub mod freeze;
+pub use self::freeze::{Freeze, FreezeThresholds};
+
+pub mod interlacing;
+pub use self::interlacing::{Interlacing, Scan};
+
+pub mod frame_size;
+pub use self::frame_size::{FrameSizes, Outlier, OutlierThresholds, Percentiles};
+
+pub mod packet_stats;
+pub use self::packet_stats::{PacketStats, StreamPackets};
+
+pub mod keyframes;
+pub use self::keyframes::{IndexSource, Keyframe, KeyframeIndex, StreamKeyframes};
+
+pub mod priming;
+pub use self::priming::Priming;
+
+pub mod range;
+pub use self::range::Range;
+
+pub mod quality;
+pub use self::quality::Quality;
+
+pub mod defects;
+pub use self::defects::{DefectThresholds, Defects};
+
+pub mod ad_breaks;
+pub use self::ad_breaks::AdBreaks;
+
+pub mod loudness;
+pub use self::loudness::{Loudness, Normalization, Target};
+
+pub mod scenes;
+pub use self::scenes::{SceneThresholds, Scenes};
+
+pub mod exact_duration;
+pub use self::exact_duration::ExactDuration;
+
+pub mod estimate;
+pub use self::estimate::{Estimate, EstimateMethod, StreamEstimate};
+
+pub mod alignment;
+pub use self::alignment::{Alignment, AlignmentThresholds};
+
+pub mod perceptual;
+pub use self::perceptual::{PerceptualHash, PerceptualOptions};
+
+#[cfg(feature = "chromaprint")]
+pub mod fingerprint;
+#[cfg(feature = "chromaprint")]
+pub use self::fingerprint::Fingerprint;
+
+#[cfg(feature = "hash")]
+pub mod hash;
+#[cfg(feature = "hash")]
+pub use self::hash::{asset_id, Algorithm, HashOptions, Hashes};
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Options {
+	pub max_frames: Option<usize>,
+	#[serde(skip)]
+	pub progress: Option<ProgressCallback>,
+	#[serde(skip)]
+	pub cancel: Option<CancellationToken>,
+	// Seconds from the start of the input, the scan seeks to the start and
+	// stops past the end.
+	pub interval: Option<Interval>,
+}
+
+impl Options {
+	pub fn between(start: f64, end: f64) -> Self {
+		Options {
+			interval: Some(Interval { start, end }),
+			..Default::default()
+		}
+	}
+
+	pub(crate) fn ticker(&self, input: &Input) -> Ticker {
+		Ticker::new(input, self.interval, self.progress.as_ref(), self.cancel.as_ref())
+	}
+
+	pub(crate) fn window(&self, input: &mut Input) -> ffmpeg::Result<Window> {
+		window(input, self.interval)
+	}
+}
+
+// The part of the input a scan looks at, in seconds as timestamps are.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Window {
+	start: f64,
+	end: f64,
+}
+
+impl Window {
+	pub fn before(&self, time: Option<f64>) -> bool {
+		time.map_or(false, |time| time < self.start)
+	}
+
+	pub fn after(&self, time: Option<f64>) -> bool {
+		time.map_or(false, |time| time > self.end)
+	}
+}
+
+// Seeks to the keyframe before the start of `interval`, or the beginning.
+pub(crate) fn window(input: &mut Input, interval: Option<Interval>) -> ffmpeg::Result<Window> {
+	let interval = match interval {
+		Some(interval) => interval,
+		None => {
+			input.seek(0, ..)?;
+
+			return Ok(Window {
+				start: f64::NEG_INFINITY,
+				end: f64::INFINITY,
+			});
+		}
+	};
+
+	let offset = start_time(input);
+	let position = ((offset + interval.start) * f64::from(ffi::AV_TIME_BASE)) as i64;
+	input.seek(position, ..position)?;
+
+	Ok(Window {
+		start: offset + interval.start,
+		end: offset + interval.end,
+	})
+}
+
+pub(crate) fn start_time(input: &Input) -> f64 {
+	match unsafe { (*input.as_ptr()).start_time } {
+		ffi::AV_NOPTS_VALUE => 0.0,
+		start => start as f64 / f64::from(ffi::AV_TIME_BASE),
+	}
+}
+
+pub(crate) fn time(packet: &Packet, time_base: Rational) -> Option<f64> {
+	packet.pts().or_else(|| packet.dts()).map(|timestamp| seconds(timestamp, time_base))
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Interval {
+	pub start: f64,
+	pub end: f64,
+}
+
+impl Interval {
+	pub fn duration(&self) -> f64 {
+		self.end - self.start
+	}
+}
+
+pub(crate) fn seconds(timestamp: i64, time_base: Rational) -> f64 {
+	timestamp as f64 * f64::from(time_base)
+}
+
+pub(crate) fn stream(
+	input: &Input,
+	index: usize,
+) -> ffmpeg::Result<ffmpeg::format::stream::Stream> {
+	input.stream(index).ok_or(ffmpeg::Error::StreamNotFound)
+}
+
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "trace", skip_all, fields(index = index))
+)]
+pub(crate) fn packets<F>(
+	input: &mut Input,
+	index: usize,
+	options: &Options,
+	mut f: F,
+) -> ffmpeg::Result<()>
+where
+	F: FnMut(&Packet, Rational) -> ffmpeg::Result<()>,
+{
+	let window = options.window(input)?;
+
+	let mut ticker = options.ticker(input);
+	let mut count = 0;
+	for (stream, packet) in input.packets() {
+		ticker.packet(&packet, stream.time_base())?;
+
+		if stream.index() != index {
+			continue;
+		}
+
+		let time = time(&packet, stream.time_base());
+		if window.before(time) {
+			continue;
+		}
+
+		if options.max_frames.map_or(false, |max| count >= max) || window.after(time) {
+			break;
+		}
+
+		f(&packet, stream.time_base())?;
+		count += 1;
+	}
+
+	Ok(())
+}
+
+pub(crate) fn audio<F>(
+	input: &mut Input,
+	index: usize,
+	options: &Options,
+	mut f: F,
+) -> ffmpeg::Result<()>
+where
+	F: FnMut(&frame::Audio, Rational) -> ffmpeg::Result<()>,
+{
+	let (mut decoder, time_base) = {
+		let stream = stream(input, index)?;
+		(compat::decoder(&stream)?.audio()?, stream.time_base())
+	};
+
+	let mut frame = frame::Audio::empty();
+	decode(input, index, options, time_base, &mut decoder, &mut frame, |frame| {
+		f(frame, time_base)
+	})
+}
+
+pub(crate) fn video<F>(
+	input: &mut Input,
+	index: usize,
+	options: &Options,
+	mut f: F,
+) -> ffmpeg::Result<()>
+where
+	F: FnMut(&frame::Video, Rational) -> ffmpeg::Result<()>,
+{
+	let (mut decoder, time_base) = {
+		let stream = stream(input, index)?;
+		(compat::decoder(&stream)?.video()?, stream.time_base())
+	};
+
+	let mut frame = frame::Video::empty();
+	decode(input, index, options, time_base, &mut decoder, &mut frame, |frame| {
+		f(frame, time_base)
+	})
+}
+
+// Decodes the first picture at or after `time`, or the last one in the stream
+// if there's nothing after it.
+pub(crate) fn video_at(
+	input: &mut Input,
+	index: usize,
+	time: f64,
+	ticker: &mut Ticker,
+) -> ffmpeg::Result<Option<frame::Video>> {
+	let (mut decoder, time_base) = {
+		let stream = stream(input, index)?;
+		(compat::decoder(&stream)?.video()?, stream.time_base())
+	};
+
+	let target = (time / f64::from(time_base)) as i64;
+	let position = (time * f64::from(ffi::AV_TIME_BASE)) as i64;
+	input.seek(position, ..position)?;
+
+	// The decoder resets the frame when it has nothing to return, a picture
+	// before the target is moved out so the last one survives.
+	let mut frame = frame::Video::empty();
+	let mut last = None;
+
+	for (stream, packet) in input.packets() {
+		ticker.packet(&packet, stream.time_base())?;
+
+		if stream.index() != index {
+			continue;
+		}
+
+		match decoder.send_packet(&packet) {
+			Ok(()) | Err(ffmpeg::Error::InvalidData) => (),
+			Err(error) => return Err(error),
+		}
+
+		while decoder.receive_frame(&mut frame).is_ok() {
+			if frame.timestamp().map_or(true, |timestamp| timestamp >= target) {
+				return Ok(Some(frame));
+			}
+
+			last = Some(mem::replace(&mut frame, frame::Video::empty()));
+		}
+	}
+
+	decoder.send_eof()?;
+	while decoder.receive_frame(&mut frame).is_ok() {
+		if frame.timestamp().map_or(true, |timestamp| timestamp >= target) {
+			return Ok(Some(frame));
+		}
+
+		last = Some(mem::replace(&mut frame, frame::Video::empty()));
+	}
+
+	Ok(last)
+}
+
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "trace", skip_all, fields(index = index))
+)]
+fn decode<D, T, F>(
+	input: &mut Input,
+	index: usize,
+	options: &Options,
+	time_base: Rational,
+	decoder: &mut D,
+	frame: &mut T,
+	mut f: F,
+) -> ffmpeg::Result<()>
+where
+	D: DerefMut<Target = decoder::Opened>,
+	T: DerefMut<Target = frame::Frame>,
+	F: FnMut(&T) -> ffmpeg::Result<()>,
+{
+	let window = options.window(input)?;
+
+	let mut ticker = options.ticker(input);
+	let mut count = 0;
+	let limit = |count: usize| options.max_frames.map_or(false, |max| count >= max);
+
+	// Decoding starts at the keyframe before the interval, the frames up to
+	// its start are decoded but not looked at.
+	let frame_time = |frame: &T| frame.timestamp().map(|timestamp| seconds(timestamp, time_base));
+
+	for (stream, packet) in input.packets() {
+		ticker.packet(&packet, stream.time_base())?;
+
+		if stream.index() != index {
+			continue;
+		}
+
+		if window.after(time(&packet, time_base)) {
+			break;
+		}
+
+		// Corrupt packets are expected in the wild, the decoder recovers on its
+		// own and the analysis works on whatever it manages to produce.
+		match decoder.send_packet(&packet) {
+			Ok(()) | Err(ffmpeg::Error::InvalidData) => (),
+			Err(error) => return Err(error),
+		}
+
+		while decoder.receive_frame(&mut **frame).is_ok() {
+			if window.before(frame_time(frame)) {
+				continue;
+			}
+
+			if window.after(frame_time(frame)) {
+				return Ok(());
+			}
+
+			f(frame)?;
+			count += 1;
+
+			if limit(count) {
+				return Ok(());
+			}
+		}
+	}
+
+	decoder.send_eof()?;
+	while decoder.receive_frame(&mut **frame).is_ok() {
+		if window.before(frame_time(frame)) {
+			continue;
+		}
+
+		if window.after(frame_time(frame)) {
+			break;
+		}
+
+		f(frame)?;
+		count += 1;
+		if limit(count) {
+			break;
+		}
+	}
+
+	Ok(())
+}
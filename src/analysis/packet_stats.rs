@@ -0,0 +1,108 @@
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+
+// Aggregated `ffprobe -show_packets`, for every stream in a single pass.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PacketStats {
+	pub streams: Vec<StreamPackets>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StreamPackets {
+	pub index: usize,
+	pub packets: usize,
+	pub keyframes: usize,
+	pub bytes: usize,
+	pub min_size: Option<usize>,
+	pub max_size: Option<usize>,
+	pub mean_size: Option<f64>,
+	pub corrupt: usize,
+	// Decoding timestamps going backwards, or skipping ahead by more than a
+	// few packets worth.
+	pub discontinuities: usize,
+	pub missing_timestamps: usize,
+}
+
+impl PacketStats {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, options: &Options) -> ffmpeg::Result<Self> {
+		let mut streams = input
+			.streams()
+			.map(|stream| StreamPackets {
+				index: stream.index(),
+				..Default::default()
+			})
+			.collect::<Vec<_>>();
+
+		let mut last = vec![None; streams.len()];
+		let mut ticker = options.ticker(input);
+		let mut count = 0;
+
+		let window = options.window(input)?;
+		for (stream, packet) in input.packets() {
+			ticker.packet(&packet, stream.time_base())?;
+
+			let time = super::time(&packet, stream.time_base());
+			if window.before(time) {
+				continue;
+			}
+
+			if options.max_frames.map_or(false, |max| count >= max) || window.after(time) {
+				break;
+			}
+
+			let index = stream.index();
+			let stats = match streams.get_mut(index) {
+				Some(stats) => stats,
+				None => continue,
+			};
+
+			let size = packet.size();
+			stats.packets += 1;
+			stats.bytes += size;
+			stats.min_size = Some(stats.min_size.map_or(size, |min| min.min(size)));
+			stats.max_size = Some(stats.max_size.map_or(size, |max| max.max(size)));
+
+			if packet.is_key() {
+				stats.keyframes += 1;
+			}
+
+			if packet.is_corrupt() {
+				stats.corrupt += 1;
+			}
+
+			match (packet.dts(), last[index]) {
+				(None, _) => stats.missing_timestamps += 1,
+
+				(Some(dts), Some((previous, duration))) => {
+					if dts < previous || (duration > 0 && dts - previous > duration * 3) {
+						stats.discontinuities += 1;
+					}
+				}
+
+				_ => (),
+			}
+
+			if let Some(dts) = packet.dts() {
+				last[index] = Some((dts, packet.duration()));
+			}
+
+			count += 1;
+		}
+
+		for stats in &mut streams {
+			let mean = stats.bytes as f64 / stats.packets as f64;
+			stats.mean_size = Some(mean).filter(|_| stats.packets > 0);
+		}
+
+		Ok(PacketStats { streams })
+	}
+
+	pub fn stream(&self, index: usize) -> Option<&StreamPackets> {
+		self.streams.iter().find(|stream| stream.index == index)
+	}
+}
@@ -0,0 +1,200 @@
+use std::f64::consts::PI;
+
+use ffmpeg::{format::context::Input, frame, media};
+use serde::{Deserialize, Serialize};
+
+use super::{picture::Gray, Options};
+use crate::compat;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PerceptualOptions {
+	pub samples: usize,
+}
+
+impl Default for PerceptualOptions {
+	fn default() -> Self {
+		PerceptualOptions { samples: 16 }
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PerceptualHash {
+	pub frames: Vec<Frame>,
+	pub signature: u64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Frame {
+	pub time: f64,
+	pub dhash: u64,
+	pub phash: u64,
+}
+
+struct Hasher {
+	difference: Gray,
+	cosine: Gray,
+}
+
+impl Hasher {
+	fn hash(&mut self, frame: &frame::Video, time: f64) -> ffmpeg::Result<Frame> {
+		Ok(Frame {
+			time,
+			dhash: dhash(&self.difference.convert(frame)?.rows().collect::<Vec<_>>()),
+			phash: phash(&self.cosine.convert(frame)?.rows().collect::<Vec<_>>()),
+		})
+	}
+}
+
+impl PerceptualHash {
+	// Frames are sampled evenly across the stream, so encodes of the same
+	// content hash the same regardless of frame rate or GOP structure.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		perceptual: &PerceptualOptions,
+	) -> ffmpeg::Result<Self> {
+		let (start, duration) = {
+			let stream = super::stream(input, index)?;
+
+			if compat::medium(&stream) != media::Type::Video {
+				return Err(ffmpeg::Error::InvalidData);
+			}
+
+			let start = Some(stream.start_time())
+				.filter(|&ts| ts != ffmpeg::ffi::AV_NOPTS_VALUE)
+				.map_or(0.0, |ts| super::seconds(ts, stream.time_base()));
+
+			let duration = if stream.duration() > 0 {
+				super::seconds(stream.duration(), stream.time_base())
+			}
+			else {
+				input.duration().max(0) as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE)
+			};
+
+			(start, duration)
+		};
+
+		// Samples are spread across the interval instead when there is one.
+		let (from, length) = match options.interval {
+			Some(interval) if duration > 0.0 => {
+				let offset = super::start_time(input);
+				let from = (offset + interval.start).max(start);
+				let to = (offset + interval.end).min(start + duration);
+
+				(from, (to - from).max(0.0))
+			}
+			_ => (start, duration),
+		};
+
+		let mut hasher = Hasher {
+			difference: Gray::exact(9, 8),
+			cosine: Gray::exact(32, 32),
+		};
+
+		let mut frames = Vec::new();
+
+		if length > 0.0 {
+			let mut ticker = options.ticker(input);
+
+			for i in 0..perceptual.samples {
+				let time = from + length * (i as f64 + 0.5) / perceptual.samples as f64;
+
+				if let Some(frame) = super::video_at(input, index, time, &mut ticker)? {
+					frames.push(hasher.hash(&frame, time - start)?);
+				}
+			}
+		}
+		// Without a duration there's no way to spread the samples, the first
+		// frames are better than nothing.
+		else {
+			let mut options = options.clone();
+			options.max_frames = Some(perceptual.samples);
+
+			super::video(input, index, &options, |frame, time_base| {
+				let time = frame.timestamp().map_or(0.0, |ts| super::seconds(ts, time_base));
+				frames.push(hasher.hash(frame, time - start)?);
+
+				Ok(())
+			})?;
+		}
+
+		// Each bit is the majority vote of the sampled frames.
+		let signature = (0..64)
+			.filter(|bit| {
+				frames.iter().filter(|f| (f.phash >> bit) & 1 == 1).count() * 2 > frames.len()
+			})
+			.fold(0u64, |signature, bit| signature | (1 << bit));
+
+		Ok(PerceptualHash { frames, signature })
+	}
+
+	// Average normalized Hamming distance between frames sampled at the same
+	// relative position, 0.0 for identical content, around 0.5 for unrelated.
+	pub fn distance(&self, other: &PerceptualHash) -> Option<f64> {
+		if self.frames.is_empty() || self.frames.len() != other.frames.len() {
+			return None;
+		}
+
+		let total = self
+			.frames
+			.iter()
+			.zip(&other.frames)
+			.map(|(a, b)| f64::from((a.phash ^ b.phash).count_ones()) / 64.0)
+			.sum::<f64>();
+
+		Some(total / self.frames.len() as f64)
+	}
+}
+
+fn dhash(rows: &[&[u8]]) -> u64 {
+	let mut hash = 0;
+
+	for row in rows {
+		for pair in row.windows(2) {
+			hash = (hash << 1) | u64::from(pair[0] < pair[1]);
+		}
+	}
+
+	hash
+}
+
+// The low frequencies of a 32x32 DCT, compared against their median. The DC
+// coefficient is left out since it only carries the average brightness.
+fn phash(rows: &[&[u8]]) -> u64 {
+	let size = rows.len();
+	let cosines = (0..8)
+		.map(|u| {
+			(0..size)
+				.map(|x| ((2 * x + 1) as f64 * u as f64 * PI / (2 * size) as f64).cos())
+				.collect::<Vec<_>>()
+		})
+		.collect::<Vec<_>>();
+
+	let mut coefficients = Vec::with_capacity(64);
+	for vertical in &cosines {
+		for horizontal in &cosines {
+			let mut sum = 0.0;
+
+			for (row, v) in rows.iter().zip(vertical) {
+				for (&pixel, u) in row.iter().zip(horizontal) {
+					sum += f64::from(pixel) * u * v;
+				}
+			}
+
+			coefficients.push(sum);
+		}
+	}
+
+	let mut sorted = coefficients[1..].to_vec();
+	sorted.sort_by(f64::total_cmp);
+	let median = sorted[sorted.len() / 2];
+
+	coefficients
+		.iter()
+		.fold(0, |hash, &coefficient| (hash << 1) | u64::from(coefficient > median))
+}
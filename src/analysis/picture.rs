@@ -0,0 +1,102 @@
+use ffmpeg::{
+	format::Pixel,
+	frame,
+	software::scaling::{self, Flags},
+};
+
+// Converts decoded pictures to a downscaled 8-bit luma plane, which is all
+// the heuristics need and keeps them independent of the source pixel format.
+pub struct Gray {
+	width: u32,
+	height: Option<u32>,
+	scaler: Option<(scaling::Context, Pixel, u32, u32)>,
+	output: frame::Video,
+}
+
+pub struct Plane<'a> {
+	pub width: usize,
+	pub height: usize,
+	pub stride: usize,
+	pub data: &'a [u8],
+}
+
+impl Gray {
+	pub fn new(width: u32) -> Self {
+		Gray {
+			width,
+			height: None,
+			scaler: None,
+			output: frame::Video::empty(),
+		}
+	}
+
+	// Squashes pictures to exactly the given size, ignoring the aspect ratio.
+	pub fn exact(width: u32, height: u32) -> Self {
+		Gray {
+			width,
+			height: Some(height),
+			scaler: None,
+			output: frame::Video::empty(),
+		}
+	}
+
+	pub fn convert(&mut self, frame: &frame::Video) -> ffmpeg::Result<Plane<'_>> {
+		let source = (frame.format(), frame.width(), frame.height());
+		let stale = match &self.scaler {
+			Some((_, format, width, height)) => (*format, *width, *height) != source,
+			None => true,
+		};
+
+		if stale {
+			let (width, height) = match self.height {
+				Some(height) => (self.width, height),
+
+				None => {
+					let width = source.1.min(self.width).max(1);
+					let height = ((u64::from(source.2) * u64::from(width))
+						/ u64::from(source.1.max(1)))
+					.max(1) as u32;
+
+					(width, height)
+				}
+			};
+
+			let context = scaling::Context::get(
+				source.0,
+				source.1,
+				source.2,
+				Pixel::GRAY8,
+				width,
+				height,
+				Flags::FAST_BILINEAR,
+			)?;
+
+			self.scaler = Some((context, source.0, source.1, source.2));
+			self.output = frame::Video::empty();
+		}
+
+		let (context, ..) = self.scaler.as_mut().unwrap();
+		context.run(frame, &mut self.output)?;
+
+		Ok(Plane {
+			width: self.output.width() as usize,
+			height: self.output.height() as usize,
+			stride: self.output.stride(0),
+			data: self.output.data(0),
+		})
+	}
+}
+
+impl<'a> Plane<'a> {
+	pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+		(0..self.height).map(move |y| &self.data[y * self.stride..y * self.stride + self.width])
+	}
+
+	pub fn pixels(&self) -> impl Iterator<Item = u8> + '_ {
+		self.rows().flat_map(|row| row.iter().copied())
+	}
+
+	pub fn area(&self) -> usize {
+		self.width * self.height
+	}
+}
@@ -0,0 +1,171 @@
+use std::convert::TryInto;
+
+use ffmpeg::{codec, format::context::Input, media, packet::side_data};
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+use crate::{compat, raw, tags::itunes};
+
+// Skip samples side data is only ever attached to the first few packets.
+const PACKETS: usize = 16;
+
+// Opus decoders need 80ms of pre-roll to converge after a seek.
+const OPUS_PREROLL: i64 = 3840;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Priming {
+	pub required: Option<i64>,
+	pub roll: Option<i64>,
+	pub signals: Vec<Signal>,
+	pub mismatches: Vec<Mismatch>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Signal {
+	pub source: Source,
+	pub samples: i64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+	PreSkip,
+	InitialPadding,
+	SkipSamples,
+	#[serde(rename = "itunsmpb")]
+	ITunSMPB,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Mismatch {
+	Missing,
+	MissingRoll { expected: i64 },
+	Insufficient { source: Source, samples: i64, required: i64 },
+	Inconsistent { first: Signal, second: Signal },
+}
+
+impl Priming {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, index: usize, options: &Options) -> ffmpeg::Result<Self> {
+		let (id, profile, roll, mut signals) = {
+			let stream = super::stream(input, index)?;
+			let parameters = stream.parameters();
+
+			if compat::medium(&stream) != media::Type::Audio {
+				return Err(ffmpeg::Error::InvalidData);
+			}
+
+			let mut signals = Vec::new();
+
+			if parameters.id() == codec::Id::OPUS {
+				let extradata = raw::extradata(&parameters);
+
+				if extradata.len() >= 12 && &extradata[..8] == b"OpusHead" {
+					signals.push(Signal {
+						source: Source::PreSkip,
+						samples: i64::from(u16::from_le_bytes([extradata[10], extradata[11]])),
+					});
+				}
+			}
+
+			if raw::initial_padding(&parameters) > 0 {
+				signals.push(Signal {
+					source: Source::InitialPadding,
+					samples: i64::from(raw::initial_padding(&parameters)),
+				});
+			}
+
+			if let Some(smpb) = itunes::find_smpb(input, &stream) {
+				signals.push(Signal {
+					source: Source::ITunSMPB,
+					samples: i64::from(smpb.delay),
+				});
+			}
+
+			(
+				parameters.id(),
+				raw::profile(&parameters),
+				i64::from(raw::seek_preroll(&parameters)),
+				signals,
+			)
+		};
+
+		let mut options = options.clone();
+		options.max_frames = Some(options.max_frames.map_or(PACKETS, |max| max.min(PACKETS)));
+
+		let mut skip = None;
+		super::packets(input, index, &options, |packet, _| {
+			for data in packet.side_data() {
+				if data.kind() == side_data::Type::SkipSamples && data.data().len() >= 4 {
+					skip.get_or_insert(i64::from(u32::from_le_bytes(
+						data.data()[..4].try_into().unwrap(),
+					)));
+				}
+			}
+
+			Ok(())
+		})?;
+
+		if let Some(samples) = skip.filter(|&samples| samples > 0) {
+			signals.push(Signal {
+				source: Source::SkipSamples,
+				samples,
+			});
+		}
+
+		let required = match id {
+			// HE-AAC and HE-AACv2 double the delay because of SBR.
+			codec::Id::AAC if profile == 4 || profile == 28 => Some(2048),
+			codec::Id::AAC => Some(1024),
+			codec::Id::MP3 => Some(529),
+			_ => None,
+		};
+
+		let mut mismatches = Vec::new();
+
+		if signals.is_empty() && required.is_some() {
+			mismatches.push(Mismatch::Missing);
+		}
+
+		if id == codec::Id::OPUS && roll < OPUS_PREROLL {
+			mismatches.push(Mismatch::MissingRoll {
+				expected: OPUS_PREROLL,
+			});
+		}
+
+		if let Some(required) = required {
+			for signal in &signals {
+				if signal.samples < required {
+					mismatches.push(Mismatch::Insufficient {
+						source: signal.source,
+						samples: signal.samples,
+						required,
+					});
+				}
+			}
+		}
+
+		for (i, first) in signals.iter().enumerate() {
+			for second in &signals[i + 1..] {
+				if first.samples != second.samples {
+					mismatches.push(Mismatch::Inconsistent {
+						first: first.clone(),
+						second: second.clone(),
+					});
+				}
+			}
+		}
+
+		Ok(Priming {
+			required,
+			roll: if roll > 0 { Some(roll) } else { None },
+			signals,
+			mismatches,
+		})
+	}
+}
@@ -0,0 +1,126 @@
+use std::{
+	fmt,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+};
+
+use ffmpeg::{ffi, format::context::Input, Packet, Rational};
+use serde::{Deserialize, Serialize};
+
+use super::Interval;
+
+// Progress is reported at most this often, in seconds of input.
+const INTERVAL: f64 = 1.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Progress {
+	// Seconds into the scan, from the start of the input or its interval.
+	pub time: f64,
+	// Only for inputs with a known duration.
+	pub fraction: Option<f64>,
+}
+
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressCallback {
+	pub fn new<F: Fn(Progress) + Send + Sync + 'static>(f: F) -> Self {
+		ProgressCallback(Arc::new(f))
+	}
+}
+
+impl fmt::Debug for ProgressCallback {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("ProgressCallback")
+	}
+}
+
+// Cancelling stops the scan at the next packet, which then fails with
+// `ffmpeg::Error::Exit`.
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+	pub fn new() -> Self {
+		CancellationToken::default()
+	}
+
+	pub fn cancel(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+pub(crate) struct Ticker<'a> {
+	progress: Option<&'a ProgressCallback>,
+	cancel: Option<&'a CancellationToken>,
+	start: f64,
+	duration: Option<f64>,
+	last: Option<f64>,
+}
+
+impl<'a> Ticker<'a> {
+	// Progress is relative to `interval` when there is one.
+	pub fn new(
+		input: &Input,
+		interval: Option<Interval>,
+		progress: Option<&'a ProgressCallback>,
+		cancel: Option<&'a CancellationToken>,
+	) -> Self {
+		let start = super::start_time(input);
+		let duration = Some(input.duration())
+			.filter(|&duration| duration > 0)
+			.map(|duration| duration as f64 / f64::from(ffi::AV_TIME_BASE));
+
+		// An interval running past the end is only as long as what's left.
+		let duration = match interval {
+			Some(interval) => {
+				let end = duration.map_or(interval.end, |duration| duration.min(interval.end));
+				Some(end - interval.start).filter(|&duration| duration > 0.0)
+			}
+
+			None => duration,
+		};
+
+		Ticker {
+			progress,
+			cancel,
+			start: start + interval.map_or(0.0, |interval| interval.start),
+			duration,
+			last: None,
+		}
+	}
+
+	pub fn tick(&mut self, time: Option<f64>) -> ffmpeg::Result<()> {
+		if self.cancel.map_or(false, CancellationToken::is_cancelled) {
+			return Err(ffmpeg::Error::Exit);
+		}
+
+		let (progress, time) = match (self.progress, time) {
+			(Some(progress), Some(time)) => (progress, (time - self.start).max(0.0)),
+			_ => return Ok(()),
+		};
+
+		if self.last.map_or(false, |last| time >= last && time - last < INTERVAL) {
+			return Ok(());
+		}
+
+		self.last = Some(time);
+		(progress.0)(Progress {
+			time,
+			fraction: self.duration.map(|duration| (time / duration).clamp(0.0, 1.0)),
+		});
+
+		Ok(())
+	}
+
+	pub fn packet(&mut self, packet: &Packet, time_base: Rational) -> ffmpeg::Result<()> {
+		self.tick(packet.dts().or_else(|| packet.pts()).map(|ts| super::seconds(ts, time_base)))
+	}
+}
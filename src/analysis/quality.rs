@@ -0,0 +1,181 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{
+	picture::{Gray, Plane},
+	Options,
+};
+use crate::compat;
+
+// Number of frames sampled when no explicit limit is given.
+const SAMPLES: usize = 300;
+
+// Only every few frames is scored, neighbouring frames look the same anyway.
+const STRIDE: usize = 5;
+
+// Block size used by practically every DCT based codec.
+const BLOCK: usize = 8;
+
+// Tiles looked at for banding, and the biggest step still considered smooth.
+const TILE: usize = 16;
+const SMOOTH: i16 = 3;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Quality {
+	pub frames: usize,
+	pub blockiness: Score,
+	pub banding: Score,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Score {
+	pub mean: f64,
+	pub max: f64,
+	pub worst: Option<f64>,
+}
+
+impl Quality {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, index: usize, options: &Options) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut options = options.clone();
+		options.max_frames.get_or_insert(SAMPLES * STRIDE);
+
+		// Block boundaries are only visible at the coded resolution.
+		let mut gray = Gray::new(u32::MAX);
+		let mut decoded = 0;
+		let mut quality = Quality {
+			frames: 0,
+			blockiness: Score::default(),
+			banding: Score::default(),
+		};
+
+		super::video(input, index, &options, |frame, time_base| {
+			decoded += 1;
+			if (decoded - 1) % STRIDE != 0 {
+				return Ok(());
+			}
+
+			let time = frame
+				.timestamp()
+				.or_else(|| frame.pts())
+				.map(|timestamp| super::seconds(timestamp, time_base));
+
+			let plane = gray.convert(frame)?;
+			quality.blockiness.add(blockiness(&plane), time);
+			quality.banding.add(banding(&plane), time);
+			quality.frames += 1;
+
+			Ok(())
+		})?;
+
+		if quality.frames > 0 {
+			quality.blockiness.mean /= quality.frames as f64;
+			quality.banding.mean /= quality.frames as f64;
+		}
+
+		Ok(quality)
+	}
+}
+
+impl Score {
+	fn add(&mut self, value: f64, time: Option<f64>) {
+		self.mean += value;
+
+		if value > self.max || self.worst.is_none() {
+			self.max = self.max.max(value);
+			self.worst = time;
+		}
+	}
+}
+
+// Compares the gradient across block boundaries with the one inside blocks,
+// unfiltered block edges make the former stand out.
+fn blockiness(plane: &Plane) -> f64 {
+	let rows = plane.rows().collect::<Vec<_>>();
+	let mut edge = (0u64, 0u64);
+	let mut inner = (0u64, 0u64);
+
+	let mut add = |x: usize, difference: u64| {
+		if x % BLOCK == BLOCK - 1 {
+			edge.0 += difference;
+			edge.1 += 1;
+		}
+		else {
+			inner.0 += difference;
+			inner.1 += 1;
+		}
+	};
+
+	for y in 0..rows.len() {
+		for x in 0..plane.width.saturating_sub(1) {
+			add(x, u64::from((i16::from(rows[y][x]) - i16::from(rows[y][x + 1])).unsigned_abs()));
+
+			if y + 1 < rows.len() {
+				add(y, u64::from((i16::from(rows[y][x]) - i16::from(rows[y + 1][x])).unsigned_abs()));
+			}
+		}
+	}
+
+	if edge.1 == 0 || inner.1 == 0 {
+		return 0.0;
+	}
+
+	let edge = edge.0 as f64 / edge.1 as f64;
+	let inner = inner.0 as f64 / inner.1 as f64;
+
+	((edge + 1.0) / (inner + 1.0) - 1.0).max(0.0)
+}
+
+// Looks for smooth tiles made of flat runs separated by sparse single level
+// steps, which is what a quantized gradient looks like.
+fn banding(plane: &Plane) -> f64 {
+	let rows = plane.rows().collect::<Vec<_>>();
+	let mut smooth = 0;
+	let mut banded = 0;
+
+	for ty in (0..rows.len().saturating_sub(TILE - 1)).step_by(TILE) {
+		for tx in (0..plane.width.saturating_sub(TILE)).step_by(TILE) {
+			let mut steps = 0;
+			let mut rough = false;
+
+			'tile: for row in &rows[ty..ty + TILE] {
+				for pair in row[tx..=tx + TILE].windows(2) {
+					let difference = (i16::from(pair[0]) - i16::from(pair[1])).abs();
+
+					if difference > SMOOTH {
+						rough = true;
+						break 'tile;
+					}
+
+					if difference != 0 {
+						steps += 1;
+					}
+				}
+			}
+
+			if rough {
+				continue;
+			}
+
+			smooth += 1;
+
+			let ratio = steps as f64 / (TILE * TILE) as f64;
+			if ratio > 0.0 && ratio < 0.1 {
+				banded += 1;
+			}
+		}
+	}
+
+	if smooth == 0 {
+		0.0
+	}
+	else {
+		banded as f64 / smooth as f64
+	}
+}
@@ -0,0 +1,217 @@
+use ffmpeg::{color, ffi, format::context::Input, frame, media};
+use serde::{Deserialize, Serialize};
+
+use super::Options;
+use crate::compat;
+
+// Number of frames sampled when no explicit limit is given.
+const SAMPLES: usize = 250;
+
+// Fraction of out of range samples for a frame to count as a violation, to
+// ignore ringing around sharp edges.
+const VIOLATION: f64 = 0.001;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Range {
+	pub tagged: Tagged,
+	pub frames: usize,
+	pub luma: Levels,
+	pub chroma: Levels,
+	pub violations: usize,
+	pub verdict: Verdict,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Tagged {
+	Unspecified,
+	Limited,
+	Full,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Levels {
+	pub min: u16,
+	pub max: u16,
+	pub below: u64,
+	pub above: u64,
+	pub total: u64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+	Consistent,
+	FullInLimited,
+	LimitedInFull,
+	Unsupported,
+}
+
+impl Range {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(input: &mut Input, index: usize, options: &Options) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut options = options.clone();
+		options.max_frames.get_or_insert(SAMPLES);
+
+		let mut tagged = Tagged::Unspecified;
+		let mut supported = true;
+		let mut frames = 0;
+		let mut violations = 0;
+		let mut luma = Levels::default();
+		let mut chroma = Levels::default();
+
+		super::video(input, index, &options, |frame, _| {
+			tagged = match frame.color_range() {
+				color::Range::MPEG => Tagged::Limited,
+				color::Range::JPEG => Tagged::Full,
+				_ => Tagged::Unspecified,
+			};
+
+			let depth = match layout(frame) {
+				Some(depth) => depth,
+				None => {
+					supported = false;
+					return Ok(());
+				}
+			};
+
+			// Nominal limited range is 16-235 for luma and 16-240 for chroma at
+			// 8 bits, scaled up for higher bit depths.
+			let shift = depth - 8;
+			let mut current = Levels::default();
+			let mut other = Levels::default();
+
+			scan(frame, 0, depth, 16 << shift, 235 << shift, &mut current);
+			for plane in 1..3.min(frame.planes()) {
+				scan(frame, plane, depth, 16 << shift, 240 << shift, &mut other);
+			}
+
+			let outside = (current.below + current.above) as f64 / current.total.max(1) as f64;
+			if tagged != Tagged::Full && outside > VIOLATION {
+				violations += 1;
+			}
+
+			luma.merge(&current);
+			chroma.merge(&other);
+			frames += 1;
+
+			Ok(())
+		})?;
+
+		let verdict = if !supported && frames == 0 {
+			Verdict::Unsupported
+		}
+		else if tagged != Tagged::Full && violations > 0 {
+			Verdict::FullInLimited
+		}
+		else if tagged == Tagged::Full && luma.total > 0 && luma.below == 0 && luma.above == 0 {
+			// Full range content never touching the limited range extremes was
+			// most likely encoded as limited and tagged wrong.
+			Verdict::LimitedInFull
+		}
+		else {
+			Verdict::Consistent
+		};
+
+		Ok(Range {
+			tagged,
+			frames,
+			luma,
+			chroma,
+			violations,
+			verdict,
+		})
+	}
+}
+
+impl Levels {
+	fn merge(&mut self, other: &Levels) {
+		if other.total == 0 {
+			return;
+		}
+
+		if self.total == 0 {
+			self.min = other.min;
+			self.max = other.max;
+		}
+		else {
+			self.min = self.min.min(other.min);
+			self.max = self.max.max(other.max);
+		}
+
+		self.below += other.below;
+		self.above += other.above;
+		self.total += other.total;
+	}
+}
+
+// Only planar YUV is inspected directly, any conversion would clip the very
+// values being looked for.
+fn layout(frame: &frame::Video) -> Option<u16> {
+	unsafe {
+		let descriptor = ffi::av_pix_fmt_desc_get(frame.format().into());
+
+		if descriptor.is_null() {
+			return None;
+		}
+
+		let flags = (*descriptor).flags;
+		let rgb = flags & ffi::AV_PIX_FMT_FLAG_RGB as u64 != 0;
+		let planar = flags & ffi::AV_PIX_FMT_FLAG_PLANAR as u64 != 0;
+		let depth = (*descriptor).comp[0].depth as u16;
+
+		if rgb || !planar || (*descriptor).nb_components < 3 || !(8..=16).contains(&depth) {
+			return None;
+		}
+
+		Some(depth)
+	}
+}
+
+fn scan(frame: &frame::Video, plane: usize, depth: u16, low: u16, high: u16, levels: &mut Levels) {
+	let width = frame.plane_width(plane) as usize;
+	let height = frame.plane_height(plane) as usize;
+	let stride = frame.stride(plane);
+	let data = frame.data(plane);
+	let wide = depth > 8;
+
+	let mut min = u16::MAX;
+	let mut max = 0;
+
+	for y in 0..height {
+		let row = &data[y * stride..];
+
+		for x in 0..width {
+			let value = if wide {
+				u16::from_le_bytes([row[x * 2], row[x * 2 + 1]])
+			}
+			else {
+				u16::from(row[x])
+			};
+
+			min = min.min(value);
+			max = max.max(value);
+
+			if value < low {
+				levels.below += 1;
+			}
+			else if value > high {
+				levels.above += 1;
+			}
+		}
+	}
+
+	if width * height > 0 {
+		levels.min = if levels.total == 0 { min } else { levels.min.min(min) };
+		levels.max = levels.max.max(max);
+		levels.total += (width * height) as u64;
+	}
+}
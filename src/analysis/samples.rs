@@ -0,0 +1,67 @@
+use std::convert::TryInto;
+use ffmpeg::{
+	format::{sample::Type, Sample},
+	frame,
+};
+
+// Converts any sample format to per channel normalized floats.
+pub fn channels(frame: &frame::Audio) -> Vec<Vec<f32>> {
+	let mut output = vec![Vec::with_capacity(frame.samples()); frame.channels() as usize];
+
+	match frame.format() {
+		Sample::None => (),
+
+		Sample::U8(kind) => read(frame, kind, 1, &mut output, |b| (f32::from(b[0]) - 128.0) / 128.0),
+
+		Sample::I16(kind) => read(frame, kind, 2, &mut output, |b| {
+			f32::from(i16::from_ne_bytes([b[0], b[1]])) / 32_768.0
+		}),
+
+		Sample::I32(kind) => read(frame, kind, 4, &mut output, |b| {
+			i32::from_ne_bytes(b.try_into().unwrap()) as f32 / 2_147_483_648.0
+		}),
+
+		Sample::I64(kind) => read(frame, kind, 8, &mut output, |b| {
+			i64::from_ne_bytes(b.try_into().unwrap()) as f32 / 9_223_372_036_854_775_808.0
+		}),
+
+		Sample::F32(kind) => read(frame, kind, 4, &mut output, |b| {
+			f32::from_ne_bytes(b.try_into().unwrap())
+		}),
+
+		Sample::F64(kind) => read(frame, kind, 8, &mut output, |b| {
+			f64::from_ne_bytes(b.try_into().unwrap()) as f32
+		}),
+	}
+
+	output
+}
+
+fn read<F>(frame: &frame::Audio, kind: Type, size: usize, output: &mut [Vec<f32>], convert: F)
+where
+	F: Fn(&[u8]) -> f32,
+{
+	let samples = frame.samples();
+	let channels = output.len();
+
+	if channels == 0 {
+		return;
+	}
+
+	match kind {
+		Type::Packed => {
+			let data = &frame.data(0)[..samples * channels * size];
+
+			for (i, sample) in data.chunks_exact(size).enumerate() {
+				output[i % channels].push(convert(sample));
+			}
+		}
+
+		Type::Planar => {
+			for (i, channel) in output.iter_mut().enumerate() {
+				let data = &frame.data(i)[..samples * size];
+				channel.extend(data.chunks_exact(size).map(&convert));
+			}
+		}
+	}
+}
@@ -0,0 +1,114 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{picture::Gray, Interval, Options};
+use crate::compat;
+
+const WIDTH: u32 = 160;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SceneThresholds {
+	pub score: f64,
+	pub min_duration: f64,
+}
+
+impl Default for SceneThresholds {
+	fn default() -> Self {
+		SceneThresholds {
+			score: 0.1,
+			min_duration: 0.5,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Scenes {
+	pub cuts: Vec<Cut>,
+	pub shots: Vec<Interval>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Cut {
+	pub time: f64,
+	pub score: f64,
+}
+
+impl Scenes {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		thresholds: &SceneThresholds,
+	) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Video {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let mut gray = Gray::new(WIDTH);
+		let mut previous = Vec::new();
+		let mut difference = 0.0;
+		let mut first = None;
+		let mut last = 0.0;
+		let mut cuts = Vec::<Cut>::new();
+
+		super::video(input, index, options, |frame, time_base| {
+			let time = match frame.timestamp().or_else(|| frame.pts()) {
+				Some(timestamp) => super::seconds(timestamp, time_base),
+				None => last,
+			};
+
+			first.get_or_insert(time);
+			last = time;
+
+			let current = gray.convert(frame)?.pixels().collect::<Vec<_>>();
+
+			if previous.len() == current.len() && !current.is_empty() {
+				let mean = current
+					.iter()
+					.zip(&previous)
+					.map(|(&a, &b)| f64::from((i16::from(a) - i16::from(b)).unsigned_abs()))
+					.sum::<f64>()
+					/ (current.len() as f64 * 255.0);
+
+				// Same scoring as FFmpeg's scene detection, the change in the
+				// difference keeps fast motion and pans from looking like cuts.
+				let score = (mean - difference).abs().min(mean);
+				difference = mean;
+
+				let spaced = cuts
+					.last()
+					.map_or(true, |cut| time - cut.time >= thresholds.min_duration);
+
+				if score >= thresholds.score && spaced {
+					cuts.push(Cut { time, score });
+				}
+			}
+
+			previous = current;
+
+			Ok(())
+		})?;
+
+		let mut shots = Vec::new();
+		if let Some(first) = first {
+			let mut start = first;
+
+			for cut in &cuts {
+				shots.push(Interval {
+					start,
+					end: cut.time,
+				});
+
+				start = cut.time;
+			}
+
+			shots.push(Interval { start, end: last });
+		}
+
+		Ok(Scenes { cuts, shots })
+	}
+}
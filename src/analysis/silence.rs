@@ -0,0 +1,108 @@
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use super::{samples, Interval, Options};
+use crate::compat;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SilenceThresholds {
+	pub noise: f64,
+	pub duration: f64,
+}
+
+impl Default for SilenceThresholds {
+	fn default() -> Self {
+		SilenceThresholds {
+			noise: -60.0,
+			duration: 2.0,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Silence {
+	pub leading: Option<Interval>,
+	pub trailing: Option<Interval>,
+	pub intervals: Vec<Interval>,
+}
+
+impl Silence {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &Options,
+		thresholds: &SilenceThresholds,
+	) -> ffmpeg::Result<Self> {
+		if compat::medium(&super::stream(input, index)?) != media::Type::Audio {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let noise = 10f64.powf(thresholds.noise / 20.0) as f32;
+		let mut first = None;
+		let mut position = 0.0;
+		let mut start = None;
+		let mut intervals = Vec::new();
+
+		super::audio(input, index, options, |frame, time_base| {
+			let rate = f64::from(frame.rate());
+			if rate <= 0.0 {
+				return Ok(());
+			}
+
+			if let Some(timestamp) = frame.timestamp().or_else(|| frame.pts()) {
+				position = super::seconds(timestamp, time_base);
+			}
+
+			first.get_or_insert(position);
+
+			let channels = samples::channels(frame);
+			for i in 0..frame.samples() {
+				let time = position + i as f64 / rate;
+				let silent = channels.iter().all(|channel| channel[i].abs() < noise);
+
+				match (silent, start) {
+					(true, None) => start = Some(time),
+
+					(false, Some(from)) => {
+						if time - from >= thresholds.duration {
+							intervals.push(Interval { start: from, end: time });
+						}
+
+						start = None;
+					}
+
+					_ => (),
+				}
+			}
+
+			position += frame.samples() as f64 / rate;
+			Ok(())
+		})?;
+
+		let mut silence = Silence {
+			leading: None,
+			trailing: None,
+			intervals,
+		};
+
+		if let Some(from) = start {
+			if position - from >= thresholds.duration {
+				let interval = Interval { start: from, end: position };
+
+				silence.intervals.push(interval);
+				silence.trailing = Some(interval);
+			}
+		}
+
+		if let (Some(first), Some(interval)) = (first, silence.intervals.first()) {
+			if interval.start <= first {
+				silence.leading = Some(*interval);
+			}
+		}
+
+		Ok(silence)
+	}
+}
@@ -0,0 +1,101 @@
+use std::{
+	ffi::CString,
+	io::{self, Read, Seek, SeekFrom},
+	ptr,
+	rc::Rc,
+};
+use ffmpeg::{ffi, Dictionary};
+
+use crate::probe::{self, Limits};
+
+// Byte level access to whatever FFmpeg can open, so container parsing that
+// FFmpeg doesn't do works the same on local files and network URLs.
+pub struct Avio {
+	context: *mut ffi::AVIOContext,
+	// The interrupt callback points into these, so they live as long as the
+	// context does.
+	limits: Option<Rc<Limits>>,
+}
+
+impl Avio {
+	pub fn open(url: &str) -> ffmpeg::Result<Self> {
+		let url = CString::new(url).map_err(|_| ffmpeg::Error::InvalidData)?;
+		let mut context = ptr::null_mut();
+		let limits = probe::current();
+		let interrupt = limits.as_ref().map(|limits| limits.interrupt());
+		let mut dictionary = limits.as_ref().map_or(ptr::null_mut(), |l| l.dictionary().disown());
+
+		unsafe {
+			let result = ffi::avio_open2(
+				&mut context,
+				url.as_ptr(),
+				ffi::AVIO_FLAG_READ as i32,
+				interrupt.as_ref().map_or(ptr::null(), |interrupt| interrupt as *const _),
+				&mut dictionary,
+			);
+
+			if !dictionary.is_null() {
+				Dictionary::own(dictionary);
+			}
+
+			match result {
+				error if error < 0 => Err(ffmpeg::Error::from(error)),
+				_ => Ok(Avio { context, limits }),
+			}
+		}
+	}
+
+	pub fn size(&self) -> Option<u64> {
+		match unsafe { ffi::avio_size(self.context) } {
+			size if size < 0 => None,
+			size => Some(size as u64),
+		}
+	}
+}
+
+impl Read for Avio {
+	fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+		let length = buffer.len().min(i32::MAX as usize) as i32;
+
+		match unsafe { ffi::avio_read(self.context, buffer.as_mut_ptr(), length) } {
+			ffi::AVERROR_EOF => Ok(0),
+			error if error < 0 => Err(io::Error::new(io::ErrorKind::Other, ffmpeg::Error::from(error))),
+			read if self.limits.as_ref().map_or(true, |limits| limits.consume(read as usize)) => {
+				Ok(read as usize)
+			}
+
+			_ => Err(io::Error::new(io::ErrorKind::Other, ffmpeg::Error::Exit)),
+		}
+	}
+}
+
+impl Seek for Avio {
+	fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+		let (offset, whence) = match position {
+			SeekFrom::Start(offset) => (offset as i64, libc::SEEK_SET),
+			SeekFrom::Current(offset) => (offset, libc::SEEK_CUR),
+			SeekFrom::End(offset) => {
+				let size = self
+					.size()
+					.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "unknown size"))?;
+
+				(size as i64 + offset, libc::SEEK_SET)
+			}
+		};
+
+		match unsafe { ffi::avio_seek(self.context, offset, whence) } {
+			error if error < 0 => {
+				Err(io::Error::new(io::ErrorKind::Other, ffmpeg::Error::from(error as i32)))
+			}
+			position => Ok(position as u64),
+		}
+	}
+}
+
+impl Drop for Avio {
+	fn drop(&mut self) {
+		unsafe {
+			ffi::avio_closep(&mut self.context);
+		}
+	}
+}
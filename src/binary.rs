@@ -0,0 +1,42 @@
+use bincode::{ErrorKind, Options};
+
+use crate::{Metadata, SCHEMA_VERSION};
+
+// The fields that only exist with some features, `created` and `modified`
+// with `chrono`, written ahead of the metadata so builds that disagree on
+// them refuse each other's bytes.
+const LAYOUT: u8 = cfg!(feature = "chrono") as u8;
+
+// Variable length integers, most of the metadata is small numbers and
+// strings. The layout depends on the struct, so there is no upgrading these
+// like JSON documents.
+fn options() -> impl Options {
+	bincode::DefaultOptions::new()
+}
+
+pub(crate) fn to_bytes(metadata: &Metadata) -> bincode::Result<Vec<u8>> {
+	options().serialize(&(LAYOUT, metadata))
+}
+
+pub(crate) fn from_bytes(bytes: &[u8]) -> bincode::Result<Metadata> {
+	// `schema_version` comes right after the layout, both checked alone before
+	// the rest gets misread.
+	let (layout, version): (u8, u32) = options().allow_trailing_bytes().deserialize(bytes)?;
+
+	if version != SCHEMA_VERSION {
+		return Err(Box::new(ErrorKind::Custom(format!(
+			"schema version {} doesn't match {}",
+			version, SCHEMA_VERSION
+		))));
+	}
+
+	if layout != LAYOUT {
+		return Err(Box::new(ErrorKind::Custom(format!(
+			"written {} the chrono feature",
+			if layout != 0 { "with" } else { "without" }
+		))));
+	}
+
+	let (_, metadata): (u8, Metadata) = options().deserialize(bytes)?;
+	Ok(metadata)
+}
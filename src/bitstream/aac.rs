@@ -0,0 +1,241 @@
+use super::bits::Reader;
+
+pub const SAMPLE_RATES: [u32; 13] = [
+	96_000, 88_200, 64_000, 48_000, 44_100, 32_000, 24_000, 22_050, 16_000, 12_000, 11_025, 8_000,
+	7_350,
+];
+
+pub const SBR: u8 = 5;
+pub const PS: u8 = 29;
+
+// What an AudioSpecificConfig (ISO 14496-3 1.6.2.1) or an ADTS header says
+// about the stream.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+	// The core codec, with SBR and PS taken out.
+	pub object_type: u8,
+	pub sample_rate: Option<u32>,
+	pub channels: u8,
+	pub sbr: bool,
+	pub ps: bool,
+	pub extension_sample_rate: Option<u32>,
+}
+
+impl Config {
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		let mut bits = Reader::new(data);
+
+		let mut object_type = object_type(&mut bits)?;
+		let sample_rate = sample_rate(&mut bits)?;
+		let channels = bits.read(4)? as u8;
+
+		let mut config = Config {
+			object_type,
+			sample_rate,
+			channels,
+			sbr: false,
+			ps: false,
+			extension_sample_rate: None,
+		};
+
+		// Explicit, hierarchical signaling.
+		if object_type == SBR || object_type == PS {
+			config.sbr = true;
+			config.ps = object_type == PS;
+			config.extension_sample_rate = self::sample_rate(&mut bits)?;
+
+			object_type = self::object_type(&mut bits)?;
+			config.object_type = object_type;
+
+			if object_type == 22 {
+				bits.skip(4)?;
+			}
+		}
+
+		match object_type {
+			1 | 2 | 3 | 4 | 6 | 7 | 17 | 19 | 20 | 21 | 22 | 23 => {
+				bits.skip(1)?;
+
+				if bits.flag()? {
+					bits.skip(14)?;
+				}
+
+				let extension = bits.flag()?;
+
+				// A program config element would have to be parsed to go on, and
+				// the channel count lives in there too.
+				if channels == 0 {
+					return Some(config);
+				}
+
+				if object_type == 6 || object_type == 20 {
+					bits.skip(3)?;
+				}
+
+				if extension {
+					if object_type == 22 {
+						bits.skip(5 + 11)?;
+					}
+
+					if matches!(object_type, 17 | 19 | 20 | 23) {
+						bits.skip(3)?;
+					}
+
+					bits.skip(1)?;
+				}
+			}
+
+			_ => return Some(config),
+		}
+
+		// Error protection configurations 2 and 3 carry more than we can skip.
+		if matches!(object_type, 17 | 19..=27) && bits.read(2)? >= 2 {
+			return Some(config);
+		}
+
+		// Backward compatible signaling, appended where old decoders stop.
+		if !config.sbr
+			&& bits.remaining() >= 16
+			&& bits.read(11)? == 0x2b7
+			&& self::object_type(&mut bits)? == SBR
+		{
+			config.sbr = bits.flag()?;
+
+			if config.sbr {
+				config.extension_sample_rate = self::sample_rate(&mut bits)?;
+
+				if bits.remaining() >= 12 && bits.read(11)? == 0x548 {
+					config.ps = bits.flag()?;
+				}
+			}
+		}
+
+		Some(config)
+	}
+
+	pub fn adts(data: &[u8]) -> Option<Self> {
+		if data.len() < 7 || data[0] != 0xff || data[1] & 0xf6 != 0xf0 {
+			return None;
+		}
+
+		Some(Config {
+			object_type: (data[2] >> 6) + 1,
+			sample_rate: SAMPLE_RATES.get(usize::from((data[2] >> 2) & 0xf)).copied(),
+			channels: ((data[2] & 0x1) << 2) | (data[3] >> 6),
+			sbr: false,
+			ps: false,
+			extension_sample_rate: None,
+		})
+	}
+}
+
+fn object_type(bits: &mut Reader) -> Option<u8> {
+	match bits.read(5)? {
+		31 => Some(32 + bits.read(6)? as u8),
+		kind => Some(kind as u8),
+	}
+}
+
+fn sample_rate(bits: &mut Reader) -> Option<Option<u32>> {
+	match bits.read(4)? {
+		15 => Some(Some(bits.read(24)?)),
+		index => Some(SAMPLE_RATES.get(index as usize).copied()),
+	}
+}
+
+// The channel configurations after 7 skip numbers, and 0 leaves it to a
+// program config element.
+pub fn channels(configuration: u8) -> Option<u16> {
+	match configuration {
+		1..=6 => Some(u16::from(configuration)),
+		7 | 12 | 14 => Some(8),
+		11 => Some(7),
+		13 => Some(24),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn lc() {
+		let config = Config::parse(&[0x12, 0x10]).unwrap();
+
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.sample_rate, Some(44_100));
+		assert_eq!(config.channels, 2);
+		assert!(!config.sbr);
+		assert!(!config.ps);
+		assert_eq!(config.extension_sample_rate, None);
+	}
+
+	#[test]
+	fn explicit_sbr() {
+		let config = Config::parse(&[0x2b, 0x92, 0x08, 0x00]).unwrap();
+
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.sample_rate, Some(22_050));
+		assert_eq!(config.channels, 2);
+		assert!(config.sbr);
+		assert!(!config.ps);
+		assert_eq!(config.extension_sample_rate, Some(44_100));
+	}
+
+	#[test]
+	fn explicit_ps() {
+		let config = Config::parse(&[0xeb, 0x09, 0x88, 0x00]).unwrap();
+
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.sample_rate, Some(24_000));
+		assert_eq!(config.channels, 1);
+		assert!(config.sbr);
+		assert!(config.ps);
+		assert_eq!(config.extension_sample_rate, Some(48_000));
+	}
+
+	#[test]
+	fn backward_compatible_signaling() {
+		let config = Config::parse(&[0x13, 0x10, 0x56, 0xe5, 0x98]).unwrap();
+
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.sample_rate, Some(24_000));
+		assert!(config.sbr);
+		assert!(!config.ps);
+		assert_eq!(config.extension_sample_rate, Some(48_000));
+
+		let config = Config::parse(&[0x13, 0x10, 0x56, 0xe5, 0x9d, 0x48, 0x80]).unwrap();
+		assert!(config.sbr);
+		assert!(config.ps);
+	}
+
+	#[test]
+	fn escaped_sample_rate() {
+		// LC at 44 kHz, which has no index.
+		let config = Config::parse(&[0x17, 0x80, 0x55, 0xf0, 0x10]).unwrap();
+
+		assert_eq!(config.sample_rate, Some(44_000));
+		assert_eq!(config.channels, 2);
+	}
+
+	#[test]
+	fn adts() {
+		let config = Config::adts(&[0xff, 0xf1, 0x50, 0x80, 0x02, 0x1f, 0xfc]).unwrap();
+
+		assert_eq!(config.object_type, 2);
+		assert_eq!(config.sample_rate, Some(44_100));
+		assert_eq!(config.channels, 2);
+
+		assert!(Config::adts(&[0xff, 0xf1, 0x50]).is_none());
+		assert!(Config::adts(&[0x49, 0x44, 0x33, 0x04, 0x00, 0x00, 0x00]).is_none());
+	}
+
+	#[test]
+	fn channel_configurations() {
+		assert_eq!(channels(2), Some(2));
+		assert_eq!(channels(7), Some(8));
+		assert_eq!(channels(11), Some(7));
+		assert_eq!(channels(0), None);
+	}
+}
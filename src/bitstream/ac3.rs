@@ -0,0 +1,316 @@
+use super::bits::Reader;
+
+const SYNC: [u8; 2] = [0x0b, 0x77];
+
+// Bit rates in kbps, indexed by half the frame size code.
+const BIT_RATES: [u32; 19] = [
+	32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640,
+];
+
+// The bit stream information at the start of an AC-3 or E-AC-3 frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Bsi {
+	pub bsid: u8,
+	// 0 for independent (and all AC-3) frames, 1 for dependent ones and 2
+	// for frames converted from AC-3.
+	pub stream_type: u8,
+	pub substream: u8,
+	pub size: usize,
+	pub acmod: u8,
+	pub lfe: bool,
+	pub bsmod: u8,
+	// Attenuation in dB, 0 is reserved and treated as 31.
+	pub dialnorm: u8,
+	pub dsurmod: Option<u8>,
+	// The two bit AC-3 codes.
+	pub cmixlev: Option<u8>,
+	pub surmixlev: Option<u8>,
+	// The three bit codes of E-AC-3 and the AC-3 alternate syntax.
+	pub ltrtcmixlev: Option<u8>,
+	pub ltrtsurmixlev: Option<u8>,
+	pub lorocmixlev: Option<u8>,
+	pub lorosurmixlev: Option<u8>,
+	// flag_ec3_extension_type_a, which Dolby sets on JOC (Atmos) streams.
+	pub extension: bool,
+}
+
+impl Bsi {
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		if data.len() < 6 || data[..2] != SYNC {
+			return None;
+		}
+
+		// Both syntaxes put the bitstream id at the same place.
+		match data[5] >> 3 {
+			bsid if bsid <= 8 => ac3(data, bsid),
+			bsid if bsid > 10 && bsid <= 16 => eac3(data, bsid),
+			_ => None,
+		}
+	}
+
+	pub fn is_eac3(&self) -> bool {
+		self.bsid > 10
+	}
+}
+
+// Splits a packet into frames, demuxers are free to pack several into one.
+pub fn frames(mut data: &[u8]) -> Vec<Bsi> {
+	let mut frames = Vec::new();
+
+	while let Some(start) = data.windows(2).position(|w| w == SYNC) {
+		data = &data[start..];
+
+		match Bsi::parse(data) {
+			Some(bsi) if bsi.size > 0 && bsi.size <= data.len() => {
+				frames.push(bsi);
+				data = &data[bsi.size..];
+			}
+
+			_ => data = &data[1..],
+		}
+	}
+
+	frames
+}
+
+fn ac3(data: &[u8], bsid: u8) -> Option<Bsi> {
+	let mut bits = Reader::new(data);
+	bits.skip(32)?;
+
+	let fscod = bits.read(2)?;
+	let frmsizecod = bits.read(6)?;
+	let rate = *BIT_RATES.get(frmsizecod as usize / 2)?;
+	let words = match fscod {
+		0 => rate * 2,
+		1 => rate * 320 / 147 + (frmsizecod & 1),
+		2 => rate * 3,
+		_ => return None,
+	};
+
+	bits.skip(5)?;
+	let bsmod = bits.read(3)? as u8;
+	let acmod = bits.read(3)? as u8;
+	let mut code = |present: bool| {
+		if present {
+			bits.read(2).map(|code| Some(code as u8))
+		}
+		else {
+			Some(None)
+		}
+	};
+
+	let cmixlev = code(acmod & 1 != 0 && acmod != 1)?;
+	let surmixlev = code(acmod & 4 != 0)?;
+	let dsurmod = code(acmod == 2)?;
+
+	let mut bsi = Bsi {
+		bsid,
+		stream_type: 0,
+		substream: 0,
+		size: words as usize * 2,
+		acmod,
+		lfe: bits.flag()?,
+		bsmod,
+		dialnorm: bits.read(5)? as u8,
+		dsurmod,
+		cmixlev,
+		surmixlev,
+		ltrtcmixlev: None,
+		ltrtsurmixlev: None,
+		lorocmixlev: None,
+		lorosurmixlev: None,
+		extension: false,
+	};
+
+	// Compression, language and production information, twice for dual mono.
+	for program in 0..if acmod == 0 { 2 } else { 1 } {
+		if program == 1 {
+			bits.skip(5)?;
+		}
+
+		if bits.flag()? {
+			bits.skip(8)?;
+		}
+
+		if bits.flag()? {
+			bits.skip(8)?;
+		}
+
+		if bits.flag()? {
+			bits.skip(7)?;
+		}
+	}
+
+	bits.skip(2)?;
+
+	// Only the alternate syntax carries the Lt/Rt and Lo/Ro levels.
+	if bsid == 6 && bits.flag()? {
+		bits.skip(2)?;
+		bsi.ltrtcmixlev = Some(bits.read(3)? as u8);
+		bsi.ltrtsurmixlev = Some(bits.read(3)? as u8);
+		bsi.lorocmixlev = Some(bits.read(3)? as u8);
+		bsi.lorosurmixlev = Some(bits.read(3)? as u8);
+	}
+
+	Some(bsi)
+}
+
+// Follows ETSI TS 102 366 annex E all the way to the additional bit stream
+// information, which is where the extension flag hides.
+fn eac3(data: &[u8], bsid: u8) -> Option<Bsi> {
+	let mut bits = Reader::new(data);
+	bits.skip(16)?;
+
+	let stream_type = bits.read(2)? as u8;
+	let substream = bits.read(3)? as u8;
+	let size = (bits.read(11)? as usize + 1) * 2;
+	let fscod = bits.read(2)?;
+	let blocks = if fscod == 3 {
+		bits.skip(2)?;
+		6
+	}
+	else {
+		[1, 2, 3, 6][bits.read(2)? as usize]
+	};
+
+	let acmod = bits.read(3)? as u8;
+	let lfe = bits.flag()?;
+	bits.skip(5)?;
+
+	let mut bsi = Bsi {
+		bsid,
+		stream_type,
+		substream,
+		size,
+		acmod,
+		lfe,
+		bsmod: 0,
+		dialnorm: 0,
+		dsurmod: None,
+		cmixlev: None,
+		surmixlev: None,
+		ltrtcmixlev: None,
+		ltrtsurmixlev: None,
+		lorocmixlev: None,
+		lorosurmixlev: None,
+		extension: false,
+	};
+
+	// Dual mono repeats the dialogue normalization and compression fields.
+	let programs = if acmod == 0 { 2 } else { 1 };
+
+	for program in 0..programs {
+		let dialnorm = bits.read(5)? as u8;
+		if program == 0 {
+			bsi.dialnorm = dialnorm;
+		}
+
+		if bits.flag()? {
+			bits.skip(8)?;
+		}
+	}
+
+	if stream_type == 1 && bits.flag()? {
+		bits.skip(16)?;
+	}
+
+	if bits.flag()? {
+		if acmod > 2 {
+			bits.skip(2)?;
+		}
+
+		if acmod & 1 != 0 && acmod > 2 {
+			bsi.ltrtcmixlev = Some(bits.read(3)? as u8);
+			bsi.lorocmixlev = Some(bits.read(3)? as u8);
+		}
+
+		if acmod & 4 != 0 {
+			bsi.ltrtsurmixlev = Some(bits.read(3)? as u8);
+			bsi.lorosurmixlev = Some(bits.read(3)? as u8);
+		}
+
+		if lfe && bits.flag()? {
+			bits.skip(5)?;
+		}
+
+		if stream_type == 0 {
+			for _ in 0..programs {
+				if bits.flag()? {
+					bits.skip(6)?;
+				}
+			}
+
+			if bits.flag()? {
+				bits.skip(6)?;
+			}
+
+			match bits.read(2)? {
+				1 => bits.skip(5)?,
+				2 => bits.skip(12)?,
+				3 => bits.skip((bits.read(5)? as usize + 2) * 8)?,
+				_ => (),
+			}
+
+			if acmod < 2 {
+				for _ in 0..programs {
+					if bits.flag()? {
+						bits.skip(14)?;
+					}
+				}
+			}
+
+			if bits.flag()? {
+				if blocks == 1 {
+					bits.skip(5)?;
+				}
+				else {
+					for _ in 0..blocks {
+						if bits.flag()? {
+							bits.skip(5)?;
+						}
+					}
+				}
+			}
+		}
+	}
+
+	if bits.flag()? {
+		bsi.bsmod = bits.read(3)? as u8;
+		bits.skip(2)?;
+
+		if acmod == 2 {
+			bsi.dsurmod = Some(bits.read(2)? as u8);
+			bits.skip(2)?;
+		}
+
+		if acmod >= 6 {
+			bits.skip(2)?;
+		}
+
+		for _ in 0..programs {
+			if bits.flag()? {
+				bits.skip(8)?;
+			}
+		}
+
+		if fscod < 3 {
+			bits.skip(1)?;
+		}
+	}
+
+	if stream_type == 0 && blocks != 6 {
+		bits.skip(1)?;
+	}
+
+	if stream_type == 2 && (blocks == 6 || bits.flag()?) {
+		bits.skip(6)?;
+	}
+
+	// The extension flag is the last bit of the first additional byte.
+	if bits.flag()? {
+		bits.skip(6 + 7)?;
+		bsi.extension = bits.flag()?;
+	}
+
+	Some(bsi)
+}
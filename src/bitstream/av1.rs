@@ -0,0 +1,234 @@
+use super::bits::Reader;
+
+const SEQUENCE_HEADER: u8 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SequenceHeader {
+	pub profile: u8,
+	pub level: u8,
+	pub tier: u8,
+	pub bit_depth: u8,
+	pub monochrome: bool,
+	pub subsampling_x: bool,
+	pub subsampling_y: bool,
+	pub superres: bool,
+	pub film_grain: bool,
+}
+
+// Finds the sequence header OBU, either after the four byte AV1 codec
+// configuration record or in a temporal unit.
+pub fn sequence_header(data: &[u8]) -> Option<SequenceHeader> {
+	let mut data = if data.first().map_or(false, |b| b & 0x80 != 0) { data.get(4..)? } else { data };
+
+	while !data.is_empty() {
+		let header = data[0];
+		let kind = (header >> 3) & 0xf;
+		let mut offset = if header & 0x4 != 0 { 2 } else { 1 };
+
+		let size = if header & 0x2 != 0 {
+			let (size, length) = leb128(data.get(offset..)?)?;
+			offset += length;
+			size
+		}
+		else {
+			data.len().checked_sub(offset)?
+		};
+
+		let end = offset.checked_add(size)?;
+		let payload = data.get(offset..end)?;
+		if kind == SEQUENCE_HEADER {
+			return SequenceHeader::parse(payload);
+		}
+
+		data = &data[end..];
+	}
+
+	None
+}
+
+impl SequenceHeader {
+	// AV1 specification 5.5, up to film_grain_params_present.
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		let mut bits = Reader::new(data);
+
+		let profile = bits.read(3)? as u8;
+		bits.skip(1)?;
+		let reduced = bits.flag()?;
+
+		let (level, tier) = if reduced {
+			(bits.read(5)? as u8, 0)
+		}
+		else {
+			let mut buffer_delay = 0;
+			let decoder_model = if bits.flag()? {
+				bits.skip(64)?;
+
+				if bits.flag()? {
+					uvlc(&mut bits)?;
+				}
+
+				if bits.flag()? {
+					buffer_delay = bits.read(5)? as usize + 1;
+					bits.skip(32 + 5 + 5)?;
+					true
+				}
+				else {
+					false
+				}
+			}
+			else {
+				false
+			};
+
+			let initial_display_delay = bits.flag()?;
+			let mut first = None;
+
+			for _ in 0..=bits.read(5)? {
+				bits.skip(12)?;
+				let level = bits.read(5)? as u8;
+				let tier = if level > 7 { bits.read(1)? as u8 } else { 0 };
+
+				if decoder_model && bits.flag()? {
+					bits.skip(buffer_delay * 2 + 1)?;
+				}
+
+				if initial_display_delay && bits.flag()? {
+					bits.skip(4)?;
+				}
+
+				first = first.or(Some((level, tier)));
+			}
+
+			first?
+		};
+
+		let width_bits = bits.read(4)? as usize + 1;
+		let height_bits = bits.read(4)? as usize + 1;
+		bits.skip(width_bits + height_bits)?;
+
+		if !reduced && bits.flag()? {
+			bits.skip(4 + 3)?;
+		}
+
+		bits.skip(3)?;
+
+		if !reduced {
+			bits.skip(4)?;
+			let order_hint = bits.flag()?;
+
+			if order_hint {
+				bits.skip(2)?;
+			}
+
+			let screen_content = if bits.flag()? { 2 } else { bits.read(1)? };
+			if screen_content > 0 && !bits.flag()? {
+				bits.skip(1)?;
+			}
+
+			if order_hint {
+				bits.skip(3)?;
+			}
+		}
+
+		let superres = bits.flag()?;
+		bits.skip(2)?;
+
+		let high_bitdepth = bits.flag()?;
+		let bit_depth = match (profile, high_bitdepth) {
+			(2, true) if bits.flag()? => 12,
+			(_, true) => 10,
+			_ => 8,
+		};
+
+		let monochrome = profile != 1 && bits.flag()?;
+
+		let (mut primaries, mut transfer, mut matrix) = (2, 2, 2);
+		if bits.flag()? {
+			primaries = bits.read(8)?;
+			transfer = bits.read(8)?;
+			matrix = bits.read(8)?;
+		}
+
+		let (subsampling_x, subsampling_y) = if monochrome {
+			bits.skip(1)?;
+			(true, true)
+		}
+		// sRGB is always 4:4:4 and full range.
+		else if primaries == 1 && transfer == 13 && matrix == 0 {
+			bits.skip(1)?;
+			(false, false)
+		}
+		else {
+			bits.skip(1)?;
+
+			let subsampling = match profile {
+				0 => (true, true),
+				1 => (false, false),
+				_ if bit_depth == 12 => {
+					let x = bits.flag()?;
+					(x, x && bits.flag()?)
+				}
+				_ => (true, false),
+			};
+
+			if subsampling == (true, true) {
+				bits.skip(2)?;
+			}
+
+			bits.skip(1)?;
+			subsampling
+		};
+
+		Some(SequenceHeader {
+			profile,
+			level,
+			tier,
+			bit_depth,
+			monochrome,
+			subsampling_x,
+			subsampling_y,
+			superres,
+			film_grain: bits.flag()?,
+		})
+	}
+}
+
+fn leb128(data: &[u8]) -> Option<(usize, usize)> {
+	let mut value = 0usize;
+
+	for (i, &byte) in data.iter().take(8).enumerate() {
+		value |= usize::from(byte & 0x7f) << (i * 7);
+
+		if byte & 0x80 == 0 {
+			return Some((value, i + 1));
+		}
+	}
+
+	None
+}
+
+fn uvlc(bits: &mut Reader) -> Option<u32> {
+	let mut zeros = 0;
+	while !bits.flag()? {
+		zeros += 1;
+
+		if zeros >= 32 {
+			return Some(u32::MAX);
+		}
+	}
+
+	Some(((1u64 << zeros) - 1 + u64::from(bits.read(zeros)?)) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn truncated() {
+		// A sequence header OBU with the extension flag and no size field.
+		assert_eq!(sequence_header(&[0x0c]), None);
+		// A size past the end of the data.
+		assert_eq!(sequence_header(&[0x0a, 0x05, 0x00]), None);
+	}
+}
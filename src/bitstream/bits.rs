@@ -0,0 +1,65 @@
+// Reads big endian bit fields, most significant bit first, the way every
+// audio and video header is laid out.
+pub struct Reader<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Reader<'a> {
+	pub fn new(data: &'a [u8]) -> Self {
+		Reader { data, position: 0 }
+	}
+
+	pub fn read(&mut self, bits: u32) -> Option<u32> {
+		debug_assert!(bits <= 32);
+
+		let mut value = 0u64;
+		for _ in 0..bits {
+			let byte = self.data.get(self.position / 8)?;
+			let bit = (byte >> (7 - self.position % 8)) & 1;
+
+			value = (value << 1) | u64::from(bit);
+			self.position += 1;
+		}
+
+		Some(value as u32)
+	}
+
+	pub fn flag(&mut self) -> Option<bool> {
+		self.read(1).map(|bit| bit == 1)
+	}
+
+	// Exponential Golomb codes, as used all over H.264 and HEVC headers.
+	pub fn ue(&mut self) -> Option<u32> {
+		let mut zeros = 0;
+		while !self.flag()? {
+			zeros += 1;
+
+			if zeros > 31 {
+				return None;
+			}
+		}
+
+		Some(((1u64 << zeros) - 1 + u64::from(self.read(zeros)?)) as u32)
+	}
+
+	pub fn se(&mut self) -> Option<i32> {
+		let code = i64::from(self.ue()?);
+		let value = if code % 2 == 1 { (code + 1) / 2 } else { -code / 2 };
+
+		Some(value as i32)
+	}
+
+	pub fn skip(&mut self, bits: usize) -> Option<()> {
+		if self.position + bits > self.data.len() * 8 {
+			return None;
+		}
+
+		self.position += bits;
+		Some(())
+	}
+
+	pub fn remaining(&self) -> usize {
+		(self.data.len() * 8).saturating_sub(self.position)
+	}
+}
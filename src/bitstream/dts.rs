@@ -0,0 +1,37 @@
+const CORE: [u8; 4] = [0x7f, 0xfe, 0x80, 0x01];
+const SUBSTREAM: [u8; 4] = [0x64, 0x58, 0x20, 0x25];
+
+// Lossless extensions carrying DTS:X objects, as found by libavcodec.
+const XLL_X: [u8; 4] = [0x02, 0x00, 0x08, 0x50];
+const XLL_X_IMAX: [u8; 4] = [0xf1, 0x40, 0x00, 0xd0];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Frame {
+	// A backwards compatible core frame leads the packet.
+	pub core: bool,
+	pub substream: bool,
+	pub x: bool,
+	pub imax: bool,
+}
+
+pub fn frame(data: &[u8]) -> Option<Frame> {
+	let core = data.starts_with(&CORE);
+	let substream = find(data, &SUBSTREAM).filter(|&at| core || at == 0);
+
+	if !core && substream.is_none() {
+		return None;
+	}
+
+	let extension = substream.map_or(&[][..], |at| &data[at..]);
+
+	Some(Frame {
+		core,
+		substream: substream.is_some(),
+		x: find(extension, &XLL_X).is_some(),
+		imax: find(extension, &XLL_X_IMAX).is_some(),
+	})
+}
+
+fn find(data: &[u8], sync: &[u8; 4]) -> Option<usize> {
+	data.windows(4).position(|w| w == sync)
+}
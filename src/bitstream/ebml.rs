@@ -0,0 +1,84 @@
+use std::io::{Read, Seek, SeekFrom};
+
+// Sizes with every value bit set mean the element runs to the end of its
+// parent, which live streams and some muxers use for segments and clusters.
+pub const UNKNOWN: u64 = u64::MAX;
+
+// Element IDs keep their length marker, sizes don't.
+fn vint(first: u8, rest: &[u8], marker: bool) -> Option<(u64, usize)> {
+	let length = first.leading_zeros() as usize + 1;
+
+	if length > 8 || rest.len() < length - 1 {
+		return None;
+	}
+
+	let first = if marker { u64::from(first) } else { u64::from(first) & (0xff >> length) };
+	let value = rest[..length - 1].iter().fold(first, |value, &b| (value << 8) | u64::from(b));
+	let all = !marker && value == (1 << (7 * length)) - 1;
+
+	Some((if all { UNKNOWN } else { value }, length))
+}
+
+// Reads an element header, returning its ID and size.
+pub fn header<R: Read>(reader: &mut R) -> Option<(u32, u64)> {
+	let mut read = |marker: bool| {
+		let mut first = [0];
+		reader.read_exact(&mut first).ok()?;
+
+		let mut rest = vec![0; (first[0].leading_zeros() as usize).min(7)];
+		reader.read_exact(&mut rest).ok()?;
+
+		vint(first[0], &rest, marker).map(|(value, _)| value)
+	};
+
+	let id = read(true)?;
+	let size = read(false)?;
+
+	Some((id as u32, size))
+}
+
+pub fn skip<R: Seek>(reader: &mut R, size: u64) -> Option<()> {
+	reader.seek(SeekFrom::Current(size as i64)).ok().map(|_| ())
+}
+
+// Splits the body of a master element into its children.
+pub fn children(mut data: &[u8]) -> Vec<(u32, &[u8])> {
+	let mut children = Vec::new();
+
+	while let Some((&first, rest)) = data.split_first() {
+		let (id, length) = match vint(first, rest, true) {
+			Some(id) => id,
+			None => break,
+		};
+
+		let (size, size_length) = match data.get(length..).and_then(|d| d.split_first()) {
+			Some((&first, rest)) => match vint(first, rest, false) {
+				Some(size) => size,
+				None => break,
+			},
+
+			None => break,
+		};
+
+		let start = length + size_length;
+		let end = if size == UNKNOWN { data.len() } else { start.saturating_add(size as usize) };
+
+		match data.get(start..end) {
+			Some(body) => children.push((id as u32, body)),
+			None => break,
+		}
+
+		data = &data[end..];
+	}
+
+	children
+}
+
+pub fn uint(data: &[u8]) -> u64 {
+	data.iter().take(8).fold(0, |value, &b| (value << 8) | u64::from(b))
+}
+
+pub fn string(data: &[u8]) -> String {
+	let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+	String::from_utf8_lossy(&data[..end]).into_owned()
+}
@@ -0,0 +1,153 @@
+use std::{
+	convert::TryInto,
+	io::{Read, Seek, SeekFrom},
+};
+
+// Box type, size and header length, the size being zero for boxes that run to
+// the end of the file.
+pub fn header<R: Read>(reader: &mut R) -> Option<([u8; 4], u64, u64)> {
+	let mut header = [0; 8];
+	reader.read_exact(&mut header).ok()?;
+
+	let kind = header[4..].try_into().unwrap();
+	match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+		1 => {
+			let mut large = [0; 8];
+			reader.read_exact(&mut large).ok()?;
+
+			Some((kind, u64::from_be_bytes(large), 16))
+		}
+
+		size => Some((kind, u64::from(size), 8)),
+	}
+}
+
+// Follows a path of boxes down from the current position, reading the last
+// one whole.
+pub fn descend<R>(reader: &mut R, end: u64, path: &[&[u8; 4]], max: u64) -> Option<Vec<u8>>
+where
+	R: Read + Seek,
+{
+	let (target, rest) = path.split_first()?;
+
+	loop {
+		let start = reader.seek(SeekFrom::Current(0)).ok()?;
+		if start >= end {
+			return None;
+		}
+
+		// Sizes that don't cover their own header or run past the parent would
+		// have the loop seek backwards or in place forever.
+		let (kind, size, length) = header(reader)?;
+		let stop = match size {
+			0 => end,
+			size if size < length => return None,
+			size => start.checked_add(size).filter(|&stop| stop <= end)?,
+		};
+
+		if &kind != *target {
+			reader.seek(SeekFrom::Start(stop)).ok()?;
+			continue;
+		}
+
+		// iTunes writes `meta` as a full box, QuickTime doesn't.
+		if &kind == b"meta" {
+			let mut version = [0; 4];
+			reader.read_exact(&mut version).ok()?;
+
+			if version != [0; 4] {
+				reader.seek(SeekFrom::Current(-4)).ok()?;
+			}
+		}
+
+		if rest.is_empty() {
+			let here = reader.seek(SeekFrom::Current(0)).ok()?;
+			let size = stop.checked_sub(here).filter(|&size| size <= max)?;
+			let mut data = vec![0; size as usize];
+			reader.read_exact(&mut data).ok()?;
+
+			return Some(data);
+		}
+
+		return descend(reader, stop, rest, max);
+	}
+}
+
+pub fn children(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+	let mut children = Vec::new();
+
+	while data.len() >= 8 {
+		let size = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+		if size < 8 || size > data.len() {
+			break;
+		}
+
+		children.push((data[4..8].try_into().unwrap(), &data[8..size]));
+		data = &data[size..];
+	}
+
+	children
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use super::*;
+
+	fn boxed(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+		let mut data = (8 + payload.len() as u32).to_be_bytes().to_vec();
+		data.extend_from_slice(kind);
+		data.extend_from_slice(payload);
+		data
+	}
+
+	#[test]
+	fn nested() {
+		let mut data = boxed(b"free", &[0; 4]);
+		data.extend(boxed(b"moov", &boxed(b"udta", b"tags")));
+
+		let end = data.len() as u64;
+		let udta = descend(&mut Cursor::new(data), end, &[b"moov", b"udta"], 64);
+
+		assert_eq!(udta.as_deref(), Some(&b"tags"[..]));
+	}
+
+	#[test]
+	fn too_large() {
+		let data = boxed(b"moov", &boxed(b"udta", b"tags"));
+		let end = data.len() as u64;
+
+		assert_eq!(descend(&mut Cursor::new(data), end, &[b"moov"], 4), None);
+	}
+
+	#[test]
+	fn zero_largesize() {
+		let mut data = vec![0, 0, 0, 1];
+		data.extend_from_slice(b"free");
+		data.extend_from_slice(&[0; 8]);
+		data.extend(boxed(b"moov", &[]));
+
+		let end = data.len() as u64;
+		assert_eq!(descend(&mut Cursor::new(data), end, &[b"moov"], 64), None);
+	}
+
+	#[test]
+	fn overflowing_size() {
+		let mut data = vec![0, 0, 0, 1];
+		data.extend_from_slice(b"free");
+		data.extend_from_slice(&u64::MAX.to_be_bytes());
+
+		let end = data.len() as u64;
+		assert_eq!(descend(&mut Cursor::new(data), end, &[b"moov"], 64), None);
+	}
+
+	#[test]
+	fn short_size() {
+		let mut data = vec![0, 0, 0, 4];
+		data.extend_from_slice(b"free");
+
+		let end = data.len() as u64;
+		assert_eq!(descend(&mut Cursor::new(data), end, &[b"moov"], 64), None);
+	}
+}
@@ -0,0 +1,14 @@
+pub mod aac;
+pub mod ac3;
+pub mod av1;
+pub mod bits;
+pub mod dts;
+pub mod ebml;
+pub mod isobmff;
+pub mod mpeg_audio;
+pub mod nal;
+pub mod scte35;
+pub mod sei;
+pub mod sps;
+pub mod truehd;
+pub mod vp9;
@@ -0,0 +1,195 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use ffmpeg::format::context::Input;
+
+use crate::{avio::Avio, raw};
+
+// Decoders output this many samples of silence before the first encoded
+// sample, on top of whatever delay the encoder introduced.
+pub const DECODER_DELAY: u32 = 529;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Header {
+	pub mpeg1: bool,
+	pub mono: bool,
+	pub layer: u8,
+	pub sample_rate: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Lame {
+	pub version: String,
+	pub method: u8,
+	pub delay: u32,
+	pub padding: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+	Xing,
+	Info,
+	Vbri,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Xing {
+	pub kind: Kind,
+	pub header: Header,
+	pub frames: Option<u32>,
+	pub bytes: Option<u32>,
+	pub quality: Option<u32>,
+	pub lame: Option<Lame>,
+}
+
+impl Header {
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		if data.len() < 4 || data[0] != 0xff || data[1] & 0xe0 != 0xe0 {
+			return None;
+		}
+
+		let version = (data[1] >> 3) & 0x3;
+		let layer = 4 - ((data[1] >> 1) & 0x3);
+		let rate = (data[2] >> 2) & 0x3;
+
+		if version == 1 || layer == 4 || rate == 3 {
+			return None;
+		}
+
+		let base = [44_100, 48_000, 32_000][rate as usize];
+		let sample_rate = match version {
+			0 => base / 4,
+			2 => base / 2,
+			_ => base,
+		};
+
+		Some(Header {
+			mpeg1: version == 3,
+			mono: data[3] >> 6 == 3,
+			layer,
+			sample_rate,
+		})
+	}
+
+	pub fn samples(&self) -> u32 {
+		match (self.layer, self.mpeg1) {
+			(1, _) => 384,
+			(3, false) => 576,
+			_ => 1152,
+		}
+	}
+
+	// The Xing tag comes right after the side information.
+	fn side_information(&self) -> usize {
+		4 + match (self.mpeg1, self.mono) {
+			(true, false) => 32,
+			(true, true) | (false, false) => 17,
+			(false, true) => 9,
+		}
+	}
+}
+
+pub fn find(input: &Input) -> Option<Xing> {
+	if input.format().name() != "mp3" {
+		return None;
+	}
+
+	xing(&first_frame(&mut Avio::open(&raw::url(input)?).ok()?)?)
+}
+
+pub fn xing(frame: &[u8]) -> Option<Xing> {
+	let header = Header::parse(frame)?;
+
+	// VBRI is always at the same offset, regardless of the channel mode.
+	if let Some(data) = frame.get(36..54).filter(|data| data.starts_with(b"VBRI")) {
+		let be32 = |at: usize| {
+			u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+		};
+
+		return Some(Xing {
+			kind: Kind::Vbri,
+			header,
+			frames: Some(be32(14)),
+			bytes: Some(be32(10)),
+			quality: Some(u32::from(u16::from_be_bytes([data[8], data[9]]))),
+			lame: None,
+		});
+	}
+
+	let data = frame.get(header.side_information()..)?;
+	let kind = if data.starts_with(b"Xing") {
+		Kind::Xing
+	}
+	else if data.starts_with(b"Info") {
+		Kind::Info
+	}
+	else {
+		return None;
+	};
+
+	let flags = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+	let mut offset = 8;
+	let mut field = |present: bool, size: usize| {
+		let value = if present {
+			data.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+		}
+		else {
+			None
+		};
+
+		if present {
+			offset += size;
+		}
+
+		value
+	};
+
+	let frames = field(flags & 0x1 != 0, 4);
+	let bytes = field(flags & 0x2 != 0, 4);
+	field(flags & 0x4 != 0, 100);
+	let quality = field(flags & 0x8 != 0, 4);
+
+	// Other encoders (e.g. Lavf) write the same extension with their own name.
+	let tag = data.get(offset..offset + 24);
+	let lame = tag.filter(|tag| tag[..4].iter().all(u8::is_ascii_alphabetic)).map(|tag| {
+		let value = (u32::from(tag[21]) << 16) | (u32::from(tag[22]) << 8) | u32::from(tag[23]);
+		let version = String::from_utf8_lossy(&tag[..9]);
+
+		Lame {
+			version: version.trim_end_matches(|c: char| c == '\0' || c == ' ').to_owned(),
+			method: tag[9] & 0xf,
+			delay: value >> 12,
+			padding: value & 0xfff,
+		}
+	});
+
+	Some(Xing {
+		kind,
+		header,
+		frames,
+		bytes,
+		quality,
+		lame,
+	})
+}
+
+// Skips any ID3v2 tag and returns the first frame, which is where encoders put
+// their Xing or VBRI header.
+pub fn first_frame<R: Read + Seek>(reader: &mut R) -> Option<Vec<u8>> {
+	let mut id3 = [0; 10];
+	reader.read_exact(&mut id3).ok()?;
+
+	if &id3[..3] == b"ID3" {
+		let size = id3[6..].iter().fold(0u64, |size, &b| (size << 7) | u64::from(b & 0x7f));
+		let footer = if id3[5] & 0x10 != 0 { 10 } else { 0 };
+		reader.seek(SeekFrom::Start(10 + size + footer)).ok()?;
+	}
+	else {
+		reader.seek(SeekFrom::Start(0)).ok()?;
+	}
+
+	let mut data = Vec::new();
+	reader.take(4096).read_to_end(&mut data).ok()?;
+
+	let start = data.windows(2).position(|w| w[0] == 0xff && w[1] & 0xe0 == 0xe0)?;
+	Some(data.split_off(start))
+}
@@ -0,0 +1,233 @@
+// Splits a packet or extradata into NAL units, either length prefixed (as
+// stored in MP4 and Matroska) or delimited by Annex B start codes.
+pub fn units(data: &[u8], length: Option<usize>) -> Vec<&[u8]> {
+	match length {
+		Some(size) => prefixed(data, size),
+		None => annexb(data),
+	}
+}
+
+fn prefixed(mut data: &[u8], size: usize) -> Vec<&[u8]> {
+	let mut units = Vec::new();
+
+	while data.len() > size {
+		let length = data[..size].iter().fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+		data = &data[size..];
+
+		if length > data.len() {
+			break;
+		}
+
+		units.push(&data[..length]);
+		data = &data[length..];
+	}
+
+	units
+}
+
+fn annexb(data: &[u8]) -> Vec<&[u8]> {
+	let mut starts = Vec::new();
+	let mut i = 0;
+
+	while i + 3 <= data.len() {
+		if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+			starts.push(i + 3);
+			i += 3;
+		}
+		else {
+			i += 1;
+		}
+	}
+
+	starts
+		.iter()
+		.enumerate()
+		.map(|(n, &start)| {
+			let mut end = starts.get(n + 1).map_or(data.len(), |next| next - 3);
+
+			// Drop the leading zero of the next four byte start code.
+			while end > start && data[end - 1] == 0 {
+				end -= 1;
+			}
+
+			&data[start..end]
+		})
+		.filter(|unit| !unit.is_empty())
+		.collect()
+}
+
+// Removes emulation prevention bytes so the payload can be parsed.
+pub fn unescape(data: &[u8]) -> Vec<u8> {
+	let mut output = Vec::with_capacity(data.len());
+	let mut zeros = 0;
+
+	for &byte in data {
+		if zeros >= 2 && byte == 3 {
+			zeros = 0;
+			continue;
+		}
+
+		zeros = if byte == 0 { zeros + 1 } else { 0 };
+		output.push(byte);
+	}
+
+	output
+}
+
+// Figures out the NAL length size from AVC or HEVC decoder configuration
+// records, `None` means the stream uses start codes.
+pub fn length_size(extradata: &[u8], hevc: bool) -> Option<usize> {
+	if extradata.first() != Some(&1) {
+		return None;
+	}
+
+	if hevc {
+		extradata.get(21).map(|b| usize::from(b & 3) + 1)
+	}
+	else {
+		extradata.get(4).map(|b| usize::from(b & 3) + 1)
+	}
+}
+
+// The parameter sets stored in extradata, unwrapped from the AVC or HEVC
+// decoder configuration record if there is one.
+pub fn parameter_sets(extradata: &[u8], hevc: bool) -> Vec<&[u8]> {
+	if extradata.first() != Some(&1) {
+		return annexb(extradata);
+	}
+
+	let mut units = Vec::new();
+
+	if hevc {
+		let arrays = extradata.get(22).copied().unwrap_or(0);
+		let mut data = extradata.get(23..).unwrap_or(&[]);
+
+		'arrays: for _ in 0..arrays {
+			let count = match data.get(1..3) {
+				Some(count) => u16::from_be_bytes([count[0], count[1]]),
+				None => break,
+			};
+
+			data = &data[3..];
+			for _ in 0..count {
+				match record(&mut data) {
+					Some(unit) => units.push(unit),
+					None => break 'arrays,
+				}
+			}
+		}
+	}
+	else {
+		// Sequence parameter sets, then picture parameter sets.
+		let count = extradata.get(5).map_or(0, |b| b & 0x1f);
+		let mut data = extradata.get(6..).unwrap_or(&[]);
+
+		for _ in 0..count {
+			match record(&mut data) {
+				Some(unit) => units.push(unit),
+				None => return units,
+			}
+		}
+
+		let count = data.first().copied().unwrap_or(0);
+		data = data.get(1..).unwrap_or(&[]);
+
+		for _ in 0..count {
+			match record(&mut data) {
+				Some(unit) => units.push(unit),
+				None => break,
+			}
+		}
+	}
+
+	units
+}
+
+// A parameter set with its 16 bit length in front.
+fn record<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+	let length = usize::from(u16::from_be_bytes([*data.first()?, *data.get(1)?]));
+	let unit = data.get(2..2 + length)?;
+
+	*data = &data[2 + length..];
+	Some(unit)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// x264's 1080p High profile SPS and its PPS.
+	const SPS: &[u8] = &[
+		0x67, 0x64, 0x00, 0x28, 0xac, 0xd9, 0x40, 0x78, 0x02, 0x27, 0xe5, 0x84, 0x00, 0x00, 0x0f, 0xa4,
+		0x00, 0x03, 0xa9, 0x82, 0x10,
+	];
+	const PPS: &[u8] = &[0x68, 0xeb, 0xe3, 0xcb, 0x22, 0xc0];
+
+	#[test]
+	fn annexb_start_codes() {
+		let mut data = vec![0, 0, 0, 1];
+		data.extend_from_slice(SPS);
+		data.extend_from_slice(&[0, 0, 1]);
+		data.extend_from_slice(PPS);
+		data.extend_from_slice(&[0, 0, 0, 1, 0x65, 0x88, 0x84]);
+
+		assert_eq!(units(&data, None), vec![SPS, PPS, &[0x65, 0x88, 0x84][..]]);
+	}
+
+	#[test]
+	fn length_prefixed() {
+		let mut data = vec![0, 0, 0, PPS.len() as u8];
+		data.extend_from_slice(PPS);
+		data.extend_from_slice(&[0, 0, 0, 3, 0x65, 0x88, 0x84]);
+
+		assert_eq!(units(&data, Some(4)), vec![PPS, &[0x65, 0x88, 0x84][..]]);
+
+		// A length running past the end stops the split there.
+		data.extend_from_slice(&[0, 0, 0, 9, 0x41]);
+		assert_eq!(units(&data, Some(4)).len(), 2);
+	}
+
+	#[test]
+	fn emulation_prevention() {
+		assert_eq!(unescape(&[0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x00]), [0, 0, 1, 0, 0, 0]);
+		assert_eq!(unescape(&[0x00, 0x03, 0x03]), [0x00, 0x03, 0x03]);
+	}
+
+	#[test]
+	fn avc_configuration_record() {
+		let mut avcc = vec![0x01, 0x64, 0x00, 0x28, 0xff, 0xe1, 0x00, SPS.len() as u8];
+		avcc.extend_from_slice(SPS);
+		avcc.extend_from_slice(&[0x01, 0x00, PPS.len() as u8]);
+		avcc.extend_from_slice(PPS);
+
+		assert_eq!(length_size(&avcc, false), Some(4));
+		assert_eq!(parameter_sets(&avcc, false), vec![SPS, PPS]);
+	}
+
+	#[test]
+	fn hevc_configuration_record() {
+		let vps = [0x40, 0x01, 0x0c, 0x01];
+		let sps = [0x42, 0x01, 0x01, 0x01, 0x60];
+
+		let mut hvcc = vec![0x01];
+		hvcc.extend_from_slice(&[0; 20]);
+		hvcc.push(0x0f);
+		hvcc.push(2);
+		hvcc.extend_from_slice(&[0xa0, 0x00, 0x01, 0x00, vps.len() as u8]);
+		hvcc.extend_from_slice(&vps);
+		hvcc.extend_from_slice(&[0xa1, 0x00, 0x01, 0x00, sps.len() as u8]);
+		hvcc.extend_from_slice(&sps);
+
+		assert_eq!(length_size(&hvcc, true), Some(4));
+		assert_eq!(parameter_sets(&hvcc, true), vec![&vps[..], &sps[..]]);
+	}
+
+	#[test]
+	fn start_codes_without_record() {
+		let mut data = vec![0, 0, 0, 1];
+		data.extend_from_slice(SPS);
+
+		assert_eq!(length_size(&data, false), None);
+		assert_eq!(parameter_sets(&data, false), vec![SPS]);
+	}
+}
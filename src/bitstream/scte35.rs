@@ -0,0 +1,157 @@
+// Minimal splice_info_section parsing, just enough to find where splices are
+// meant to happen and for how long.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Splice {
+	pub pts: Option<u64>,
+	pub duration: Option<u64>,
+	pub out: bool,
+}
+
+pub fn parse(data: &[u8]) -> Option<Splice> {
+	if data.len() < 14 || data[0] != 0xfc {
+		return None;
+	}
+
+	// Encrypted commands can't be looked into.
+	if data[4] & 0x80 != 0 {
+		return None;
+	}
+
+	let adjustment = (u64::from(data[4] & 0x1) << 32)
+		| u64::from(u32::from_be_bytes([data[5], data[6], data[7], data[8]]));
+	let kind = data[13];
+	let command = &data[14..];
+
+	// A length of 0xfff is left by old encoders that didn't fill it in.
+	let length = usize::from(u16::from_be_bytes([data[11] & 0x0f, data[12]]));
+	let descriptors = command.get(length..).filter(|_| length != 0xfff).unwrap_or(&[]);
+
+	let adjust = |pts: u64| (pts + adjustment) & ((1 << 33) - 1);
+
+	match kind {
+		// splice_insert
+		0x05 => {
+			if command.len() < 5 || command[4] & 0x80 != 0 {
+				return None;
+			}
+
+			let flags = *command.get(5)?;
+			let out = flags & 0x80 != 0;
+			let program = flags & 0x40 != 0;
+			let has_duration = flags & 0x20 != 0;
+			let immediate = flags & 0x10 != 0;
+
+			let mut rest = &command[6..];
+			let mut pts = None;
+
+			if program && !immediate {
+				let (time, next) = time(rest)?;
+				pts = time.map(adjust);
+				rest = next;
+			}
+			else if !program {
+				// Per component splice times, the first one stands for them all.
+				let count = *rest.first()?;
+				rest = &rest[1..];
+
+				for _ in 0..count {
+					rest = rest.get(1..)?;
+
+					if !immediate {
+						let (time, next) = time(rest)?;
+						pts = pts.or_else(|| time.map(adjust));
+						rest = next;
+					}
+				}
+			}
+
+			let duration = if has_duration && rest.len() >= 5 {
+				Some(bits33(rest))
+			}
+			else {
+				None
+			};
+
+			Some(Splice { pts, duration, out })
+		}
+
+		// time_signal, which leaves what it signals to a segmentation
+		// descriptor.
+		0x06 => {
+			let (out, duration) = segmentation(descriptors)?;
+
+			Some(Splice {
+				pts: time(command)?.0.map(adjust),
+				duration,
+				out,
+			})
+		}
+
+		_ => None,
+	}
+}
+
+// Whether the first break or placement opportunity descriptor starts or ends
+// one, and its duration.
+fn segmentation(data: &[u8]) -> Option<(bool, Option<u64>)> {
+	let length = usize::from(u16::from_be_bytes([*data.first()?, *data.get(1)?]));
+	let mut data = data.get(2..2 + length)?;
+
+	while data.len() >= 2 {
+		let (tag, size) = (data[0], usize::from(data[1]));
+		let descriptor = data.get(2..2 + size)?;
+		data = &data[2 + size..];
+
+		// segmentation_descriptor, past its CUEI identifier.
+		if tag != 0x02 || descriptor.len() < 10 || descriptor[8] & 0x80 != 0 {
+			continue;
+		}
+
+		let flags = descriptor[9];
+		let mut rest = &descriptor[10..];
+
+		if flags & 0x80 == 0 {
+			let count = usize::from(*rest.first()?);
+			rest = rest.get(1 + count * 6..)?;
+		}
+
+		let mut duration = None;
+		if flags & 0x40 != 0 {
+			let field = rest.get(..5)?;
+			duration = Some(
+				(u64::from(field[0]) << 32)
+					| u64::from(u32::from_be_bytes([field[1], field[2], field[3], field[4]])),
+			);
+			rest = &rest[5..];
+		}
+
+		let upid = usize::from(*rest.get(1)?);
+		let kind = *rest.get(2 + upid)?;
+
+		// Breaks, provider and distributor advertisements and placement
+		// opportunities, each start followed by its end.
+		if (0x22..=0x3f).contains(&kind) {
+			return Some((kind % 2 == 0, duration));
+		}
+	}
+
+	None
+}
+
+fn time(data: &[u8]) -> Option<(Option<u64>, &[u8])> {
+	let first = *data.first()?;
+
+	if first & 0x80 == 0 {
+		return Some((None, &data[1..]));
+	}
+
+	if data.len() < 5 {
+		return None;
+	}
+
+	Some((Some(bits33(data)), &data[5..]))
+}
+
+fn bits33(data: &[u8]) -> u64 {
+	(u64::from(data[0] & 0x1) << 32) | u64::from(u32::from_be_bytes([data[1], data[2], data[3], data[4]]))
+}
@@ -0,0 +1,111 @@
+// Splits an H.264 or HEVC SEI RBSP into its (payload type, payload) messages.
+pub fn messages(mut data: &[u8]) -> Vec<(usize, &[u8])> {
+	let mut messages = Vec::new();
+
+	while data.len() > 2 {
+		let (kind, rest) = varint(data);
+		let (size, rest) = varint(rest);
+
+		if size > rest.len() {
+			break;
+		}
+
+		messages.push((kind, &rest[..size]));
+		data = &rest[size..];
+	}
+
+	messages
+}
+
+// Extracts the SEI RBSPs out of an access unit.
+pub fn payloads(data: &[u8], length: Option<usize>, hevc: bool) -> Vec<Vec<u8>> {
+	super::nal::units(data, length)
+		.into_iter()
+		.filter_map(|unit| {
+			if hevc {
+				if unit.len() > 2 && (unit[0] >> 1) & 0x3f == 39 {
+					return Some(super::nal::unescape(&unit[2..]));
+				}
+			}
+			else if unit.first().map(|b| b & 0x1f) == Some(6) {
+				return Some(super::nal::unescape(&unit[1..]));
+			}
+
+			None
+		})
+		.collect()
+}
+
+fn varint(data: &[u8]) -> (usize, &[u8]) {
+	let mut value = 0;
+	let mut data = data;
+
+	while let Some((&byte, rest)) = data.split_first() {
+		value += usize::from(byte);
+		data = rest;
+
+		if byte != 0xff {
+			break;
+		}
+	}
+
+	(value, data)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// An ATSC A/53 caption payload, registered ITU-T T.35 with one CEA-608
+	// byte pair.
+	const CAPTIONS: &[u8] = &[
+		0xb5, 0x00, 0x31, 0x47, 0x41, 0x39, 0x34, 0x03, 0xc1, 0xff, 0xfc, 0x94, 0x2c, 0xff,
+	];
+
+	#[test]
+	fn split_messages() {
+		let mut rbsp = vec![0x04, CAPTIONS.len() as u8];
+		rbsp.extend_from_slice(CAPTIONS);
+		rbsp.extend_from_slice(&[0x06, 0x01, 0x00, 0x80]);
+
+		assert_eq!(messages(&rbsp), vec![(4, CAPTIONS), (6, &[0x00][..])]);
+	}
+
+	#[test]
+	fn long_sizes() {
+		let mut rbsp = vec![0x05, 0xff, 0x2d];
+		rbsp.extend_from_slice(&[0x5a; 300]);
+		rbsp.push(0x80);
+
+		let messages = messages(&rbsp);
+		assert_eq!(messages.len(), 1);
+		assert_eq!(messages[0].0, 5);
+		assert_eq!(messages[0].1.len(), 300);
+
+		// Truncated payloads are left out.
+		assert!(super::messages(&rbsp[..100]).is_empty());
+	}
+
+	#[test]
+	fn h264_payloads() {
+		let mut data = vec![0, 0, 0, 1, 0x09, 0xf0, 0, 0, 0, 1, 0x06, 0x04, CAPTIONS.len() as u8];
+		data.extend_from_slice(CAPTIONS);
+		data.extend_from_slice(&[0x80, 0, 0, 0, 1, 0x65, 0x88]);
+
+		let payloads = payloads(&data, None, false);
+		assert_eq!(payloads.len(), 1);
+		assert_eq!(messages(&payloads[0]), vec![(4, CAPTIONS)]);
+	}
+
+	#[test]
+	fn hevc_payloads() {
+		// The payload holds `00 00 01`, escaped in the NAL unit.
+		let unit = [0x4e, 0x01, 0x05, 0x04, 0x00, 0x00, 0x03, 0x01, 0x80];
+		let mut data = vec![0, 0, 0, unit.len() as u8];
+		data.extend_from_slice(&unit);
+
+		let payloads = payloads(&data, Some(4), true);
+		assert_eq!(payloads, vec![vec![0x05, 0x04, 0x00, 0x00, 0x01, 0x80]]);
+		assert_eq!(messages(&payloads[0]), vec![(5, &[0x00, 0x00, 0x01, 0x80][..])]);
+	}
+}
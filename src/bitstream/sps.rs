@@ -0,0 +1,408 @@
+use super::{bits::Reader, nal};
+use crate::sps::{ChromaFormat, HevcTier, SequenceParameters, VuiTiming};
+
+// Profiles whose SPS carries the chroma format and bit depths.
+const HIGH_PROFILES: &[u32] = &[100, 110, 122, 244, 44, 83, 86, 118, 128, 138, 139, 134, 135];
+
+// H.264 SPS, as laid out in ITU-T H.264 7.3.2.1.1.
+pub fn h264(unit: &[u8]) -> Option<SequenceParameters> {
+	let data = nal::unescape(unit.get(1..)?);
+	let mut bits = Reader::new(&data);
+
+	let profile_idc = bits.read(8)?;
+	let constraints = bits.read(8)?;
+	let level_idc = bits.read(8)?;
+	bits.ue()?;
+
+	let (mut chroma_format_idc, mut separate, mut depth_luma, mut depth_chroma) = (1, false, 8, 8);
+	if HIGH_PROFILES.contains(&profile_idc) {
+		chroma_format_idc = bits.ue()?;
+		separate = chroma_format_idc == 3 && bits.flag()?;
+		depth_luma = bits.ue()? + 8;
+		depth_chroma = bits.ue()? + 8;
+		bits.skip(1)?;
+
+		if bits.flag()? {
+			for list in 0..if chroma_format_idc == 3 { 12 } else { 8 } {
+				if bits.flag()? {
+					scaling_list(&mut bits, if list < 6 { 16 } else { 64 })?;
+				}
+			}
+		}
+	}
+
+	// Separate colour planes are coded as monochrome pictures.
+	if separate {
+		chroma_format_idc = 0;
+	}
+
+	bits.ue()?;
+	match bits.ue()? {
+		0 => {
+			bits.ue()?;
+		}
+
+		1 => {
+			bits.skip(1)?;
+			bits.se()?;
+			bits.se()?;
+
+			for _ in 0..bits.ue()? {
+				bits.se()?;
+			}
+		}
+
+		_ => (),
+	}
+
+	let max_ref_frames = bits.ue()?;
+	bits.skip(1)?;
+
+	let width_mbs = bits.ue()? + 1;
+	let height_units = bits.ue()? + 1;
+	let frame_mbs_only = bits.flag()?;
+
+	if !frame_mbs_only {
+		bits.skip(1)?;
+	}
+
+	bits.skip(1)?;
+
+	let height_mbs = height_units * if frame_mbs_only { 1 } else { 2 };
+	let (mut width, mut height) = (width_mbs * 16, height_mbs * 16);
+
+	if bits.flag()? {
+		let (left, right, top, bottom) = (bits.ue()?, bits.ue()?, bits.ue()?, bits.ue()?);
+		let (unit_x, unit_y) = match chroma_format_idc {
+			1 => (2, 2),
+			2 => (2, 1),
+			_ => (1, 1),
+		};
+		let unit_y = unit_y * if frame_mbs_only { 1 } else { 2 };
+
+		width = width.saturating_sub(unit_x * (left + right));
+		height = height.saturating_sub(unit_y * (top + bottom));
+	}
+
+	let timing = if bits.flag()? { vui_timing(&mut bits) } else { None };
+
+	// Level 1b is signalled through constraint_set3_flag in the baseline
+	// profiles, with level_idc 11.
+	let level_1b =
+		level_idc == 9 || (level_idc == 11 && constraints & 0x10 != 0 && profile_idc < 100);
+	let max_dpb_mbs = match level_idc {
+		_ if level_1b => 396,
+		10 => 396,
+		11 => 900,
+		12 | 13 | 20 => 2376,
+		21 => 4752,
+		22 | 30 => 8100,
+		31 => 18_000,
+		32 => 20_480,
+		40 | 41 => 32_768,
+		42 => 34_816,
+		50 => 110_400,
+		51 | 52 => 184_320,
+		60 | 61 | 62 => 696_320,
+		_ => 0,
+	};
+
+	Some(SequenceParameters {
+		profile_idc: profile_idc as u8,
+		level_idc: level_idc as u8,
+		tier: None,
+		chroma_format: chroma_format(chroma_format_idc)?,
+		bit_depth_luma: depth_luma as u8,
+		bit_depth_chroma: depth_chroma as u8,
+		width,
+		height,
+		interlaced: !frame_mbs_only,
+		max_ref_frames,
+		max_dpb_frames: Some(max_dpb_mbs / (width_mbs * height_mbs))
+			.filter(|_| max_dpb_mbs > 0)
+			.map(|frames| frames.min(16)),
+		timing: timing.map(|(num_units_in_tick, time_scale, fixed)| VuiTiming {
+			num_units_in_tick,
+			time_scale,
+			fixed_frame_rate: Some(fixed),
+			// Ticks count fields.
+			frame_rate: rate(time_scale, f64::from(num_units_in_tick) * 2.0),
+		}),
+	})
+}
+
+// HEVC SPS from ITU-T H.265 7.3.2.2, with the timing from the VPS since
+// reaching the VUI would mean parsing every reference picture set.
+pub fn hevc(unit: &[u8], vps: Option<&[u8]>) -> Option<SequenceParameters> {
+	let data = nal::unescape(unit.get(2..)?);
+	let mut bits = Reader::new(&data);
+
+	bits.skip(4)?;
+	let sub_layers = bits.read(3)?;
+	bits.skip(1)?;
+
+	let (tier, profile_idc, interlaced, level_idc) = profile_tier_level(&mut bits, sub_layers)?;
+	bits.ue()?;
+
+	let mut chroma_format_idc = bits.ue()?;
+	if chroma_format_idc == 3 && bits.flag()? {
+		chroma_format_idc = 0;
+	}
+
+	let mut width = bits.ue()?;
+	let mut height = bits.ue()?;
+
+	if bits.flag()? {
+		let (left, right, top, bottom) = (bits.ue()?, bits.ue()?, bits.ue()?, bits.ue()?);
+		let (unit_x, unit_y) = match chroma_format_idc {
+			1 => (2, 2),
+			2 => (2, 1),
+			_ => (1, 1),
+		};
+
+		width = width.saturating_sub(unit_x * (left + right));
+		height = height.saturating_sub(unit_y * (top + bottom));
+	}
+
+	let depth_luma = bits.ue()? + 8;
+	let depth_chroma = bits.ue()? + 8;
+	bits.ue()?;
+
+	let mut buffering = 0;
+	let first = if bits.flag()? { 0 } else { sub_layers };
+
+	for _ in first..=sub_layers {
+		buffering = buffering.max(bits.ue()?);
+		bits.ue()?;
+		bits.ue()?;
+	}
+
+	let timing = vps.and_then(vps_timing);
+
+	Some(SequenceParameters {
+		profile_idc,
+		level_idc,
+		tier: Some(tier),
+		chroma_format: chroma_format(chroma_format_idc)?,
+		bit_depth_luma: depth_luma as u8,
+		bit_depth_chroma: depth_chroma as u8,
+		width,
+		height,
+		interlaced,
+		max_ref_frames: buffering,
+		max_dpb_frames: None,
+		timing: timing.map(|(num_units_in_tick, time_scale)| VuiTiming {
+			num_units_in_tick,
+			time_scale,
+			fixed_frame_rate: None,
+			frame_rate: rate(time_scale, f64::from(num_units_in_tick)),
+		}),
+	})
+}
+
+fn profile_tier_level(bits: &mut Reader, sub_layers: u32) -> Option<(HevcTier, u8, bool, u8)> {
+	bits.skip(2)?;
+	let tier = if bits.flag()? { HevcTier::High } else { HevcTier::Main };
+	let profile_idc = bits.read(5)? as u8;
+	bits.skip(32 + 1)?;
+	let interlaced = bits.flag()?;
+	bits.skip(2 + 44)?;
+	let level_idc = bits.read(8)? as u8;
+
+	let mut present = Vec::new();
+	for _ in 0..sub_layers {
+		present.push((bits.flag()?, bits.flag()?));
+	}
+
+	if sub_layers > 0 {
+		bits.skip(2 * (8 - sub_layers as usize))?;
+	}
+
+	for (profile, level) in present {
+		if profile {
+			bits.skip(88)?;
+		}
+
+		if level {
+			bits.skip(8)?;
+		}
+	}
+
+	Some((tier, profile_idc, interlaced, level_idc))
+}
+
+fn vps_timing(unit: &[u8]) -> Option<(u32, u32)> {
+	let data = nal::unescape(unit.get(2..)?);
+	let mut bits = Reader::new(&data);
+
+	bits.skip(4 + 2 + 6)?;
+	let sub_layers = bits.read(3)?;
+	bits.skip(1 + 16)?;
+	profile_tier_level(&mut bits, sub_layers)?;
+
+	let first = if bits.flag()? { 0 } else { sub_layers };
+	for _ in first..=sub_layers {
+		bits.ue()?;
+		bits.ue()?;
+		bits.ue()?;
+	}
+
+	let max_layer_id = bits.read(6)?;
+	let layer_sets = bits.ue()?;
+	bits.skip(layer_sets as usize * (max_layer_id as usize + 1))?;
+
+	if !bits.flag()? {
+		return None;
+	}
+
+	Some((bits.read(32)?, bits.read(32)?))
+}
+
+fn vui_timing(bits: &mut Reader) -> Option<(u32, u32, bool)> {
+	if bits.flag()? && bits.read(8)? == 255 {
+		bits.skip(32)?;
+	}
+
+	if bits.flag()? {
+		bits.skip(1)?;
+	}
+
+	if bits.flag()? {
+		bits.skip(4)?;
+
+		if bits.flag()? {
+			bits.skip(24)?;
+		}
+	}
+
+	if bits.flag()? {
+		bits.ue()?;
+		bits.ue()?;
+	}
+
+	if !bits.flag()? {
+		return None;
+	}
+
+	Some((bits.read(32)?, bits.read(32)?, bits.flag()?))
+}
+
+fn scaling_list(bits: &mut Reader, size: usize) -> Option<()> {
+	let (mut last, mut next) = (8i32, 8i32);
+
+	for _ in 0..size {
+		if next != 0 {
+			next = (last + bits.se()? + 256) % 256;
+		}
+
+		if next != 0 {
+			last = next;
+		}
+	}
+
+	Some(())
+}
+
+fn chroma_format(idc: u32) -> Option<ChromaFormat> {
+	match idc {
+		0 => Some(ChromaFormat::Monochrome),
+		1 => Some(ChromaFormat::Yuv420),
+		2 => Some(ChromaFormat::Yuv422),
+		3 => Some(ChromaFormat::Yuv444),
+		_ => None,
+	}
+}
+
+fn rate(time_scale: u32, ticks: f64) -> Option<f64> {
+	Some(f64::from(time_scale) / ticks).filter(|_| ticks > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// x264 at 1080p, High profile level 4.0 with 29.97 fps timing in the VUI.
+	const HIGH: &[u8] = &[
+		0x67, 0x64, 0x00, 0x28, 0xac, 0xd9, 0x40, 0x78, 0x02, 0x27, 0xe5, 0x84, 0x00, 0x00, 0x0f, 0xa4,
+		0x00, 0x03, 0xa9, 0x82, 0x10,
+	];
+	// QCIF Baseline at level 1b, without a VUI.
+	const BASELINE: &[u8] = &[0x67, 0x42, 0xd0, 0x0b, 0xe9, 0x05, 0x89, 0xc8];
+	// x265 style VPS with 25 fps timing, and 2160p Main 10 High tier and
+	// 1080p Main SPSs.
+	const VPS: &[u8] = &[
+		0x40, 0x01, 0x0c, 0x01, 0xff, 0xff, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03,
+		0x00, 0x00, 0x03, 0x00, 0x78, 0x97, 0x03, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x03, 0x00,
+		0x19, 0x50,
+	];
+	const MAIN10: &[u8] = &[
+		0x42, 0x01, 0x01, 0x22, 0x20, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+		0x00, 0x99, 0xa0, 0x01, 0xe0, 0x20, 0x02, 0x1c, 0x4d, 0x96, 0x67, 0x80,
+	];
+	const MAIN: &[u8] = &[
+		0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+		0x00, 0x78, 0xa0, 0x03, 0xc0, 0x80, 0x11, 0x07, 0xcb, 0x96, 0x57, 0x80,
+	];
+
+	#[test]
+	fn h264_high() {
+		let sps = h264(HIGH).unwrap();
+
+		assert_eq!((sps.profile_idc, sps.level_idc), (100, 40));
+		assert_eq!(sps.chroma_format, ChromaFormat::Yuv420);
+		assert_eq!((sps.bit_depth_luma, sps.bit_depth_chroma), (8, 8));
+		assert_eq!((sps.width, sps.height), (1920, 1080));
+		assert!(!sps.interlaced);
+		assert_eq!(sps.max_ref_frames, 4);
+		assert_eq!(sps.max_dpb_frames, Some(4));
+
+		let timing = sps.timing.unwrap();
+		assert_eq!((timing.num_units_in_tick, timing.time_scale), (1001, 60000));
+		assert_eq!(timing.fixed_frame_rate, Some(true));
+		assert!((timing.frame_rate.unwrap() - 29.97).abs() < 0.001);
+	}
+
+	#[test]
+	fn h264_level_1b() {
+		let sps = h264(BASELINE).unwrap();
+
+		assert_eq!((sps.profile_idc, sps.level_idc), (66, 11));
+		assert_eq!((sps.width, sps.height), (176, 144));
+		assert_eq!(sps.max_ref_frames, 1);
+		// 396 macroblocks of buffer, rather than 900 for level 1.1.
+		assert_eq!(sps.max_dpb_frames, Some(4));
+		assert!(sps.timing.is_none());
+	}
+
+	#[test]
+	fn h264_truncated() {
+		assert!(h264(&HIGH[..8]).is_none());
+	}
+
+	#[test]
+	fn hevc_main10() {
+		let sps = hevc(MAIN10, Some(VPS)).unwrap();
+
+		assert_eq!((sps.profile_idc, sps.level_idc), (2, 153));
+		assert_eq!(sps.tier, Some(HevcTier::High));
+		assert_eq!(sps.chroma_format, ChromaFormat::Yuv420);
+		assert_eq!((sps.bit_depth_luma, sps.bit_depth_chroma), (10, 10));
+		assert_eq!((sps.width, sps.height), (3840, 2160));
+		assert_eq!(sps.max_ref_frames, 5);
+
+		let timing = sps.timing.unwrap();
+		assert_eq!((timing.num_units_in_tick, timing.time_scale), (1, 25));
+		assert_eq!(timing.frame_rate, Some(25.0));
+	}
+
+	#[test]
+	fn hevc_conformance_window() {
+		let sps = hevc(MAIN, None).unwrap();
+
+		assert_eq!((sps.profile_idc, sps.level_idc), (1, 120));
+		assert_eq!(sps.tier, Some(HevcTier::Main));
+		assert_eq!((sps.width, sps.height), (1920, 1080));
+		assert_eq!(sps.max_ref_frames, 4);
+		assert!(sps.timing.is_none());
+	}
+}
@@ -0,0 +1,32 @@
+const MAJOR_SYNC: [u8; 4] = [0xf8, 0x72, 0x6f, 0xba];
+const SIGNATURE: [u8; 2] = [0xb7, 0x52];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MajorSync {
+	pub substreams: u8,
+	pub substream_info: u8,
+}
+
+impl MajorSync {
+	// Only access units starting a restart point have one, the four byte
+	// access unit header comes right before the sync word.
+	pub fn find(data: &[u8]) -> Option<Self> {
+		let start = data.windows(4).position(|w| w == MAJOR_SYNC)?;
+		let sync = data.get(start..start + 18)?;
+
+		if sync[8..10] != SIGNATURE {
+			return None;
+		}
+
+		Some(MajorSync {
+			substreams: sync[16] >> 4,
+			substream_info: sync[17],
+		})
+	}
+
+	// A fourth substream carrying a 16 channel presentation is how Atmos
+	// rides along.
+	pub fn atmos(&self) -> bool {
+		self.substreams == 4 && self.substream_info & 0x80 != 0
+	}
+}
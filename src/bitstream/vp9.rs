@@ -0,0 +1,74 @@
+use super::bits::Reader;
+
+const SYNC: u32 = 0x49_83_42;
+const RGB: u32 = 7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FrameHeader {
+	pub profile: u8,
+	pub bit_depth: u8,
+	pub subsampling_x: bool,
+	pub subsampling_y: bool,
+}
+
+impl FrameHeader {
+	// The uncompressed header of a key frame (VP9 bitstream specification
+	// 6.2), only key frames carry the color configuration.
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		let mut bits = Reader::new(data);
+
+		if bits.read(2)? != 2 {
+			return None;
+		}
+
+		let low = bits.read(1)?;
+		let profile = ((bits.read(1)? << 1) | low) as u8;
+
+		if profile == 3 {
+			bits.skip(1)?;
+		}
+
+		// Not a shown existing frame, and a key frame.
+		if bits.flag()? || bits.flag()? {
+			return None;
+		}
+
+		bits.skip(2)?;
+
+		if bits.read(24)? != SYNC {
+			return None;
+		}
+
+		let bit_depth = if profile >= 2 && bits.flag()? {
+			12
+		}
+		else if profile >= 2 {
+			10
+		}
+		else {
+			8
+		};
+
+		let color_space = bits.read(3)?;
+		let (subsampling_x, subsampling_y) = if color_space != RGB {
+			bits.skip(1)?;
+
+			if profile == 1 || profile == 3 {
+				(bits.flag()?, bits.flag()?)
+			}
+			else {
+				(true, true)
+			}
+		}
+		else {
+			(false, false)
+		};
+
+		Some(FrameHeader {
+			profile,
+			bit_depth,
+			subsampling_x,
+			subsampling_y,
+		})
+	}
+}
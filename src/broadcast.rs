@@ -0,0 +1,226 @@
+use std::convert::TryInto;
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	avio::Avio,
+	markers::{chunks, le32, text},
+	raw,
+};
+
+// Offsets into the fixed part of the chunks, from EBU Tech 3285 and AES46.
+const BEXT_HISTORY: usize = 602;
+const CART_TAG_TEXT: usize = 2048;
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BroadcastMetadata {
+	pub bext: Option<Bext>,
+	pub ixml: Option<IXml>,
+	pub cart: Option<Cart>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Bext {
+	pub description: Option<String>,
+	pub originator: Option<String>,
+	pub originator_reference: Option<String>,
+	pub origination_date: Option<String>,
+	pub origination_time: Option<String>,
+	// Samples since midnight of the first sample.
+	pub time_reference: u64,
+	pub version: u16,
+	pub umid: Option<String>,
+	pub loudness: Option<BextLoudness>,
+	pub coding_history: Vec<String>,
+}
+
+// Only present from version 2 of the chunk, in LUFS, LU and dBTP.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BextLoudness {
+	pub integrated: Option<f64>,
+	pub range: Option<f64>,
+	pub true_peak: Option<f64>,
+	pub max_momentary: Option<f64>,
+	pub max_short_term: Option<f64>,
+}
+
+// The handful of fields recorders agree on, plus the whole document for
+// everything else.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IXml {
+	pub project: Option<String>,
+	pub scene: Option<String>,
+	pub take: Option<String>,
+	pub tape: Option<String>,
+	pub note: Option<String>,
+	pub circled: Option<bool>,
+	pub xml: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Cart {
+	pub version: Option<String>,
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	pub cut_id: Option<String>,
+	pub client_id: Option<String>,
+	pub category: Option<String>,
+	pub classification: Option<String>,
+	pub out_cue: Option<String>,
+	pub start: Option<String>,
+	pub end: Option<String>,
+	pub producer: Option<String>,
+	pub producer_version: Option<String>,
+	pub user_defined: Option<String>,
+	pub url: Option<String>,
+	pub tag_text: Option<String>,
+}
+
+impl BroadcastMetadata {
+	// FFmpeg flattens some of bext into tags and ignores the rest.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if input.format().name() != "wav" {
+			return None;
+		}
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+		let mut broadcast = BroadcastMetadata::default();
+
+		chunks(&mut file, false, &[b"bext", b"iXML", b"cart"], |id, data| match id {
+			b"bext" => broadcast.bext = bext(&data),
+			b"iXML" => broadcast.ixml = ixml(&data),
+			_ => broadcast.cart = cart(&data),
+		})?;
+
+		if broadcast.bext.is_none() && broadcast.ixml.is_none() && broadcast.cart.is_none() {
+			None
+		}
+		else {
+			Some(broadcast)
+		}
+	}
+}
+
+fn field(data: &[u8], offset: usize, length: usize) -> Option<String> {
+	let value = text(data.get(offset..offset + length)?);
+	Some(value).filter(|value| !value.is_empty())
+}
+
+fn bext(data: &[u8]) -> Option<Bext> {
+	if data.len() < BEXT_HISTORY {
+		return None;
+	}
+
+	let version = u16::from_le_bytes(data[346..348].try_into().ok()?);
+	let umid = &data[348..412];
+
+	// Writers that don't measure leave the values zeroed, unknown single
+	// values are 0x7fff.
+	let level = |offset: usize| {
+		let value = i16::from_le_bytes(data[offset..offset + 2].try_into().ok()?);
+		Some(f64::from(value) / 100.0).filter(|_| value != 0x7fff)
+	};
+
+	let loudness = if version >= 2 && data[412..422].iter().any(|&b| b != 0) {
+		Some(BextLoudness {
+			integrated: level(412),
+			range: level(414),
+			true_peak: level(416),
+			max_momentary: level(418),
+			max_short_term: level(420),
+		})
+	}
+	else {
+		None
+	};
+
+	Some(Bext {
+		description: field(data, 0, 256),
+		originator: field(data, 256, 32),
+		originator_reference: field(data, 288, 32),
+		origination_date: field(data, 320, 10),
+		origination_time: field(data, 330, 8),
+		time_reference: u64::from(le32(data, 338)?) | (u64::from(le32(data, 342)?) << 32),
+		version,
+		umid: if umid.iter().all(|&b| b == 0) {
+			None
+		}
+		else {
+			Some(umid.iter().map(|b| format!("{:02x}", b)).collect())
+		},
+		loudness,
+		coding_history: text(&data[BEXT_HISTORY..])
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty())
+			.map(String::from)
+			.collect(),
+	})
+}
+
+fn ixml(data: &[u8]) -> Option<IXml> {
+	let xml = text(data);
+
+	if xml.is_empty() {
+		return None;
+	}
+
+	Some(IXml {
+		project: element(&xml, "PROJECT"),
+		scene: element(&xml, "SCENE"),
+		take: element(&xml, "TAKE"),
+		tape: element(&xml, "TAPE"),
+		note: element(&xml, "NOTE"),
+		circled: element(&xml, "CIRCLED").map(|value| value.eq_ignore_ascii_case("true")),
+		xml,
+	})
+}
+
+// iXML is flat enough at the top level that the first matching element is
+// the one we want, without pulling in an XML parser.
+fn element(xml: &str, name: &str) -> Option<String> {
+	let open = format!("<{}>", name);
+	let close = format!("</{}>", name);
+
+	let start = xml.find(&open)? + open.len();
+	let end = start + xml[start..].find(&close)?;
+	let value = xml[start..end].trim();
+
+	Some(value.to_owned()).filter(|value| !value.is_empty())
+}
+
+fn cart(data: &[u8]) -> Option<Cart> {
+	if data.len() < CART_TAG_TEXT {
+		return None;
+	}
+
+	let moment = |date: usize, time: usize| match (field(data, date, 10), field(data, time, 8)) {
+		(Some(date), Some(time)) => Some(format!("{} {}", date, time)),
+		(date, time) => date.or(time),
+	};
+
+	Some(Cart {
+		version: field(data, 0, 4),
+		title: field(data, 4, 64),
+		artist: field(data, 68, 64),
+		cut_id: field(data, 132, 64),
+		client_id: field(data, 196, 64),
+		category: field(data, 260, 64),
+		classification: field(data, 324, 64),
+		out_cue: field(data, 388, 64),
+		start: moment(452, 462),
+		end: moment(470, 480),
+		producer: field(data, 488, 64),
+		producer_version: field(data, 552, 64),
+		user_defined: field(data, 616, 64),
+		url: field(data, 1024, 1024),
+		tag_text: Some(text(&data[CART_TAG_TEXT..])).filter(|text| !text.is_empty()),
+	})
+}
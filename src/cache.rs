@@ -0,0 +1,208 @@
+use std::{
+	collections::HashMap,
+	fs::{self, File},
+	io::{self, BufReader, BufWriter},
+	path::{Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Metadata, SCHEMA_VERSION};
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheKey {
+	// Size and modification time, what `make` and `rsync` trust.
+	Identity,
+	// Also hashes the contents, for file systems where modification times
+	// lie. Every lookup reads the whole file.
+	#[cfg(feature = "hash")]
+	Content,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+struct Fingerprint {
+	size: u64,
+	modified: Option<u128>,
+	hash: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Entry {
+	fingerprint: Fingerprint,
+	metadata: Metadata,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stored {
+	// Caches written for another schema are dropped rather than misread, or
+	// handed back without the fields added since.
+	schema_version: u32,
+	entries: HashMap<PathBuf, Entry>,
+}
+
+// Probe results of local files, handed back until the file changes. Nothing
+// is written until `save`.
+#[derive(Clone, Debug)]
+pub struct MetadataCache {
+	path: Option<PathBuf>,
+	key: CacheKey,
+	entries: HashMap<PathBuf, Entry>,
+}
+
+impl MetadataCache {
+	pub fn new(key: CacheKey) -> Self {
+		MetadataCache {
+			path: None,
+			key,
+			entries: HashMap::new(),
+		}
+	}
+
+	// Starts empty when `path` doesn't exist yet, or was written under
+	// another `SCHEMA_VERSION`.
+	pub fn open<P: AsRef<Path>>(path: P, key: CacheKey) -> io::Result<Self> {
+		let entries = match File::open(&path) {
+			Ok(file) => serde_json::from_reader::<_, Stored>(BufReader::new(file))
+				.ok()
+				.filter(|stored| stored.schema_version == SCHEMA_VERSION)
+				.map(|stored| stored.entries)
+				.unwrap_or_default(),
+
+			Err(error) if error.kind() == io::ErrorKind::NotFound => HashMap::new(),
+			Err(error) => return Err(error),
+		};
+
+		Ok(MetadataCache {
+			path: Some(path.as_ref().to_path_buf()),
+			key,
+			entries,
+		})
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	// Only returns results for files that didn't change since.
+	pub fn get<P: AsRef<Path>>(&self, path: P) -> io::Result<Option<&Metadata>> {
+		let entry = match self.entries.get(path.as_ref()) {
+			Some(entry) => entry,
+			None => return Ok(None),
+		};
+
+		let fingerprint = fingerprint(path.as_ref(), self.key)?;
+		Ok(Some(&entry.metadata).filter(|_| entry.fingerprint == fingerprint))
+	}
+
+	pub fn insert<P: AsRef<Path>>(&mut self, path: P, metadata: Metadata) -> io::Result<()> {
+		let fingerprint = fingerprint(path.as_ref(), self.key)?;
+		self.entries.insert(
+			path.as_ref().to_path_buf(),
+			Entry {
+				fingerprint,
+				metadata,
+			},
+		);
+
+		Ok(())
+	}
+
+	pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Option<Metadata> {
+		self.entries.remove(path.as_ref()).map(|entry| entry.metadata)
+	}
+
+	// Returns the cached result, or probes the file and caches it.
+	pub fn probe<P: AsRef<Path>>(&mut self, path: P) -> ffmpeg::Result<Metadata> {
+		let path = path.as_ref();
+
+		if let Ok(Some(metadata)) = self.get(path) {
+			return Ok(metadata.clone());
+		}
+
+		let metadata = Metadata::probe(path)?;
+		// A file that can be probed but not stat'ed is just not cached.
+		let _ = self.insert(path, metadata.clone());
+
+		Ok(metadata)
+	}
+
+	// Forgets files that were deleted, or can't be read anymore.
+	pub fn prune(&mut self) {
+		self.entries.retain(|path, _| path.is_file());
+	}
+
+	// Writes next to the cache and renames, so a crash never leaves half a
+	// cache behind.
+	pub fn save(&self) -> io::Result<()> {
+		let path = match &self.path {
+			Some(path) => path,
+			None => return Ok(()),
+		};
+
+		let temporary = path.with_extension("tmp");
+		let stored = Stored {
+			schema_version: SCHEMA_VERSION,
+			entries: self.entries.clone(),
+		};
+
+		let mut writer = BufWriter::new(File::create(&temporary)?);
+		serde_json::to_writer(&mut writer, &stored)
+			.map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+		// Flushing by hand, dropping the writer would swallow a failed write.
+		writer.into_inner()?.sync_all()?;
+		fs::rename(temporary, path)
+	}
+}
+
+fn fingerprint(path: &Path, key: CacheKey) -> io::Result<Fingerprint> {
+	let metadata = fs::metadata(path)?;
+	let modified = || {
+		metadata
+			.modified()
+			.ok()
+			.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+			.map(|time| time.as_nanos())
+	};
+
+	let (modified, hash) = match key {
+		CacheKey::Identity => (modified(), None),
+
+		#[cfg(feature = "hash")]
+		CacheKey::Content => {
+			use std::hash::Hasher;
+
+			let mut hasher = twox_hash::XxHash64::with_seed(0);
+			io::copy(&mut File::open(path)?, &mut HashWriter(&mut hasher))?;
+
+			(None, Some(hasher.finish()))
+		}
+	};
+
+	Ok(Fingerprint {
+		size: metadata.len(),
+		modified,
+		hash,
+	})
+}
+
+#[cfg(feature = "hash")]
+struct HashWriter<'a, H>(&'a mut H);
+
+#[cfg(feature = "hash")]
+impl<'a, H: std::hash::Hasher> io::Write for HashWriter<'a, H> {
+	fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+		self.0.write(buffer);
+		Ok(buffer.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
@@ -0,0 +1,314 @@
+use std::{
+	ffi::{CStr, CString},
+	os::raw::{c_char, c_void},
+	ptr,
+};
+
+use ffmpeg::{codec, ffi, media};
+use serde::{Deserialize, Serialize};
+
+use crate::Video;
+
+// What the FFmpeg this was linked against was built with, which varies a lot
+// between distributions and static builds.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CodecCapability {
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
+	pub id: codec::Id,
+	pub name: String,
+	pub description: String,
+	pub kind: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FormatCapability {
+	pub name: String,
+	pub description: String,
+	pub extensions: Vec<String>,
+	pub mime_types: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProtocolCapability {
+	pub name: String,
+	pub input: bool,
+	pub output: bool,
+}
+
+fn string(value: *const c_char) -> String {
+	if value.is_null() {
+		String::new()
+	}
+	else {
+		unsafe { CStr::from_ptr(value).to_string_lossy().into_owned() }
+	}
+}
+
+fn list(value: *const c_char) -> Vec<String> {
+	string(value).split(',').filter(|v| !v.is_empty()).map(String::from).collect()
+}
+
+fn kind(medium: media::Type) -> &'static str {
+	match medium {
+		media::Type::Audio => "audio",
+		media::Type::Video => "video",
+		media::Type::Data => "data",
+		media::Type::Subtitle => "subtitle",
+		media::Type::Attachment => "attachment",
+		media::Type::Unknown => "unknown",
+	}
+}
+
+fn codecs(decoders: bool) -> Vec<CodecCapability> {
+	let mut codecs = Vec::new();
+	let mut opaque = ptr::null_mut::<c_void>();
+
+	unsafe {
+		loop {
+			let codec = ffi::av_codec_iterate(&mut opaque);
+			if codec.is_null() {
+				break;
+			}
+
+			let decoder = ffi::av_codec_is_decoder(codec) != 0;
+			if decoder != decoders {
+				continue;
+			}
+
+			codecs.push(CodecCapability {
+				id: codec::Id::from((*codec).id),
+				name: string((*codec).name),
+				description: string((*codec).long_name),
+				kind: kind(media::Type::from((*codec).type_)).into(),
+			});
+		}
+	}
+
+	codecs
+}
+
+pub fn decoders() -> Vec<CodecCapability> {
+	codecs(true)
+}
+
+pub fn encoders() -> Vec<CodecCapability> {
+	codecs(false)
+}
+
+pub fn demuxers() -> Vec<FormatCapability> {
+	let mut formats = Vec::new();
+	let mut opaque = ptr::null_mut::<c_void>();
+
+	unsafe {
+		loop {
+			let format = ffi::av_demuxer_iterate(&mut opaque);
+			if format.is_null() {
+				break;
+			}
+
+			formats.push(FormatCapability {
+				name: string((*format).name),
+				description: string((*format).long_name),
+				extensions: list((*format).extensions),
+				mime_types: list((*format).mime_type),
+			});
+		}
+	}
+
+	formats
+}
+
+pub fn muxers() -> Vec<FormatCapability> {
+	let mut formats = Vec::new();
+	let mut opaque = ptr::null_mut::<c_void>();
+
+	unsafe {
+		loop {
+			let format = ffi::av_muxer_iterate(&mut opaque);
+			if format.is_null() {
+				break;
+			}
+
+			formats.push(FormatCapability {
+				name: string((*format).name),
+				description: string((*format).long_name),
+				extensions: list((*format).extensions),
+				mime_types: list((*format).mime_type),
+			});
+		}
+	}
+
+	formats
+}
+
+pub fn protocols() -> Vec<ProtocolCapability> {
+	let names = |output| {
+		let mut names = Vec::new();
+		let mut opaque = ptr::null_mut::<c_void>();
+
+		unsafe {
+			loop {
+				let name = ffi::avio_enum_protocols(&mut opaque, output);
+				if name.is_null() {
+					break;
+				}
+
+				names.push(string(name));
+			}
+		}
+
+		names
+	};
+
+	let input = names(0);
+	let output = names(1);
+	let mut protocols = input
+		.iter()
+		.map(|name| ProtocolCapability {
+			name: name.clone(),
+			input: true,
+			output: output.contains(name),
+		})
+		.collect::<Vec<_>>();
+
+	protocols.extend(output.iter().filter(|name| !input.contains(name)).map(|name| {
+		ProtocolCapability {
+			name: name.clone(),
+			input: false,
+			output: true,
+		}
+	}));
+
+	protocols
+}
+
+pub fn can_decode(id: codec::Id) -> bool {
+	ffmpeg::decoder::find(id).is_some()
+}
+
+pub fn can_encode(id: codec::Id) -> bool {
+	ffmpeg::encoder::find(id).is_some()
+}
+
+pub fn can_demux(name: &str) -> bool {
+	CString::new(name).map_or(false, |name| unsafe {
+		!ffi::av_find_input_format(name.as_ptr()).is_null()
+	})
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HardwareDevice {
+	// As FFmpeg names them, e.g. `vaapi`, `cuda` or `videotoolbox`.
+	pub name: String,
+	// Whether a device of the kind could be opened on this machine, built in
+	// support alone doesn't mean there's a GPU behind it.
+	pub available: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HardwareDecoding {
+	pub device: String,
+	pub available: bool,
+	// Whether the bit depth and chroma subsampling are within what the
+	// hardware APIs decode. Newer GPUs may do more, never less.
+	pub supported: bool,
+}
+
+fn device_types() -> Vec<ffi::AVHWDeviceType> {
+	let mut types = Vec::new();
+	let mut kind = ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_NONE;
+
+	loop {
+		kind = unsafe { ffi::av_hwdevice_iterate_types(kind) };
+		if kind == ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_NONE {
+			break;
+		}
+
+		types.push(kind);
+	}
+
+	types
+}
+
+fn device(kind: ffi::AVHWDeviceType) -> HardwareDevice {
+	unsafe {
+		let mut context = ptr::null_mut();
+		let available =
+			ffi::av_hwdevice_ctx_create(&mut context, kind, ptr::null(), ptr::null_mut(), 0) >= 0;
+		ffi::av_buffer_unref(&mut context);
+
+		HardwareDevice {
+			name: string(ffi::av_hwdevice_get_type_name(kind)),
+			available,
+		}
+	}
+}
+
+// Opens a device of every kind FFmpeg was built with, which takes a moment
+// on machines with drivers installed.
+pub fn hardware_devices() -> Vec<HardwareDevice> {
+	device_types().into_iter().map(device).collect()
+}
+
+// The devices the decoder for `video` can use, and whether its format is one
+// they handle.
+pub fn hardware_decoding(video: &Video) -> Vec<HardwareDecoding> {
+	let codec = unsafe { ffi::avcodec_find_decoder(video.codec.id.into()) };
+	if codec.is_null() {
+		return Vec::new();
+	}
+
+	let mut kinds = Vec::new();
+	for index in 0.. {
+		let config = unsafe { ffi::avcodec_get_hw_config(codec, index) };
+		if config.is_null() {
+			break;
+		}
+
+		let methods = unsafe { (*config).methods } as u32;
+		let context = methods & ffi::AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX as u32 != 0;
+		let kind = unsafe { (*config).device_type };
+
+		if context && !kinds.contains(&kind) {
+			kinds.push(kind);
+		}
+	}
+
+	kinds
+		.into_iter()
+		.map(|kind| {
+			let device = device(kind);
+
+			HardwareDecoding {
+				supported: supported(video, &device.name),
+				device: device.name,
+				available: device.available,
+			}
+		})
+		.collect()
+}
+
+// What every hardware API of the last few years decodes, as opposed to what
+// the codecs allow.
+fn supported(video: &Video, device: &str) -> bool {
+	let descriptor = unsafe { ffi::av_pix_fmt_desc_get(video.format.into()) };
+	let subsampled = descriptor.is_null()
+		|| unsafe { (*descriptor).log2_chroma_w == 1 && (*descriptor).log2_chroma_h == 1 };
+	let depth = video.bit_depth.unwrap_or(8);
+
+	match video.codec.id {
+		codec::Id::H264 => subsampled && depth <= 8,
+		codec::Id::HEVC | codec::Id::VP9 | codec::Id::AV1 => {
+			// NVDEC and VideoToolbox go further, up to 12 bits and 4:4:4.
+			let extended = matches!(device, "cuda" | "videotoolbox");
+			(subsampled || extended) && depth <= if extended { 12 } else { 10 }
+		}
+
+		_ => subsampled && depth <= 8,
+	}
+}
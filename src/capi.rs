@@ -0,0 +1,67 @@
+use std::{
+	ffi::{CStr, CString},
+	os::raw::{c_char, c_int},
+	panic, ptr,
+};
+
+use crate::Metadata;
+
+/// Probes the file or URL at `path` and stores the metadata serialized as a
+/// NUL-terminated JSON string in `json_out`, to be released with
+/// `avmetadata_free`.
+///
+/// Returns zero on success or a negative FFmpeg error code, `AVERROR_BUG` when
+/// the probe panicked.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string and `json_out` must point to
+/// writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn avmetadata_probe(
+	path: *const c_char,
+	json_out: *mut *mut c_char,
+) -> c_int {
+	if path.is_null() || json_out.is_null() {
+		return -libc::EINVAL;
+	}
+
+	*json_out = ptr::null_mut();
+
+	let path = match CStr::from_ptr(path).to_str() {
+		Ok(path) => path,
+		Err(_) => return -libc::EINVAL,
+	};
+
+	// Unwinding into C is undefined behavior.
+	match panic::catch_unwind(|| probe(path)) {
+		Ok(Ok(json)) => {
+			*json_out = json.into_raw();
+			0
+		}
+		Ok(Err(error)) => error,
+		Err(_) => ffmpeg::Error::Bug.into(),
+	}
+}
+
+fn probe(path: &str) -> Result<CString, c_int> {
+	let metadata = Metadata::probe(path).map_err(c_int::from)?;
+
+	serde_json::to_string(&metadata)
+		.ok()
+		.and_then(|json| CString::new(json).ok())
+		.ok_or(-libc::ENOMEM)
+}
+
+/// Releases a string returned by `avmetadata_probe`.
+///
+/// # Safety
+///
+/// `json` must have been returned by `avmetadata_probe` and not freed
+/// already, or be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn avmetadata_free(json: *mut c_char) {
+	if !json.is_null() {
+		drop(CString::from_raw(json));
+	}
+}
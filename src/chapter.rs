@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use ffmpeg::{format::context::Input, Rational};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Chapter {
+	pub id: i64,
+	#[cfg_attr(feature = "schemars", schemars(with = "crate::schema::Rational"))]
+	pub time_base: Rational,
+	pub start: i64,
+	pub end: i64,
+	pub title: Option<String>,
+	pub details: HashMap<String, String>,
+}
+
+impl Chapter {
+	pub fn new(id: i64, start: f64, end: f64, title: Option<&str>) -> Self {
+		Chapter {
+			id,
+			time_base: Rational::new(1, 1000),
+			start: (start * 1000.0).round() as i64,
+			end: (end * 1000.0).round() as i64,
+			title: title.map(String::from),
+			details: HashMap::new(),
+		}
+	}
+
+	pub fn find(input: &Input) -> Vec<Self> {
+		input
+			.chapters()
+			.map(|chapter| Chapter {
+				id: i64::from(chapter.id()),
+				time_base: chapter.time_base(),
+				start: chapter.start(),
+				end: chapter.end(),
+				title: chapter.metadata().get("title").map(String::from),
+				details: chapter
+					.metadata()
+					.iter()
+					.map(|(a, b)| (a.into(), b.into()))
+					.collect(),
+			})
+			.collect()
+	}
+
+	// Parses OGM style chapters, as used by mkvmerge and most chaptering tools:
+	//
+	// ```text
+	// CHAPTER01=00:00:00.000
+	// CHAPTER01NAME=Intro
+	// ```
+	//
+	// The format has no end times, every chapter lasts until the next one and
+	// the last one is left empty.
+	pub fn parse_ogm(text: &str) -> Option<Vec<Self>> {
+		let mut starts = Vec::new();
+		let mut titles = HashMap::new();
+
+		for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+			let (key, value) = line.split_once('=')?;
+			let key = key.trim().strip_prefix("CHAPTER")?;
+
+			if let Some(number) = key.strip_suffix("NAME") {
+				titles.insert(number.to_owned(), value.trim().to_owned());
+			}
+			else {
+				starts.push((key.to_owned(), timestamp(value.trim())?));
+			}
+		}
+
+		if starts.is_empty() {
+			return None;
+		}
+
+		let mut chapters = starts
+			.iter()
+			.enumerate()
+			.map(|(id, (number, start))| {
+				Chapter::new(id as i64, *start, *start, titles.get(number).map(String::as_str))
+			})
+			.collect::<Vec<_>>();
+
+		let ends = chapters.iter().skip(1).map(|chapter| chapter.start).collect::<Vec<_>>();
+		for (chapter, end) in chapters.iter_mut().zip(ends) {
+			chapter.end = end;
+		}
+
+		Some(chapters)
+	}
+
+	pub fn start_seconds(&self) -> f64 {
+		self.start as f64 * f64::from(self.time_base)
+	}
+
+	pub fn end_seconds(&self) -> f64 {
+		self.end as f64 * f64::from(self.time_base)
+	}
+
+	pub fn duration_seconds(&self) -> f64 {
+		self.end_seconds() - self.start_seconds()
+	}
+}
+
+fn timestamp(value: &str) -> Option<f64> {
+	let mut parts = value.splitn(3, ':');
+	let hours = parts.next()?.parse::<u32>().ok()?;
+	let minutes = parts.next()?.parse::<u32>().ok()?;
+	let seconds = parts.next()?.parse::<f64>().ok()?;
+
+	// In floating point, hours past a million would overflow as integers.
+	Some(f64::from(hours) * 3600.0 + f64::from(minutes) * 60.0 + seconds)
+}
@@ -0,0 +1,230 @@
+use std::io::Read;
+
+use chrono::{DateTime, TimeZone, Utc};
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, manifest::{self, Manifest}, raw, tags};
+
+// Upper bound on how much of a transport stream is scanned for time tables,
+// broadcasters send them at least every 30 seconds. The stream is read a
+// packet at a time, never all at once.
+const SCAN: u64 = 64 * 1024 * 1024;
+
+const TS: usize = 188;
+const TIME_PID: u16 = 0x14;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WallClock {
+	pub source: ClockSource,
+	pub anchors: Vec<Anchor>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ClockSource {
+	ProgramDateTime,
+	TimeTables,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Anchor {
+	pub media: f64,
+	pub wall: DateTime<Utc>,
+}
+
+impl WallClock {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let url = raw::url(input)?;
+		let start = input.start_time() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+		let start = if start.is_finite() && input.start_time() != ffmpeg::ffi::AV_NOPTS_VALUE {
+			start
+		}
+		else {
+			0.0
+		};
+
+		let clock = match input.format().name() {
+			"hls" | "applehttp" => playlist(&url, start)?,
+			name if name.split(',').any(|name| name == "mpegts") => tables(&url)?,
+			_ => return None,
+		};
+
+		if clock.anchors.is_empty() {
+			None
+		}
+		else {
+			Some(clock)
+		}
+	}
+
+	// Maps a media timestamp to wall-clock time using the closest preceding
+	// anchor, or the first one for timestamps before it.
+	pub fn at(&self, media: f64) -> Option<DateTime<Utc>> {
+		let anchor = self
+			.anchors
+			.iter()
+			.rev()
+			.find(|anchor| anchor.media <= media)
+			.or_else(|| self.anchors.first())?;
+
+		let offset = ((media - anchor.media) * 1_000_000.0) as i64;
+		Some(anchor.wall + chrono::Duration::microseconds(offset))
+	}
+}
+
+fn playlist(url: &str, start: f64) -> Option<WallClock> {
+	let text = manifest::read(url).ok()?;
+
+	// Master playlists only point at the media playlists, which are the ones
+	// carrying the program date time, so the first variant is followed.
+	let text = if text.contains("#EXT-X-STREAM-INF") {
+		let manifest = Manifest::parse(url, &text)?;
+		manifest::read(&manifest.variants.into_iter().find_map(|variant| variant.url)?).ok()?
+	}
+	else {
+		text
+	};
+
+	let mut media = start;
+	let mut anchors = Vec::new();
+
+	for line in text.lines().map(str::trim) {
+		if let Some(value) = line.strip_prefix("#EXT-X-PROGRAM-DATE-TIME:") {
+			if let Some(wall) = tags::date::parse(value) {
+				anchors.push(Anchor { media, wall });
+			}
+		}
+		else if let Some(value) = line.strip_prefix("#EXTINF:") {
+			media += value.split(',').next()?.trim().parse::<f64>().ok()?;
+		}
+	}
+
+	Some(WallClock {
+		source: ClockSource::ProgramDateTime,
+		anchors,
+	})
+}
+
+// Only the first program is followed, anchors pair its PCR with the time
+// tables that come after it.
+fn tables(url: &str) -> Option<WallClock> {
+	let mut reader = Avio::open(url).ok()?.take(SCAN);
+	let mut head = [0; 2 * TS];
+	reader.read_exact(&mut head).ok()?;
+
+	let offset = (0..TS).find(|&i| head[i] == 0x47 && head[i + TS] == 0x47)?;
+	let mut reader = (&head[offset..]).chain(reader);
+	let mut packet = [0; TS];
+
+	let mut pmt = None;
+	let mut pcr_pid = None;
+	let mut clock = Pcr::default();
+	let mut pcr = None;
+	let mut anchors = Vec::new();
+
+	while reader.read_exact(&mut packet).is_ok() {
+		if packet[0] != 0x47 {
+			continue;
+		}
+
+		let pid = (u16::from(packet[1] & 0x1f) << 8) | u16::from(packet[2]);
+		let start = packet[1] & 0x40 != 0;
+		let control = (packet[3] >> 4) & 0x3;
+		let mut payload = 4;
+
+		if control & 0x2 != 0 {
+			let length = usize::from(packet[4]);
+
+			if Some(pid) == pcr_pid && length > 0 && packet[5] & 0x10 != 0 && length >= 7 {
+				let base = (u64::from(packet[6]) << 25)
+					| (u64::from(packet[7]) << 17)
+					| (u64::from(packet[8]) << 9)
+					| (u64::from(packet[9]) << 1)
+					| (u64::from(packet[10]) >> 7);
+
+				pcr = Some(clock.extend(base) as f64 / 90_000.0);
+			}
+
+			payload += 1 + length;
+		}
+
+		if !start || control & 0x1 == 0 || payload >= TS {
+			continue;
+		}
+
+		let pointer = usize::from(packet[payload]);
+		let section = match packet.get(payload + 1 + pointer..) {
+			Some(section) if section.len() >= 12 => section,
+			_ => continue,
+		};
+
+		match (pid, section[0]) {
+			// The first program of the PAT, program 0 points at the NIT instead.
+			(0, 0x00) if pmt.is_none() => {
+				let length = (usize::from(section[1] & 0x0f) << 8) | usize::from(section[2]);
+				let end = (3 + length).saturating_sub(4).min(section.len());
+
+				pmt = section
+					.get(8..end)
+					.into_iter()
+					.flat_map(|programs| programs.chunks_exact(4))
+					.find(|program| program[0] != 0 || program[1] != 0)
+					.map(|program| (u16::from(program[2] & 0x1f) << 8) | u16::from(program[3]));
+			}
+
+			(pid, 0x02) if Some(pid) == pmt && pcr_pid.is_none() => {
+				pcr_pid = Some((u16::from(section[8] & 0x1f) << 8) | u16::from(section[9]));
+			}
+
+			// Both the TDT and the TOT start with the UTC time right after the
+			// section header.
+			(TIME_PID, 0x70) | (TIME_PID, 0x73) => {
+				if let (Some(media), Some(wall)) = (pcr, utc(&section[3..8])) {
+					anchors.push(Anchor { media, wall });
+				}
+			}
+
+			_ => (),
+		}
+	}
+
+	Some(WallClock {
+		source: ClockSource::TimeTables,
+		anchors,
+	})
+}
+
+// The PCR base is 33 bits of a 90 kHz clock, it wraps around every 26 hours
+// or so.
+#[derive(Default)]
+struct Pcr {
+	last: Option<u64>,
+	wraps: u64,
+}
+
+impl Pcr {
+	const WRAP: u64 = 1 << 33;
+
+	fn extend(&mut self, base: u64) -> u64 {
+		if self.last.map_or(false, |last| base + Self::WRAP / 2 < last) {
+			self.wraps += Self::WRAP;
+		}
+
+		self.last = Some(base);
+		base + self.wraps
+	}
+}
+
+// Modified Julian Date followed by BCD encoded hours, minutes and seconds.
+fn utc(data: &[u8]) -> Option<DateTime<Utc>> {
+	let mjd = i64::from(u16::from_be_bytes([data[0], data[1]]));
+	let bcd = |b: u8| i64::from((b >> 4) * 10 + (b & 0xf));
+	let seconds = (mjd - 40_587) * 86_400 + bcd(data[2]) * 3600 + bcd(data[3]) * 60 + bcd(data[4]);
+
+	Utc.timestamp_opt(seconds, 0).single()
+}
@@ -0,0 +1,88 @@
+use ffmpeg::{
+	codec,
+	format::{context::Input, stream::Stream},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	bitstream::{av1, vp9},
+	probe, raw,
+};
+
+// Raw streams may not show a key frame or sequence header right away.
+const PACKETS: usize = 8;
+
+// The AV1 sequence header or VP9 key frame header, which is what hardware
+// decoders check before accepting a stream.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VideoCodecConfig {
+	pub profile: u8,
+	// seq_level_idx and seq_tier of the first operating point, AV1 only.
+	pub level: Option<u8>,
+	pub tier: Option<u8>,
+	pub bit_depth: u8,
+	pub monochrome: bool,
+	pub subsampling_x: bool,
+	pub subsampling_y: bool,
+	// VP9 has neither.
+	pub film_grain: Option<bool>,
+	pub superres: Option<bool>,
+}
+
+impl VideoCodecConfig {
+	pub fn find(input: &Input, stream: &Stream) -> Option<Self> {
+		Self::with_packets(stream, &probe::Packets::new(input))
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub(crate) fn with_packets(stream: &Stream, packets: &probe::Packets) -> Option<Self> {
+		let parameters = stream.parameters();
+
+		match parameters.id() {
+			codec::Id::AV1 => {
+				let header = av1::sequence_header(raw::extradata(&parameters)).or_else(|| {
+					packets
+						.stream(stream.index())
+						.iter()
+						.take(PACKETS)
+						.find_map(|packet| av1::sequence_header(packet))
+				})?;
+
+				Some(VideoCodecConfig {
+					profile: header.profile,
+					level: Some(header.level),
+					tier: Some(header.tier),
+					bit_depth: header.bit_depth,
+					monochrome: header.monochrome,
+					subsampling_x: header.subsampling_x,
+					subsampling_y: header.subsampling_y,
+					film_grain: Some(header.film_grain),
+					superres: Some(header.superres),
+				})
+			}
+
+			codec::Id::VP9 => {
+				let header = packets
+					.stream(stream.index())
+					.iter()
+					.take(PACKETS)
+					.find_map(|packet| vp9::FrameHeader::parse(packet))?;
+
+				Some(VideoCodecConfig {
+					profile: header.profile,
+					level: None,
+					tier: None,
+					bit_depth: header.bit_depth,
+					monochrome: false,
+					subsampling_x: header.subsampling_x,
+					subsampling_y: header.subsampling_y,
+					film_grain: None,
+					superres: None,
+				})
+			}
+
+			_ => None,
+		}
+	}
+}
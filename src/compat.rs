@@ -0,0 +1,121 @@
+use std::slice;
+use ffmpeg::{
+	codec, decoder,
+	ffi::{self, AVPacketSideDataType},
+	format::stream::Stream,
+	media, ChannelLayout,
+};
+
+use crate::probe;
+
+pub fn medium(stream: &Stream) -> media::Type {
+	stream.parameters().medium()
+}
+
+pub fn decoder(stream: &Stream) -> ffmpeg::Result<decoder::Decoder> {
+	Ok(codec::Context::from_parameters(stream.parameters())?.decoder())
+}
+
+// Without opening the decoder the getters only see what the demuxer put in
+// the codec parameters, anything else stays at its default.
+pub fn audio(stream: &Stream) -> ffmpeg::Result<decoder::Audio> {
+	if probe::headers_only() {
+		Ok(decoder::Audio(decoder::Opened(decoder(stream)?)))
+	}
+	else {
+		decoder(stream)?.audio()
+	}
+}
+
+pub fn video(stream: &Stream) -> ffmpeg::Result<decoder::Video> {
+	if probe::headers_only() {
+		Ok(decoder::Video(decoder::Opened(decoder(stream)?)))
+	}
+	else {
+		decoder(stream)?.video()
+	}
+}
+
+pub fn channels(audio: &decoder::Audio) -> u16 {
+	#[cfg(not(ffmpeg_6))]
+	{
+		audio.channels()
+	}
+
+	#[cfg(ffmpeg_6)]
+	unsafe {
+		(*audio.as_ptr()).ch_layout.nb_channels as u16
+	}
+}
+
+pub fn channel_layout(audio: &decoder::Audio) -> ChannelLayout {
+	#[cfg(not(ffmpeg_6))]
+	{
+		audio.channel_layout()
+	}
+
+	#[cfg(ffmpeg_6)]
+	unsafe {
+		let layout = &(*audio.as_ptr()).ch_layout;
+
+		if layout.order == ffmpeg::ffi::AVChannelOrder::AV_CHANNEL_ORDER_NATIVE {
+			ChannelLayout::from_bits_truncate(layout.u.mask)
+		}
+		else {
+			ChannelLayout::default(layout.nb_channels)
+		}
+	}
+}
+
+// Stream level side data moved into the codec parameters with FFmpeg 7.
+pub fn side_data(stream: &Stream) -> Vec<AVPacketSideDataType> {
+	unsafe {
+		#[cfg(not(ffmpeg_7))]
+		let (data, count) = {
+			let stream = stream.as_ptr();
+			((*stream).side_data, (*stream).nb_side_data)
+		};
+
+		#[cfg(ffmpeg_7)]
+		let (data, count) = {
+			let parameters = (*stream.as_ptr()).codecpar;
+			((*parameters).coded_side_data, (*parameters).nb_coded_side_data)
+		};
+
+		if data.is_null() || count <= 0 {
+			return Vec::new();
+		}
+
+		slice::from_raw_parts(data, count as usize).iter().map(|data| data.type_).collect()
+	}
+}
+
+// The demuxer's seek index as (timestamp, position, size, keyframe), which
+// became opaque with FFmpeg 5.
+pub fn index_entries(stream: &Stream) -> Vec<(i64, i64, i32, bool)> {
+	unsafe {
+		let stream = stream.as_ptr() as *mut ffi::AVStream;
+		let entry = |entry: &ffi::AVIndexEntry| {
+			let key = entry.flags() as i32 & ffi::AVINDEX_KEYFRAME as i32 != 0;
+			(entry.timestamp, entry.pos, entry.size() as i32, key)
+		};
+
+		#[cfg(not(ffmpeg_5))]
+		{
+			let (entries, count) = ((*stream).index_entries, (*stream).nb_index_entries);
+			if entries.is_null() || count <= 0 {
+				return Vec::new();
+			}
+
+			slice::from_raw_parts(entries, count as usize).iter().map(entry).collect()
+		}
+
+		#[cfg(ffmpeg_5)]
+		{
+			(0..ffi::avformat_index_get_entries_count(stream))
+				.filter_map(|index| ffi::avformat_index_get_entry(stream, index).as_ref())
+				.map(entry)
+				.collect()
+		}
+	}
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, Metadata, Stream};
+
+pub(crate) const KINDS: &[&str] = &["video", "audio", "subtitle", "data", "attachment", "unknown"];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DiffThresholds {
+	pub duration: f64,
+}
+
+impl Default for DiffThresholds {
+	fn default() -> Self {
+		DiffThresholds { duration: 0.1 }
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MetadataDiff {
+	pub changes: Vec<Change>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Change {
+	Format { before: String, after: String },
+	StreamCount { kind: String, before: usize, after: usize },
+	Lost { kind: String, stream: usize, language: Option<String> },
+	Added { kind: String, stream: usize, language: Option<String> },
+	Codec { stream: usize, before: String, after: String },
+	Resolution { stream: usize, before: (u32, u32), after: (u32, u32) },
+	Channels { stream: usize, before: u16, after: u16 },
+	SampleRate { stream: usize, before: u32, after: u32 },
+	Language { stream: usize, before: Option<String>, after: Option<String> },
+	Duration { stream: Option<usize>, before: f64, after: f64 },
+	Chapters { before: usize, after: usize },
+}
+
+impl MetadataDiff {
+	// Streams are paired by kind and order, the nth audio track of one file with
+	// the nth audio track of the other, since remuxing and transcoding are free
+	// to renumber them. Reported indices are the ones from `before`, `Added`
+	// being the exception.
+	pub fn new(before: &Metadata, after: &Metadata, thresholds: &DiffThresholds) -> Self {
+		let mut changes = Vec::new();
+
+		if before.format.name != after.format.name {
+			changes.push(Change::Format {
+				before: before.format.name.clone(),
+				after: after.format.name.clone(),
+			});
+		}
+
+		if let (Some(a), Some(b)) = (before.duration_seconds(), after.duration_seconds()) {
+			if (a - b).abs() > thresholds.duration {
+				changes.push(Change::Duration {
+					stream: None,
+					before: a,
+					after: b,
+				});
+			}
+		}
+
+		for kind in KINDS {
+			let a = kind_of(before, kind);
+			let b = kind_of(after, kind);
+
+			if a.len() != b.len() {
+				changes.push(Change::StreamCount {
+					kind: kind.to_string(),
+					before: a.len(),
+					after: b.len(),
+				});
+			}
+
+			for stream in a.iter().skip(b.len()) {
+				changes.push(Change::Lost {
+					kind: kind.to_string(),
+					stream: stream.index,
+					language: language(stream),
+				});
+			}
+
+			for stream in b.iter().skip(a.len()) {
+				changes.push(Change::Added {
+					kind: kind.to_string(),
+					stream: stream.index,
+					language: language(stream),
+				});
+			}
+
+			for (a, b) in a.iter().zip(&b) {
+				compare(a, b, thresholds, &mut changes);
+			}
+		}
+
+		if before.chapters.len() != after.chapters.len() {
+			changes.push(Change::Chapters {
+				before: before.chapters.len(),
+				after: after.chapters.len(),
+			});
+		}
+
+		MetadataDiff { changes }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.changes.is_empty()
+	}
+
+	// Whether anything went missing, as opposed to e.g. a codec change which is
+	// expected after a transcode.
+	pub fn has_losses(&self) -> bool {
+		self.changes.iter().any(|change| match change {
+			Change::Lost { .. } | Change::Duration { .. } => true,
+			Change::StreamCount { before, after, .. } | Change::Chapters { before, after } => {
+				after < before
+			}
+
+			Change::Channels { before, after, .. } => after < before,
+			_ => false,
+		})
+	}
+}
+
+pub(crate) fn kind_of<'a>(metadata: &'a Metadata, kind: &str) -> Vec<&'a Stream> {
+	metadata
+		.streams
+		.iter()
+		.filter(|stream| stream.content.kind() == kind)
+		.collect()
+}
+
+fn language(stream: &Stream) -> Option<String> {
+	stream.language.as_ref().map(|language| language.code.clone())
+}
+
+fn compare(
+	before: &Stream,
+	after: &Stream,
+	thresholds: &DiffThresholds,
+	changes: &mut Vec<Change>,
+) {
+	let stream = before.index;
+
+	if let (Some(a), Some(b)) = (before.content.codec(), after.content.codec()) {
+		if a.id != b.id {
+			changes.push(Change::Codec {
+				stream,
+				before: a.name.clone(),
+				after: b.name.clone(),
+			});
+		}
+	}
+
+	match (&before.content, &after.content) {
+		(Content::Video(a), Content::Video(b)) => {
+			if (a.width, a.height) != (b.width, b.height) {
+				changes.push(Change::Resolution {
+					stream,
+					before: (a.width, a.height),
+					after: (b.width, b.height),
+				});
+			}
+		}
+
+		(Content::Audio(a), Content::Audio(b)) => {
+			if a.channels != b.channels {
+				changes.push(Change::Channels {
+					stream,
+					before: a.channels,
+					after: b.channels,
+				});
+			}
+
+			if a.sample_rate != b.sample_rate {
+				changes.push(Change::SampleRate {
+					stream,
+					before: a.sample_rate,
+					after: b.sample_rate,
+				});
+			}
+		}
+
+		_ => (),
+	}
+
+	if language(before) != language(after) {
+		changes.push(Change::Language {
+			stream,
+			before: language(before),
+			after: language(after),
+		});
+	}
+
+	if let (Some(a), Some(b)) = (before.duration_seconds(), after.duration_seconds()) {
+		if (a - b).abs() > thresholds.duration {
+			changes.push(Change::Duration {
+				stream: Some(stream),
+				before: a,
+				after: b,
+			});
+		}
+	}
+}
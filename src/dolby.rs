@@ -0,0 +1,98 @@
+use ffmpeg::{
+	codec,
+	format::{context::Input, stream::Stream},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{bitstream::ac3, probe};
+
+// Linear gains for the mix level codes, reserved codes map to the level the
+// specification says to fall back to.
+const CENTER: [f64; 4] = [0.707, 0.595, 0.5, 0.595];
+const SURROUND: [f64; 4] = [0.707, 0.5, 0.0, 0.5];
+const EXTENDED_CENTER: [f64; 8] = [1.414, 1.189, 1.0, 0.841, 0.707, 0.595, 0.5, 0.0];
+const EXTENDED_SURROUND: [f64; 8] = [0.841, 0.841, 0.841, 0.841, 0.707, 0.595, 0.5, 0.0];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DolbyMetadata {
+	pub eac3: bool,
+	// Average dialogue level in dBFS, between -1 and -31.
+	pub dialnorm: i8,
+	pub bitstream_mode: BitstreamMode,
+	// The audio coding mode, e.g. 2 for stereo or 7 for 3/2.
+	pub acmod: u8,
+	pub lfe: bool,
+	pub dolby_surround: Option<bool>,
+	// Downmix gains, 0 drops the channel. Plain AC-3 only has the Lo/Ro ones.
+	pub center_mix_level: Option<f64>,
+	pub surround_mix_level: Option<f64>,
+	pub ltrt_center_mix_level: Option<f64>,
+	pub ltrt_surround_mix_level: Option<f64>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum BitstreamMode {
+	CompleteMain,
+	MusicAndEffects,
+	VisuallyImpaired,
+	HearingImpaired,
+	Dialogue,
+	Commentary,
+	Emergency,
+	VoiceOver,
+	Karaoke,
+}
+
+impl DolbyMetadata {
+	pub fn find(input: &Input, stream: &Stream) -> Option<Self> {
+		Self::with_packets(stream, &probe::Packets::new(input))
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub(crate) fn with_packets(stream: &Stream, packets: &probe::Packets) -> Option<Self> {
+		let id = stream.parameters().id();
+
+		if id != codec::Id::AC3 && id != codec::Id::EAC3 {
+			return None;
+		}
+
+		let bsi = packets
+			.stream(stream.index())
+			.iter()
+			.take(1)
+			.flat_map(|packet| ac3::frames(packet))
+			.find(|bsi| bsi.stream_type != 1)?;
+
+		Some(DolbyMetadata {
+			eac3: bsi.is_eac3(),
+			dialnorm: if bsi.dialnorm == 0 { -31 } else { -(bsi.dialnorm as i8) },
+			bitstream_mode: match bsi.bsmod {
+				0 => BitstreamMode::CompleteMain,
+				1 => BitstreamMode::MusicAndEffects,
+				2 => BitstreamMode::VisuallyImpaired,
+				3 => BitstreamMode::HearingImpaired,
+				4 => BitstreamMode::Dialogue,
+				5 => BitstreamMode::Commentary,
+				6 => BitstreamMode::Emergency,
+				_ if bsi.acmod == 1 => BitstreamMode::VoiceOver,
+				_ => BitstreamMode::Karaoke,
+			},
+			acmod: bsi.acmod,
+			lfe: bsi.lfe,
+			dolby_surround: bsi.dsurmod.filter(|&mode| mode == 1 || mode == 2).map(|mode| mode == 2),
+			center_mix_level: gain(&EXTENDED_CENTER, bsi.lorocmixlev)
+				.or_else(|| gain(&CENTER, bsi.cmixlev)),
+			surround_mix_level: gain(&EXTENDED_SURROUND, bsi.lorosurmixlev)
+				.or_else(|| gain(&SURROUND, bsi.surmixlev)),
+			ltrt_center_mix_level: gain(&EXTENDED_CENTER, bsi.ltrtcmixlev),
+			ltrt_surround_mix_level: gain(&EXTENDED_SURROUND, bsi.ltrtsurmixlev),
+		})
+	}
+}
+
+fn gain(table: &[f64], code: Option<u8>) -> Option<f64> {
+	code.and_then(|code| table.get(code as usize).copied())
+}
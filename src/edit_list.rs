@@ -0,0 +1,183 @@
+use std::{collections::HashMap, convert::TryInto};
+
+use ffmpeg::{format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use crate::{compat, movie::Movie, raw};
+
+// The presentation timeline an MP4 edit list makes out of a track, which
+// FFmpeg applies without saying so.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EditList {
+	pub edits: Vec<Edit>,
+	// When the first sample is presented, in seconds, after leading empty
+	// edits.
+	pub start: f64,
+	// Zero for fragmented files, which leave it to the fragments.
+	pub duration: f64,
+	// Media cut from the start, in seconds.
+	pub skipped: f64,
+	// The same for audio, in samples, which is usually encoder priming.
+	pub priming_samples: Option<u64>,
+	// Composition offsets going below zero, so some samples are presented
+	// before they are decoded.
+	pub negative_cts: bool,
+	pub issues: Vec<EditListIssue>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Edit {
+	pub duration: f64,
+	// `None` for empty edits, which present nothing for their duration.
+	pub media_time: Option<f64>,
+	pub rate: f64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum EditListIssue {
+	// More than one edit presents media, players disagree on these.
+	MultipleEdits,
+	// An empty edit after the media started, i.e. a gap in the middle.
+	Gap,
+	// A rate other than 1, including 0 for dwelling on a single frame.
+	Rate,
+	// An edit points past the end of the media.
+	BeyondMedia,
+}
+
+// Edit lists per stream index, for the tracks that have one.
+pub(crate) fn find(input: &Input, movie: &Movie) -> HashMap<usize, EditList> {
+	let timescale = match movie.timescale() {
+		Some(timescale) => f64::from(timescale),
+		None => return HashMap::new(),
+	};
+
+	let tracks = movie
+		.tracks()
+		.into_iter()
+		.filter_map(|track| {
+			let elst = track.find(&[b"edts", b"elst"])?;
+			let (media_scale, media_duration) = track.media()?;
+			let ctts = track.find(&[b"mdia", b"minf", b"stbl", b"ctts"]);
+			let cslg = track.find(&[b"mdia", b"minf", b"stbl", b"cslg"]);
+
+			Some((track.id, parse(elst, timescale, media_scale, media_duration, ctts, cslg)?))
+		})
+		.collect::<HashMap<_, _>>();
+
+	// The demuxer uses the track ID as the stream ID.
+	input
+		.streams()
+		.filter_map(|stream| {
+			let mut list = tracks.get(&(stream.id() as u32)).cloned()?;
+			let rate = raw::sample_rate(&stream.parameters());
+
+			if compat::medium(&stream) == media::Type::Audio && rate > 0 && list.skipped > 0.0 {
+				list.priming_samples = Some((list.skipped * f64::from(rate)).round() as u64);
+			}
+
+			Some((stream.index(), list))
+		})
+		.collect()
+}
+
+fn parse(
+	elst: &[u8],
+	timescale: f64,
+	media_scale: u32,
+	media_duration: u64,
+	ctts: Option<&[u8]>,
+	cslg: Option<&[u8]>,
+) -> Option<EditList> {
+	let version = *elst.first()?;
+	let count = u32::from_be_bytes(elst.get(4..8)?.try_into().unwrap()) as usize;
+	let size = if version == 1 { 20 } else { 12 };
+	let media_scale = f64::from(media_scale);
+	let media_duration = media_duration as f64 / media_scale;
+
+	let mut edits = Vec::new();
+	let mut issues = Vec::new();
+	let mut issue = |issue: EditListIssue| {
+		if !issues.contains(&issue) {
+			issues.push(issue);
+		}
+	};
+
+	for entry in elst.get(8..)?.chunks_exact(size).take(count) {
+		let (duration, media_time, rate) = if version == 1 {
+			(
+				u64::from_be_bytes(entry[..8].try_into().unwrap()),
+				i64::from_be_bytes(entry[8..16].try_into().unwrap()),
+				&entry[16..],
+			)
+		}
+		else {
+			(
+				u64::from(u32::from_be_bytes(entry[..4].try_into().unwrap())),
+				i64::from(i32::from_be_bytes(entry[4..8].try_into().unwrap())),
+				&entry[8..],
+			)
+		};
+
+		let rate = f64::from(i16::from_be_bytes([rate[0], rate[1]]))
+			+ f64::from(i16::from_be_bytes([rate[2], rate[3]])) / 65536.0;
+
+		let media = media_time >= 0;
+		if media && rate != 1.0 {
+			issue(EditListIssue::Rate);
+		}
+
+		// Segment durations are in the movie timescale, media times in the
+		// track's.
+		let duration = duration as f64 / timescale;
+		let media_time = media_time as f64 / media_scale;
+
+		if media && media_time + duration > media_duration + 0.001 {
+			issue(EditListIssue::BeyondMedia);
+		}
+
+		edits.push(Edit {
+			duration,
+			media_time: Some(media_time).filter(|_| media),
+			rate,
+		});
+	}
+
+	let first = edits.iter().position(|edit| edit.media_time.is_some());
+	if let Some(first) = first {
+		if edits[first + 1..].iter().any(|edit| edit.media_time.is_none()) {
+			issue(EditListIssue::Gap);
+		}
+
+		if edits[first + 1..].iter().any(|edit| edit.media_time.is_some()) {
+			issue(EditListIssue::MultipleEdits);
+		}
+	}
+
+	let start: f64 = edits.iter().take(first.unwrap_or(edits.len())).map(|edit| edit.duration).sum();
+
+	// Version 1 `ctts` offsets are signed, and version 0 ones written by
+	// broken muxers are too.
+	let negative_cts = cslg
+		.and_then(|cslg| cslg.get(8..12))
+		.map_or(false, |least| i32::from_be_bytes(least.try_into().unwrap()) < 0)
+		|| ctts.map_or(false, |ctts| {
+			ctts.get(8..).unwrap_or_default().chunks_exact(8).any(|entry| {
+				i32::from_be_bytes(entry[4..8].try_into().unwrap()) < 0
+			})
+		});
+
+	Some(EditList {
+		start,
+		duration: edits.iter().map(|edit| edit.duration).sum(),
+		skipped: first.and_then(|first| edits[first].media_time).unwrap_or(0.0),
+		priming_samples: None,
+		negative_cts,
+		issues,
+		edits,
+	})
+}
@@ -0,0 +1,308 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io,
+	path::{Path, PathBuf},
+};
+use ffmpeg::{
+	codec, encoder,
+	format::{self, context::Output, stream::Disposition},
+	media, packet, Dictionary, DictionaryRef, Packet, Rational,
+};
+
+use crate::{raw, Chapter, FfMetadata};
+
+#[derive(Clone, Debug)]
+pub struct CoverArt {
+	pub data: Vec<u8>,
+	pub mime: String,
+}
+
+// Edits are collected and only applied when remuxing into the destination,
+// streams are always copied as they are.
+#[derive(Clone, Debug)]
+pub struct MetadataEditor {
+	path: PathBuf,
+	cover: Option<CoverArt>,
+	remove_cover: bool,
+	chapters: Option<Vec<Chapter>>,
+	// `None` removes the tag.
+	tags: HashMap<String, Option<String>>,
+	stream_tags: HashMap<usize, HashMap<String, Option<String>>>,
+}
+
+impl MetadataEditor {
+	pub fn open<P: AsRef<Path>>(path: P) -> ffmpeg::Result<Self> {
+		format::input(&path)?;
+
+		Ok(MetadataEditor {
+			path: path.as_ref().to_path_buf(),
+			cover: None,
+			remove_cover: false,
+			chapters: None,
+			tags: HashMap::new(),
+			stream_tags: HashMap::new(),
+		})
+	}
+
+	// Replaces any existing cover art, only JPEG and PNG are understood by
+	// every container supporting it.
+	pub fn set_cover_art<D: Into<Vec<u8>>>(
+		&mut self,
+		data: D,
+		mime: &str,
+	) -> ffmpeg::Result<&mut Self> {
+		image(mime)?;
+
+		self.cover = Some(CoverArt {
+			data: data.into(),
+			mime: mime.into(),
+		});
+		self.remove_cover = false;
+
+		Ok(self)
+	}
+
+	pub fn remove_cover_art(&mut self) -> &mut Self {
+		self.cover = None;
+		self.remove_cover = true;
+		self
+	}
+
+	pub fn set_tag(&mut self, key: &str, value: &str) -> &mut Self {
+		self.tags.insert(key.into(), Some(value.into()));
+		self
+	}
+
+	pub fn remove_tag(&mut self, key: &str) -> &mut Self {
+		self.tags.insert(key.into(), None);
+		self
+	}
+
+	// Streams are numbered as in the input, even when cover art is replaced.
+	pub fn set_stream_tag(&mut self, stream: usize, key: &str, value: &str) -> &mut Self {
+		self.stream_tags.entry(stream).or_default().insert(key.into(), Some(value.into()));
+		self
+	}
+
+	pub fn remove_stream_tag(&mut self, stream: usize, key: &str) -> &mut Self {
+		self.stream_tags.entry(stream).or_default().insert(key.into(), None);
+		self
+	}
+
+	// Replaces the chapter list, chapters without an end last until the next
+	// one, or the end of the file.
+	pub fn set_chapters(&mut self, chapters: Vec<Chapter>) -> &mut Self {
+		self.chapters = Some(chapters);
+		self
+	}
+
+	// Accepts either an `;FFMETADATA1` file or OGM style chapters.
+	pub fn set_chapters_text(&mut self, text: &str) -> ffmpeg::Result<&mut Self> {
+		let chapters = FfMetadata::parse(text)
+			.map(|metadata| metadata.chapters)
+			.or_else(|| Chapter::parse_ogm(text))
+			.ok_or(ffmpeg::Error::InvalidData)?;
+
+		Ok(self.set_chapters(chapters))
+	}
+
+	// Remuxes next to the original and replaces it once done, so a failure
+	// leaves it untouched.
+	pub fn save(&self) -> ffmpeg::Result<()> {
+		let name = self.path.file_stem().ok_or(ffmpeg::Error::InvalidData)?.to_string_lossy();
+		// The muxer is picked from the extension, it has to stay last.
+		let temporary = self.path.with_file_name(match self.path.extension() {
+			Some(extension) => format!(".{}.avmetadata.{}", name, extension.to_string_lossy()),
+			None => format!(".{}.avmetadata", name),
+		});
+
+		if let Err(error) = self.save_to(&temporary) {
+			let _ = fs::remove_file(&temporary);
+			return Err(error);
+		}
+
+		fs::rename(&temporary, &self.path).map_err(|error| {
+			let _ = fs::remove_file(&temporary);
+			io_error(error)
+		})
+	}
+
+	pub fn save_to<P: AsRef<Path>>(&self, path: P) -> ffmpeg::Result<()> {
+		let mut input = format::input(&self.path)?;
+		let mut output = format::output(&path)?;
+		let mut mapping = vec![None; input.nb_streams() as usize];
+
+		for stream in input.streams() {
+			if (self.cover.is_some() || self.remove_cover) && is_cover(&stream) {
+				continue;
+			}
+
+			let mut copy = output.add_stream(encoder::find(codec::Id::None))?;
+			copy.set_parameters(stream.parameters());
+			copy.set_metadata(edit(stream.metadata(), self.stream_tags.get(&stream.index())));
+			copy.set_time_base(stream.time_base());
+			raw::clear_codec_tag(&mut copy);
+			raw::set_disposition(&mut copy, stream.disposition());
+
+			mapping[stream.index()] = Some(copy.index());
+		}
+
+		let cover = match &self.cover {
+			Some(cover) => attach(&mut output, cover)?,
+			None => None,
+		};
+
+		output.set_metadata(edit(input.metadata(), Some(&self.tags)));
+
+		let chapters = match &self.chapters {
+			Some(chapters) => close(chapters, input.duration()),
+			None => Chapter::find(&input),
+		};
+
+		for chapter in chapters {
+			output.add_chapter(
+				chapter.id,
+				chapter.time_base,
+				chapter.start,
+				chapter.end,
+				chapter.title.as_deref().unwrap_or_default(),
+			)?;
+		}
+
+		output.write_header()?;
+
+		if let (Some(index), Some(art)) = (cover, &self.cover) {
+			let mut packet = Packet::copy(&art.data);
+			packet.set_stream(index);
+			packet.set_pts(Some(0));
+			packet.set_dts(Some(0));
+			packet.set_flags(packet::Flags::KEY);
+			packet.write_interleaved(&mut output)?;
+		}
+
+		// Muxers are free to change the time base when writing the header.
+		let time_bases = output.streams().map(|s| s.time_base()).collect::<Vec<Rational>>();
+
+		// Streams that only show up while reading weren't mapped, and are left
+		// out like the dropped ones.
+		for (stream, mut packet) in input.packets() {
+			let index = match mapping.get(stream.index()).copied().flatten() {
+				Some(index) => index,
+				None => continue,
+			};
+
+			packet.rescale_ts(stream.time_base(), time_bases[index]);
+			packet.set_position(-1);
+			packet.set_stream(index);
+			packet.write_interleaved(&mut output)?;
+		}
+
+		output.write_trailer()
+	}
+}
+
+// Tag keys compare ignoring case, like FFmpeg does.
+fn edit(
+	tags: DictionaryRef,
+	edits: Option<&HashMap<String, Option<String>>>,
+) -> Dictionary<'static> {
+	let mut edited = Dictionary::new();
+	let edits = match edits {
+		Some(edits) => edits,
+		None => return tags.to_owned(),
+	};
+
+	for (key, value) in tags.iter() {
+		if !edits.keys().any(|edit| edit.eq_ignore_ascii_case(key)) {
+			edited.set(key, value);
+		}
+	}
+
+	for (key, value) in edits {
+		if let Some(value) = value {
+			edited.set(key, value);
+		}
+	}
+
+	edited
+}
+
+fn io_error(error: io::Error) -> ffmpeg::Error {
+	ffmpeg::Error::from(-error.raw_os_error().unwrap_or(libc::EIO))
+}
+
+fn close(chapters: &[Chapter], duration: i64) -> Vec<Chapter> {
+	let mut chapters = chapters.to_vec();
+	chapters.sort_by(|a, b| a.start_seconds().total_cmp(&b.start_seconds()));
+
+	let duration = duration as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+	let starts = chapters
+		.iter()
+		.skip(1)
+		.map(|chapter| Some(chapter.start_seconds()))
+		.chain(Some(Some(duration).filter(|&d| d > 0.0)))
+		.collect::<Vec<_>>();
+
+	for (chapter, next) in chapters.iter_mut().zip(starts) {
+		if chapter.end > chapter.start {
+			continue;
+		}
+
+		if let Some(next) = next {
+			chapter.end = (next / f64::from(chapter.time_base)).round() as i64;
+		}
+	}
+
+	chapters
+}
+
+fn image(mime: &str) -> ffmpeg::Result<codec::Id> {
+	match mime {
+		"image/jpeg" | "image/jpg" => Ok(codec::Id::MJPEG),
+		"image/png" => Ok(codec::Id::PNG),
+		_ => Err(ffmpeg::Error::InvalidData),
+	}
+}
+
+fn is_cover(stream: &format::stream::Stream) -> bool {
+	stream.disposition().contains(Disposition::ATTACHED_PIC)
+		|| stream
+			.metadata()
+			.get("filename")
+			.map_or(false, |name| name.to_lowercase().starts_with("cover."))
+}
+
+// MP4 (`covr`), MP3 (`APIC`) and FLAC (`PICTURE`) muxers turn an attached
+// picture stream into the right tag, its only packet being the image. Matroska
+// wants a plain attachment instead, with the image in the extradata.
+fn attach(output: &mut Output, cover: &CoverArt) -> ffmpeg::Result<Option<usize>> {
+	let id = image(&cover.mime)?;
+	let name = output.format().name().to_owned();
+
+	match name.as_str() {
+		"mp4" | "mov" | "ipod" | "mp3" | "flac" => {
+			let mut stream = output.add_stream(encoder::find(codec::Id::None))?;
+			raw::set_codec(&mut stream, media::Type::Video, id);
+			raw::set_disposition(&mut stream, Disposition::ATTACHED_PIC);
+			stream.set_time_base((1, 90_000));
+
+			Ok(Some(stream.index()))
+		}
+
+		"matroska" => {
+			let mut stream = output.add_stream(encoder::find(codec::Id::None))?;
+			raw::set_codec(&mut stream, media::Type::Attachment, id);
+			raw::set_extradata(&mut stream, &cover.data);
+
+			let mut tags = Dictionary::new();
+			tags.set("filename", if id == codec::Id::PNG { "cover.png" } else { "cover.jpg" });
+			tags.set("mimetype", &cover.mime);
+			stream.set_metadata(tags);
+
+			Ok(None)
+		}
+
+		_ => Err(ffmpeg::Error::PatchWelcome),
+	}
+}
@@ -0,0 +1,132 @@
+use ffmpeg::{ffi, format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis, compat, raw};
+
+// Demuxers that hand out a bare bitstream, with no container to tell how long
+// it is.
+const FORMATS: &[&str] = &[
+	"h264", "hevc", "vvc", "m4v", "mpegvideo", "cavsvideo", "avs2", "obu", "aac", "ac3", "eac3",
+	"dts", "truehd", "mlp", "loas",
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Elementary {
+	pub frames: Option<u64>,
+	pub duration: Option<f64>,
+	pub estimate: DurationEstimate,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum DurationEstimate {
+	// Every frame went through the parser and got counted, see
+	// `Metadata::count_frames`.
+	Frames,
+	// FFmpeg's guess from the file size and the bit rate of the first frames.
+	BitRate,
+	// The timestamps of the first and last packets FFmpeg read.
+	Timestamps,
+	// A duration the demuxer had for the stream.
+	Stream,
+	None,
+}
+
+impl Elementary {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if !elementary(input) {
+			return None;
+		}
+
+		let estimate = if input.duration() == ffi::AV_NOPTS_VALUE || input.duration() <= 0 {
+			DurationEstimate::None
+		}
+		else {
+			match raw::duration_estimation(input) {
+				ffi::AVDurationEstimationMethod::AVFMT_DURATION_FROM_BITRATE => DurationEstimate::BitRate,
+				ffi::AVDurationEstimationMethod::AVFMT_DURATION_FROM_PTS => DurationEstimate::Timestamps,
+				_ => DurationEstimate::Stream,
+			}
+		};
+
+		Some(Elementary {
+			frames: None,
+			duration: Some(input.duration())
+				.filter(|_| estimate != DurationEstimate::None)
+				.map(|duration| duration as f64 / f64::from(ffi::AV_TIME_BASE)),
+			estimate,
+		})
+	}
+
+	// The demuxer runs the codec parser on the way out, so every packet is one
+	// frame (or access unit) with its duration filled in. That's the whole
+	// input read, `None` when `max_frames` stops it short.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn count(input: &mut Input, options: &analysis::Options) -> ffmpeg::Result<Option<Self>> {
+		if !elementary(input) {
+			return Ok(None);
+		}
+
+		let stream = match input.streams().next() {
+			Some(stream) => stream,
+			None => return Ok(None),
+		};
+
+		let index = stream.index();
+		let time_base = stream.time_base();
+		let medium = compat::medium(&stream);
+		let rate = stream.avg_frame_rate();
+		let rate = if rate.numerator() > 0 { rate } else { stream.rate() };
+
+		let mut frames = 0u64;
+		let mut length = 0i64;
+		let mut complete = true;
+
+		input.seek(0, ..)?;
+		let mut ticker = options.ticker(input);
+		for (stream, packet) in input.packets() {
+			ticker.packet(&packet, stream.time_base())?;
+
+			if stream.index() != index {
+				continue;
+			}
+
+			if options.max_frames.map_or(false, |max| frames >= max as u64) {
+				complete = false;
+				break;
+			}
+
+			frames += 1;
+			length += packet.duration().max(0);
+		}
+		input.seek(0, ..)?;
+
+		if !complete || frames == 0 {
+			return Ok(None);
+		}
+
+		let duration = if length > 0 {
+			length as f64 * f64::from(time_base)
+		}
+		else if medium == media::Type::Video && rate.numerator() > 0 {
+			frames as f64 / f64::from(rate)
+		}
+		else {
+			return Ok(None);
+		};
+
+		Ok(Some(Elementary {
+			frames: Some(frames),
+			duration: Some(duration),
+			estimate: DurationEstimate::Frames,
+		}))
+	}
+}
+
+fn elementary(input: &Input) -> bool {
+	let name = input.format().name();
+	name.split(',').any(|name| FORMATS.contains(&name))
+}
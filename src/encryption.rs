@@ -0,0 +1,217 @@
+use std::{collections::HashMap, convert::TryInto};
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	bitstream::isobmff::children,
+	manifest,
+	movie::{self, Movie},
+	raw,
+};
+
+const SYSTEMS: &[([u8; 16], DrmSystem)] = &[
+	(uuid(0xedef8ba979d64ace, 0xa3c827dcd51d21ed), DrmSystem::Widevine),
+	(uuid(0x9a04f07998404286, 0xab92e65be0885f95), DrmSystem::PlayReady),
+	(uuid(0x94ce86fb07ff4f43, 0xadb893d2fa968ca2), DrmSystem::FairPlay),
+	(uuid(0x1077efecc0b24d02, 0xace33c1e52e2fb4b), DrmSystem::ClearKey),
+	(uuid(0x5e629af538da4063, 0x897797ffbd9902d4), DrmSystem::Marlin),
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EncryptionInfo {
+	// The protection scheme for MP4 (`cenc`, `cbcs`, ...), the key method for
+	// HLS (`aes-128`, `sample-aes`, ...).
+	pub scheme: Option<String>,
+	pub systems: Vec<DrmSystem>,
+	// Hex encoded.
+	pub key_ids: Vec<String>,
+	// The sample entry the track had before it was encrypted, e.g. `avc1`.
+	pub original_format: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum DrmSystem {
+	Widevine,
+	PlayReady,
+	FairPlay,
+	ClearKey,
+	Marlin,
+	// The system ID or HLS key format, for anything else.
+	Other(String),
+}
+
+// Protection per stream index, for the streams that have any.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub(crate) fn find(input: &Input, movie: Option<&Movie>) -> HashMap<usize, EncryptionInfo> {
+	if let Some(movie) = movie {
+		mp4(input, movie)
+	}
+	else if input.format().name().split(',').any(|name| name == "hls") {
+		hls(input).unwrap_or_default()
+	}
+	else {
+		HashMap::new()
+	}
+}
+
+fn mp4(input: &Input, movie: &Movie) -> HashMap<usize, EncryptionInfo> {
+	let boxes = movie.boxes();
+
+	// Systems apply to the whole presentation, each box lists the keys it
+	// can unlock from version 1 on.
+	let mut systems = Vec::new();
+	let mut key_ids = Vec::new();
+
+	for (_, data) in boxes.iter().filter(|(kind, _)| kind == b"pssh") {
+		let id: [u8; 16] = match data.get(4..20) {
+			Some(id) => id.try_into().unwrap(),
+			None => continue,
+		};
+
+		let system = SYSTEMS
+			.iter()
+			.find(|(known, _)| *known == id)
+			.map_or_else(|| DrmSystem::Other(uuid_string(&id)), |(_, system)| system.clone());
+
+		if !systems.contains(&system) {
+			systems.push(system);
+		}
+
+		if data[0] > 0 {
+			let count = data.get(20..24).map_or(0, |b| u32::from_be_bytes(b.try_into().unwrap()));
+
+			for at in (0..count as usize).map(|n| 24 + n * 16) {
+				if let Some(kid) = data.get(at..at + 16).map(hex) {
+					if !key_ids.contains(&kid) {
+						key_ids.push(kid);
+					}
+				}
+			}
+		}
+	}
+
+	let mut tracks = HashMap::new();
+
+	for track in movie.tracks() {
+		let info = track
+			.find(&[b"mdia", b"minf", b"stbl", b"stsd"])
+			.and_then(|stsd| sinf(stsd.get(8..)?));
+
+		if let Some(mut info) = info {
+			info.systems = systems.clone();
+
+			for kid in &key_ids {
+				if !info.key_ids.contains(kid) {
+					info.key_ids.push(kid.clone());
+				}
+			}
+
+			tracks.insert(track.id, info);
+		}
+	}
+
+	// The demuxer uses the track ID as the stream ID.
+	input
+		.streams()
+		.filter_map(|stream| {
+			let info = tracks.get(&(stream.id() as u32))?;
+			Some((stream.index(), info.clone()))
+		})
+		.collect()
+}
+
+// Sample entries have fixed fields of their own before any child box, the
+// protection scheme box is looked up by name instead.
+fn sinf(entries: &[u8]) -> Option<EncryptionInfo> {
+	let at = entries.windows(4).position(|w| w == b"sinf").filter(|&at| at >= 4)?;
+	let size = u32::from_be_bytes(entries[at - 4..at].try_into().unwrap()) as usize;
+	let sinf = children(entries.get(at + 4..at - 4 + size)?);
+
+	let fourcc = |data: &[u8]| String::from_utf8_lossy(data).trim().to_owned();
+
+	Some(EncryptionInfo {
+		scheme: sinf
+			.iter()
+			.find(|(kind, _)| kind == b"schm")
+			.and_then(|(_, schm)| schm.get(4..8))
+			.map(fourcc),
+		systems: Vec::new(),
+		key_ids: movie::find(&sinf, &[b"schi", b"tenc"])
+			.and_then(|tenc| tenc.get(8..24))
+			.map(hex)
+			.into_iter()
+			.collect(),
+		original_format: sinf
+			.iter()
+			.find(|(kind, _)| kind == b"frma")
+			.and_then(|(_, frma)| frma.get(..4))
+			.map(fourcc),
+	})
+}
+
+// Every stream comes out of the same segments, so they share the keys.
+fn hls(input: &Input) -> Option<HashMap<usize, EncryptionInfo>> {
+	let keys = manifest::keys(&raw::url(input)?);
+	let keys = keys
+		.iter()
+		.filter(|key| key.get("METHOD").map_or(false, |method| method != "NONE"))
+		.collect::<Vec<_>>();
+
+	let first = keys.first()?;
+	let mut info = EncryptionInfo {
+		scheme: first.get("METHOD").map(|method| method.to_lowercase()),
+		systems: Vec::new(),
+		key_ids: Vec::new(),
+		original_format: None,
+	};
+
+	for key in keys {
+		let system = match key.get("KEYFORMAT").map(String::as_str) {
+			None | Some("identity") => None,
+			Some("com.apple.streamingkeydelivery") => Some(DrmSystem::FairPlay),
+			Some("com.microsoft.playready") => Some(DrmSystem::PlayReady),
+			Some(format) => {
+				let id = format.strip_prefix("urn:uuid:").unwrap_or(format).to_lowercase();
+				let known = SYSTEMS.iter().find(|(known, _)| uuid_string(known) == id);
+
+				Some(known.map_or_else(|| DrmSystem::Other(format.into()), |(_, system)| system.clone()))
+			}
+		};
+
+		if let Some(system) = system.filter(|system| !info.systems.contains(system)) {
+			info.systems.push(system);
+		}
+
+		if let Some(kid) = key.get("KEYID") {
+			let kid = kid.trim_start_matches("0x").trim_start_matches("0X").to_lowercase();
+
+			if !info.key_ids.contains(&kid) {
+				info.key_ids.push(kid);
+			}
+		}
+	}
+
+	Some(input.streams().map(|stream| (stream.index(), info.clone())).collect())
+}
+
+const fn uuid(high: u64, low: u64) -> [u8; 16] {
+	let (high, low) = (high.to_be_bytes(), low.to_be_bytes());
+
+	[
+		high[0], high[1], high[2], high[3], high[4], high[5], high[6], high[7], low[0], low[1],
+		low[2], low[3], low[4], low[5], low[6], low[7],
+	]
+}
+
+fn uuid_string(id: &[u8; 16]) -> String {
+	let hex = hex(id);
+	format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn hex(data: &[u8]) -> String {
+	data.iter().map(|b| format!("{:02x}", b)).collect()
+}
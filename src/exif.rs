@@ -0,0 +1,297 @@
+use std::{convert::TryInto, io::Read};
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, raw};
+
+// EXIF and XMP have to come before the image data, and JPEG segments top out
+// at 64 KiB each.
+const MAX_HEAD: u64 = 4 * 1024 * 1024;
+
+const XMP_JPEG: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_PNG: &[u8] = b"XML:com.adobe.xmp\0";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExifInfo {
+	// EXIF orientation, 1 is upright and 6 needs a 90° clockwise rotation.
+	pub orientation: Option<u16>,
+	pub make: Option<String>,
+	pub model: Option<String>,
+	pub lens: Option<String>,
+	pub software: Option<String>,
+	pub date_time: Option<String>,
+	// Seconds, millimeters and the ISO speed.
+	pub exposure_time: Option<f64>,
+	pub f_number: Option<f64>,
+	pub iso: Option<u32>,
+	pub focal_length: Option<f64>,
+	pub flash: Option<bool>,
+	// From XMP, -1 for rejected and 0 to 5 stars otherwise.
+	pub rating: Option<i8>,
+	pub keywords: Vec<String>,
+}
+
+impl ExifInfo {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let mut exif = ExifInfo::default();
+		let mut found = false;
+
+		let name = input.format().name();
+		if name.starts_with("image2") || name.ends_with("_pipe") || name == "apng" {
+			let mut head = Vec::new();
+			Avio::open(&raw::url(input)?).ok()?.take(MAX_HEAD).read_to_end(&mut head).ok()?;
+
+			for blob in blobs(&head) {
+				found |= match blob {
+					Blob::Exif(data) => exif.tiff(data).is_some(),
+					Blob::Xmp(data) => exif.xmp(&String::from_utf8_lossy(data)),
+				};
+			}
+		}
+
+		// The MOV and MP4 demuxers hand XMP out as a tag when asked to.
+		if let Some(xmp) = input.metadata().get("xmp") {
+			found |= exif.xmp(xmp);
+		}
+
+		if found {
+			Some(exif)
+		}
+		else {
+			None
+		}
+	}
+
+	fn tiff(&mut self, data: &[u8]) -> Option<()> {
+		let tiff = Tiff {
+			data,
+			big: match data.get(..2)? {
+				b"MM" => true,
+				b"II" => false,
+				_ => return None,
+			},
+		};
+
+		let ifd = tiff.u32(4)? as usize;
+		let mut sub = None;
+
+		for (tag, entry) in tiff.entries(ifd) {
+			match tag {
+				0x010f => self.make = tiff.ascii(entry),
+				0x0110 => self.model = tiff.ascii(entry),
+				0x0112 => self.orientation = tiff.short(entry),
+				0x0131 => self.software = tiff.ascii(entry),
+				0x0132 => self.date_time = tiff.ascii(entry),
+				0x8769 => sub = tiff.u32(entry + 8),
+				_ => (),
+			}
+		}
+
+		for (tag, entry) in sub.map(|sub| tiff.entries(sub as usize)).unwrap_or_default() {
+			match tag {
+				0x829a => self.exposure_time = tiff.rational(entry),
+				0x829d => self.f_number = tiff.rational(entry),
+				0x8827 => self.iso = tiff.short(entry).map(u32::from),
+				0x9003 => self.date_time = tiff.ascii(entry).or_else(|| self.date_time.take()),
+				0x9209 => self.flash = tiff.short(entry).map(|flash| flash & 1 != 0),
+				0x920a => self.focal_length = tiff.rational(entry),
+				0xa434 => self.lens = tiff.ascii(entry),
+				_ => (),
+			}
+		}
+
+		Some(())
+	}
+
+	// XMP is RDF that writers serialize either as attributes or as elements,
+	// a full XML parser is overkill for the handful of properties we read.
+	fn xmp(&mut self, xmp: &str) -> bool {
+		if !xmp.contains("x:xmpmeta") && !xmp.contains("rdf:RDF") {
+			return false;
+		}
+
+		if let Some(rating) = property(xmp, "xmp:Rating").and_then(|r| r.parse::<f64>().ok()) {
+			self.rating = Some(rating.round().clamp(-1.0, 5.0) as i8);
+		}
+
+		if self.orientation.is_none() {
+			self.orientation = property(xmp, "tiff:Orientation").and_then(|o| o.parse().ok());
+		}
+
+		if let Some(start) = xmp.find("<dc:subject>") {
+			let end = xmp[start..].find("</dc:subject>").map_or(xmp.len(), |end| start + end);
+
+			let keywords = xmp[start..end].split("<rdf:li").skip(1).filter_map(|item| {
+				let keyword = item[item.find('>')? + 1..item.find("</rdf:li>")?].trim();
+				Some(keyword).filter(|keyword| !keyword.is_empty()).map(String::from)
+			});
+
+			self.keywords.extend(keywords);
+		}
+
+		true
+	}
+}
+
+enum Blob<'a> {
+	Exif(&'a [u8]),
+	Xmp(&'a [u8]),
+}
+
+// JPEG APP1 segments and PNG eXIf/iTXt chunks, the only places images carry
+// either.
+fn blobs(data: &[u8]) -> Vec<Blob> {
+	let mut blobs = Vec::new();
+
+	if data.starts_with(&[0xff, 0xd8]) {
+		let mut offset = 2;
+
+		while let Some(&[0xff, marker, high, low]) = data.get(offset..offset + 4) {
+			// Start of scan, the rest is image data.
+			if marker == 0xda {
+				break;
+			}
+
+			let size = usize::from(u16::from_be_bytes([high, low]));
+			let segment = match data.get(offset + 4..offset + 2 + size) {
+				Some(segment) => segment,
+				None => break,
+			};
+
+			if marker == 0xe1 {
+				if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+					blobs.push(Blob::Exif(tiff));
+				}
+				else if let Some(xmp) = segment.strip_prefix(XMP_JPEG) {
+					blobs.push(Blob::Xmp(xmp));
+				}
+			}
+
+			offset += 2 + size;
+		}
+	}
+	else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+		let mut offset = 8;
+
+		while let Some(header) = data.get(offset..offset + 8) {
+			let size = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+			let chunk = match data.get(offset + 8..offset + 8 + size) {
+				Some(chunk) => chunk,
+				None => break,
+			};
+
+			match &header[4..] {
+				b"eXIf" => blobs.push(Blob::Exif(chunk)),
+
+				// Keyword, then compression flag and method, language and
+				// translated keyword, XMP is never compressed.
+				b"iTXt" if chunk.starts_with(XMP_PNG) => {
+					let rest = &chunk[XMP_PNG.len()..];
+					let text = rest.get(2..).and_then(|rest| {
+						let (_, rest) = split(rest)?;
+						let (_, rest) = split(rest)?;
+
+						Some(rest)
+					});
+
+					blobs.extend(text.filter(|_| rest[0] == 0).map(Blob::Xmp));
+				}
+
+				b"IDAT" | b"IEND" => break,
+				_ => (),
+			}
+
+			offset += 12 + size;
+		}
+	}
+
+	blobs
+}
+
+fn split(data: &[u8]) -> Option<(&[u8], &[u8])> {
+	let end = data.iter().position(|&b| b == 0)?;
+	Some((&data[..end], &data[end + 1..]))
+}
+
+// `name="value"` or `<name>value</name>`.
+fn property<'a>(xmp: &'a str, name: &str) -> Option<&'a str> {
+	let attribute = format!("{}=\"", name);
+	if let Some(start) = xmp.find(&attribute).map(|start| start + attribute.len()) {
+		return Some(&xmp[start..start + xmp[start..].find('"')?]);
+	}
+
+	let open = format!("<{}>", name);
+	let start = xmp.find(&open)? + open.len();
+	let end = start + xmp[start..].find("</")?;
+
+	Some(xmp[start..end].trim())
+}
+
+struct Tiff<'a> {
+	data: &'a [u8],
+	big: bool,
+}
+
+impl Tiff<'_> {
+	fn u16(&self, offset: usize) -> Option<u16> {
+		let bytes = self.data.get(offset..offset + 2)?.try_into().ok()?;
+		Some(if self.big { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+	}
+
+	fn u32(&self, offset: usize) -> Option<u32> {
+		let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+		Some(if self.big { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+	}
+
+	// Tags with the offset of their 12 byte entry.
+	fn entries(&self, ifd: usize) -> Vec<(u16, usize)> {
+		let count = self.u16(ifd).unwrap_or(0);
+
+		(0..usize::from(count))
+			.map(|i| ifd + 2 + i * 12)
+			.filter_map(|entry| Some((self.u16(entry)?, entry)))
+			.collect()
+	}
+
+	// Values that fit in four bytes are stored in the entry itself.
+	fn value(&self, entry: usize, size: usize) -> Option<(usize, usize)> {
+		let count = self.u32(entry + 4)? as usize;
+		let length = count.checked_mul(size)?;
+		let offset = if length <= 4 { entry + 8 } else { self.u32(entry + 8)? as usize };
+
+		self.data.get(offset..offset + length).map(|_| (offset, length))
+	}
+
+	fn ascii(&self, entry: usize) -> Option<String> {
+		let (offset, length) = self.value(entry, 1)?;
+		let value = &self.data[offset..offset + length];
+		let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+		let text = String::from_utf8_lossy(&value[..end]).trim().to_owned();
+
+		Some(text).filter(|text| !text.is_empty())
+	}
+
+	fn short(&self, entry: usize) -> Option<u16> {
+		match self.u16(entry + 2)? {
+			3 => self.u16(entry + 8),
+			4 => self.u32(entry + 8).map(|value| value as u16),
+			_ => None,
+		}
+	}
+
+	fn rational(&self, entry: usize) -> Option<f64> {
+		let (offset, _) = self.value(entry, 8)?;
+		let numerator = self.u32(offset)?;
+		let denominator = self.u32(offset + 4)?;
+
+		if denominator == 0 {
+			None
+		}
+		else {
+			Some(f64::from(numerator) / f64::from(denominator))
+		}
+	}
+}
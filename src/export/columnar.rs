@@ -0,0 +1,77 @@
+use std::{fs::File, io::Write, sync::Arc};
+
+use ::arrow::{
+	array::{ArrayRef, Float64Array, StringArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array},
+	datatypes::{DataType, Field, Schema},
+	error::Result as ArrowResult,
+	ipc::writer::FileWriter,
+	record_batch::RecordBatch,
+};
+use ::parquet::{arrow::ArrowWriter, errors::Result as ParquetResult};
+
+use super::Row;
+
+pub fn record_batch(rows: &[Row]) -> ArrowResult<RecordBatch> {
+	let schema = Schema::new(vec![
+		Field::new("source", DataType::Utf8, true),
+		Field::new("format", DataType::Utf8, false),
+		Field::new("duration", DataType::Float64, true),
+		Field::new("index", DataType::UInt64, false),
+		Field::new("kind", DataType::Utf8, false),
+		Field::new("codec", DataType::Utf8, true),
+		Field::new("profile", DataType::Utf8, true),
+		Field::new("bit_rate", DataType::UInt64, true),
+		Field::new("stream_duration", DataType::Float64, true),
+		Field::new("language", DataType::Utf8, true),
+		Field::new("width", DataType::UInt32, true),
+		Field::new("height", DataType::UInt32, true),
+		Field::new("fps", DataType::Float64, true),
+		Field::new("bit_depth", DataType::UInt8, true),
+		Field::new("sample_rate", DataType::UInt32, true),
+		Field::new("channels", DataType::UInt16, true),
+	]);
+
+	let strings = |f: fn(&Row) -> Option<&str>| -> ArrayRef {
+		Arc::new(rows.iter().map(f).collect::<StringArray>())
+	};
+
+	let columns: Vec<ArrayRef> = vec![
+		strings(|row| row.source.as_deref()),
+		strings(|row| Some(row.format.as_str())),
+		Arc::new(rows.iter().map(|row| row.duration).collect::<Float64Array>()),
+		Arc::new(rows.iter().map(|row| Some(row.index as u64)).collect::<UInt64Array>()),
+		strings(|row| Some(row.kind.as_str())),
+		strings(|row| row.codec.as_deref()),
+		strings(|row| row.profile.as_deref()),
+		Arc::new(rows.iter().map(|row| row.bit_rate.map(|b| b as u64)).collect::<UInt64Array>()),
+		Arc::new(rows.iter().map(|row| row.stream_duration).collect::<Float64Array>()),
+		strings(|row| row.language.as_deref()),
+		Arc::new(rows.iter().map(|row| row.width).collect::<UInt32Array>()),
+		Arc::new(rows.iter().map(|row| row.height).collect::<UInt32Array>()),
+		Arc::new(rows.iter().map(|row| row.fps).collect::<Float64Array>()),
+		Arc::new(rows.iter().map(|row| row.bit_depth).collect::<UInt8Array>()),
+		Arc::new(rows.iter().map(|row| row.sample_rate).collect::<UInt32Array>()),
+		Arc::new(rows.iter().map(|row| row.channels).collect::<UInt16Array>()),
+	];
+
+	RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+// Arrow IPC file format, what `pyarrow.ipc.open_file` and DuckDB read.
+pub fn arrow<W: Write>(writer: W, rows: &[Row]) -> ArrowResult<()> {
+	let batch = record_batch(rows)?;
+	let mut writer = FileWriter::try_new(writer, &batch.schema())?;
+
+	writer.write(&batch)?;
+	writer.finish()
+}
+
+pub fn parquet(file: File, rows: &[Row]) -> ParquetResult<()> {
+	let batch = record_batch(rows)?;
+	let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+
+	writer.write(&batch)?;
+	writer.close()?;
+
+	Ok(())
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, FfMetadata, Metadata};
+
+#[cfg(feature = "parquet")]
+mod columnar;
+#[cfg(feature = "parquet")]
+pub use self::columnar::{arrow, parquet, record_batch};
+
+#[cfg(feature = "yaml")]
+pub fn yaml(metadata: &Metadata) -> Result<String, serde_yaml::Error> {
+	serde_yaml::to_string(metadata)
+}
+
+// Going through a value lets the serializer sort plain values before tables,
+// which TOML requires and the struct field order doesn't respect.
+#[cfg(feature = "toml")]
+pub fn toml(metadata: &Metadata) -> Result<String, ::toml::ser::Error> {
+	let value = ::toml::Value::try_from(metadata)?;
+	::toml::to_string_pretty(&value)
+}
+
+pub fn ffmetadata(metadata: &Metadata) -> String {
+	FfMetadata::from(metadata).to_string()
+}
+
+#[cfg(feature = "csv")]
+pub fn csv<W: std::io::Write>(writer: W, rows: &[Row], delimiter: u8) -> Result<(), ::csv::Error> {
+	let mut writer = ::csv::WriterBuilder::new().delimiter(delimiter).from_writer(writer);
+
+	for row in rows {
+		writer.serialize(row)?;
+	}
+
+	writer.flush()?;
+	Ok(())
+}
+
+// One flat row per stream, for spreadsheets and analytics databases.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Row {
+	pub source: Option<String>,
+	pub format: String,
+	pub duration: Option<f64>,
+	pub index: usize,
+	pub kind: String,
+	pub codec: Option<String>,
+	pub profile: Option<String>,
+	pub bit_rate: Option<usize>,
+	pub stream_duration: Option<f64>,
+	pub language: Option<String>,
+	pub width: Option<u32>,
+	pub height: Option<u32>,
+	pub fps: Option<f64>,
+	pub bit_depth: Option<u8>,
+	pub sample_rate: Option<u32>,
+	pub channels: Option<u16>,
+}
+
+impl Row {
+	pub fn rows(metadata: &Metadata, source: Option<&str>) -> Vec<Row> {
+		metadata
+			.streams
+			.iter()
+			.map(|stream| {
+				let mut row = Row {
+					source: source.map(String::from),
+					format: metadata.format.name.clone(),
+					duration: metadata.duration_seconds(),
+					index: stream.index,
+					stream_duration: stream.duration_seconds(),
+					language: stream.language.as_ref().map(|l| l.code.clone()),
+					..Default::default()
+				};
+
+				match &stream.content {
+					Content::Video(video) => {
+						row.kind = "video".into();
+						row.codec = Some(video.codec.name.clone());
+						row.profile = video.codec.profile.clone();
+						row.bit_rate = Some(video.bit_rate).filter(|&b| b > 0);
+						row.width = Some(video.width);
+						row.height = Some(video.height);
+						row.fps = stream.fps();
+						row.bit_depth = video.bit_depth;
+					}
+
+					Content::Audio(audio) => {
+						row.kind = "audio".into();
+						row.codec = Some(audio.codec.name.clone());
+						row.profile = audio.codec.profile.clone();
+						row.bit_rate = Some(audio.bit_rate).filter(|&b| b > 0);
+						row.sample_rate = Some(audio.sample_rate);
+						row.channels = Some(audio.channels);
+						row.bit_depth = audio.bit_depth;
+					}
+
+					Content::Subtitle(subtitle) => {
+						row.kind = "subtitle".into();
+						row.codec = Some(subtitle.codec.name.clone());
+					}
+
+					Content::Data(_) => row.kind = "data".into(),
+					Content::Attachment(_) => row.kind = "attachment".into(),
+					Content::Unknown(_) => row.kind = "unknown".into(),
+				}
+
+				row
+			})
+			.collect()
+	}
+}
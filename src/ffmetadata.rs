@@ -0,0 +1,228 @@
+use std::{collections::HashMap, fmt};
+use ffmpeg::Rational;
+use serde::{Deserialize, Serialize};
+
+use crate::{Chapter, Metadata};
+
+const HEADER: &str = ";FFMETADATA1";
+
+// FFmpeg's metadata text format, as read and written by the `ffmetadata`
+// demuxer and muxer.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FfMetadata {
+	pub global: HashMap<String, String>,
+	pub streams: Vec<HashMap<String, String>>,
+	pub chapters: Vec<Chapter>,
+}
+
+enum Section {
+	Global,
+	Stream,
+	Chapter,
+	Unknown,
+}
+
+impl FfMetadata {
+	pub fn parse(text: &str) -> Option<Self> {
+		if !text.trim_start().starts_with(HEADER) {
+			return None;
+		}
+
+		let mut metadata = FfMetadata::default();
+		let mut section = Section::Global;
+
+		for line in lines(text) {
+			if line.starts_with('[') {
+				section = match line.trim_end() {
+					"[STREAM]" => {
+						metadata.streams.push(HashMap::new());
+						Section::Stream
+					}
+
+					"[CHAPTER]" => {
+						metadata.chapters.push(Chapter {
+							id: metadata.chapters.len() as i64,
+							time_base: Rational::new(1, 1_000_000_000),
+							start: 0,
+							end: 0,
+							title: None,
+							details: HashMap::new(),
+						});
+
+						Section::Chapter
+					}
+
+					_ => Section::Unknown,
+				};
+
+				continue;
+			}
+
+			let (key, value) = match split(&line) {
+				Some(pair) => pair,
+				None => continue,
+			};
+
+			match section {
+				Section::Global => {
+					metadata.global.insert(key, value);
+				}
+
+				Section::Stream => {
+					metadata.streams.last_mut()?.insert(key, value);
+				}
+
+				Section::Chapter => chapter(metadata.chapters.last_mut()?, key, value)?,
+				Section::Unknown => (),
+			}
+		}
+
+		Some(metadata)
+	}
+}
+
+impl From<&Metadata> for FfMetadata {
+	fn from(metadata: &Metadata) -> Self {
+		FfMetadata {
+			global: metadata.details.clone(),
+			streams: Vec::new(),
+			chapters: metadata.chapters.clone(),
+		}
+	}
+}
+
+impl fmt::Display for FfMetadata {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "{}", HEADER)?;
+		tags(f, &self.global, None)?;
+
+		for stream in &self.streams {
+			writeln!(f, "[STREAM]")?;
+			tags(f, stream, None)?;
+		}
+
+		for chapter in &self.chapters {
+			writeln!(f, "[CHAPTER]")?;
+			writeln!(
+				f,
+				"TIMEBASE={}/{}",
+				chapter.time_base.numerator(),
+				chapter.time_base.denominator()
+			)?;
+			writeln!(f, "START={}", chapter.start)?;
+			writeln!(f, "END={}", chapter.end)?;
+
+			if let Some(title) = &chapter.title {
+				writeln!(f, "title={}", escape(title))?;
+			}
+
+			tags(f, &chapter.details, Some("title"))?;
+		}
+
+		Ok(())
+	}
+}
+
+// Keys are sorted so exports of the same file are identical.
+fn tags(f: &mut fmt::Formatter, tags: &HashMap<String, String>, skip: Option<&str>) -> fmt::Result {
+	let mut keys = tags.keys().filter(|&key| Some(key.as_str()) != skip).collect::<Vec<_>>();
+	keys.sort();
+
+	for key in keys {
+		writeln!(f, "{}={}", escape(key), escape(&tags[key]))?;
+	}
+
+	Ok(())
+}
+
+fn escape(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+
+	for c in value.chars() {
+		if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+			escaped.push('\\');
+		}
+
+		escaped.push(c);
+	}
+
+	escaped
+}
+
+fn chapter(chapter: &mut Chapter, key: String, value: String) -> Option<()> {
+	match key.as_str() {
+		"TIMEBASE" => {
+			let (num, den) = value.split_once('/')?;
+			let (num, den) = (num.trim().parse().ok()?, den.trim().parse().ok()?);
+			chapter.time_base = Rational::new(num, den);
+		}
+
+		"START" => chapter.start = value.trim().parse().ok()?,
+		"END" => chapter.end = value.trim().parse().ok()?,
+
+		_ => {
+			if key == "title" {
+				chapter.title = Some(value.clone());
+			}
+
+			chapter.details.insert(key, value);
+		}
+	}
+
+	Some(())
+}
+
+// Joins lines continued with a trailing backslash and drops comments, escapes
+// are kept for `split` to handle.
+fn lines(text: &str) -> Vec<String> {
+	let mut lines = Vec::new();
+	let mut pending = String::new();
+
+	for line in text.lines() {
+		let escaped = line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1;
+
+		if escaped {
+			pending.push_str(&line[..line.len() - 1]);
+			pending.push('\n');
+			continue;
+		}
+
+		pending.push_str(line);
+
+		let line = std::mem::take(&mut pending);
+		if !line.starts_with(';') && !line.starts_with('#') && !line.trim().is_empty() {
+			lines.push(line);
+		}
+	}
+
+	lines
+}
+
+fn split(line: &str) -> Option<(String, String)> {
+	let mut key = String::new();
+	let mut value = String::new();
+	let mut target = &mut key;
+	let mut found = false;
+	let mut chars = line.chars();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => target.extend(chars.next()),
+
+			'=' if !found => {
+				found = true;
+				target = &mut value;
+			}
+
+			_ => target.push(c),
+		}
+	}
+
+	if found {
+		Some((key, value))
+	}
+	else {
+		None
+	}
+}
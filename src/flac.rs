@@ -0,0 +1,188 @@
+use std::{
+	convert::TryInto,
+	io::{Read, Seek, SeekFrom},
+};
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, raw};
+
+// Seek points are fixed size, everything else we keep is small.
+const SEEK_POINT: u32 = 18;
+const MAX_BLOCK: u32 = 1024 * 1024;
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Flac {
+	// MD5 of the unencoded audio, missing when the encoder left it zeroed.
+	pub md5: Option<String>,
+	pub seek_points: usize,
+	pub padding: u64,
+	pub applications: Vec<String>,
+	pub cuesheet: Option<CueSheet>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CueSheet {
+	pub catalog: Option<String>,
+	pub lead_in: u64,
+	pub cd: bool,
+	pub tracks: Vec<CueTrack>,
+}
+
+// Offsets are in samples, indices relative to their track.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CueTrack {
+	pub number: u8,
+	pub offset: u64,
+	pub isrc: Option<String>,
+	pub audio: bool,
+	pub pre_emphasis: bool,
+	pub indices: Vec<CueIndex>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CueIndex {
+	pub number: u8,
+	pub offset: u64,
+}
+
+impl Flac {
+	// FFmpeg turns the cuesheet into chapters and keeps the seek table to
+	// itself, so the metadata blocks are read again.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if input.format().name() != "flac" {
+			return None;
+		}
+
+		blocks(&mut Avio::open(&raw::url(input)?).ok()?)
+	}
+}
+
+fn blocks<R: Read + Seek>(reader: &mut R) -> Option<Flac> {
+	let mut magic = [0; 4];
+	reader.read_exact(&mut magic).ok()?;
+
+	if &magic != b"fLaC" {
+		return None;
+	}
+
+	let mut flac = Flac::default();
+
+	loop {
+		let mut header = [0; 4];
+		reader.read_exact(&mut header).ok()?;
+
+		let last = header[0] & 0x80 != 0;
+		let size = u32::from_be_bytes([0, header[1], header[2], header[3]]);
+
+		match header[0] & 0x7f {
+			1 => {
+				flac.padding += u64::from(size);
+				reader.seek(SeekFrom::Current(i64::from(size))).ok()?;
+			}
+
+			// Placeholder points are there to be filled in later and don't
+			// help seeking.
+			3 => {
+				let mut data = vec![0; size as usize];
+				reader.read_exact(&mut data).ok()?;
+
+				flac.seek_points = data
+					.chunks_exact(SEEK_POINT as usize)
+					.filter(|point| point[..8] != [0xff; 8])
+					.count();
+			}
+
+			kind @ (0 | 2 | 5) if size <= MAX_BLOCK => {
+				let mut data = vec![0; size as usize];
+				reader.read_exact(&mut data).ok()?;
+
+				match kind {
+					0 => flac.md5 = md5(&data),
+					2 => flac.applications.extend(data.get(..4).map(fourcc)),
+					_ => flac.cuesheet = cuesheet(&data),
+				}
+			}
+
+			_ => {
+				reader.seek(SeekFrom::Current(i64::from(size))).ok()?;
+			}
+		}
+
+		if last {
+			return Some(flac);
+		}
+	}
+}
+
+fn fourcc(id: &[u8]) -> String {
+	String::from_utf8_lossy(id).into_owned()
+}
+
+fn be64(data: &[u8], offset: usize) -> Option<u64> {
+	Some(u64::from_be_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+fn text(data: &[u8]) -> Option<String> {
+	let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+	let text = String::from_utf8_lossy(&data[..end]).trim().to_owned();
+
+	Some(text).filter(|text| !text.is_empty())
+}
+
+fn md5(streaminfo: &[u8]) -> Option<String> {
+	let md5 = streaminfo.get(18..34)?;
+
+	if md5.iter().all(|&b| b == 0) {
+		None
+	}
+	else {
+		Some(md5.iter().map(|b| format!("{:02x}", b)).collect())
+	}
+}
+
+fn cuesheet(data: &[u8]) -> Option<CueSheet> {
+	let count = *data.get(395)?;
+	let mut offset = 396;
+	let mut tracks = Vec::with_capacity(count.into());
+
+	for _ in 0..count {
+		let track = data.get(offset..offset + 36)?;
+		let indices = track[35];
+		offset += 36;
+
+		let indices = (0..usize::from(indices))
+			.map(|i| {
+				let at = offset + i * 12;
+
+				Some(CueIndex {
+					number: *data.get(at + 8)?,
+					offset: be64(data, at)?,
+				})
+			})
+			.collect::<Option<Vec<_>>>()?;
+
+		offset += indices.len() * 12;
+
+		tracks.push(CueTrack {
+			number: track[8],
+			offset: be64(track, 0)?,
+			isrc: text(&track[9..21]),
+			audio: track[21] & 0x80 == 0,
+			pre_emphasis: track[21] & 0x40 != 0,
+			indices,
+		});
+	}
+
+	Some(CueSheet {
+		catalog: text(&data[..128]),
+		lead_in: be64(data, 128)?,
+		cd: data[136] & 0x80 != 0,
+		tracks,
+	})
+}
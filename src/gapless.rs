@@ -0,0 +1,99 @@
+use ffmpeg::format::{context::Input, stream::Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{bitstream::mpeg_audio, raw, tags::itunes};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GaplessInfo {
+	pub source: SampleSource,
+	// Samples to drop from the start and the end of the decoded output.
+	pub delay: u32,
+	pub padding: Option<u32>,
+	pub samples: Option<u64>,
+	pub duration: Option<f64>,
+}
+
+// Where a sample count came from, for gapless playback and exact durations
+// alike.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum SampleSource {
+	Decoded,
+	#[serde(rename = "itunsmpb")]
+	ITunSMPB,
+	Lame,
+	EditList,
+	Container,
+}
+
+impl GaplessInfo {
+	pub fn find(input: &Input, stream: &Stream, sample_rate: u32) -> Option<Self> {
+		let duration = |samples: u64| {
+			if sample_rate > 0 {
+				Some(samples as f64 / f64::from(sample_rate))
+			}
+			else {
+				None
+			}
+		};
+
+		if let Some(smpb) = itunes::find_smpb(input, stream) {
+			return Some(GaplessInfo {
+				source: SampleSource::ITunSMPB,
+				delay: smpb.delay,
+				padding: Some(smpb.padding),
+				samples: Some(smpb.samples),
+				duration: duration(smpb.samples),
+			});
+		}
+
+		match input.format().name() {
+			"mp3" => lame(input),
+
+			// FFmpeg applies edit lists itself, the stream duration is already
+			// the playable one and the priming is what got cut from the start.
+			name if name.split(',').any(|name| name == "mov") => {
+				let delay = raw::initial_padding(&stream.parameters());
+				let padding = raw::trailing_padding(&stream.parameters());
+
+				if delay <= 0 || stream.duration() <= 0 {
+					return None;
+				}
+
+				let seconds = stream.duration() as f64 * f64::from(stream.time_base());
+
+				Some(GaplessInfo {
+					source: SampleSource::EditList,
+					delay: delay as u32,
+					padding: Some(padding as u32).filter(|_| padding > 0),
+					samples: Some((seconds * f64::from(sample_rate)).round() as u64),
+					duration: Some(seconds),
+				})
+			}
+
+			_ => None,
+		}
+	}
+}
+
+// The LAME tag stores the encoder delay and padding, decoders add their own
+// delay on top which shifts the padding by the same amount.
+fn lame(input: &Input) -> Option<GaplessInfo> {
+	let xing = mpeg_audio::find(input)?;
+	let lame = xing.lame.as_ref()?;
+
+	let samples = xing.frames.and_then(|frames| {
+		(u64::from(frames) * u64::from(xing.header.samples()))
+			.checked_sub(u64::from(lame.delay + lame.padding))
+	});
+
+	Some(GaplessInfo {
+		source: SampleSource::Lame,
+		delay: lame.delay + mpeg_audio::DECODER_DELAY,
+		padding: Some(lame.padding.saturating_sub(mpeg_audio::DECODER_DELAY)),
+		samples,
+		duration: samples.map(|samples| samples as f64 / f64::from(xing.header.sample_rate)),
+	})
+}
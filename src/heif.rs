@@ -0,0 +1,317 @@
+use std::{collections::HashMap, convert::TryInto};
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	avio::Avio,
+	bitstream::isobmff::{children, descend},
+	raw,
+};
+
+// Item metadata is tiny next to the image data it points at.
+const MAX_META: u64 = 16 * 1024 * 1024;
+
+const BRANDS: &[&str] = &["mif1", "msf1", "heic", "heix", "hevc", "avif", "avis"];
+
+// HEIF files are a bag of items tied together by references, FFmpeg only shows
+// the image sequence track (if any) or the primary item.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Heif {
+	pub primary: Option<u32>,
+	pub items: Vec<HeifItem>,
+	// Whether there is also a `pict` track, as in animated AVIF.
+	pub sequence: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HeifItem {
+	pub id: u32,
+	// Item type, e.g. `hvc1`, `av01`, `grid`, `Exif` or `mime`.
+	pub kind: String,
+	pub name: Option<String>,
+	pub role: ItemRole,
+	pub hidden: bool,
+	pub width: Option<u32>,
+	pub height: Option<u32>,
+	pub auxiliary_type: Option<String>,
+	// The items this one is a thumbnail, auxiliary image or description of,
+	// or the inputs a derived image is built from.
+	pub references: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ItemRole {
+	Primary,
+	Image,
+	Thumbnail,
+	Alpha,
+	Depth,
+	Auxiliary,
+	// A grid, overlay or transformed view of other items.
+	Derived,
+	// Tiles and other inputs of a derived image.
+	Tile,
+	// EXIF or XMP describing another item.
+	Metadata,
+}
+
+impl Heif {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let metadata = input.metadata();
+		let brands = [metadata.get("major_brand"), metadata.get("compatible_brands")];
+
+		let has = |wanted: &[&str]| {
+			let mut all = brands.iter().flatten().flat_map(|brands| brands.as_bytes().chunks(4));
+			all.any(|brand| wanted.iter().any(|b| b.as_bytes() == brand))
+		};
+
+		if !has(BRANDS) {
+			return None;
+		}
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+		let end = file.size()?;
+		let meta = descend(&mut file, end, &[b"meta"], MAX_META)?;
+		let sequence = has(&["msf1", "avis"]);
+
+		Some(Heif::parse(&meta, sequence))
+	}
+
+	fn parse(meta: &[u8], sequence: bool) -> Self {
+		let mut primary = None;
+		let mut items = Vec::new();
+		let mut references = Vec::new();
+		let mut properties = Vec::new();
+		let mut associations = HashMap::new();
+
+		for (kind, data) in children(meta) {
+			match &kind {
+				b"pitm" => primary = full(data).and_then(|(version, data)| id(data, version, 0)),
+				b"iinf" => items = full(data).map(infos).unwrap_or_default(),
+				b"iref" => references = full(data).map(refs).unwrap_or_default(),
+
+				b"iprp" => {
+					for (kind, data) in children(data) {
+						match &kind {
+							b"ipco" => properties = children(data),
+							b"ipma" => associations = ipma(data),
+							_ => (),
+						}
+					}
+				}
+
+				_ => (),
+			}
+		}
+
+		for item in &mut items {
+			for &index in associations.get(&item.id).into_iter().flatten() {
+				let (kind, data) = match index.checked_sub(1).and_then(|i| properties.get(i)) {
+					Some(property) => *property,
+					None => continue,
+				};
+
+				match &kind {
+					b"ispe" => {
+						if let Some((_, data)) = full(data) {
+							item.width = be32(data, 0);
+							item.height = be32(data, 4);
+						}
+					}
+
+					b"auxC" => {
+						item.auxiliary_type = full(data).map(|(_, data)| {
+							let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+							String::from_utf8_lossy(&data[..end]).into_owned()
+						});
+					}
+
+					_ => (),
+				}
+			}
+
+			if matches!(item.kind.as_str(), "grid" | "iovl" | "iden") {
+				item.role = ItemRole::Derived;
+			}
+		}
+
+		// References go from the thumbnail, auxiliary image or metadata to
+		// what it belongs to, and from a derived image to its inputs.
+		for (kind, from, to) in references {
+			let role = match &kind {
+				b"thmb" => ItemRole::Thumbnail,
+				b"cdsc" => ItemRole::Metadata,
+				b"dimg" => ItemRole::Derived,
+				b"auxl" => ItemRole::Auxiliary,
+				_ => continue,
+			};
+
+			if role == ItemRole::Derived {
+				for item in items.iter_mut().filter(|item| to.contains(&item.id)) {
+					item.role = ItemRole::Tile;
+				}
+			}
+
+			if let Some(item) = items.iter_mut().find(|item| item.id == from) {
+				item.references.extend(&to);
+
+				item.role = match (role, item.auxiliary_type.as_deref()) {
+					(ItemRole::Auxiliary, Some(kind)) if is_alpha(kind) => ItemRole::Alpha,
+					(ItemRole::Auxiliary, Some(kind)) if is_depth(kind) => ItemRole::Depth,
+					(role, _) => role,
+				};
+			}
+		}
+
+		for item in &mut items {
+			if Some(item.id) == primary {
+				item.role = ItemRole::Primary;
+			}
+		}
+
+		Heif {
+			primary,
+			items,
+			sequence,
+		}
+	}
+}
+
+// Auxiliary types are URNs, the HEVC ones numbered and the CICP ones named.
+fn is_alpha(kind: &str) -> bool {
+	kind.ends_with(":alpha") || kind == "urn:mpeg:hevc:2015:auxid:1"
+}
+
+fn is_depth(kind: &str) -> bool {
+	kind.ends_with(":depth") || kind == "urn:mpeg:hevc:2015:auxid:2"
+}
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+	Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+// Splits off the version and flags of a full box, returning the version.
+fn full(data: &[u8]) -> Option<(u8, &[u8])> {
+	Some((*data.first()?, data.get(4..)?))
+}
+
+// Item IDs grew from 16 to 32 bits in later box versions.
+fn id(data: &[u8], version: u8, offset: usize) -> Option<u32> {
+	if version == 0 {
+		be16(data, offset).map(u32::from)
+	}
+	else {
+		be32(data, offset)
+	}
+}
+
+fn infos((version, data): (u8, &[u8])) -> Vec<HeifItem> {
+	let skip = if version == 0 { 2 } else { 4 };
+
+	children(data.get(skip..).unwrap_or_default())
+		.into_iter()
+		.filter(|(kind, _)| kind == b"infe")
+		.filter_map(|(_, data)| {
+			let hidden = data.get(3)? & 1 != 0;
+			let (version, data) = full(data)?;
+
+			// Versions before 2 predate item types and only describe files.
+			if version < 2 {
+				return None;
+			}
+
+			let id = if version == 2 { u32::from(be16(data, 0)?) } else { be32(data, 0)? };
+			let at = if version == 2 { 4 } else { 6 };
+			let kind = String::from_utf8_lossy(data.get(at..at + 4)?).into_owned();
+			let name = data.get(at + 4..).map(|name| {
+				let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+				String::from_utf8_lossy(&name[..end]).into_owned()
+			});
+
+			Some(HeifItem {
+				id,
+				kind,
+				name: name.filter(|name| !name.is_empty()),
+				role: ItemRole::Image,
+				hidden,
+				width: None,
+				height: None,
+				auxiliary_type: None,
+				references: Vec::new(),
+			})
+		})
+		.collect()
+}
+
+fn refs((version, data): (u8, &[u8])) -> Vec<([u8; 4], u32, Vec<u32>)> {
+	let size = if version == 0 { 2 } else { 4 };
+
+	children(data)
+		.into_iter()
+		.filter_map(|(kind, data)| {
+			let from = id(data, version, 0)?;
+			let count = be16(data, size)?;
+			let to = (0..usize::from(count))
+				.map(|i| id(data, version, size + 2 + i * size))
+				.collect::<Option<Vec<_>>>()?;
+
+			Some((kind, from, to))
+		})
+		.collect()
+}
+
+// Property indices per item, one based with zero meaning none.
+fn ipma(data: &[u8]) -> HashMap<u32, Vec<usize>> {
+	let mut associations = HashMap::new();
+
+	// The lowest flag bit widens the indices from 7 to 15 bits.
+	let (version, wide, data) = match (data.first(), data.get(3), data.get(4..)) {
+		(Some(&version), Some(&flags), Some(data)) => (version, flags & 1 != 0, data),
+		_ => return associations,
+	};
+
+	let count = be32(data, 0).unwrap_or(0);
+	let mut offset = 4;
+
+	for _ in 0..count {
+		let item = match id(data, version, offset) {
+			Some(item) => item,
+			None => break,
+		};
+
+		offset += if version == 0 { 2 } else { 4 };
+		let entries = match data.get(offset) {
+			Some(&entries) => usize::from(entries),
+			None => break,
+		};
+
+		offset += 1;
+		let mut indices = Vec::with_capacity(entries);
+
+		for _ in 0..entries {
+			let index = if wide {
+				be16(data, offset).map(|index| usize::from(index & 0x7fff))
+			}
+			else {
+				data.get(offset).map(|&index| usize::from(index & 0x7f))
+			};
+
+			indices.extend(index);
+			offset += if wide { 2 } else { 1 };
+		}
+
+		associations.insert(item, indices);
+	}
+
+	associations
+}
@@ -0,0 +1,459 @@
+use std::{convert::TryInto, io::Read};
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, markers::chunks, raw};
+
+// Tags are mostly cover art, anything bigger is broken.
+const MAX_TAG: u32 = 64 * 1024 * 1024;
+
+// The frames FFmpeg flattens or drops, decoded by type.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3 {
+	pub version: u8,
+	pub chapters: Vec<Id3Chapter>,
+	pub tables: Vec<Id3TableOfContents>,
+	pub pictures: Vec<Id3Picture>,
+	pub lyrics: Vec<Id3Lyrics>,
+	pub synced_lyrics: Vec<Id3SyncedLyrics>,
+	pub private: Vec<Id3Private>,
+}
+
+// Times in seconds, offsets in bytes from the start of the first frame and
+// only when the tagger wrote them.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3Chapter {
+	pub id: String,
+	pub start: f64,
+	pub end: f64,
+	pub start_offset: Option<u32>,
+	pub end_offset: Option<u32>,
+	pub title: Option<String>,
+	pub description: Option<String>,
+	pub url: Option<String>,
+	pub pictures: Vec<Id3Picture>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3TableOfContents {
+	pub id: String,
+	pub top_level: bool,
+	pub ordered: bool,
+	pub children: Vec<String>,
+	pub title: Option<String>,
+}
+
+// Picture types follow the ID3 list, 3 is the front cover.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3Picture {
+	pub picture_type: u8,
+	pub mime_type: String,
+	pub description: Option<String>,
+	pub size: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3Lyrics {
+	pub language: String,
+	pub description: Option<String>,
+	pub text: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3SyncedLyrics {
+	pub language: String,
+	pub description: Option<String>,
+	pub timestamps: TimestampFormat,
+	// Lyrics, transcription, movement, events, chords and so on, see the spec.
+	pub content_type: u8,
+	pub lines: Vec<SyncedLine>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SyncedLine {
+	pub time: u32,
+	pub text: String,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampFormat {
+	Frames,
+	Milliseconds,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Id3Private {
+	pub owner: String,
+	pub size: usize,
+}
+
+impl Id3 {
+	// MP3 puts the tag at the start of the file, AIFF and WAV in a chunk.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let name = input.format().name();
+		if !matches!(name, "mp3" | "aiff" | "wav") {
+			return None;
+		}
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+
+		let tag = match name {
+			"mp3" => read(&mut file)?,
+
+			name @ ("aiff" | "wav") => {
+				let mut tag = None;
+				chunks(&mut file, name == "aiff", &[b"ID3 ", b"id3 "], |_, data| {
+					tag.get_or_insert(data);
+				})?;
+
+				tag?
+			}
+
+			_ => return None,
+		};
+
+		parse(&tag)
+	}
+}
+
+fn syncsafe(data: &[u8]) -> u32 {
+	data.iter().fold(0, |size, &b| (size << 7) | u32::from(b & 0x7f))
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read<R: Read>(reader: &mut R) -> Option<Vec<u8>> {
+	let mut header = [0; 10];
+	reader.read_exact(&mut header).ok()?;
+
+	let size = syncsafe(&header[6..]);
+	if &header[..3] != b"ID3" || size > MAX_TAG {
+		return None;
+	}
+
+	let mut tag = header.to_vec();
+	tag.resize(10 + size as usize, 0);
+	reader.read_exact(&mut tag[10..]).ok()?;
+
+	Some(tag)
+}
+
+// Undoes the 0xff 0x00 stuffing that keeps tags from looking like MPEG sync.
+fn resync(data: &[u8]) -> Vec<u8> {
+	let mut out = Vec::with_capacity(data.len());
+
+	for (i, &b) in data.iter().enumerate() {
+		if !(b == 0 && i > 0 && data[i - 1] == 0xff) {
+			out.push(b);
+		}
+	}
+
+	out
+}
+
+fn parse(tag: &[u8]) -> Option<Id3> {
+	if tag.len() < 10 || &tag[..3] != b"ID3" {
+		return None;
+	}
+
+	let version = tag[3];
+	let flags = tag[5];
+	let mut body = tag[10..].to_vec();
+
+	if flags & 0x80 != 0 && version < 4 {
+		body = resync(&body);
+	}
+
+	let mut offset = 0;
+	if flags & 0x40 != 0 && version >= 3 {
+		offset = match version {
+			3 => be32(&body, 0)? as usize + 4,
+			_ => syncsafe(body.get(..4)?) as usize,
+		};
+	}
+
+	let mut id3 = Id3 {
+		version,
+		..Default::default()
+	};
+
+	for (id, data) in frames(&body[offset.min(body.len())..], version) {
+		match id.as_str() {
+			"CHAP" => id3.chapters.extend(chapter(&data, version)),
+			"CTOC" => id3.tables.extend(table(&data, version)),
+			"APIC" | "PIC" => id3.pictures.extend(picture(&data, id == "PIC")),
+			"USLT" | "ULT" => id3.lyrics.extend(lyrics(&data)),
+			"SYLT" | "SLT" => id3.synced_lyrics.extend(synced(&data)),
+
+			"PRIV" => {
+				let (owner, rest) = latin1(&data);
+				id3.private.push(Id3Private {
+					owner,
+					size: rest.len(),
+				});
+			}
+
+			_ => (),
+		}
+	}
+
+	Some(id3)
+}
+
+// ID3v2.2 has three character IDs and sizes, later versions four, with
+// syncsafe sizes from 2.4 on.
+fn frames(mut data: &[u8], version: u8) -> Vec<(String, Vec<u8>)> {
+	let (id_length, header) = if version == 2 { (3, 6) } else { (4, 10) };
+	let mut frames = Vec::new();
+
+	while data.len() >= header && data[0] != 0 {
+		let id = String::from_utf8_lossy(&data[..id_length]).into_owned();
+		let size = match version {
+			2 => u32::from_be_bytes([0, data[3], data[4], data[5]]),
+			3 => u32::from_be_bytes(data[4..8].try_into().unwrap()),
+			_ => syncsafe(&data[4..8]),
+		} as usize;
+
+		let body = match data.get(header..header + size) {
+			Some(body) => body,
+			None => break,
+		};
+
+		let format = if version >= 3 { data[9] } else { 0 };
+		let mut body = body.to_vec();
+
+		// Compressed and encrypted frames are skipped, they need zlib or a key.
+		let skip = match version {
+			3 => format & 0xc0 != 0,
+			4 => format & 0x0c != 0,
+			_ => false,
+		};
+
+		if version == 4 {
+			if format & 0x01 != 0 && body.len() >= 4 {
+				body.drain(..4);
+			}
+
+			if format & 0x02 != 0 {
+				body = resync(&body);
+			}
+		}
+
+		if !skip {
+			frames.push((id, body));
+		}
+
+		data = &data[header + size..];
+	}
+
+	frames
+}
+
+// Splits off a NUL terminated Latin-1 string, which is what IDs and MIME types
+// are always written as.
+fn latin1(data: &[u8]) -> (String, &[u8]) {
+	let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+	let text = data[..end].iter().map(|&b| char::from(b)).collect();
+
+	(text, data.get(end + 1..).unwrap_or_default())
+}
+
+// Splits off a terminated string in the given text encoding.
+fn text(encoding: u8, data: &[u8]) -> (String, &[u8]) {
+	let wide = encoding == 1 || encoding == 2;
+	let end = if wide {
+		data.chunks_exact(2).position(|unit| unit[0] == 0 && unit[1] == 0).map(|i| i * 2)
+	}
+	else {
+		data.iter().position(|&b| b == 0)
+	};
+
+	let (text, rest) = match end {
+		Some(end) => (&data[..end], &data[end + if wide { 2 } else { 1 }..]),
+		None => (data, &data[data.len()..]),
+	};
+
+	let decoded = match encoding {
+		0 => text.iter().map(|&b| char::from(b)).collect(),
+		3 => String::from_utf8_lossy(text).into_owned(),
+
+		_ => {
+			// Without a BOM, UTF-16 is big endian.
+			let (little, text) = match text {
+				[0xff, 0xfe, rest @ ..] => (true, rest),
+				[0xfe, 0xff, rest @ ..] => (false, rest),
+				_ => (false, text),
+			};
+
+			let units = text.chunks_exact(2).map(|unit| {
+				if little {
+					u16::from_le_bytes([unit[0], unit[1]])
+				}
+				else {
+					u16::from_be_bytes([unit[0], unit[1]])
+				}
+			});
+
+			String::from_utf16_lossy(&units.collect::<Vec<_>>())
+		}
+	};
+
+	(decoded, rest)
+}
+
+fn non_empty(text: String) -> Option<String> {
+	Some(text).filter(|text| !text.is_empty())
+}
+
+// Text frames embedded in chapters and tables, e.g. TIT2 for the title.
+fn text_frame(data: &[u8]) -> Option<String> {
+	let (&encoding, rest) = data.split_first()?;
+	non_empty(text(encoding, rest).0)
+}
+
+fn chapter(data: &[u8], version: u8) -> Option<Id3Chapter> {
+	let (id, rest) = latin1(data);
+	let offset = |at: usize| be32(rest, at).filter(|&offset| offset != u32::MAX);
+
+	let mut chapter = Id3Chapter {
+		id,
+		start: f64::from(be32(rest, 0)?) / 1000.0,
+		end: f64::from(be32(rest, 4)?) / 1000.0,
+		start_offset: offset(8),
+		end_offset: offset(12),
+		title: None,
+		description: None,
+		url: None,
+		pictures: Vec::new(),
+	};
+
+	for (id, data) in frames(rest.get(16..)?, version) {
+		match id.as_str() {
+			"TIT2" => chapter.title = text_frame(&data),
+			"TIT3" => chapter.description = text_frame(&data),
+			"APIC" => chapter.pictures.extend(picture(&data, false)),
+
+			// The description comes first, then the URL always in Latin-1.
+			"WXXX" => {
+				if let Some((&encoding, rest)) = data.split_first() {
+					chapter.url = non_empty(latin1(text(encoding, rest).1).0);
+				}
+			}
+
+			_ => (),
+		}
+	}
+
+	Some(chapter)
+}
+
+fn table(data: &[u8], version: u8) -> Option<Id3TableOfContents> {
+	let (id, rest) = latin1(data);
+	let flags = *rest.first()?;
+	let count = *rest.get(1)?;
+
+	let mut rest = rest.get(2..)?;
+	let mut children = Vec::with_capacity(count.into());
+
+	for _ in 0..count {
+		let (child, next) = latin1(rest);
+		children.push(child);
+		rest = next;
+	}
+
+	let title = frames(rest, version)
+		.into_iter()
+		.find(|(id, _)| id == "TIT2")
+		.and_then(|(_, data)| text_frame(&data));
+
+	Some(Id3TableOfContents {
+		id,
+		top_level: flags & 0x02 != 0,
+		ordered: flags & 0x01 != 0,
+		children,
+		title,
+	})
+}
+
+// ID3v2.2 gives a three letter image format instead of a MIME type.
+fn picture(data: &[u8], short: bool) -> Option<Id3Picture> {
+	let (&encoding, rest) = data.split_first()?;
+
+	let (mime_type, rest) = if short {
+		let format = String::from_utf8_lossy(rest.get(..3)?).to_ascii_lowercase();
+		let format = if format == "jpg" { "jpeg".to_owned() } else { format };
+
+		(format!("image/{}", format), rest.get(3..)?)
+	}
+	else {
+		latin1(rest)
+	};
+
+	let (&picture_type, rest) = rest.split_first()?;
+	let (description, rest) = text(encoding, rest);
+
+	Some(Id3Picture {
+		picture_type,
+		mime_type,
+		description: non_empty(description),
+		size: rest.len(),
+	})
+}
+
+fn lyrics(data: &[u8]) -> Option<Id3Lyrics> {
+	let (&encoding, rest) = data.split_first()?;
+	let language = String::from_utf8_lossy(rest.get(..3)?).into_owned();
+	let (description, rest) = text(encoding, rest.get(3..)?);
+
+	Some(Id3Lyrics {
+		language,
+		description: non_empty(description),
+		text: text(encoding, rest).0,
+	})
+}
+
+fn synced(data: &[u8]) -> Option<Id3SyncedLyrics> {
+	let (&encoding, rest) = data.split_first()?;
+	let language = String::from_utf8_lossy(rest.get(..3)?).into_owned();
+	let timestamps = match rest.get(3)? {
+		1 => TimestampFormat::Frames,
+		_ => TimestampFormat::Milliseconds,
+	};
+
+	let content_type = *rest.get(4)?;
+	let (description, mut rest) = text(encoding, rest.get(5..)?);
+	let mut lines = Vec::new();
+
+	while !rest.is_empty() {
+		let (line, next) = text(encoding, rest);
+		let time = match be32(next, 0) {
+			Some(time) => time,
+			None => break,
+		};
+
+		lines.push(SyncedLine { time, text: line });
+		rest = &next[4..];
+	}
+
+	Some(Id3SyncedLyrics {
+		language,
+		description: non_empty(description),
+		timestamps,
+		content_type,
+		lines,
+	})
+}
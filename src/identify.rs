@@ -0,0 +1,107 @@
+use std::{
+	ffi::{CStr, CString},
+	fs::File,
+	io::{self, Read},
+	os::raw::{c_char, c_int},
+	path::Path,
+	ptr,
+};
+
+use ffmpeg::ffi;
+use serde::{Deserialize, Serialize};
+
+// Enough for every container FFmpeg knows to show its signature.
+const SNIFF: usize = 4096;
+
+// For demuxers that don't declare one.
+const MIME_TYPES: &[(&str, &str)] = &[
+	("aac", "audio/aac"),
+	("ac3", "audio/ac3"),
+	("aiff", "audio/aiff"),
+	("asf", "video/x-ms-asf"),
+	("avi", "video/x-msvideo"),
+	("flac", "audio/flac"),
+	("flv", "video/x-flv"),
+	("matroska", "video/x-matroska"),
+	("mov", "video/mp4"),
+	("mp3", "audio/mpeg"),
+	("mpeg", "video/mpeg"),
+	("mpegts", "video/mp2t"),
+	("mxf", "application/mxf"),
+	("ogg", "audio/ogg"),
+	("wav", "audio/wav"),
+	("wv", "audio/x-wavpack"),
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FormatGuess {
+	pub name: String,
+	pub aliases: Vec<String>,
+	pub description: String,
+	// Out of `AVPROBE_SCORE_MAX` (100), extensions alone score 50.
+	pub score: i32,
+	pub mime_type: Option<String>,
+}
+
+// Reads the first few kilobytes of `path`, its extension counts towards the
+// score.
+pub fn identify<P: AsRef<Path>>(path: P) -> io::Result<Option<FormatGuess>> {
+	let mut data = Vec::with_capacity(SNIFF);
+	File::open(&path)?.take(SNIFF as u64).read_to_end(&mut data)?;
+
+	Ok(identify_bytes(&data, path.as_ref().file_name().and_then(|name| name.to_str())))
+}
+
+// Only looks at what's given, the start of the input, with `name` used for
+// its extension when there is one.
+pub fn identify_bytes(data: &[u8], name: Option<&str>) -> Option<FormatGuess> {
+	let length = data.len().min(SNIFF);
+
+	// The probe reads past the end, into zeroed padding.
+	let mut buffer = vec![0; length + ffi::AVPROBE_PADDING_SIZE as usize];
+	buffer[..length].copy_from_slice(&data[..length]);
+
+	let name = CString::new(name.unwrap_or_default()).unwrap_or_default();
+	let mut probe = ffi::AVProbeData {
+		filename: name.as_ptr(),
+		buf: buffer.as_mut_ptr(),
+		buf_size: length as c_int,
+		mime_type: ptr::null(),
+	};
+
+	let mut score = 0;
+	let format = unsafe { ffi::av_probe_input_format3(&mut probe, 1, &mut score) };
+
+	if format.is_null() || score <= 0 {
+		return None;
+	}
+
+	let string = |value: *const c_char| unsafe {
+		if value.is_null() {
+			None
+		}
+		else {
+			Some(CStr::from_ptr(value).to_string_lossy().into_owned())
+		}
+	};
+
+	let names = string(unsafe { (*format).name })?;
+	let mut names = names.split(',').map(String::from);
+	let name = names.next()?;
+
+	let mime_type = string(unsafe { (*format).mime_type })
+		.and_then(|mime| mime.split(',').next().map(String::from))
+		.filter(|mime| !mime.is_empty())
+		.or_else(|| {
+			MIME_TYPES.iter().find(|(format, _)| *format == name).map(|(_, mime)| mime.to_string())
+		});
+
+	Some(FormatGuess {
+		description: string(unsafe { (*format).long_name }).unwrap_or_default(),
+		aliases: names.collect(),
+		name,
+		score,
+		mime_type,
+	})
+}
@@ -0,0 +1,169 @@
+use std::convert::TryInto;
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	avio::Avio,
+	bitstream::isobmff::{children, descend},
+	raw,
+};
+
+// Cover art makes up most of it, anything bigger is broken.
+const MAX_ILST: u64 = 64 * 1024 * 1024;
+
+// The iTunes item list, with values decoded by their declared type instead of
+// stringified. Keys are the atom names with `©` spelled out, freeform atoms
+// are keyed `mean:name` (e.g. `com.apple.iTunes:iTunSMPB`).
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ItunesTags {
+	pub items: Vec<ItunesItem>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ItunesItem {
+	pub key: String,
+	pub values: Vec<ItunesValue>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ItunesValue {
+	Text(String),
+	Integer(i64),
+	Boolean(bool),
+	// Track and disc numbers.
+	Position { number: u16, total: Option<u16> },
+	Picture { format: PictureFormat, size: usize },
+	Binary { size: usize },
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum PictureFormat {
+	Jpeg,
+	Png,
+	Bmp,
+	Unknown,
+}
+
+impl ItunesTags {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if !input.format().name().split(',').any(|name| name == "mp4" || name == "mov") {
+			return None;
+		}
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+		let end = file.size()?;
+		let data = descend(&mut file, end, &[b"moov", b"udta", b"meta", b"ilst"], MAX_ILST)?;
+
+		let items = children(&data).into_iter().filter_map(|(kind, data)| item(kind, data));
+		Some(ItunesTags { items: items.collect() })
+	}
+
+	pub fn get(&self, key: &str) -> Option<&ItunesValue> {
+		self.items.iter().find(|item| item.key == key).and_then(|item| item.values.first())
+	}
+}
+
+// Atom names are Latin-1, which is where the `©` comes from.
+fn name(kind: [u8; 4]) -> String {
+	kind.iter().map(|&b| char::from(b)).collect()
+}
+
+fn item(kind: [u8; 4], data: &[u8]) -> Option<ItunesItem> {
+	let atoms = children(data);
+	let mut key = name(kind);
+
+	if &kind == b"----" {
+		let text = |wanted: &[u8; 4]| {
+			atoms
+				.iter()
+				.find(|(kind, _)| kind == wanted)
+				.and_then(|(_, data)| data.get(4..))
+				.map(|data| String::from_utf8_lossy(data).into_owned())
+		};
+
+		key = format!("{}:{}", text(b"mean")?, text(b"name")?);
+	}
+
+	let values = atoms
+		.iter()
+		.filter(|(kind, _)| kind == b"data")
+		.filter_map(|(_, data)| value(&kind, data))
+		.collect::<Vec<_>>();
+
+	if values.is_empty() {
+		None
+	}
+	else {
+		Some(ItunesItem { key, values })
+	}
+}
+
+fn value(kind: &[u8; 4], data: &[u8]) -> Option<ItunesValue> {
+	let flags = u32::from_be_bytes(data.get(..4)?.try_into().unwrap()) & 0xff_ffff;
+	let payload = data.get(8..)?;
+
+	// Integers are big endian of whatever size fits, signedness by type.
+	let integer = |signed: bool| {
+		let value = payload.iter().fold(0u64, |value, &b| (value << 8) | u64::from(b));
+		let bits = payload.len() * 8;
+
+		if signed && bits > 0 && bits < 64 && value >> (bits - 1) != 0 {
+			value as i64 - (1 << bits)
+		}
+		else {
+			value as i64
+		}
+	};
+
+	Some(match (kind, flags) {
+		(b"trkn" | b"disk", 0) if payload.len() >= 4 => {
+			let number = u16::from_be_bytes(payload[2..4].try_into().unwrap());
+			let total = payload.get(4..6).map(|total| u16::from_be_bytes([total[0], total[1]]));
+
+			ItunesValue::Position {
+				number,
+				total: total.filter(|&total| total > 0),
+			}
+		}
+
+		(b"cpil" | b"pgap" | b"pcst" | b"hdvd", 0 | 21) => {
+			ItunesValue::Boolean(integer(false) != 0)
+		}
+
+		(b"covr", 13) => picture(PictureFormat::Jpeg, payload),
+		(b"covr", 14) => picture(PictureFormat::Png, payload),
+		(b"covr", 27) => picture(PictureFormat::Bmp, payload),
+		(b"covr", _) => picture(PictureFormat::Unknown, payload),
+		(_, 1) => ItunesValue::Text(String::from_utf8_lossy(payload).into_owned()),
+		(_, 2) => ItunesValue::Text(utf16(payload)),
+		(_, 21) => ItunesValue::Integer(integer(true)),
+		(_, 22) => ItunesValue::Integer(integer(false)),
+
+		// Old files store tmpo and other counters as implicit data.
+		(b"tmpo" | b"stik" | b"rtng" | b"tves" | b"tvsn", 0) => {
+			ItunesValue::Integer(integer(false))
+		}
+
+		_ => ItunesValue::Binary { size: payload.len() },
+	})
+}
+
+fn picture(format: PictureFormat, data: &[u8]) -> ItunesValue {
+	ItunesValue::Picture {
+		format,
+		size: data.len(),
+	}
+}
+
+fn utf16(data: &[u8]) -> String {
+	let units = data.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+	String::from_utf16_lossy(&units.collect::<Vec<_>>())
+}
@@ -0,0 +1,96 @@
+use ffmpeg::{
+	codec,
+	format::{context::Input, stream::Stream},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	bitstream::{ac3, dts, truehd},
+	probe, raw,
+};
+
+// TrueHD only repeats its major sync every few dozen access units.
+const PACKETS: usize = 64;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ObjectAudio {
+	// TrueHD with an Atmos substream, or E-AC-3 with JOC.
+	pub atmos: bool,
+	pub dts_x: bool,
+	pub imax: bool,
+	// What a decoder that knows nothing of the extensions plays, `ac3` for
+	// Blu-ray TrueHD and E-AC-3, `dts` for DTS-HD.
+	pub core: Option<String>,
+}
+
+impl ObjectAudio {
+	pub fn find(input: &Input, stream: &Stream) -> Option<Self> {
+		Self::with_packets(stream, &probe::Packets::new(input))
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub(crate) fn with_packets(stream: &Stream, packets: &probe::Packets) -> Option<Self> {
+		let parameters = stream.parameters();
+		let id = parameters.id();
+
+		if id != codec::Id::TRUEHD && id != codec::Id::EAC3 && id != codec::Id::DTS {
+			return None;
+		}
+
+		// Recent FFmpeg versions tell these apart in the profile already.
+		let profile = raw::profile_name(id, raw::profile(&parameters)).unwrap_or_default();
+		let mut object = ObjectAudio {
+			atmos: profile.contains("Atmos"),
+			dts_x: profile.contains("DTS:X"),
+			imax: profile.contains("IMAX"),
+			core: None,
+		};
+
+		let input = packets.input();
+		let packets = packets.stream(stream.index());
+		let packets = &packets[..packets.len().min(PACKETS)];
+
+		match id {
+			codec::Id::TRUEHD => {
+				object.atmos |= packets
+					.iter()
+					.filter_map(|packet| truehd::MajorSync::find(packet))
+					.any(|sync| sync.atmos());
+
+				// Blu-ray interleaves the AC-3 core under the same PID, and the
+				// demuxer hands it out as a stream of its own.
+				let core = input.streams().any(|other| {
+					other.index() != stream.index()
+						&& other.id() == stream.id()
+						&& other.parameters().id() == codec::Id::AC3
+				});
+
+				if core {
+					object.core = Some("ac3".into());
+				}
+			}
+
+			codec::Id::EAC3 => {
+				let frames = packets.iter().flat_map(|packet| ac3::frames(packet)).collect::<Vec<_>>();
+				object.atmos |= frames.iter().any(|frame| frame.extension);
+
+				if frames.iter().any(|frame| !frame.is_eac3()) {
+					object.core = Some("ac3".into());
+				}
+			}
+
+			_ => {
+				let frames = packets.iter().filter_map(|packet| dts::frame(packet)).collect::<Vec<_>>();
+				object.dts_x |= frames.iter().any(|frame| frame.x || frame.imax);
+				object.imax |= frames.iter().any(|frame| frame.imax);
+
+				if frames.iter().any(|frame| frame.core && frame.substream) {
+					object.core = Some("dts".into());
+				}
+			}
+		}
+
+		Some(object).filter(|object| object.atmos || object.dts_x || object.core.is_some())
+	}
+}
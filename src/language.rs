@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Language {
+	pub code: String,
+	pub name: Option<String>,
+	pub raw: String,
+}
+
+// ISO 639-2/B, ISO 639-2/T, ISO 639-1 and English name.
+const LANGUAGES: &[(&str, &str, &str, &str)] = &[
+	("afr", "afr", "af", "Afrikaans"),
+	("alb", "sqi", "sq", "Albanian"),
+	("amh", "amh", "am", "Amharic"),
+	("ara", "ara", "ar", "Arabic"),
+	("arm", "hye", "hy", "Armenian"),
+	("aze", "aze", "az", "Azerbaijani"),
+	("baq", "eus", "eu", "Basque"),
+	("bel", "bel", "be", "Belarusian"),
+	("ben", "ben", "bn", "Bengali"),
+	("bos", "bos", "bs", "Bosnian"),
+	("bre", "bre", "br", "Breton"),
+	("bul", "bul", "bg", "Bulgarian"),
+	("bur", "mya", "my", "Burmese"),
+	("cat", "cat", "ca", "Catalan"),
+	("chi", "zho", "zh", "Chinese"),
+	("hrv", "hrv", "hr", "Croatian"),
+	("cze", "ces", "cs", "Czech"),
+	("dan", "dan", "da", "Danish"),
+	("dut", "nld", "nl", "Dutch"),
+	("eng", "eng", "en", "English"),
+	("epo", "epo", "eo", "Esperanto"),
+	("est", "est", "et", "Estonian"),
+	("fao", "fao", "fo", "Faroese"),
+	("fil", "fil", "fil", "Filipino"),
+	("fin", "fin", "fi", "Finnish"),
+	("fre", "fra", "fr", "French"),
+	("geo", "kat", "ka", "Georgian"),
+	("ger", "deu", "de", "German"),
+	("gle", "gle", "ga", "Irish"),
+	("glg", "glg", "gl", "Galician"),
+	("gre", "ell", "el", "Greek"),
+	("guj", "guj", "gu", "Gujarati"),
+	("heb", "heb", "he", "Hebrew"),
+	("hin", "hin", "hi", "Hindi"),
+	("hun", "hun", "hu", "Hungarian"),
+	("ice", "isl", "is", "Icelandic"),
+	("ind", "ind", "id", "Indonesian"),
+	("ita", "ita", "it", "Italian"),
+	("jpn", "jpn", "ja", "Japanese"),
+	("kan", "kan", "kn", "Kannada"),
+	("kaz", "kaz", "kk", "Kazakh"),
+	("khm", "khm", "km", "Khmer"),
+	("kor", "kor", "ko", "Korean"),
+	("kur", "kur", "ku", "Kurdish"),
+	("lao", "lao", "lo", "Lao"),
+	("lat", "lat", "la", "Latin"),
+	("lav", "lav", "lv", "Latvian"),
+	("lit", "lit", "lt", "Lithuanian"),
+	("ltz", "ltz", "lb", "Luxembourgish"),
+	("mac", "mkd", "mk", "Macedonian"),
+	("mal", "mal", "ml", "Malayalam"),
+	("mao", "mri", "mi", "Maori"),
+	("mar", "mar", "mr", "Marathi"),
+	("may", "msa", "ms", "Malay"),
+	("mlt", "mlt", "mt", "Maltese"),
+	("mon", "mon", "mn", "Mongolian"),
+	("nep", "nep", "ne", "Nepali"),
+	("nob", "nob", "nb", "Norwegian Bokmål"),
+	("nno", "nno", "nn", "Norwegian Nynorsk"),
+	("nor", "nor", "no", "Norwegian"),
+	("per", "fas", "fa", "Persian"),
+	("pol", "pol", "pl", "Polish"),
+	("por", "por", "pt", "Portuguese"),
+	("pan", "pan", "pa", "Punjabi"),
+	("pus", "pus", "ps", "Pashto"),
+	("rum", "ron", "ro", "Romanian"),
+	("rus", "rus", "ru", "Russian"),
+	("srp", "srp", "sr", "Serbian"),
+	("sin", "sin", "si", "Sinhala"),
+	("slo", "slk", "sk", "Slovak"),
+	("slv", "slv", "sl", "Slovenian"),
+	("som", "som", "so", "Somali"),
+	("spa", "spa", "es", "Spanish"),
+	("swa", "swa", "sw", "Swahili"),
+	("swe", "swe", "sv", "Swedish"),
+	("tam", "tam", "ta", "Tamil"),
+	("tel", "tel", "te", "Telugu"),
+	("tgl", "tgl", "tl", "Tagalog"),
+	("tha", "tha", "th", "Thai"),
+	("tib", "bod", "bo", "Tibetan"),
+	("tur", "tur", "tr", "Turkish"),
+	("ukr", "ukr", "uk", "Ukrainian"),
+	("urd", "urd", "ur", "Urdu"),
+	("uzb", "uzb", "uz", "Uzbek"),
+	("vie", "vie", "vi", "Vietnamese"),
+	("wel", "cym", "cy", "Welsh"),
+	("yid", "yid", "yi", "Yiddish"),
+	("yor", "yor", "yo", "Yoruba"),
+	("zul", "zul", "zu", "Zulu"),
+];
+
+// Codes that explicitly say there is no language information.
+const UNDETERMINED: &[&str] = &["und", "unk", "mis", "mul", "zxx", "none"];
+
+impl Language {
+	pub fn parse(raw: &str) -> Option<Self> {
+		let trimmed = raw.trim();
+		let mut parts = trimmed.split(|c| c == '-' || c == '_');
+		let primary = parts.next()?.to_ascii_lowercase();
+
+		if primary.is_empty() || UNDETERMINED.contains(&primary.as_str()) {
+			return None;
+		}
+
+		if !(2..=3).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+			return None;
+		}
+
+		let entry = LANGUAGES
+			.iter()
+			.find(|(b, t, a, _)| *b == primary || *t == primary || *a == primary);
+
+		let mut code = entry.map_or(primary, |(.., alpha2, _)| (*alpha2).to_owned());
+
+		// Subtags keep their conventional casing, title case for scripts and
+		// upper case for regions.
+		for part in parts.filter(|part| !part.is_empty()) {
+			code.push('-');
+
+			match part.len() {
+				4 => {
+					let (first, rest) = part.split_at(1);
+					code.push_str(&first.to_ascii_uppercase());
+					code.push_str(&rest.to_ascii_lowercase());
+				}
+
+				2 | 3 if part.chars().all(|c| c.is_ascii_alphanumeric()) => {
+					code.push_str(&part.to_ascii_uppercase());
+				}
+
+				_ => code.push_str(&part.to_ascii_lowercase()),
+			}
+		}
+
+		Some(Language {
+			code,
+			name: entry.map(|(.., name)| (*name).to_owned()),
+			raw: raw.into(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn code(raw: &str) -> Option<String> {
+		Language::parse(raw).map(|language| language.code)
+	}
+
+	#[test]
+	fn iso_639_codes() {
+		let language = Language::parse("fre").unwrap();
+		assert_eq!(language.code, "fr");
+		assert_eq!(language.name.as_deref(), Some("French"));
+		assert_eq!(language.raw, "fre");
+
+		assert_eq!(code("fra").as_deref(), Some("fr"));
+		assert_eq!(code("FR").as_deref(), Some("fr"));
+		assert_eq!(code(" ger ").as_deref(), Some("de"));
+		assert_eq!(code("fil").as_deref(), Some("fil"));
+	}
+
+	#[test]
+	fn subtags() {
+		assert_eq!(code("pt-br").as_deref(), Some("pt-BR"));
+		assert_eq!(code("zh_hant_tw").as_deref(), Some("zh-Hant-TW"));
+		assert_eq!(code("spa-419").as_deref(), Some("es-419"));
+		assert_eq!(code("en-US-").as_deref(), Some("en-US"));
+	}
+
+	#[test]
+	fn unknown_codes() {
+		let language = Language::parse("tlh").unwrap();
+		assert_eq!(language.code, "tlh");
+		assert_eq!(language.name, None);
+	}
+
+	#[test]
+	fn undetermined() {
+		for raw in &["und", "UND", "zxx", "none", "", "  ", "english", "e1", "-en"] {
+			assert_eq!(code(raw), None, "{:?}", raw);
+		}
+	}
+}
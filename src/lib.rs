@@ -1,29 +1,266 @@
-use std::collections::HashMap;
+use std::{
+	collections::{HashMap, HashSet},
+	path::Path,
+	time::Duration,
+};
 use ffmpeg::{
 	codec,
+	ffi::{AVFieldOrder, AVPacketSideDataType},
 	format::{context::Input, stream::Disposition},
 	media, Discard, Rational,
 };
 use serde::{Deserialize, Serialize};
 
+mod avio;
+mod bitstream;
+mod compat;
+mod movie;
+mod raw;
+mod report;
+mod tags;
+
+mod timecode;
+pub use timecode::{Timecode, TimecodeSource};
+
+mod location;
+pub use location::Location;
+
+mod gapless;
+pub use gapless::{GaplessInfo, SampleSource};
+
+mod mp3;
+pub use mp3::{BitrateMode, VbrHeader, VbrHeaderKind};
+
+mod replaygain;
+pub use replaygain::{Gain, ReplayGain, ReplayGainSource};
+
+mod immersive;
+pub use immersive::ObjectAudio;
+
+mod dolby;
+pub use dolby::{BitstreamMode, DolbyMetadata};
+
+mod aac;
+pub use aac::{AacConfigSource, AacInfo, AacSignaling};
+
+mod sps;
+pub use sps::{ChromaFormat, HevcTier, SequenceParameters, VuiTiming};
+
+mod codec_config;
+pub use codec_config::VideoCodecConfig;
+
+mod markers;
+pub use markers::{Cue, Loop, LoopKind, Markers};
+
+mod broadcast;
+pub use broadcast::{Bext, BextLoudness, BroadcastMetadata, Cart, IXml};
+
+mod exif;
+pub use exif::ExifInfo;
+
+mod flac;
+pub use flac::{CueIndex, CueSheet, CueTrack, Flac};
+
+mod heif;
+pub use heif::{Heif, HeifItem, ItemRole};
+
+mod id3;
+pub use id3::{
+	Id3, Id3Chapter, Id3Lyrics, Id3Picture, Id3Private, Id3SyncedLyrics, Id3TableOfContents,
+	SyncedLine, TimestampFormat,
+};
+
+mod ilst;
+pub use ilst::{ItunesItem, ItunesTags, ItunesValue, PictureFormat};
+
+mod matroska;
+pub use matroska::{
+	ChapterTitle, Edition, EditionChapter, Matroska, MatroskaTag, SimpleTag, TagTarget,
+};
+
+mod module;
+pub use module::{ModuleFormat, TrackerModule};
+
+mod ogg;
+pub use ogg::{OggChain, OggLink, OggStream};
+
+mod manifest;
+pub use manifest::{Manifest, ManifestKind, Rendition, Variant};
+
+mod encryption;
+pub use encryption::{DrmSystem, EncryptionInfo};
+
+mod edit_list;
+pub use edit_list::{Edit, EditList, EditListIssue};
+
+mod elementary;
+pub use elementary::{DurationEstimate, Elementary};
+
+mod language;
+pub use language::Language;
+
+mod chapter;
+pub use chapter::Chapter;
+
+mod ffmetadata;
+pub use ffmetadata::FfMetadata;
+
+mod thumbnail;
+pub use thumbnail::{extract_thumbnail, Image, ImageFormat, ThumbnailOptions};
+
+mod editor;
+pub use editor::{CoverArt, MetadataEditor};
+
+mod remux;
+pub use remux::{RemuxAction, RemuxPreview, StreamPlan};
+
+mod diff;
+pub use diff::{Change, DiffThresholds, MetadataDiff};
+
+mod redact;
+pub use redact::{Redaction, RedactionPolicy};
+
+mod warning;
+pub use warning::Warning;
+
+mod summary;
+pub use summary::{Issues, Summary};
+
+mod origin;
+pub use origin::{Origin, Origins, StreamOrigins};
+
+mod provenance;
+pub use provenance::{Provenance, ProvenanceEntry, ProvenanceSource};
+
+mod identify;
+pub use identify::{identify, identify_bytes, FormatGuess};
+
+mod log;
+pub use log::{capture_log, LogLevel, LogMessage};
+
+mod probe;
+pub use probe::{ProbeOptions, RawHints, StreamSelection};
+
+mod live;
+pub use live::{LiveOptions, LiveProbe, LiveStream};
+
+mod pipe;
+pub use pipe::PipeOptions;
+
+#[cfg(feature = "json")]
+mod migrate;
+
+#[cfg(feature = "binary")]
+mod binary;
+
+#[cfg(feature = "json")]
+mod projection;
+#[cfg(feature = "json")]
+pub use projection::Projection;
+
+#[cfg(feature = "chrono")]
+mod clock;
+#[cfg(feature = "chrono")]
+pub use clock::{Anchor, ClockSource, WallClock};
+
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+pub use cache::{CacheKey, MetadataCache};
+
+pub mod analysis;
+pub mod capabilities;
+pub mod export;
+pub mod scanner;
+pub mod validate;
+
+#[cfg(feature = "schemars")]
+pub mod schema;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "python")]
+mod python;
+
+// FFmpeg global initialization for the entry points that don't get an already
+// opened input from the caller.
+pub(crate) fn init() -> ffmpeg::Result<()> {
+	static INIT: std::sync::Once = std::sync::Once::new();
+	static mut RESULT: Option<ffmpeg::Error> = None;
+
+	unsafe {
+		INIT.call_once(|| RESULT = ffmpeg::init().err());
+
+		match RESULT {
+			Some(error) => Err(error),
+			None => Ok(()),
+		}
+	}
+}
+
+// Nothing borrows from FFmpeg, so results can be cached and moved or shared
+// across threads freely.
+const _: fn() = || {
+	fn owned<T: Send + Sync + 'static>() {}
+	owned::<Metadata>();
+};
+
+// Bumped whenever `Metadata` gains or changes a field, with a step in `migrate`
+// bringing older documents up to date. The cache drops entries written under
+// another version, so they get probed again.
+pub const SCHEMA_VERSION: u32 = 2;
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Metadata {
+	// Missing from documents written before versioning.
+	#[serde(default)]
+	pub schema_version: u32,
 	pub format: Format,
 	pub best: Best,
 	pub streams: Vec<Stream>,
+	pub chapters: Vec<Chapter>,
 	pub details: HashMap<String, String>,
+	pub duration: Option<i64>,
+	pub bit_rate: usize,
+	pub timecode: Option<Timecode>,
+	pub location: Option<Location>,
+	pub exif: Option<ExifInfo>,
+	pub heif: Option<Heif>,
+	pub markers: Option<Markers>,
+	pub broadcast: Option<BroadcastMetadata>,
+	pub flac: Option<Flac>,
+	pub id3: Option<Id3>,
+	pub itunes: Option<ItunesTags>,
+	pub matroska: Option<Matroska>,
+	pub module: Option<TrackerModule>,
+	pub ogg: Option<OggChain>,
+	pub elementary: Option<Elementary>,
+	pub origins: Origins,
+	pub provenance: Provenance,
+	pub warnings: Vec<Warning>,
+	#[cfg(feature = "chrono")]
+	pub created: Option<chrono::DateTime<chrono::Utc>>,
+	#[cfg(feature = "chrono")]
+	pub modified: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Format {
 	pub name: String,
 	pub aliases: Vec<String>,
 	pub description: String,
 	pub extensions: Vec<String>,
 	pub mime_types: Vec<String>,
+	pub major_brand: Option<String>,
+	pub minor_version: Option<u32>,
+	pub compatible_brands: Vec<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Best {
 	pub audio: Option<usize>,
 	pub video: Option<usize>,
@@ -31,28 +268,43 @@ pub struct Best {
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Stream {
 	pub index: usize,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Rational"))]
 	pub time_base: Rational,
 	pub start_time: Option<i64>,
 	pub duration: Option<i64>,
 	pub frames: i64,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Flags"))]
 	pub disposition: Disposition,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub discard: Discard,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Rational"))]
 	pub frame_rate: Rational,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Rational"))]
 	pub avg_frame_rate: Rational,
-	// TODO(meh): side_data
+	pub language: Option<Language>,
+	pub side_data: Vec<SideData>,
+	pub encryption: Option<EncryptionInfo>,
+	pub edit_list: Option<EditList>,
+	// Only known after `Metadata::detect_empty_streams`.
+	pub is_empty: bool,
 	pub content: Content,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Codec {
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub id: codec::Id,
 	pub name: String,
 	pub description: String,
+	pub profile: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum Content {
 	Unknown(Unknown),
@@ -63,10 +315,13 @@ pub enum Content {
 	Attachment(Attachment),
 }
 
+// Braced rather than a unit struct, TOML has nothing to write a unit as.
 #[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct Unknown;
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Unknown {}
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Audio {
 	pub codec: Codec,
 	pub bit_rate: usize,
@@ -74,14 +329,24 @@ pub struct Audio {
 	pub delay: usize,
 	pub sample_rate: u32,
 	pub channels: u16,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Sample"))]
 	pub format: ffmpeg::format::Sample,
+	pub bit_depth: Option<u8>,
 	pub frames: usize,
 	pub align: usize,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Flags"))]
 	pub channel_layout: ffmpeg::ChannelLayout,
 	pub frame_start: Option<usize>,
+	pub gapless: Option<GaplessInfo>,
+	pub vbr: Option<VbrHeader>,
+	pub replaygain: Option<ReplayGain>,
+	pub object: Option<ObjectAudio>,
+	pub dolby: Option<DolbyMetadata>,
+	pub aac: Option<AacInfo>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Video {
 	pub codec: Codec,
 	pub bit_rate: usize,
@@ -89,30 +354,246 @@ pub struct Video {
 	pub delay: usize,
 	pub width: u32,
 	pub height: u32,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub format: ffmpeg::format::Pixel,
+	pub bit_depth: Option<u8>,
 	pub has_b_frames: bool,
+	#[cfg_attr(feature = "schemars", schemars(with = "schema::Rational"))]
 	pub aspect_ratio: ffmpeg::Rational,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub color_space: ffmpeg::color::Space,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub color_range: ffmpeg::color::Range,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub color_primaries: ffmpeg::color::Primaries,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub color_transfer_characteristic: ffmpeg::color::TransferCharacteristic,
+	#[cfg_attr(feature = "schemars", schemars(with = "String"))]
 	pub chroma_location: ffmpeg::chroma::Location,
 	pub references: usize,
 	pub intra_dc_precision: u8,
+	pub field_order: FieldOrder,
+	pub closed_captions: Option<ClosedCaptionInfo>,
+	pub sps: Option<SequenceParameters>,
+	pub codec_config: Option<VideoCodecConfig>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldOrder {
+	Unknown,
+	Progressive,
+	TopFirst,
+	BottomFirst,
+	TopCodedBottomFirst,
+	BottomCodedTopFirst,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum SideData {
+	DisplayMatrix,
+	ReplayGain,
+	Stereo3d,
+	Spherical,
+	MasteringDisplay,
+	ContentLightLevel,
+	DolbyVision,
+	AudioServiceType,
+	CpbProperties,
+	Other(String),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ClosedCaptionInfo {
+	pub standards: Vec<CaptionStandard>,
+	pub services: Vec<CaptionService>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptionStandard {
+	Eia608,
+	Cea708,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptionService {
+	Eia608(u8),
+	Cea708(u8),
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Data {}
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Subtitle {
 	pub codec: Codec,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Attachment {}
 
+impl From<AVFieldOrder> for FieldOrder {
+	fn from(value: AVFieldOrder) -> Self {
+		match value {
+			AVFieldOrder::AV_FIELD_UNKNOWN => FieldOrder::Unknown,
+			AVFieldOrder::AV_FIELD_PROGRESSIVE => FieldOrder::Progressive,
+			AVFieldOrder::AV_FIELD_TT => FieldOrder::TopFirst,
+			AVFieldOrder::AV_FIELD_BB => FieldOrder::BottomFirst,
+			AVFieldOrder::AV_FIELD_TB => FieldOrder::TopCodedBottomFirst,
+			AVFieldOrder::AV_FIELD_BT => FieldOrder::BottomCodedTopFirst,
+		}
+	}
+}
+
+impl From<AVPacketSideDataType> for SideData {
+	fn from(value: AVPacketSideDataType) -> Self {
+		match value {
+			AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX => SideData::DisplayMatrix,
+			AVPacketSideDataType::AV_PKT_DATA_REPLAYGAIN => SideData::ReplayGain,
+			AVPacketSideDataType::AV_PKT_DATA_STEREO3D => SideData::Stereo3d,
+			AVPacketSideDataType::AV_PKT_DATA_SPHERICAL => SideData::Spherical,
+			AVPacketSideDataType::AV_PKT_DATA_MASTERING_DISPLAY_METADATA => {
+				SideData::MasteringDisplay
+			}
+			AVPacketSideDataType::AV_PKT_DATA_CONTENT_LIGHT_LEVEL => SideData::ContentLightLevel,
+			#[cfg(ffmpeg_5)]
+			AVPacketSideDataType::AV_PKT_DATA_DOVI_CONF => SideData::DolbyVision,
+			AVPacketSideDataType::AV_PKT_DATA_AUDIO_SERVICE_TYPE => SideData::AudioServiceType,
+			AVPacketSideDataType::AV_PKT_DATA_CPB_PROPERTIES => SideData::CpbProperties,
+			other => SideData::Other(raw::side_data_name(other)),
+		}
+	}
+}
+
+impl Content {
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Content::Unknown(_) => "unknown",
+			Content::Audio(_) => "audio",
+			Content::Video(_) => "video",
+			Content::Data(_) => "data",
+			Content::Subtitle(_) => "subtitle",
+			Content::Attachment(_) => "attachment",
+		}
+	}
+
+	pub fn codec(&self) -> Option<&Codec> {
+		match self {
+			Content::Audio(audio) => Some(&audio.codec),
+			Content::Video(video) => Some(&video.codec),
+			Content::Subtitle(subtitle) => Some(&subtitle.codec),
+			_ => None,
+		}
+	}
+}
+
+impl Stream {
+	pub fn remux_preview(&self, format: &str) -> ffmpeg::Result<RemuxPreview> {
+		RemuxPreview::new(self, format)
+	}
+
+	pub fn duration_seconds(&self) -> Option<f64> {
+		self.seconds(self.duration?)
+	}
+
+	pub fn duration_time(&self) -> Option<Duration> {
+		self.duration_seconds().filter(|s| s.is_finite() && *s >= 0.0).map(Duration::from_secs_f64)
+	}
+
+	pub fn start_time_seconds(&self) -> Option<f64> {
+		self.seconds(self.start_time?)
+	}
+
+	// The average is what players end up showing, the base rate is the
+	// fallback for streams without enough timing information.
+	pub fn fps(&self) -> Option<f64> {
+		[self.avg_frame_rate, self.frame_rate]
+			.iter()
+			.find(|rate| rate.numerator() > 0 && rate.denominator() > 0)
+			.map(|&rate| f64::from(rate))
+	}
+
+	fn seconds(&self, timestamp: i64) -> Option<f64> {
+		if self.time_base.denominator() == 0 {
+			return None;
+		}
+
+		Some(timestamp as f64 * f64::from(self.time_base))
+	}
+}
+
+impl Video {
+	pub fn sample_aspect_ratio(&self) -> Rational {
+		if self.aspect_ratio.numerator() > 0 && self.aspect_ratio.denominator() > 0 {
+			self.aspect_ratio
+		}
+		else {
+			Rational::new(1, 1)
+		}
+	}
+
+	pub fn display_aspect_ratio(&self) -> Option<Rational> {
+		if self.width == 0 || self.height == 0 {
+			return None;
+		}
+
+		let sar = self.sample_aspect_ratio();
+		let num = i64::from(self.width) * i64::from(sar.numerator());
+		let den = i64::from(self.height) * i64::from(sar.denominator());
+		let divisor = gcd(num, den);
+
+		Some(Rational::new((num / divisor) as i32, (den / divisor) as i32))
+	}
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+	if b == 0 {
+		a
+	}
+	else {
+		gcd(b, a % b)
+	}
+}
+
+impl Codec {
+	fn new(parameters: &codec::Parameters) -> ffmpeg::Result<Self> {
+		let id = parameters.id();
+
+		// The descriptor names the codec rather than the decoder, e.g. `av1`
+		// where the decoder would be `libdav1d`.
+		let (name, description) = if probe::headers_only() {
+			(id.name().into(), raw::codec_description(id).unwrap_or_default())
+		}
+		else {
+			let codec = ffmpeg::decoder::find(id).ok_or(ffmpeg::Error::DecoderNotFound)?;
+			(codec.name().into(), codec.description().into())
+		};
+
+		Ok(Codec {
+			id,
+			name,
+			description,
+			profile: raw::profile_name(id, raw::profile(parameters)),
+		})
+	}
+}
+
 impl Metadata {
+	#[cfg_attr(
+		feature = "tracing",
+		tracing::instrument(name = "probe", skip_all, fields(url = ?raw::url(input)))
+	)]
 	pub fn new(input: &Input) -> ffmpeg::Result<Self> {
 		let format = Format {
 			name: input.format().name().split(",").next().unwrap().into(),
@@ -130,6 +611,21 @@ impl Metadata {
 				.into_iter()
 				.map(String::from)
 				.collect(),
+			major_brand: input.metadata().get("major_brand").map(String::from),
+			minor_version: input.metadata().get("minor_version").and_then(|v| v.parse().ok()),
+			// Brands are four character codes concatenated together, padded with
+			// spaces when shorter (e.g. `qt  `).
+			compatible_brands: input
+				.metadata()
+				.get("compatible_brands")
+				.map(|brands| {
+					brands
+						.as_bytes()
+						.chunks(4)
+						.map(|brand| String::from_utf8_lossy(brand).into_owned())
+						.collect()
+				})
+				.unwrap_or_default(),
 		};
 
 		let best = Best {
@@ -141,52 +637,60 @@ impl Metadata {
 				.map(|s| s.index()),
 		};
 
+		let movie = movie::Movie::open(input);
+		let packets = probe::Packets::new(input);
+		let encryption = encryption::find(input, movie.as_ref());
+		let edit_lists = movie.as_ref().map(|movie| edit_list::find(input, movie)).unwrap_or_default();
 		let streams = input
 			.streams()
 			.into_iter()
+			.filter(probe::selected)
 			.map(|stream| {
-				let content = match stream.codec().medium() {
+				#[cfg(feature = "tracing")]
+				let _span = tracing::debug_span!("stream", index = stream.index()).entered();
+
+				let content = match compat::medium(&stream) {
 					media::Type::Unknown => {
-						Content::Unknown(Unknown)
+						Content::Unknown(Unknown {})
 					}
 
 					media::Type::Audio => {
-						let audio = stream.codec().decoder().audio()?;
+						let audio = compat::audio(&stream)?;
 
 						Content::Audio(Audio {
-							codec: Codec {
-								id: audio.codec().ok_or(ffmpeg::Error::Bug)?.id(),
-								name: audio.codec().ok_or(ffmpeg::Error::Bug)?.name().into(),
-								description: audio.codec().ok_or(ffmpeg::Error::Bug)?.description().into(),
-							},
+							codec: Codec::new(&stream.parameters())?,
 							bit_rate: audio.bit_rate(),
 							max_bit_rate: audio.max_bit_rate(),
 							delay: audio.delay(),
 							sample_rate: audio.sample_rate(),
-							channels: audio.channels(),
+							channels: compat::channels(&audio),
 							format: audio.format(),
+							bit_depth: raw::bits_per_raw_sample(&stream.parameters()),
 							frames: audio.frames(),
 							align: audio.align(),
-							channel_layout: audio.channel_layout(),
+							channel_layout: compat::channel_layout(&audio),
 							frame_start: audio.frame_start(),
+							gapless: GaplessInfo::find(input, &stream, audio.sample_rate()),
+							vbr: VbrHeader::find(input),
+							replaygain: ReplayGain::find(input, &stream),
+							object: ObjectAudio::with_packets(&stream, &packets),
+							dolby: DolbyMetadata::with_packets(&stream, &packets),
+							aac: AacInfo::with_packets(&stream, &packets),
 						})
 					}
 
 					media::Type::Video => {
-						let video = stream.codec().decoder().video()?;
+						let video = compat::video(&stream)?;
 
 						Content::Video(Video {
-							codec: Codec {
-								id: video.codec().ok_or(ffmpeg::Error::Bug)?.id(),
-								name: video.codec().ok_or(ffmpeg::Error::Bug)?.name().into(),
-								description: video.codec().ok_or(ffmpeg::Error::Bug)?.description().into(),
-							},
+							codec: Codec::new(&stream.parameters())?,
 							bit_rate: video.bit_rate(),
 							max_bit_rate: video.max_bit_rate(),
 							delay: video.delay(),
 							width: video.width(),
 							height: video.height(),
 							format: video.format(),
+							bit_depth: raw::depth(video.format()),
 							has_b_frames: video.has_b_frames(),
 							aspect_ratio: video.aspect_ratio(),
 							color_space: video.color_space(),
@@ -196,6 +700,10 @@ impl Metadata {
 							chroma_location: video.chroma_location(),
 							references: video.references(),
 							intra_dc_precision: video.intra_dc_precision(),
+							field_order: raw::field_order(&stream.parameters()).into(),
+							closed_captions: None,
+							sps: SequenceParameters::find(&stream),
+							codec_config: VideoCodecConfig::with_packets(&stream, &packets),
 						})
 					}
 
@@ -204,14 +712,12 @@ impl Metadata {
 					}
 
 					media::Type::Subtitle => {
-						let subtitle = stream.codec().decoder().subtitle()?;
+						if !probe::headers_only() {
+							compat::decoder(&stream)?.subtitle()?;
+						}
 
 						Content::Subtitle(Subtitle {
-							codec: Codec {
-								id: subtitle.codec().ok_or(ffmpeg::Error::Bug)?.id(),
-								name: subtitle.codec().ok_or(ffmpeg::Error::Bug)?.name().into(),
-								description: subtitle.codec().ok_or(ffmpeg::Error::Bug)?.description().into(),
-							},
+							codec: Codec::new(&stream.parameters())?,
 						})
 					}
 
@@ -230,18 +736,297 @@ impl Metadata {
 					discard: stream.discard(),
 					frame_rate: stream.frame_rate(),
 					avg_frame_rate: stream.avg_frame_rate(),
+					language: stream.metadata().get("language").and_then(Language::parse),
+					side_data: compat::side_data(&stream).into_iter().map(SideData::from).collect(),
+					encryption: encryption.get(&stream.index()).cloned(),
+					edit_list: edit_lists.get(&stream.index()).cloned(),
+					is_empty: false,
 					content,
 				})
 			})
 			.collect::<ffmpeg::Result<Vec<_>>>()?;
 
+		let warnings = streams
+			.iter()
+			.filter_map(|stream| {
+				let list = stream.edit_list.as_ref().filter(|list| !list.issues.is_empty())?;
+				Some(Warning::EditList { stream: stream.index, issues: list.issues.clone() })
+			})
+			.collect();
+
 		let details = input.metadata().iter().map(|(a, b)| (a.into(), b.into())).collect();
+		let elementary = Elementary::find(input);
+		let duration = Some(input.duration()).filter(|&d| d != ffmpeg::ffi::AV_NOPTS_VALUE && d > 0);
 
 		Ok(Metadata {
+			schema_version: SCHEMA_VERSION,
 			format,
 			best,
 			streams,
+			chapters: Chapter::find(input),
 			details,
+			duration,
+			bit_rate: input.bit_rate().max(0) as usize,
+			timecode: Timecode::find(input),
+			location: Location::find(input),
+			exif: ExifInfo::find(input),
+			heif: Heif::find(input),
+			markers: Markers::find(input),
+			broadcast: BroadcastMetadata::find(input),
+			flac: Flac::find(input),
+			id3: Id3::find(input),
+			itunes: ItunesTags::find(input),
+			matroska: Matroska::find(input),
+			module: TrackerModule::find(input),
+			ogg: OggChain::find(input),
+			origins: Origins::new(input, elementary.as_ref()),
+			elementary,
+			provenance: Provenance::new(input),
+			warnings,
+			#[cfg(feature = "chrono")]
+			created: tags::date::created(input),
+			#[cfg(feature = "chrono")]
+			modified: tags::date::modified(input),
 		})
 	}
+
+	// Protected content can't be decoded without a license, ingest usually
+	// wants to turn it away before trying.
+	pub fn is_encrypted(&self) -> bool {
+		self.streams.iter().any(|stream| stream.encryption.is_some())
+	}
+
+	// Reads a document serialized by any version of the crate, upgrading it
+	// to the current layout first.
+	#[cfg(feature = "json")]
+	pub fn from_json_any_version(json: &str) -> serde_json::Result<Self> {
+		migrate::upgrade(serde_json::from_str(json)?)
+	}
+
+	#[cfg(feature = "json")]
+	pub fn project(&self, projection: &Projection) -> serde_json::Result<serde_json::Value> {
+		projection.apply(self)
+	}
+
+	// A compact encoding for storing large numbers of results, only readable
+	// by releases with the same `SCHEMA_VERSION` and `chrono` feature.
+	#[cfg(feature = "binary")]
+	pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+		binary::to_bytes(self)
+	}
+
+	#[cfg(feature = "binary")]
+	pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Self> {
+		binary::from_bytes(bytes)
+	}
+
+	// Opens and probes the file at `path`, initializing FFmpeg first if that
+	// wasn't done already.
+	pub fn probe<P: AsRef<Path>>(path: P) -> ffmpeg::Result<Self> {
+		init()?;
+		Metadata::new(&ffmpeg::format::input(&path)?)
+	}
+
+	// Opens and probes `url` itself, so reads stop once `options` runs out of
+	// time or bytes rather than whenever the server gives up.
+	pub fn with_options(url: &str, options: &ProbeOptions) -> ffmpeg::Result<Self> {
+		let (metadata, log) =
+			capture_log(|| probe::scoped(url, options, |input| Metadata::new(input)));
+
+		let mut metadata = metadata?;
+		metadata.warnings.extend(log.into_iter().map(Warning::Log));
+
+		Ok(metadata)
+	}
+
+	pub fn detect_closed_captions(
+		&mut self,
+		input: &mut Input,
+		options: &analysis::Options,
+	) -> ffmpeg::Result<()> {
+		for stream in &mut self.streams {
+			if let Content::Video(video) = &mut stream.content {
+				video.closed_captions = ClosedCaptionInfo::analyze(input, stream.index, options)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	// Reads packets until every stream had one, or the input ends. Anything
+	// left over is empty, unless `max_frames` stopped the scan first.
+	pub fn detect_empty_streams(
+		&mut self,
+		input: &mut Input,
+		options: &analysis::Options,
+	) -> ffmpeg::Result<()> {
+		let mut seen = HashSet::new();
+		let mut count = 0;
+		let mut complete = true;
+
+		input.seek(0, ..)?;
+		let mut ticker = options.ticker(input);
+		for (stream, packet) in input.packets() {
+			ticker.packet(&packet, stream.time_base())?;
+
+			if options.max_frames.map_or(false, |max| count >= max) {
+				complete = false;
+				break;
+			}
+
+			if packet.size() > 0 {
+				seen.insert(stream.index());
+			}
+
+			// Unselected streams still hand out packets, only the ones kept
+			// here count.
+			if self.streams.iter().all(|stream| seen.contains(&stream.index)) {
+				break;
+			}
+
+			count += 1;
+		}
+
+		if !complete {
+			return Ok(());
+		}
+
+		self.warnings.retain(|warning| !matches!(warning, Warning::EmptyStream { .. }));
+		for stream in &mut self.streams {
+			stream.is_empty = !seen.contains(&stream.index);
+
+			if stream.is_empty {
+				self.warnings.push(Warning::EmptyStream {
+					stream: stream.index,
+					kind: stream.content.kind().into(),
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn detect_provenance(
+		&mut self,
+		input: &mut Input,
+		options: &analysis::Options,
+	) -> ffmpeg::Result<()> {
+		self.provenance.analyze(input, options)
+	}
+
+	// Counts the frames of a bare bitstream for its duration, which beats
+	// FFmpeg's guess from the bit rate. Reads the whole input, unless
+	// `max_frames` gives up first and leaves everything as it was.
+	pub fn count_frames(
+		&mut self,
+		input: &mut Input,
+		options: &analysis::Options,
+	) -> ffmpeg::Result<()> {
+		let counted = match Elementary::count(input, options)? {
+			Some(counted) => counted,
+			None => return Ok(()),
+		};
+
+		if let Some(duration) = counted.duration {
+			self.duration = Some((duration * f64::from(ffmpeg::ffi::AV_TIME_BASE)) as i64);
+			self.origins.duration = Some(Origin::Measured);
+		}
+
+		self.elementary = Some(counted);
+		Ok(())
+	}
+
+	// Fills in the bit rates and durations the input doesn't declare from a
+	// bounded packet scan, marking them as estimated in `origins`.
+	pub fn estimate_missing(
+		&mut self,
+		input: &mut Input,
+		options: &analysis::Options,
+	) -> ffmpeg::Result<analysis::Estimate> {
+		let estimate = analysis::Estimate::analyze(input, options)?;
+		let time_base = f64::from(ffmpeg::ffi::AV_TIME_BASE);
+
+		if let (None, Some(duration)) = (self.duration, estimate.duration) {
+			self.duration = Some((duration * time_base).round() as i64);
+			self.origins.duration = Some(Origin::Estimated);
+		}
+
+		if let (0, Some(rate)) = (self.bit_rate, estimate.bit_rate) {
+			self.bit_rate = rate;
+			self.origins.bit_rate = Some(Origin::Estimated);
+		}
+
+		for stream in &mut self.streams {
+			let found = match estimate.stream(stream.index) {
+				Some(found) => found,
+				None => continue,
+			};
+
+			let mut origins = self.origins.streams.iter_mut().find(|entry| entry.index == stream.index);
+
+			if let (None, Some(duration)) = (stream.duration, found.duration) {
+				if stream.time_base.numerator() > 0 {
+					stream.duration = Some((duration / f64::from(stream.time_base)).round() as i64);
+
+					if let Some(origins) = origins.as_mut() {
+						origins.duration = Some(Origin::Estimated);
+					}
+				}
+			}
+
+			let bit_rate = match &mut stream.content {
+				Content::Audio(audio) => &mut audio.bit_rate,
+				Content::Video(video) => &mut video.bit_rate,
+				_ => continue,
+			};
+
+			if let (0, Some(rate)) = (*bit_rate, found.bit_rate) {
+				*bit_rate = rate;
+
+				if let Some(origins) = origins {
+					origins.bit_rate = Some(Origin::Estimated);
+				}
+			}
+		}
+
+		Ok(estimate)
+	}
+
+	pub fn duration_seconds(&self) -> Option<f64> {
+		self.duration.map(|d| d as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE))
+	}
+
+	#[cfg(feature = "hash")]
+	pub fn asset_id(&self, input: &mut Input) -> ffmpeg::Result<String> {
+		analysis::asset_id(self, input)
+	}
+
+	// Reads, and optionally decodes, the whole input looking for damage.
+	pub fn validate(
+		&self,
+		input: &mut Input,
+		options: &validate::ValidationOptions,
+	) -> ffmpeg::Result<validate::ValidationReport> {
+		validate::ValidationReport::scan(self, input, options)
+	}
+
+	pub fn check(&self, profile: &validate::Profile) -> Vec<validate::profile::Violation> {
+		profile.check(self)
+	}
+
+	pub fn timing(&self) -> validate::Timing {
+		validate::Timing::check(self, &validate::TimingThresholds::default())
+	}
+
+	pub fn diff(&self, other: &Metadata) -> MetadataDiff {
+		MetadataDiff::new(self, other, &DiffThresholds::default())
+	}
+
+	// A copy without what `policy` considers private, for republishing.
+	pub fn redacted(&self, policy: &RedactionPolicy) -> Metadata {
+		let mut metadata = self.clone();
+		policy.apply(&mut metadata);
+
+		metadata
+	}
 }
@@ -0,0 +1,168 @@
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use ffmpeg::{format::context::Input, Packet};
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis::seconds, capture_log, probe, Metadata, ProbeOptions, Warning};
+
+// Timestamps jumping further than this between two packets of a stream are a
+// discontinuity, as much as going backwards is.
+const DISCONTINUITY: f64 = 1.0;
+
+#[derive(Clone, Debug)]
+pub struct LiveOptions {
+	pub duration: Duration,
+	// Without a timeout, one is derived from `duration` so a stalled source
+	// can't hold the probe forever.
+	pub probe: ProbeOptions,
+}
+
+impl Default for LiveOptions {
+	fn default() -> Self {
+		LiveOptions {
+			duration: Duration::from_secs(5),
+			probe: ProbeOptions::default(),
+		}
+	}
+}
+
+// What a live source actually delivered while being watched, next to what it
+// announced.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LiveProbe {
+	pub metadata: Metadata,
+	pub elapsed: f64,
+	pub streams: Vec<LiveStream>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LiveStream {
+	pub index: usize,
+	pub packets: u64,
+	pub bytes: u64,
+	pub corrupt: u64,
+	// From the timestamps of the packets received.
+	pub frame_rate: Option<f64>,
+	// From the bytes received over wall clock time.
+	pub bit_rate: Option<u64>,
+	// Interarrival jitter in seconds, as RTP receivers compute it.
+	pub jitter: Option<f64>,
+	pub discontinuities: u64,
+}
+
+#[derive(Default)]
+struct State {
+	stream: LiveStream,
+	first: Option<f64>,
+	last: Option<(f64, Instant)>,
+	jitter: f64,
+}
+
+impl LiveProbe {
+	#[cfg_attr(feature = "tracing", tracing::instrument(skip(options)))]
+	pub fn run(url: &str, options: &LiveOptions) -> ffmpeg::Result<Self> {
+		let mut probe = options.probe.clone();
+		probe.timeout = probe.timeout.or(Some(options.duration * 2 + Duration::from_secs(10)));
+
+		let (live, log) = capture_log(|| {
+			probe::scoped(url, &probe, |input| {
+				let metadata = Metadata::new(input)?;
+				let (elapsed, streams) = watch(input, options.duration);
+
+				Ok(LiveProbe {
+					metadata,
+					elapsed,
+					streams,
+				})
+			})
+		});
+
+		let mut live = live?;
+		live.metadata.warnings.extend(log.into_iter().map(Warning::Log));
+
+		Ok(live)
+	}
+
+	pub fn stream(&self, index: usize) -> Option<&LiveStream> {
+		self.streams.iter().find(|stream| stream.index == index)
+	}
+}
+
+// Reads until `duration` went by or the source stops. Errors only end the
+// watch, a timeout is the expected way out of a stalled source.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(input)))]
+fn watch(input: &mut Input, duration: Duration) -> (f64, Vec<LiveStream>) {
+	let time_bases: HashMap<_, _> =
+		input.streams().map(|stream| (stream.index(), stream.time_base())).collect();
+	let mut states: HashMap<usize, State> = HashMap::new();
+	let start = Instant::now();
+
+	while start.elapsed() < duration {
+		let mut packet = Packet::empty();
+		if packet.read(input).is_err() {
+			break;
+		}
+
+		let now = Instant::now();
+		let index = packet.stream();
+		let state = states.entry(index).or_default();
+
+		state.stream.packets += 1;
+		state.stream.bytes += packet.size() as u64;
+		if packet.is_corrupt() {
+			state.stream.corrupt += 1;
+		}
+
+		let time_base = match time_bases.get(&index) {
+			Some(&time_base) => time_base,
+			None => continue,
+		};
+
+		let time = match packet.dts().or_else(|| packet.pts()) {
+			Some(timestamp) => seconds(timestamp, time_base),
+			None => continue,
+		};
+
+		if let Some((previous, arrival)) = state.last {
+			let delta = time - previous;
+			if delta < 0.0 || delta > DISCONTINUITY {
+				state.stream.discontinuities += 1;
+			}
+			else {
+				let transit = now.duration_since(arrival).as_secs_f64() - delta;
+				state.jitter += (transit.abs() - state.jitter) / 16.0;
+			}
+		}
+
+		state.first.get_or_insert(time);
+		state.last = Some((time, now));
+	}
+
+	let elapsed = start.elapsed().as_secs_f64();
+	let mut streams: Vec<_> = states
+		.into_iter()
+		.map(|(index, state)| {
+			let mut stream = state.stream;
+			stream.index = index;
+
+			let span = state.first.zip(state.last).map(|(first, (last, _))| last - first);
+			stream.frame_rate = span
+				.filter(|&span| span > 0.0 && stream.packets > 1)
+				.map(|span| (stream.packets - 1) as f64 / span);
+			stream.bit_rate = Some(elapsed)
+				.filter(|&elapsed| elapsed > 0.0)
+				.map(|elapsed| (stream.bytes as f64 * 8.0 / elapsed) as u64);
+			stream.jitter = Some(state.jitter).filter(|_| stream.packets > 1);
+
+			stream
+		})
+		.collect();
+
+	streams.sort_by_key(|stream| stream.index);
+	(elapsed, streams)
+}
@@ -0,0 +1,164 @@
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+const KEYS: &[&str] = &[
+	"com.apple.quicktime.location.ISO6709",
+	"location",
+	"location-eng",
+	"com.android.location",
+	"xyz",
+];
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Location {
+	pub latitude: f64,
+	pub longitude: f64,
+	pub altitude: Option<f64>,
+	pub key: String,
+	pub raw: String,
+}
+
+impl Location {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let format = input.metadata();
+
+		KEYS.iter().find_map(|key| {
+			let parse = |value: &str| Location::parse(value).map(|l| l.with_source(key, value));
+
+			format.get(key).and_then(parse).or_else(|| {
+				input
+					.streams()
+					.find_map(|stream| stream.metadata().get(key).and_then(parse))
+			})
+		})
+	}
+
+	// Parses ISO 6709 points, e.g. `+37.3349-122.0090+030.000/`, in any of
+	// the degree, degree-minute and degree-minute-second forms.
+	pub fn parse(value: &str) -> Option<Self> {
+		let value = value.trim().trim_end_matches('/');
+		let value = value.split("CRS").next()?;
+
+		let mut components = Vec::new();
+		let mut start = None;
+
+		for (i, c) in value.char_indices() {
+			if c == '+' || c == '-' {
+				if let Some(start) = start {
+					components.push(&value[start..i]);
+				}
+
+				start = Some(i);
+			}
+		}
+
+		components.push(&value[start?..]);
+
+		if components.len() < 2 {
+			return None;
+		}
+
+		let latitude = angle(components[0], 2)?;
+		let longitude = angle(components[1], 3)?;
+
+		if latitude.abs() > 90.0 || longitude.abs() > 180.0 {
+			return None;
+		}
+
+		Some(Location {
+			latitude,
+			longitude,
+			altitude: components.get(2).and_then(|altitude| altitude.parse().ok()),
+			key: String::new(),
+			raw: String::new(),
+		})
+	}
+
+	fn with_source(mut self, key: &str, raw: &str) -> Self {
+		self.key = key.into();
+		self.raw = raw.into();
+		self
+	}
+}
+
+fn angle(value: &str, degrees: usize) -> Option<f64> {
+	let sign = if value.starts_with('-') { -1.0 } else { 1.0 };
+	let digits = &value[1..];
+	let integer = digits.find('.').unwrap_or_else(|| digits.len());
+
+	let number = |range: std::ops::Range<usize>| digits.get(range)?.parse::<f64>().ok();
+	let rest = |from: usize| digits.get(from..)?.parse::<f64>().ok();
+
+	let angle = match integer.checked_sub(degrees)? {
+		0 => rest(0)?,
+		2 => number(0..degrees)? + rest(degrees)? / 60.0,
+		4 => number(0..degrees)? + number(degrees..degrees + 2)? / 60.0 + rest(degrees + 2)? / 3600.0,
+		_ => return None,
+	};
+
+	Some(sign * angle)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-6
+	}
+
+	#[test]
+	fn degrees() {
+		// What iPhones write in `com.apple.quicktime.location.ISO6709`.
+		let location = Location::parse("+37.3349-122.0090+030.000/").unwrap();
+
+		assert!(close(location.latitude, 37.3349));
+		assert!(close(location.longitude, -122.009));
+		assert_eq!(location.altitude, Some(30.0));
+
+		// And Android in `location`, without an altitude.
+		let location = Location::parse("+48.8582+002.2945/").unwrap();
+		assert!(close(location.latitude, 48.8582));
+		assert!(close(location.longitude, 2.2945));
+		assert_eq!(location.altitude, None);
+	}
+
+	#[test]
+	fn minutes_and_seconds() {
+		let location = Location::parse("+4012.5-07530.2/").unwrap();
+		assert!(close(location.latitude, 40.0 + 12.5 / 60.0));
+		assert!(close(location.longitude, -(75.0 + 30.2 / 60.0)));
+
+		let location = Location::parse("-334512+1511234.5/").unwrap();
+		assert!(close(location.latitude, -(33.0 + 45.0 / 60.0 + 12.0 / 3600.0)));
+		assert!(close(location.longitude, 151.0 + 12.0 / 60.0 + 34.5 / 3600.0));
+	}
+
+	#[test]
+	fn coordinate_reference_system() {
+		let location = Location::parse("+27.5916+086.5640+8850CRSWGS_84/").unwrap();
+
+		assert!(close(location.latitude, 27.5916));
+		assert!(close(location.longitude, 86.564));
+		assert_eq!(location.altitude, Some(8850.0));
+	}
+
+	#[test]
+	fn invalid() {
+		let values = [
+			"",
+			"/",
+			"37.3349 -122.0090",
+			"+37.3349/",
+			"+91.0+000.0/",
+			"+00.0+181.0/",
+			"+1+2/",
+		];
+
+		for value in &values {
+			assert_eq!(Location::parse(value), None, "{:?}", value);
+		}
+	}
+}
@@ -0,0 +1,176 @@
+use std::{
+	cell::RefCell,
+	ffi::CStr,
+	os::raw::{c_char, c_int, c_void},
+	sync::Once,
+};
+
+use ffmpeg::ffi;
+use serde::{Deserialize, Serialize};
+
+// Enough for a broken file to say what's wrong, without a stream of
+// per-packet complaints growing without bounds.
+const MAX_MESSAGES: usize = 100;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+	Panic,
+	Fatal,
+	Error,
+	Warning,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LogMessage {
+	pub level: LogLevel,
+	// The demuxer, decoder or protocol that logged it, e.g. `mov,mp4,m4a,3gp,3g2,mj2`.
+	pub component: Option<String>,
+	pub message: String,
+	// Identical messages are logged once with how many times they came up.
+	pub count: usize,
+}
+
+#[derive(Default)]
+struct Collector {
+	messages: Vec<LogMessage>,
+	partial: Option<(LogLevel, Option<String>, String)>,
+}
+
+impl Collector {
+	fn push(&mut self, level: LogLevel, component: Option<String>, message: &str) {
+		let message = message.trim();
+		if message.is_empty() {
+			return;
+		}
+
+		let existing = self
+			.messages
+			.iter_mut()
+			.find(|m| m.level == level && m.component == component && m.message == message);
+
+		if let Some(existing) = existing {
+			existing.count += 1;
+		}
+		else if self.messages.len() < MAX_MESSAGES {
+			self.messages.push(LogMessage {
+				level,
+				component,
+				message: message.to_owned(),
+				count: 1,
+			});
+		}
+	}
+}
+
+thread_local! {
+	static COLLECTOR: RefCell<Option<Collector>> = RefCell::new(None);
+}
+
+// Collects what FFmpeg logs at warning level or above from this thread while
+// `f` runs, instead of printing it. Messages from decoder worker threads and
+// from other threads are printed as usual.
+//
+// The first call takes over FFmpeg's logging for the whole process, for good:
+// there's no way to read back a callback set before, so one the application
+// installed is replaced and everything not captured goes to
+// `av_log_default_callback`. A callback installed afterwards stops the capture
+// instead.
+pub fn capture_log<T, F: FnOnce() -> T>(f: F) -> (T, Vec<LogMessage>) {
+	static INSTALL: Once = Once::new();
+	INSTALL.call_once(|| unsafe { ffi::av_log_set_callback(Some(callback)) });
+
+	let previous = COLLECTOR.with(|c| c.replace(Some(Collector::default())));
+	let result = f();
+	let mut collector = COLLECTOR.with(|c| c.replace(previous)).unwrap_or_default();
+	if let Some((level, component, message)) = collector.partial.take() {
+		collector.push(level, component, &message);
+	}
+
+	(result, collector.messages)
+}
+
+fn level(level: c_int) -> Option<LogLevel> {
+	match level {
+		l if l <= ffi::AV_LOG_PANIC as c_int => Some(LogLevel::Panic),
+		l if l <= ffi::AV_LOG_FATAL as c_int => Some(LogLevel::Fatal),
+		l if l <= ffi::AV_LOG_ERROR as c_int => Some(LogLevel::Error),
+		l if l <= ffi::AV_LOG_WARNING as c_int => Some(LogLevel::Warning),
+		_ => None,
+	}
+}
+
+unsafe fn component(avcl: *mut c_void) -> Option<String> {
+	if avcl.is_null() {
+		return None;
+	}
+
+	let class = *(avcl as *mut *const ffi::AVClass);
+	if class.is_null() {
+		return None;
+	}
+
+	let name = match (*class).item_name {
+		Some(item_name) => item_name(avcl),
+		None => (*class).class_name,
+	};
+
+	if name.is_null() {
+		None
+	}
+	else {
+		Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+	}
+}
+
+unsafe extern "C" fn callback(
+	avcl: *mut c_void,
+	level: c_int,
+	format: *const c_char,
+	arguments: ffi::va_list,
+) {
+	let collecting = COLLECTOR.with(|c| c.try_borrow().map_or(false, |c| c.is_some()));
+	if !collecting {
+		return ffi::av_log_default_callback(avcl, level, format, arguments);
+	}
+
+	// The upper bits are flags, e.g. for the color of the message.
+	let value = level;
+	let level = match self::level(level & 0xff) {
+		Some(level) => level,
+		None => return,
+	};
+
+	let mut line = [0 as c_char; 1024];
+	let mut prefix = 0;
+	ffi::av_log_format_line2(
+		avcl,
+		value,
+		format,
+		arguments,
+		line.as_mut_ptr(),
+		line.len() as c_int,
+		&mut prefix,
+	);
+
+	let text = CStr::from_ptr(line.as_ptr()).to_string_lossy().into_owned();
+	let component = component(avcl);
+
+	// Messages can come in pieces, only a newline ends one.
+	COLLECTOR.with(|c| {
+		if let Some(collector) = c.borrow_mut().as_mut() {
+			let (level, component, mut message) =
+				collector.partial.take().unwrap_or((level, component, String::new()));
+			message.push_str(&text);
+
+			if message.ends_with('\n') {
+				collector.push(level, component, &message);
+			}
+			else {
+				collector.partial = Some((level, component, message));
+			}
+		}
+	});
+}
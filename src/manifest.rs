@@ -0,0 +1,367 @@
+use std::{collections::HashMap, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, Metadata};
+
+// Manifests are text, anything bigger is not one.
+const MAX_MANIFEST: u64 = 16 * 1024 * 1024;
+
+// The ladder behind an HLS master playlist or a DASH MPD. FFmpeg opens these
+// as a single input with every variant muxed together, which hides how the
+// ladder is built.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Manifest {
+	pub kind: ManifestKind,
+	pub url: String,
+	pub variants: Vec<Variant>,
+	pub renditions: Vec<Rendition>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ManifestKind {
+	Hls,
+	Dash,
+}
+
+// HLS variants reference their audio and subtitle renditions by group, DASH
+// representations stand alone and say what they carry.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Variant {
+	pub id: Option<String>,
+	pub url: Option<String>,
+	pub content_type: Option<String>,
+	pub bandwidth: Option<u64>,
+	pub average_bandwidth: Option<u64>,
+	pub width: Option<u32>,
+	pub height: Option<u32>,
+	pub frame_rate: Option<f64>,
+	pub sample_rate: Option<u32>,
+	pub codecs: Vec<String>,
+	pub language: Option<String>,
+	pub audio: Option<String>,
+	pub subtitles: Option<String>,
+	pub closed_captions: Option<String>,
+	// Only after `Manifest::probe`.
+	pub metadata: Option<Box<Metadata>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Rendition {
+	pub kind: String,
+	pub group: String,
+	pub name: Option<String>,
+	pub language: Option<String>,
+	pub default: bool,
+	pub url: Option<String>,
+}
+
+impl Manifest {
+	pub fn open(url: &str) -> ffmpeg::Result<Self> {
+		Manifest::parse(url, &read(url)?).ok_or(ffmpeg::Error::InvalidData)
+	}
+
+	pub fn parse(url: &str, text: &str) -> Option<Self> {
+		if text.trim_start().starts_with("#EXTM3U") {
+			Some(hls(url, text))
+		}
+		else if text.contains("<MPD") {
+			Some(dash(url, text))
+		}
+		else {
+			None
+		}
+	}
+
+	// Opens every HLS variant on its own, which has FFmpeg read its media
+	// playlist and first segment. DASH representations can't be opened apart
+	// from the MPD and are left alone.
+	pub fn probe(&mut self) -> ffmpeg::Result<()> {
+		if self.kind != ManifestKind::Hls {
+			return Ok(());
+		}
+
+		for variant in &mut self.variants {
+			if let Some(url) = &variant.url {
+				let input = ffmpeg::format::input(url)?;
+				variant.metadata = Some(Box::new(Metadata::new(&input)?));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+pub(crate) fn read(url: &str) -> ffmpeg::Result<String> {
+	let mut text = String::new();
+	Avio::open(url)?
+		.take(MAX_MANIFEST)
+		.read_to_string(&mut text)
+		.map_err(|_| ffmpeg::Error::InvalidData)?;
+
+	Ok(text)
+}
+
+// The key tags of an HLS playlist. Media playlists are where segment keys
+// are, so a master playlist is followed to its first variant.
+pub(crate) fn keys(url: &str) -> Vec<HashMap<String, String>> {
+	let mut keys = Vec::new();
+	let mut url = url.to_owned();
+
+	for _ in 0..2 {
+		let text = match read(&url) {
+			Ok(text) => text,
+			Err(_) => break,
+		};
+
+		keys.extend(
+			text.lines()
+				.map(str::trim)
+				.filter_map(|line| {
+					line.strip_prefix("#EXT-X-KEY:")
+						.or_else(|| line.strip_prefix("#EXT-X-SESSION-KEY:"))
+				})
+				.map(attributes),
+		);
+
+		match hls(&url, &text).variants.into_iter().find_map(|variant| variant.url) {
+			Some(variant) => url = variant,
+			None => break,
+		}
+	}
+
+	keys
+}
+
+// Relative references resolve against the manifest, like in a browser.
+fn resolve(base: &str, reference: &str) -> String {
+	if reference.contains("://") {
+		return reference.to_owned();
+	}
+
+	if reference.starts_with('/') {
+		if let Some(scheme) = base.find("://") {
+			let host = base[scheme + 3..].find('/').map_or(base.len(), |end| scheme + 3 + end);
+			return format!("{}{}", &base[..host], reference);
+		}
+
+		return reference.to_owned();
+	}
+
+	let base = base.split(['?', '#'].as_ref()).next().unwrap_or(base);
+	match base.rfind('/') {
+		Some(end) => format!("{}{}", &base[..=end], reference),
+		None => reference.to_owned(),
+	}
+}
+
+// `KEY=value,KEY="quoted, value"` as used by every HLS tag.
+fn attributes(list: &str) -> HashMap<String, String> {
+	let mut attributes = HashMap::new();
+	let mut rest = list;
+
+	while let Some(equals) = rest.find('=') {
+		let key = rest[..equals].trim().to_owned();
+		rest = &rest[equals + 1..];
+
+		let value = if let Some(quoted) = rest.strip_prefix('"') {
+			let end = quoted.find('"').unwrap_or(quoted.len());
+			let value = &quoted[..end];
+			rest = quoted.get(end + 1..).unwrap_or_default();
+
+			value
+		}
+		else {
+			let end = rest.find(',').unwrap_or(rest.len());
+			let value = &rest[..end];
+			rest = &rest[end..];
+
+			value
+		};
+
+		attributes.insert(key, value.to_owned());
+		rest = rest.trim_start_matches(',');
+	}
+
+	attributes
+}
+
+fn codecs(value: &str) -> Vec<String> {
+	value.split(',').map(str::trim).filter(|c| !c.is_empty()).map(String::from).collect()
+}
+
+fn hls(url: &str, text: &str) -> Manifest {
+	let mut variants = Vec::new();
+	let mut renditions = Vec::new();
+	let mut pending = None::<Variant>;
+
+	for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+		if let Some(list) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+			let attributes = attributes(list);
+			let get = |key: &str| attributes.get(key).cloned();
+			let resolution = get("RESOLUTION").unwrap_or_default();
+			let mut resolution = resolution.split('x').map(|v| v.parse().ok());
+
+			pending = Some(Variant {
+				bandwidth: get("BANDWIDTH").and_then(|b| b.parse().ok()),
+				average_bandwidth: get("AVERAGE-BANDWIDTH").and_then(|b| b.parse().ok()),
+				width: resolution.next().flatten(),
+				height: resolution.next().flatten(),
+				frame_rate: get("FRAME-RATE").and_then(|f| f.parse().ok()),
+				codecs: get("CODECS").as_deref().map(codecs).unwrap_or_default(),
+				audio: get("AUDIO"),
+				subtitles: get("SUBTITLES"),
+				closed_captions: get("CLOSED-CAPTIONS").filter(|cc| cc != "NONE"),
+				..Default::default()
+			});
+		}
+		else if let Some(list) = line.strip_prefix("#EXT-X-MEDIA:") {
+			let attributes = attributes(list);
+			let get = |key: &str| attributes.get(key).cloned();
+
+			renditions.push(Rendition {
+				kind: get("TYPE").unwrap_or_default(),
+				group: get("GROUP-ID").unwrap_or_default(),
+				name: get("NAME"),
+				language: get("LANGUAGE"),
+				default: get("DEFAULT").as_deref() == Some("YES"),
+				url: get("URI").map(|uri| resolve(url, &uri)),
+			});
+		}
+		else if !line.starts_with('#') {
+			if let Some(mut variant) = pending.take() {
+				variant.url = Some(resolve(url, line));
+				variants.push(variant);
+			}
+		}
+	}
+
+	Manifest {
+		kind: ManifestKind::Hls,
+		url: url.to_owned(),
+		variants,
+		renditions,
+	}
+}
+
+// Reads `name="value"` out of a single XML start tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+	let needle = format!(" {}=\"", name);
+	let start = tag.find(&needle)? + needle.len();
+	let end = start + tag[start..].find('"')?;
+
+	Some(tag[start..end].to_owned())
+}
+
+// Representations inherit what their adaptation set declares. The MPD is
+// scanned tag by tag, which is all the structure this needs.
+fn dash(url: &str, text: &str) -> Manifest {
+	let mut variants = Vec::new();
+	let mut set = String::new();
+
+	for tag in text.split('<').skip(1) {
+		let tag = &tag[..tag.find('>').map_or(tag.len(), |end| end)];
+		let closing = tag.starts_with('/');
+
+		// Exact names, `RepresentationIndex` and the like aren't representations.
+		let name = tag
+			.trim_start_matches('/')
+			.split(|c: char| c.is_whitespace() || c == '/')
+			.next()
+			.unwrap_or_default();
+
+		if name == "AdaptationSet" && !closing {
+			set = tag.to_owned();
+		}
+		else if name == "AdaptationSet" {
+			set.clear();
+		}
+		else if name == "Representation" && !closing {
+			let get = |name: &str| attribute(tag, name).or_else(|| attribute(&set, name));
+			let content = get("contentType").or_else(|| {
+				get("mimeType").and_then(|mime| mime.split('/').next().map(String::from))
+			});
+
+			// Frame rates are written as fractions, e.g. `30000/1001`.
+			let frame_rate = get("frameRate").and_then(|rate| match rate.split_once('/') {
+				Some((n, d)) => Some(n.parse::<f64>().ok()? / d.parse::<f64>().ok()?),
+				None => rate.parse().ok(),
+			});
+
+			variants.push(Variant {
+				id: get("id"),
+				content_type: content,
+				bandwidth: get("bandwidth").and_then(|b| b.parse().ok()),
+				width: get("width").and_then(|w| w.parse().ok()),
+				height: get("height").and_then(|h| h.parse().ok()),
+				frame_rate,
+				sample_rate: get("audioSamplingRate").and_then(|r| r.parse().ok()),
+				codecs: get("codecs").as_deref().map(codecs).unwrap_or_default(),
+				language: get("lang"),
+				..Default::default()
+			});
+		}
+	}
+
+	Manifest {
+		kind: ManifestKind::Dash,
+		url: url.to_owned(),
+		variants,
+		renditions: Vec::new(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn segment_base() {
+		let mpd = r#"<?xml version="1.0"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static">
+  <Period>
+    <AdaptationSet contentType="video" mimeType="video/mp4" codecs="avc1.64001f">
+      <Representation id="720p" bandwidth="3000000" width="1280" height="720">
+        <BaseURL>720p.mp4</BaseURL>
+        <SegmentBase indexRange="800-1999">
+          <Initialization range="0-799"/>
+          <RepresentationIndex range="800-1999"/>
+        </SegmentBase>
+      </Representation>
+      <Representation id="360p" bandwidth="800000" width="640" height="360">
+        <BaseURL>360p.mp4</BaseURL>
+        <SegmentBase indexRange="800-1499">
+          <RepresentationIndex range="800-1499"/>
+        </SegmentBase>
+      </Representation>
+    </AdaptationSet>
+    <AdaptationSet contentType="audio" mimeType="audio/mp4" lang="en">
+      <Representation id="audio" bandwidth="128000" codecs="mp4a.40.2" audioSamplingRate="48000"/>
+    </AdaptationSet>
+  </Period>
+</MPD>"#;
+
+		let manifest = Manifest::parse("https://example.com/vod/manifest.mpd", mpd).unwrap();
+		let ids = manifest.variants.iter().map(|v| v.id.as_deref()).collect::<Vec<_>>();
+
+		assert_eq!(manifest.kind, ManifestKind::Dash);
+		assert_eq!(ids, [Some("720p"), Some("360p"), Some("audio")]);
+
+		let video = &manifest.variants[1];
+		assert_eq!(video.content_type.as_deref(), Some("video"));
+		assert_eq!(video.bandwidth, Some(800_000));
+		assert_eq!((video.width, video.height), (Some(640), Some(360)));
+		assert_eq!(video.codecs, ["avc1.64001f"]);
+
+		let audio = &manifest.variants[2];
+		assert_eq!(audio.content_type.as_deref(), Some("audio"));
+		assert_eq!(audio.sample_rate, Some(48000));
+		assert_eq!(audio.language.as_deref(), Some("en"));
+		assert_eq!(audio.width, None);
+	}
+}
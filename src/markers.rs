@@ -0,0 +1,445 @@
+use std::{
+	collections::HashMap,
+	convert::TryInto,
+	io::{Read, Seek, SeekFrom},
+};
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, raw};
+
+// Marker chunks are tiny, anything bigger is either corrupt or not worth
+// holding in memory.
+const MAX_CHUNK: u32 = 1024 * 1024;
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Markers {
+	pub cues: Vec<Cue>,
+	pub loops: Vec<Loop>,
+	pub unity_note: Option<u8>,
+}
+
+// Positions and lengths are in sample frames.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Cue {
+	pub id: u32,
+	pub position: u64,
+	pub length: Option<u64>,
+	pub label: Option<String>,
+	pub note: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Loop {
+	pub kind: LoopKind,
+	pub start: u64,
+	pub end: u64,
+	// Zero means the loop plays forever.
+	pub play_count: u32,
+	// Only AIFF instruments tell sustain and release loops apart.
+	pub release: bool,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopKind {
+	Forward,
+	PingPong,
+	Backward,
+	Other(u32),
+}
+
+impl Markers {
+	// FFmpeg skips these chunks entirely, so the file is read again looking
+	// only at chunk headers.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let big = match input.format().name() {
+			"wav" => false,
+			"aiff" => true,
+			_ => return None,
+		};
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+		let markers = if big { aiff(&mut file) } else { wav(&mut file) }?;
+
+		if markers.cues.is_empty() && markers.loops.is_empty() {
+			None
+		}
+		else {
+			Some(markers)
+		}
+	}
+}
+
+pub(crate) fn chunks<R, F>(reader: &mut R, big: bool, ids: &[&[u8; 4]], mut f: F) -> Option<()>
+where
+	R: Read + Seek,
+	F: FnMut(&[u8; 4], Vec<u8>),
+{
+	let mut header = [0; 12];
+	reader.read_exact(&mut header).ok()?;
+
+	loop {
+		let mut chunk = [0; 8];
+		if reader.read_exact(&mut chunk).is_err() {
+			return Some(());
+		}
+
+		let id: [u8; 4] = chunk[..4].try_into().unwrap();
+		let size = if big {
+			u32::from_be_bytes(chunk[4..].try_into().unwrap())
+		}
+		else {
+			u32::from_le_bytes(chunk[4..].try_into().unwrap())
+		};
+
+		let padded = i64::from(size) + i64::from(size & 1);
+
+		if ids.contains(&&id) && size <= MAX_CHUNK {
+			let mut data = vec![0; size as usize];
+			reader.read_exact(&mut data).ok()?;
+			reader.seek(SeekFrom::Current(padded - i64::from(size))).ok()?;
+
+			f(&id, data);
+		}
+		else {
+			reader.seek(SeekFrom::Current(padded)).ok()?;
+		}
+	}
+}
+
+pub(crate) fn le32(data: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+pub(crate) fn text(data: &[u8]) -> String {
+	let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+	String::from_utf8_lossy(&data[..end]).trim().to_owned()
+}
+
+// The cue point ID and its sample offset.
+fn cue_point(point: &[u8]) -> Option<(u32, u32)> {
+	Some((le32(point, 0)?, le32(point, 20)?))
+}
+
+fn sample_loop(entry: &[u8]) -> Option<Loop> {
+	Some(Loop {
+		kind: match le32(entry, 4)? {
+			0 => LoopKind::Forward,
+			1 => LoopKind::PingPong,
+			2 => LoopKind::Backward,
+			other => LoopKind::Other(other),
+		},
+		start: u64::from(le32(entry, 8)?),
+		end: u64::from(le32(entry, 12)?),
+		play_count: le32(entry, 20)?,
+		release: false,
+	})
+}
+
+fn wav<R: Read + Seek>(reader: &mut R) -> Option<Markers> {
+	let mut markers = Markers::default();
+	let mut labels = HashMap::new();
+	let mut notes = HashMap::new();
+	let mut lengths = HashMap::new();
+	let mut points = Vec::new();
+
+	chunks(reader, false, &[b"cue ", b"smpl", b"LIST"], |id, data| match id {
+		b"cue " => {
+			let count = le32(&data, 0).unwrap_or(0) as usize;
+
+			let entries = data.get(4..).unwrap_or_default().chunks_exact(24).take(count);
+			points.extend(entries.filter_map(cue_point));
+		}
+
+		b"smpl" => {
+			markers.unity_note = le32(&data, 12).map(|note| note as u8);
+			let count = le32(&data, 28).unwrap_or(0) as usize;
+
+			let entries = data.get(36..).unwrap_or_default().chunks_exact(24).take(count);
+			markers.loops.extend(entries.filter_map(sample_loop));
+		}
+
+		// Labels, notes and labeled regions live in an associated data list.
+		b"LIST" if data.starts_with(b"adtl") => {
+			let mut offset = 4;
+
+			while let (Some(kind), Some(size)) =
+				(data.get(offset..offset + 4), le32(&data, offset + 4))
+			{
+				let (body, cue) = match data.get(offset + 8..offset + 8 + size as usize) {
+					Some(body) => match le32(body, 0) {
+						Some(cue) => (body, cue),
+						None => break,
+					},
+					None => break,
+				};
+
+				match kind {
+					b"labl" => {
+						labels.insert(cue, text(&body[4..]));
+					}
+
+					b"note" => {
+						notes.insert(cue, text(&body[4..]));
+					}
+
+					b"ltxt" => {
+						if let Some(length) = le32(body, 4) {
+							lengths.insert(cue, u64::from(length));
+						}
+					}
+
+					_ => (),
+				}
+
+				offset += 8 + size as usize + (size as usize & 1);
+			}
+		}
+
+		_ => (),
+	})?;
+
+	markers.cues = points
+		.into_iter()
+		.map(|(id, position)| Cue {
+			id,
+			position: u64::from(position),
+			length: lengths.remove(&id),
+			label: labels.remove(&id),
+			note: notes.remove(&id),
+		})
+		.collect();
+
+	Some(markers)
+}
+
+fn aiff<R: Read + Seek>(reader: &mut R) -> Option<Markers> {
+	let mut markers = Markers::default();
+	let mut instrument = None;
+
+	chunks(reader, true, &[b"MARK", b"INST"], |id, data| match id {
+		b"MARK" => {
+			let count = data.get(..2).map_or(0, |n| u16::from_be_bytes([n[0], n[1]]));
+			let mut offset = 2;
+
+			for _ in 0..count {
+				let header = match data.get(offset..offset + 7) {
+					Some(header) => header,
+					None => break,
+				};
+
+				let id = u16::from_be_bytes([header[0], header[1]]);
+				let position = u32::from_be_bytes(header[2..6].try_into().unwrap());
+				let length = header[6] as usize;
+				let name = data.get(offset + 7..offset + 7 + length).map(text);
+
+				markers.cues.push(Cue {
+					id: u32::from(id),
+					position: u64::from(position),
+					length: None,
+					label: name.filter(|name| !name.is_empty()),
+					note: None,
+				});
+
+				// Pascal strings are padded to an even total length.
+				offset += 7 + length + (1 - length % 2);
+			}
+		}
+
+		b"INST" if data.len() >= 20 => {
+			instrument = Some(data);
+		}
+
+		_ => (),
+	})?;
+
+	if let Some(data) = instrument {
+		markers.unity_note = Some(data[0]);
+
+		let position = |id: u16| {
+			markers
+				.cues
+				.iter()
+				.find(|cue| cue.id == u32::from(id))
+				.map(|cue| cue.position)
+		};
+
+		// Sustain and release loops, each a play mode and two marker ids.
+		let mut loops = Vec::new();
+		for &(offset, release) in &[(8, false), (14, true)] {
+			let field = |at: usize| u16::from_be_bytes([data[offset + at], data[offset + at + 1]]);
+
+			let kind = match field(0) {
+				0 => continue,
+				1 => LoopKind::Forward,
+				2 => LoopKind::PingPong,
+				other => LoopKind::Other(u32::from(other)),
+			};
+
+			if let (Some(start), Some(end)) = (position(field(2)), position(field(4))) {
+				loops.push(Loop {
+					kind,
+					start,
+					end,
+					play_count: 0,
+					release,
+				});
+			}
+		}
+
+		markers.loops = loops;
+	}
+
+	Some(markers)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use super::*;
+
+	fn chunk(id: &[u8; 4], data: &[u8], big: bool) -> Vec<u8> {
+		let size = data.len() as u32;
+		let mut chunk = id.to_vec();
+		chunk.extend_from_slice(&if big { size.to_be_bytes() } else { size.to_le_bytes() });
+		chunk.extend_from_slice(data);
+
+		if data.len() % 2 == 1 {
+			chunk.push(0);
+		}
+
+		chunk
+	}
+
+	fn words(values: &[u32]) -> Vec<u8> {
+		values.iter().flat_map(|value| value.to_le_bytes().to_vec()).collect()
+	}
+
+	fn riff(chunks: &[Vec<u8>]) -> Cursor<Vec<u8>> {
+		let body = chunks.concat();
+		let mut file = b"RIFF".to_vec();
+		file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+		file.extend_from_slice(b"WAVE");
+		file.extend_from_slice(&body);
+
+		Cursor::new(file)
+	}
+
+	// 16 bit stereo PCM at 44.1 kHz.
+	const FMT: &[u8] = b"\x01\x00\x02\x00\x44\xac\x00\x00\x10\xb1\x02\x00\x04\x00\x10\x00";
+
+	fn cue(points: &[(u32, u32)]) -> Vec<u8> {
+		let mut data = words(&[points.len() as u32]);
+		for &(id, offset) in points {
+			data.extend(words(&[id, 0]));
+			data.extend_from_slice(b"data");
+			data.extend(words(&[0, 0, offset]));
+		}
+
+		chunk(b"cue ", &data, false)
+	}
+
+	#[test]
+	fn wav_cues_and_loops() {
+		let mut adtl = b"adtl".to_vec();
+		adtl.extend(chunk(b"labl", b"\x01\x00\x00\x00Intro\x00", false));
+		adtl.extend(chunk(b"note", b"\x01\x00\x00\x00Fade\x00", false));
+		let mut ltxt = words(&[2, 11025]);
+		ltxt.extend_from_slice(b"rgn \0\0\0\0\0\0\0\0");
+		adtl.extend(chunk(b"ltxt", &ltxt, false));
+
+		let mut smpl = words(&[0, 0, 22676, 60, 0, 0, 0, 1, 0]);
+		smpl.extend(words(&[0, 0, 4410, 22050, 0, 0]));
+
+		let mut file = riff(&[
+			chunk(b"fmt ", FMT, false),
+			cue(&[(1, 4410), (2, 22050)]),
+			chunk(b"LIST", &adtl, false),
+			chunk(b"smpl", &smpl, false),
+			chunk(b"data", &[0; 8], false),
+		]);
+
+		let markers = wav(&mut file).unwrap();
+
+		assert_eq!(markers.cues.len(), 2);
+		assert_eq!((markers.cues[0].id, markers.cues[0].position), (1, 4410));
+		assert_eq!(markers.cues[0].label.as_deref(), Some("Intro"));
+		assert_eq!(markers.cues[0].note.as_deref(), Some("Fade"));
+		assert_eq!(markers.cues[0].length, None);
+		assert_eq!((markers.cues[1].id, markers.cues[1].position), (2, 22050));
+		assert_eq!(markers.cues[1].length, Some(11025));
+
+		assert_eq!(markers.unity_note, Some(60));
+		assert_eq!(markers.loops.len(), 1);
+		assert_eq!(markers.loops[0].kind, LoopKind::Forward);
+		assert_eq!((markers.loops[0].start, markers.loops[0].end), (4410, 22050));
+		assert_eq!(markers.loops[0].play_count, 0);
+	}
+
+	#[test]
+	fn wav_short_chunks() {
+		// Counts larger than what follows, and chunks too short for one.
+		let mut short = cue(&[(1, 4410)]);
+		short[8] = 3;
+
+		let mut file = riff(&[
+			chunk(b"fmt ", FMT, false),
+			short,
+			chunk(b"smpl", &words(&[0, 0, 22676, 60, 0, 0, 0, 2]), false),
+			chunk(b"LIST", b"adtllabl\x08\x00\x00\x00\x01\x00", false),
+		]);
+
+		let markers = wav(&mut file).unwrap();
+		assert_eq!(markers.cues.len(), 1);
+		assert!(markers.loops.is_empty());
+
+		let mut file = riff(&[chunk(b"cue ", b"\x01\x00", false), chunk(b"smpl", b"\x00", false)]);
+		let markers = wav(&mut file).unwrap();
+		assert!(markers.cues.is_empty());
+		assert!(markers.loops.is_empty());
+	}
+
+	#[test]
+	fn aiff_markers_and_instrument() {
+		let mut mark = b"\x00\x02".to_vec();
+		mark.extend_from_slice(b"\x00\x01\x00\x00\x11\x3a\x05Start");
+		mark.extend_from_slice(b"\x00\x02\x00\x00\x56\x22\x04Stop\x00");
+
+		// Unity note 60, a forward sustain loop between the two markers.
+		let inst = b"\x3c\x00\x00\x7f\x00\x7f\x00\x00\x00\x01\x00\x01\x00\x02\x00\x00\x00\x00\x00\x00";
+
+		let body = [
+			chunk(b"COMM", &[0; 18], true),
+			chunk(b"MARK", &mark, true),
+			chunk(b"INST", inst, true),
+			chunk(b"SSND", &[0; 8], true),
+		]
+		.concat();
+
+		let mut file = b"FORM".to_vec();
+		file.extend_from_slice(&(body.len() as u32 + 4).to_be_bytes());
+		file.extend_from_slice(b"AIFF");
+		file.extend_from_slice(&body);
+
+		let markers = aiff(&mut Cursor::new(file)).unwrap();
+
+		assert_eq!(markers.cues.len(), 2);
+		assert_eq!((markers.cues[0].id, markers.cues[0].position), (1, 4410));
+		assert_eq!(markers.cues[0].label.as_deref(), Some("Start"));
+		assert_eq!((markers.cues[1].id, markers.cues[1].position), (2, 22050));
+		assert_eq!(markers.cues[1].label.as_deref(), Some("Stop"));
+
+		assert_eq!(markers.unity_note, Some(60));
+		assert_eq!(markers.loops.len(), 1);
+		assert_eq!(markers.loops[0].kind, LoopKind::Forward);
+		assert_eq!((markers.loops[0].start, markers.loops[0].end), (4410, 22050));
+		assert!(!markers.loops[0].release);
+	}
+}
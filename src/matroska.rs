@@ -0,0 +1,345 @@
+use std::io::Read;
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, bitstream::ebml, raw};
+
+const EBML: u32 = 0x1a45_dfa3;
+const SEGMENT: u32 = 0x1853_8067;
+const CHAPTERS: u32 = 0x1043_a770;
+const TAGS: u32 = 0x1254_c367;
+
+// Chapters and tags are small, anything bigger isn't worth holding.
+const MAX_ELEMENT: u64 = 16 * 1024 * 1024;
+
+// The structure FFmpeg flattens: every edition with its chapter tree, and the
+// tags attached to whatever they target.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Matroska {
+	pub editions: Vec<Edition>,
+	// Tags targeting the whole segment, tracks or attachments.
+	pub tags: Vec<MatroskaTag>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Edition {
+	pub uid: Option<u64>,
+	pub default: bool,
+	pub hidden: bool,
+	pub ordered: bool,
+	pub chapters: Vec<EditionChapter>,
+	pub tags: Vec<MatroskaTag>,
+}
+
+// Times in seconds. Ordered chapters with a segment UID play from another
+// file.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EditionChapter {
+	pub uid: Option<u64>,
+	pub start: f64,
+	pub end: Option<f64>,
+	pub hidden: bool,
+	pub enabled: bool,
+	pub segment: Option<String>,
+	pub titles: Vec<ChapterTitle>,
+	pub tags: Vec<MatroskaTag>,
+	pub chapters: Vec<EditionChapter>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ChapterTitle {
+	pub title: String,
+	pub languages: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MatroskaTag {
+	pub target: TagTarget,
+	pub tags: Vec<SimpleTag>,
+}
+
+// Target type values go from 70 (collection) down to 10 (shot), 50 being an
+// album, movie or episode.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TagTarget {
+	pub value: u64,
+	pub kind: Option<String>,
+	pub tracks: Vec<u64>,
+	pub editions: Vec<u64>,
+	pub chapters: Vec<u64>,
+	pub attachments: Vec<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SimpleTag {
+	pub name: String,
+	pub language: Option<String>,
+	pub default: bool,
+	pub value: Option<String>,
+	pub binary: bool,
+	pub children: Vec<SimpleTag>,
+}
+
+impl Matroska {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if !input.format().name().split(',').any(|name| name == "matroska") {
+			return None;
+		}
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+		let (chapters, tags) = elements(&mut file)?;
+
+		let mut tags = tags.as_deref().map(parse_tags).unwrap_or_default();
+		let mut editions = chapters.as_deref().map(parse_editions).unwrap_or_default();
+
+		for edition in &mut editions {
+			edition.tags = take(&mut tags, |target| &target.editions, edition.uid);
+
+			for chapter in &mut edition.chapters {
+				attach(chapter, &mut tags);
+			}
+		}
+
+		if editions.is_empty() && tags.is_empty() {
+			None
+		}
+		else {
+			Some(Matroska { editions, tags })
+		}
+	}
+}
+
+// Walks the segment children looking for chapters and tags, seeking over
+// everything else. Tags often come after the clusters, so a segment or
+// cluster of unknown size stops the walk.
+fn elements(file: &mut Avio) -> Option<(Option<Vec<u8>>, Option<Vec<u8>>)> {
+	let (id, size) = ebml::header(file)?;
+	if id != EBML {
+		return None;
+	}
+
+	ebml::skip(file, size)?;
+
+	let (id, _) = ebml::header(file)?;
+	if id != SEGMENT {
+		return None;
+	}
+
+	let mut chapters = None;
+	let mut tags = None;
+
+	while let Some((id, size)) = ebml::header(file) {
+		if size == ebml::UNKNOWN {
+			break;
+		}
+
+		match id {
+			CHAPTERS | TAGS if size <= MAX_ELEMENT => {
+				let mut data = vec![0; size as usize];
+				file.read_exact(&mut data).ok()?;
+
+				if id == CHAPTERS {
+					chapters.get_or_insert(data);
+				}
+				else {
+					tags.get_or_insert_with(Vec::new).push(data);
+				}
+			}
+
+			_ => {
+				ebml::skip(file, size)?;
+			}
+		}
+	}
+
+	// Multiple tags elements are allowed and all count.
+	Some((chapters, tags.map(|tags| tags.concat())))
+}
+
+fn flag(data: &[u8]) -> bool {
+	ebml::uint(data) != 0
+}
+
+fn parse_editions(data: &[u8]) -> Vec<Edition> {
+	ebml::children(data)
+		.into_iter()
+		.filter(|&(id, _)| id == 0x45b9)
+		.map(|(_, data)| {
+			let mut edition = Edition {
+				uid: None,
+				default: false,
+				hidden: false,
+				ordered: false,
+				chapters: Vec::new(),
+				tags: Vec::new(),
+			};
+
+			for (id, data) in ebml::children(data) {
+				match id {
+					0x45bc => edition.uid = Some(ebml::uint(data)),
+					0x45db => edition.default = flag(data),
+					0x45bd => edition.hidden = flag(data),
+					0x45dd => edition.ordered = flag(data),
+					0xb6 => edition.chapters.push(parse_chapter(data)),
+					_ => (),
+				}
+			}
+
+			edition
+		})
+		.collect()
+}
+
+fn parse_chapter(data: &[u8]) -> EditionChapter {
+	let mut chapter = EditionChapter {
+		uid: None,
+		start: 0.0,
+		end: None,
+		hidden: false,
+		enabled: true,
+		segment: None,
+		titles: Vec::new(),
+		tags: Vec::new(),
+		chapters: Vec::new(),
+	};
+
+	// Chapter times are always in nanoseconds, regardless of the timestamp
+	// scale.
+	for (id, data) in ebml::children(data) {
+		match id {
+			0x73c4 => chapter.uid = Some(ebml::uint(data)),
+			0x91 => chapter.start = ebml::uint(data) as f64 / 1e9,
+			0x92 => chapter.end = Some(ebml::uint(data) as f64 / 1e9),
+			0x98 => chapter.hidden = flag(data),
+			0x4598 => chapter.enabled = flag(data),
+			0x6e67 => chapter.segment = Some(data.iter().map(|b| format!("{:02x}", b)).collect()),
+			0xb6 => chapter.chapters.push(parse_chapter(data)),
+
+			0x80 => {
+				let mut title = ChapterTitle {
+					title: String::new(),
+					languages: Vec::new(),
+				};
+
+				for (id, data) in ebml::children(data) {
+					match id {
+						0x85 => title.title = ebml::string(data),
+						0x437c | 0x437d => title.languages.push(ebml::string(data)),
+						_ => (),
+					}
+				}
+
+				chapter.titles.push(title);
+			}
+
+			_ => (),
+		}
+	}
+
+	chapter
+}
+
+fn parse_tags(data: &[u8]) -> Vec<MatroskaTag> {
+	ebml::children(data)
+		.into_iter()
+		.filter(|&(id, _)| id == 0x7373)
+		.map(|(_, data)| {
+			let mut tag = MatroskaTag {
+				target: TagTarget {
+					value: 50,
+					kind: None,
+					tracks: Vec::new(),
+					editions: Vec::new(),
+					chapters: Vec::new(),
+					attachments: Vec::new(),
+				},
+				tags: Vec::new(),
+			};
+
+			for (id, data) in ebml::children(data) {
+				match id {
+					0x63c0 => {
+						for (id, data) in ebml::children(data) {
+							let target = &mut tag.target;
+
+							match id {
+								0x68ca => target.value = ebml::uint(data),
+								0x63ca => target.kind = Some(ebml::string(data)),
+								0x63c5 => target.tracks.push(ebml::uint(data)),
+								0x63c9 => target.editions.push(ebml::uint(data)),
+								0x63c4 => target.chapters.push(ebml::uint(data)),
+								0x63c6 => target.attachments.push(ebml::uint(data)),
+								_ => (),
+							}
+						}
+					}
+
+					0x67c8 => tag.tags.push(parse_simple(data)),
+					_ => (),
+				}
+			}
+
+			tag
+		})
+		.collect()
+}
+
+fn parse_simple(data: &[u8]) -> SimpleTag {
+	let mut tag = SimpleTag {
+		name: String::new(),
+		language: None,
+		default: true,
+		value: None,
+		binary: false,
+		children: Vec::new(),
+	};
+
+	for (id, data) in ebml::children(data) {
+		match id {
+			0x45a3 => tag.name = ebml::string(data),
+			0x447a | 0x447b => tag.language = Some(ebml::string(data)),
+			0x4484 => tag.default = flag(data),
+			0x4487 => tag.value = Some(ebml::string(data)),
+			0x4485 => tag.binary = true,
+			0x67c8 => tag.children.push(parse_simple(data)),
+			_ => (),
+		}
+	}
+
+	tag
+}
+
+// Pulls out the tags targeting the given UID, a tag can only sit in one place
+// of the tree.
+fn take<F>(tags: &mut Vec<MatroskaTag>, uids: F, uid: Option<u64>) -> Vec<MatroskaTag>
+where
+	F: Fn(&TagTarget) -> &Vec<u64>,
+{
+	let uid = match uid {
+		Some(uid) => uid,
+		None => return Vec::new(),
+	};
+
+	let (taken, rest): (Vec<_>, Vec<_>) =
+		tags.drain(..).partition(|tag| uids(&tag.target).contains(&uid));
+	*tags = rest;
+
+	taken
+}
+
+fn attach(chapter: &mut EditionChapter, tags: &mut Vec<MatroskaTag>) {
+	chapter.tags = take(tags, |target| &target.chapters, chapter.uid);
+
+	for child in &mut chapter.chapters {
+		attach(child, tags);
+	}
+}
@@ -0,0 +1,85 @@
+use serde::de::Error as _;
+use serde_json::{json, Map, Value};
+
+use crate::{Metadata, SCHEMA_VERSION};
+
+pub(crate) fn upgrade(mut document: Value) -> serde_json::Result<Metadata> {
+	let version = document.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+
+	if version > u64::from(SCHEMA_VERSION) {
+		return Err(serde_json::Error::custom(format!(
+			"schema version {} is newer than {}",
+			version, SCHEMA_VERSION
+		)));
+	}
+
+	if version < 1 {
+		v1(&mut document);
+	}
+
+	if version < 2 {
+		v2(&mut document);
+	}
+
+	serde_json::from_value(document)
+}
+
+// Documents from before versioning, missing everything added since that
+// isn't optional.
+fn v1(document: &mut Value) {
+	let metadata = match document.as_object_mut() {
+		Some(metadata) => metadata,
+		None => return,
+	};
+
+	defaults(
+		metadata,
+		&[
+			("chapters", json!([])),
+			("bit_rate", json!(0)),
+			("origins", json!({ "duration": null, "bit_rate": null, "streams": [] })),
+			("provenance", json!({ "entries": [] })),
+			("warnings", json!([])),
+		],
+	);
+
+	if let Some(format) = metadata.get_mut("format").and_then(Value::as_object_mut) {
+		defaults(format, &[("compatible_brands", json!([]))]);
+	}
+
+	let streams = metadata.get_mut("streams").and_then(Value::as_array_mut);
+	for stream in streams.into_iter().flatten().filter_map(Value::as_object_mut) {
+		defaults(stream, &[("side_data", json!([])), ("is_empty", json!(false))]);
+
+		let video = stream.get_mut("content").and_then(|content| content.get_mut("video"));
+		if let Some(video) = video.and_then(Value::as_object_mut) {
+			defaults(video, &[("field_order", json!("unknown"))]);
+		}
+	}
+
+	metadata.insert("schema_version".into(), json!(1));
+}
+
+// Unknown streams went from `null` to an empty table.
+fn v2(document: &mut Value) {
+	let metadata = match document.as_object_mut() {
+		Some(metadata) => metadata,
+		None => return,
+	};
+
+	let streams = metadata.get_mut("streams").and_then(Value::as_array_mut);
+	for stream in streams.into_iter().flatten() {
+		let unknown = stream.get_mut("content").and_then(|content| content.get_mut("unknown"));
+		if let Some(unknown) = unknown.filter(|unknown| unknown.is_null()) {
+			*unknown = json!({});
+		}
+	}
+
+	metadata.insert("schema_version".into(), json!(2));
+}
+
+fn defaults(object: &mut Map<String, Value>, fields: &[(&str, Value)]) {
+	for (key, value) in fields {
+		object.entry(*key).or_insert_with(|| value.clone());
+	}
+}
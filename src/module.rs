@@ -0,0 +1,234 @@
+use std::{convert::TryInto, io::Read};
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, raw};
+
+// Modules rarely get past a few megabytes, most of it sample data we don't
+// need, but instrument headers can sit anywhere in the file.
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TrackerModule {
+	pub format: ModuleFormat,
+	pub title: Option<String>,
+	pub tracker: Option<String>,
+	pub channels: u16,
+	pub patterns: u16,
+	pub orders: u16,
+	pub instruments: Vec<String>,
+	pub samples: Vec<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleFormat {
+	Mod,
+	S3m,
+	Xm,
+	It,
+}
+
+impl TrackerModule {
+	// libopenmpt renders modules as a plain stereo stream, the structure only
+	// shows up in the file itself.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if !matches!(input.format().name(), "libopenmpt" | "libmodplug") {
+			return None;
+		}
+
+		let mut data = Vec::new();
+		Avio::open(&raw::url(input)?).ok()?.take(MAX_SIZE).read_to_end(&mut data).ok()?;
+
+		TrackerModule::parse(&data)
+	}
+
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		if data.starts_with(b"Extended Module: ") {
+			xm(data)
+		}
+		else if data.starts_with(b"IMPM") {
+			it(data)
+		}
+		else if data.get(0x2c..0x30) == Some(&b"SCRM"[..]) {
+			s3m(data)
+		}
+		else {
+			protracker(data)
+		}
+	}
+}
+
+fn u16le(data: &[u8], at: usize) -> Option<u16> {
+	Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32le(data: &[u8], at: usize) -> Option<u32> {
+	Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn text(data: &[u8], at: usize, len: usize) -> Option<String> {
+	let bytes = data.get(at..at + len)?;
+	let bytes = &bytes[..bytes.iter().position(|&b| b == 0).unwrap_or(len)];
+
+	Some(String::from_utf8_lossy(bytes).trim().to_owned())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+	value.filter(|value| !value.is_empty())
+}
+
+fn protracker(data: &[u8]) -> Option<TrackerModule> {
+	let signature = data.get(1080..1084)?;
+	let channels = match signature {
+		b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => 4,
+		b"FLT8" | b"8CHN" | b"OKTA" | b"CD81" => 8,
+		[n, b'C', b'H', b'N'] if n.is_ascii_digit() => u16::from(n - b'0'),
+		[a, b, b'C', b'H'] if a.is_ascii_digit() && b.is_ascii_digit() => {
+			u16::from(a - b'0') * 10 + u16::from(b - b'0')
+		}
+
+		_ => return None,
+	};
+
+	let orders = data[950];
+	let patterns = data[952..1080].iter().max().map_or(0, |&max| u16::from(max) + 1);
+	let samples = (0..31).filter_map(|i| text(data, 20 + i * 30, 22)).collect();
+
+	Some(TrackerModule {
+		format: ModuleFormat::Mod,
+		title: non_empty(text(data, 0, 20)),
+		tracker: None,
+		channels,
+		patterns,
+		orders: u16::from(orders),
+		instruments: Vec::new(),
+		samples,
+	})
+}
+
+fn s3m(data: &[u8]) -> Option<TrackerModule> {
+	let orders = u16le(data, 0x20)?;
+	let count = u16le(data, 0x22)?;
+	let patterns = u16le(data, 0x24)?;
+
+	// Disabled channels have the top bit set.
+	let channels = data.get(0x40..0x60)?.iter().filter(|&&setting| setting < 0x80).count();
+
+	// Parapointers count in 16 byte paragraphs.
+	let base = 0x60 + usize::from(orders);
+	let samples = (0..usize::from(count))
+		.filter_map(|i| u16le(data, base + i * 2))
+		.filter_map(|pointer| text(data, usize::from(pointer) * 16 + 0x30, 28))
+		.collect();
+
+	Some(TrackerModule {
+		format: ModuleFormat::S3m,
+		title: non_empty(text(data, 0, 28)),
+		tracker: Some("Scream Tracker 3".to_owned()),
+		channels: channels as u16,
+		patterns,
+		orders,
+		instruments: Vec::new(),
+		samples,
+	})
+}
+
+fn xm(data: &[u8]) -> Option<TrackerModule> {
+	let header = u32le(data, 60)? as usize;
+	let orders = u16le(data, 64)?;
+	let channels = u16le(data, 68)?;
+	let patterns = u16le(data, 70)?;
+	let count = u16le(data, 72)?;
+
+	// Instruments follow the patterns, which have to be walked to find them.
+	let mut offset = 60 + header;
+	for _ in 0..patterns {
+		let length = u32le(data, offset)? as usize;
+		let packed = usize::from(u16le(data, offset + 7)?);
+		offset += length + packed;
+	}
+
+	let mut instruments = Vec::new();
+	let mut samples = Vec::new();
+
+	for _ in 0..count {
+		let size = match u32le(data, offset) {
+			Some(size) if size >= 29 => size as usize,
+			_ => break,
+		};
+
+		instruments.push(text(data, offset + 4, 22)?);
+
+		let count = u16le(data, offset + 27)?;
+		offset += size;
+
+		if count == 0 {
+			continue;
+		}
+
+		// The sample headers size sits in the instrument header, the sample
+		// data comes after all of it.
+		let header = u32le(data, offset - size + 29)? as usize;
+		let mut length = 0;
+
+		for i in 0..usize::from(count) {
+			let at = offset + i * header;
+			length += u32le(data, at)? as usize;
+			samples.push(text(data, at + 18, 22)?);
+		}
+
+		offset += usize::from(count) * header + length;
+	}
+
+	Some(TrackerModule {
+		format: ModuleFormat::Xm,
+		title: non_empty(text(data, 17, 20)),
+		tracker: non_empty(text(data, 38, 20)),
+		channels,
+		patterns,
+		orders,
+		instruments,
+		samples,
+	})
+}
+
+fn it(data: &[u8]) -> Option<TrackerModule> {
+	let orders = u16le(data, 0x20)?;
+	let instruments = u16le(data, 0x22)?;
+	let samples = u16le(data, 0x24)?;
+	let patterns = u16le(data, 0x26)?;
+	let created = u16le(data, 0x28)?;
+
+	// Channel pan values at or above 128 mark the channel as disabled.
+	let channels = data.get(0x40..0x80)?.iter().filter(|&&pan| pan < 0x80).count();
+
+	let names = |base: usize, count: u16, name: usize| -> Vec<String> {
+		(0..usize::from(count))
+			.filter_map(|i| u32le(data, base + i * 4))
+			.filter_map(|pointer| text(data, pointer as usize + name, 26))
+			.collect()
+	};
+
+	let base = 0xc0 + usize::from(orders);
+	let tracker = match created >> 12 {
+		1 => format!("Impulse Tracker {:x}.{:02x}", (created >> 8) & 0xf, created & 0xff),
+		5 => "OpenMPT".to_owned(),
+		_ => format!("Unknown ({:04x})", created),
+	};
+
+	Some(TrackerModule {
+		format: ModuleFormat::It,
+		title: non_empty(text(data, 4, 26)),
+		tracker: Some(tracker),
+		channels: channels as u16,
+		patterns,
+		orders,
+		instruments: names(base, instruments, 0x20),
+		samples: names(base + usize::from(instruments) * 4, samples, 0x14),
+	})
+}
@@ -0,0 +1,98 @@
+use std::convert::TryInto;
+
+use ffmpeg::format::context::Input;
+
+use crate::{
+	avio::Avio,
+	bitstream::isobmff::{children, descend},
+	raw,
+};
+
+// Sample tables of very long files run into the tens of megabytes.
+const MAX_MOOV: u64 = 64 * 1024 * 1024;
+
+// The `moov` box of an MP4 or QuickTime file, read once for everything that
+// needs more than FFmpeg keeps.
+pub(crate) struct Movie {
+	moov: Vec<u8>,
+}
+
+pub(crate) struct Track<'a> {
+	pub id: u32,
+	pub boxes: Vec<([u8; 4], &'a [u8])>,
+}
+
+impl Movie {
+	pub fn open(input: &Input) -> Option<Self> {
+		if !input.format().name().split(',').any(|name| name == "mp4" || name == "mov") {
+			return None;
+		}
+
+		let mut file = Avio::open(&raw::url(input)?).ok()?;
+		let end = file.size()?;
+
+		Some(Movie {
+			moov: descend(&mut file, end, &[b"moov"], MAX_MOOV)?,
+		})
+	}
+
+	pub fn boxes(&self) -> Vec<([u8; 4], &[u8])> {
+		children(&self.moov)
+	}
+
+	pub fn timescale(&self) -> Option<u32> {
+		timescale(find(&self.boxes(), &[b"mvhd"])?)
+	}
+
+	pub fn tracks(&self) -> Vec<Track<'_>> {
+		self.boxes()
+			.into_iter()
+			.filter(|(kind, _)| kind == b"trak")
+			.filter_map(|(_, trak)| {
+				let boxes = children(trak);
+				let tkhd = find(&boxes, &[b"tkhd"])?;
+				let at = if tkhd.first() == Some(&1) { 20 } else { 12 };
+				let id = u32::from_be_bytes(tkhd.get(at..at + 4)?.try_into().unwrap());
+
+				Some(Track { id, boxes })
+			})
+			.collect()
+	}
+}
+
+impl<'a> Track<'a> {
+	pub fn find(&self, path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+		find(&self.boxes, path)
+	}
+
+	// The media timescale and duration, from `mdhd`.
+	pub fn media(&self) -> Option<(u32, u64)> {
+		let mdhd = self.find(&[b"mdia", b"mdhd"])?;
+		let duration = if mdhd.first() == Some(&1) {
+			u64::from_be_bytes(mdhd.get(24..32)?.try_into().unwrap())
+		}
+		else {
+			u64::from(u32::from_be_bytes(mdhd.get(16..20)?.try_into().unwrap()))
+		};
+
+		Some((timescale(mdhd)?, duration))
+	}
+}
+
+pub(crate) fn find<'a>(boxes: &[([u8; 4], &'a [u8])], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+	let (first, rest) = path.split_first()?;
+	let &(_, data) = boxes.iter().find(|(kind, _)| kind == *first)?;
+
+	if rest.is_empty() {
+		Some(data)
+	}
+	else {
+		find(&children(data), rest)
+	}
+}
+
+// `mvhd` and `mdhd` share the layout up to the duration.
+fn timescale(header: &[u8]) -> Option<u32> {
+	let at = if header.first() == Some(&1) { 20 } else { 12 };
+	Some(u32::from_be_bytes(header.get(at..at + 4)?.try_into().unwrap())).filter(|&scale| scale > 0)
+}
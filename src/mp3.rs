@@ -0,0 +1,83 @@
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::bitstream::mpeg_audio::{self, Kind};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VbrHeader {
+	pub kind: VbrHeaderKind,
+	pub mode: BitrateMode,
+	pub frames: Option<u32>,
+	pub bytes: Option<u32>,
+	pub quality: Option<u32>,
+	pub encoder: Option<String>,
+	// Derived from the frame count, these are exact where the generic estimate
+	// from the first frames is not.
+	pub duration: Option<f64>,
+	pub bit_rate: Option<u64>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum VbrHeaderKind {
+	Xing,
+	Info,
+	Vbri,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum BitrateMode {
+	Cbr,
+	Vbr,
+	Abr,
+}
+
+impl VbrHeader {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let xing = mpeg_audio::find(input)?;
+
+		// LAME records the actual mode, otherwise LAME and FFmpeg only write an
+		// Info tag for constant bit rate files.
+		let mode = match xing.lame.as_ref().map(|lame| lame.method) {
+			Some(1) | Some(8) => BitrateMode::Cbr,
+			Some(2) | Some(9) => BitrateMode::Abr,
+			Some(3..=6) => BitrateMode::Vbr,
+			_ if xing.kind == Kind::Info => BitrateMode::Cbr,
+			_ => BitrateMode::Vbr,
+		};
+
+		let header = xing.header;
+		let duration = xing.frames.filter(|_| header.sample_rate > 0).map(|frames| {
+			f64::from(frames) * f64::from(header.samples()) / f64::from(header.sample_rate)
+		});
+
+		let bit_rate = xing.bytes.zip(duration).and_then(|(bytes, duration)| {
+			if duration > 0.0 {
+				Some((f64::from(bytes) * 8.0 / duration).round() as u64)
+			}
+			else {
+				None
+			}
+		});
+
+		Some(VbrHeader {
+			kind: match xing.kind {
+				Kind::Xing => VbrHeaderKind::Xing,
+				Kind::Info => VbrHeaderKind::Info,
+				Kind::Vbri => VbrHeaderKind::Vbri,
+			},
+			mode,
+			frames: xing.frames,
+			bytes: xing.bytes,
+			quality: xing.quality,
+			encoder: xing.lame.map(|lame| lame.version).filter(|version| !version.is_empty()),
+			duration,
+			bit_rate,
+		})
+	}
+}
@@ -0,0 +1,304 @@
+use std::{
+	collections::HashMap,
+	convert::TryInto,
+	io::{Read, Seek, SeekFrom},
+};
+
+use ffmpeg::format::context::Input;
+use serde::{Deserialize, Serialize};
+
+use crate::{avio::Avio, raw};
+
+// Comment headers can carry cover art, which we don't want in memory.
+const MAX_HEADER: usize = 1024 * 1024;
+
+// A chained Ogg file is several complete Ogg streams one after another, each
+// starting over with its own headers, comments and granule positions.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OggChain {
+	pub links: Vec<OggLink>,
+}
+
+// Offsets in bytes, start and duration in seconds.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OggLink {
+	pub offset: u64,
+	pub start: f64,
+	pub duration: Option<f64>,
+	pub streams: Vec<OggStream>,
+	pub vendor: Option<String>,
+	pub comments: HashMap<String, String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OggStream {
+	pub serial: u32,
+	pub codec: String,
+	pub sample_rate: Option<u32>,
+}
+
+impl OggChain {
+	// FFmpeg follows the chain while demuxing but only reports the first link.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		if input.format().name() != "ogg" {
+			return None;
+		}
+
+		let links = links(&mut Avio::open(&raw::url(input)?).ok()?);
+
+		if links.len() > 1 {
+			Some(OggChain { links })
+		}
+		else {
+			None
+		}
+	}
+}
+
+struct Logical {
+	serial: u32,
+	packets: Vec<Vec<u8>>,
+	partial: Vec<u8>,
+	rate: Option<u32>,
+	skip: u64,
+	granule: Option<u64>,
+}
+
+struct Link {
+	offset: u64,
+	streams: Vec<Logical>,
+	data: bool,
+}
+
+fn links<R: Read + Seek>(reader: &mut R) -> Vec<OggLink> {
+	let mut done = Vec::new();
+	let mut current: Option<Link> = None;
+
+	while let Ok(offset) = reader.seek(SeekFrom::Current(0)) {
+		let mut header = [0; 27];
+		if reader.read_exact(&mut header).is_err() || &header[..4] != b"OggS" {
+			break;
+		}
+
+		let mut lacing = vec![0; usize::from(header[26])];
+		if reader.read_exact(&mut lacing).is_err() {
+			break;
+		}
+
+		let bos = header[5] & 0x02 != 0;
+		let granule = u64::from_le_bytes(header[6..14].try_into().unwrap());
+		let serial = u32::from_le_bytes(header[14..18].try_into().unwrap());
+		let size = lacing.iter().map(|&l| i64::from(l)).sum::<i64>();
+
+		// A beginning of stream page after data pages starts the next link.
+		if bos && current.as_ref().map_or(true, |link| link.data) {
+			done.extend(current.take());
+			current = Some(Link {
+				offset,
+				streams: Vec::new(),
+				data: false,
+			});
+		}
+
+		let link = match current.as_mut() {
+			Some(link) => link,
+			None => break,
+		};
+
+		if bos {
+			link.streams.push(Logical {
+				serial,
+				packets: Vec::new(),
+				partial: Vec::new(),
+				rate: None,
+				skip: 0,
+				granule: None,
+			});
+		}
+		else {
+			link.data = true;
+		}
+
+		let stream = match link.streams.iter_mut().find(|stream| stream.serial == serial) {
+			Some(stream) => stream,
+			None => {
+				if reader.seek(SeekFrom::Current(size)).is_err() {
+					break;
+				}
+
+				continue;
+			}
+		};
+
+		// -1 marks pages where no packet ends.
+		if granule != u64::MAX {
+			stream.granule = Some(granule);
+		}
+
+		if stream.packets.len() >= 2 {
+			if reader.seek(SeekFrom::Current(size)).is_err() {
+				break;
+			}
+
+			continue;
+		}
+
+		let mut body = vec![0; size as usize];
+		if reader.read_exact(&mut body).is_err() {
+			break;
+		}
+
+		let mut at = 0;
+		for &length in &lacing {
+			let length = usize::from(length);
+
+			if stream.partial.len() < MAX_HEADER {
+				stream.partial.extend_from_slice(&body[at..at + length]);
+			}
+
+			at += length;
+
+			if length < 255 && stream.packets.len() < 2 {
+				let packet = std::mem::take(&mut stream.partial);
+
+				if stream.packets.is_empty() {
+					let (rate, skip) = identify(&packet).1;
+					stream.rate = rate;
+					stream.skip = skip;
+				}
+
+				stream.packets.push(packet);
+			}
+		}
+	}
+
+	done.extend(current);
+
+	let mut start = 0.0;
+	done.into_iter()
+		.map(|link| {
+			let mut link = finish(link);
+			link.start = start;
+			start += link.duration.unwrap_or(0.0);
+
+			link
+		})
+		.collect()
+}
+
+// Codec name, sample rate and the Opus pre-skip from the first packet.
+fn identify(packet: &[u8]) -> (Option<&'static str>, (Option<u32>, u64)) {
+	let le32 = |at: usize| {
+		packet.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+	};
+
+	if packet.starts_with(b"\x01vorbis") {
+		(Some("vorbis"), (le32(12), 0))
+	}
+	else if packet.starts_with(b"OpusHead") {
+		let skip = packet.get(10..12).map_or(0, |b| u16::from_le_bytes([b[0], b[1]]));
+		(Some("opus"), (Some(48_000), u64::from(skip)))
+	}
+	else if packet.starts_with(b"\x7fFLAC") {
+		// The 20 bit rate inside the STREAMINFO block.
+		let rate = packet.get(27..30).map(|b| {
+			(u32::from(b[0]) << 12) | (u32::from(b[1]) << 4) | (u32::from(b[2]) >> 4)
+		});
+
+		(Some("flac"), (rate, 0))
+	}
+	else if packet.starts_with(b"Speex   ") {
+		(Some("speex"), (le32(36), 0))
+	}
+	else if packet.starts_with(b"\x80theora") {
+		(Some("theora"), (None, 0))
+	}
+	else {
+		(None, (None, 0))
+	}
+}
+
+// Vorbis comments, behind whatever framing the codec puts around them.
+fn comments(codec: &str, packet: &[u8]) -> Option<(String, HashMap<String, String>)> {
+	let data = match codec {
+		"vorbis" => packet.strip_prefix(b"\x03vorbis")?,
+		"opus" => packet.strip_prefix(b"OpusTags")?,
+		"theora" => packet.strip_prefix(b"\x81theora")?,
+		"flac" if (packet.first()? & 0x7f) == 4 => packet.get(4..)?,
+		"speex" => packet,
+		_ => return None,
+	};
+
+	let le32 = |at: usize| -> Option<usize> {
+		Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize)
+	};
+
+	let length = le32(0)?;
+	let vendor = String::from_utf8_lossy(data.get(4..4 + length)?).into_owned();
+	let count = le32(4 + length)?;
+
+	let mut comments = HashMap::<String, String>::new();
+	let mut at = 8 + length;
+
+	for _ in 0..count {
+		let length = le32(at)?;
+		let comment = String::from_utf8_lossy(data.get(at + 4..at + 4 + length)?).into_owned();
+		at += 4 + length;
+
+		// Keys are case insensitive and repeatable, FFmpeg joins the values.
+		if let Some((key, value)) = comment.split_once('=') {
+			comments
+				.entry(key.to_ascii_lowercase())
+				.and_modify(|existing| {
+					existing.push(';');
+					existing.push_str(value);
+				})
+				.or_insert_with(|| value.to_owned());
+		}
+	}
+
+	Some((vendor, comments))
+}
+
+fn finish(link: Link) -> OggLink {
+	let mut streams = Vec::new();
+	let mut vendor = None;
+	let mut tags = HashMap::new();
+	let mut duration = None;
+
+	for stream in &link.streams {
+		let codec = stream.packets.first().and_then(|packet| identify(packet).0);
+
+		let header = codec.zip(stream.packets.get(1));
+		if let Some((found, values)) = header.and_then(|(codec, packet)| comments(codec, packet)) {
+			vendor.get_or_insert(found);
+			tags.extend(values);
+		}
+
+		// The longest of the audio streams, video granules aren't sample counts.
+		let rate = stream.rate.filter(|&rate| rate > 0);
+		if let (Some(rate), Some(granule)) = (rate, stream.granule) {
+			let seconds = granule.saturating_sub(stream.skip) as f64 / f64::from(rate);
+			duration = Some(duration.map_or(seconds, |duration: f64| duration.max(seconds)));
+		}
+
+		streams.push(OggStream {
+			serial: stream.serial,
+			codec: codec.unwrap_or("unknown").to_owned(),
+			sample_rate: stream.rate,
+		});
+	}
+
+	OggLink {
+		offset: link.offset,
+		start: 0.0,
+		duration,
+		streams,
+		vendor,
+		comments: tags,
+	}
+}
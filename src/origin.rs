@@ -0,0 +1,105 @@
+use ffmpeg::{ffi, format::context::Input};
+use serde::{Deserialize, Serialize};
+
+use crate::{raw, DurationEstimate, Elementary};
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Origin {
+	// Written by the muxer in the container headers or index.
+	Container,
+	// Parsed out of the codec bitstream headers.
+	Codec,
+	// Counted or timed by reading the media itself.
+	Measured,
+	// Extrapolated from something else, usually the file size.
+	Estimated,
+}
+
+// Where the headline values came from, for consumers that need to know how far
+// to trust them.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Origins {
+	pub duration: Option<Origin>,
+	pub bit_rate: Option<Origin>,
+	pub streams: Vec<StreamOrigins>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StreamOrigins {
+	pub index: usize,
+	pub duration: Option<Origin>,
+	pub frame_rate: Option<Origin>,
+	pub bit_rate: Option<Origin>,
+}
+
+impl Origins {
+	pub fn new(input: &Input, elementary: Option<&Elementary>) -> Self {
+		let estimated = raw::duration_estimation(input)
+			== ffi::AVDurationEstimationMethod::AVFMT_DURATION_FROM_BITRATE;
+
+		// Without a container, whatever isn't counted comes from frame headers.
+		let header = if elementary.is_some() || input.format().name() == "mp3" {
+			Origin::Codec
+		}
+		else {
+			Origin::Container
+		};
+
+		let duration = match elementary.map(|elementary| elementary.estimate) {
+			Some(DurationEstimate::Frames) => Some(Origin::Measured),
+			_ if input.duration() == ffi::AV_NOPTS_VALUE || input.duration() <= 0 => None,
+			_ if estimated => Some(Origin::Estimated),
+			_ => Some(Origin::Container),
+		};
+
+		// FFmpeg fills the overall bit rate in from the size and duration when
+		// the container doesn't say.
+		let bit_rate = match input.bit_rate() {
+			rate if rate <= 0 => None,
+			_ if estimated => Some(Origin::Estimated),
+			_ => Some(header),
+		};
+
+		let streams = input
+			.streams()
+			.map(|stream| {
+				let parameters = stream.parameters();
+
+				StreamOrigins {
+					index: stream.index(),
+					duration: match stream.duration() {
+						duration if duration == ffi::AV_NOPTS_VALUE || duration <= 0 => None,
+						_ if estimated => Some(Origin::Estimated),
+						_ => Some(Origin::Container),
+					},
+					// The average is set by demuxers that know it, the real base
+					// rate is guessed from the first timestamps.
+					frame_rate: if stream.avg_frame_rate().numerator() > 0 {
+						Some(Origin::Container)
+					}
+					else if stream.rate().numerator() > 0 {
+						Some(Origin::Estimated)
+					}
+					else {
+						None
+					},
+					bit_rate: if raw::bit_rate(&parameters) > 0 { Some(header) } else { None },
+				}
+			})
+			.collect();
+
+		Origins {
+			duration,
+			bit_rate,
+			streams,
+		}
+	}
+
+	pub fn stream(&self, index: usize) -> Option<&StreamOrigins> {
+		self.streams.iter().find(|stream| stream.index == index)
+	}
+}
@@ -0,0 +1,181 @@
+use std::{
+	ffi::CString,
+	io::Read,
+	os::raw::{c_int, c_void},
+	ptr, slice,
+	time::Duration,
+};
+
+use ffmpeg::{ffi, format::context::Input};
+
+use crate::{capture_log, raw, Metadata, Warning};
+
+const BUFFER: usize = 32 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct PipeOptions {
+	// How much FFmpeg reads before settling on the streams, there is no going
+	// back for more later.
+	pub probe_size: u64,
+	pub analyze_duration: Option<Duration>,
+	// Raw streams can't always be told apart from their first bytes.
+	pub format: Option<String>,
+}
+
+impl Default for PipeOptions {
+	fn default() -> Self {
+		PipeOptions {
+			probe_size: 5_000_000,
+			analyze_duration: None,
+			format: None,
+		}
+	}
+}
+
+// An AVIOContext reading from `R`, without a seek callback so demuxers know
+// not to look at the end of the input.
+struct Pipe<R> {
+	context: *mut ffi::AVIOContext,
+	reader: *mut R,
+}
+
+unsafe extern "C" fn read<R: Read>(opaque: *mut c_void, buffer: *mut u8, size: c_int) -> c_int {
+	let reader = &mut *(opaque as *mut R);
+	let buffer = slice::from_raw_parts_mut(buffer, size.max(0) as usize);
+
+	match reader.read(buffer) {
+		Ok(0) => ffi::AVERROR_EOF,
+		Ok(read) => read as c_int,
+		Err(_) => ffi::AVERROR(libc::EIO),
+	}
+}
+
+impl<R: Read> Pipe<R> {
+	fn new(reader: R) -> ffmpeg::Result<Self> {
+		unsafe {
+			let buffer = ffi::av_malloc(BUFFER) as *mut u8;
+			if buffer.is_null() {
+				return Err(ffmpeg::Error::from(ffi::AVERROR(libc::ENOMEM)));
+			}
+
+			let reader = Box::into_raw(Box::new(reader));
+			let context = ffi::avio_alloc_context(
+				buffer,
+				BUFFER as c_int,
+				0,
+				reader as *mut c_void,
+				Some(read::<R>),
+				None,
+				None,
+			);
+
+			if context.is_null() {
+				ffi::av_free(buffer as *mut c_void);
+				drop(Box::from_raw(reader));
+
+				return Err(ffmpeg::Error::from(ffi::AVERROR(libc::ENOMEM)));
+			}
+
+			(*context).seekable = 0;
+			Ok(Pipe { context, reader })
+		}
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(name = "open", skip_all))]
+	fn open(&self, options: &PipeOptions) -> ffmpeg::Result<Input> {
+		unsafe {
+			let mut context = ffi::avformat_alloc_context();
+			if context.is_null() {
+				return Err(ffmpeg::Error::from(ffi::AVERROR(libc::ENOMEM)));
+			}
+
+			(*context).pb = self.context;
+			(*context).flags |= ffi::AVFMT_FLAG_CUSTOM_IO as c_int;
+			(*context).probesize = options.probe_size.min(i64::MAX as u64) as i64;
+			if let Some(duration) = options.analyze_duration {
+				(*context).max_analyze_duration = duration.as_micros().min(i64::MAX as u128) as i64;
+			}
+
+			let format = match &options.format {
+				Some(name) => {
+					let name = CString::new(name.as_str()).map_err(|_| ffmpeg::Error::InvalidData)?;
+					let format = ffi::av_find_input_format(name.as_ptr());
+					if format.is_null() {
+						ffi::avformat_free_context(context);
+						return Err(ffmpeg::Error::DemuxerNotFound);
+					}
+
+					format as *const ffi::AVInputFormat
+				}
+
+				None => ptr::null(),
+			};
+
+			let result =
+				ffi::avformat_open_input(&mut context, ptr::null(), format as _, ptr::null_mut());
+			if result < 0 {
+				return Err(ffmpeg::Error::from(result));
+			}
+
+			match ffi::avformat_find_stream_info(context, ptr::null_mut()) {
+				error if error < 0 => {
+					ffi::avformat_close_input(&mut context);
+					Err(ffmpeg::Error::from(error))
+				}
+
+				_ => Ok(Input::wrap(context)),
+			}
+		}
+	}
+}
+
+impl<R> Drop for Pipe<R> {
+	fn drop(&mut self) {
+		unsafe {
+			ffi::av_freep(&mut (*self.context).buffer as *mut _ as *mut c_void);
+			ffi::avio_context_free(&mut self.context);
+			drop(Box::from_raw(self.reader));
+		}
+	}
+}
+
+impl Metadata {
+	// Probes whatever comes out of `reader` from the first `probe_size` bytes.
+	// The duration is only kept when the container declares it, and what
+	// couldn't be known is listed in a `Warning::Unseekable`.
+	pub fn from_nonseekable<R: Read>(reader: R, options: &PipeOptions) -> ffmpeg::Result<Self> {
+		let (metadata, log) = capture_log(|| nonseekable(reader, options));
+
+		let mut metadata = metadata?;
+		metadata.warnings.extend(log.into_iter().map(Warning::Log));
+
+		Ok(metadata)
+	}
+}
+
+fn nonseekable<R: Read>(reader: R, options: &PipeOptions) -> ffmpeg::Result<Metadata> {
+	let pipe = Pipe::new(reader)?;
+	let input = pipe.open(options)?;
+	let mut metadata = Metadata::new(&input)?;
+
+	if raw::duration_estimation(&input)
+		== ffi::AVDurationEstimationMethod::AVFMT_DURATION_FROM_BITRATE
+	{
+		metadata.duration = None;
+	}
+
+	let mut unavailable = Vec::new();
+	if metadata.duration.is_none() {
+		unavailable.push("duration".to_owned());
+	}
+
+	if metadata.streams.iter().any(|stream| stream.frames == 0) {
+		unavailable.push("frames".to_owned());
+	}
+
+	if !unavailable.is_empty() {
+		metadata.warnings.push(Warning::Unseekable { unavailable });
+	}
+
+	Ok(metadata)
+}
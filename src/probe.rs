@@ -0,0 +1,430 @@
+use std::{
+	cell::{Cell, Ref, RefCell},
+	collections::{BTreeMap, HashMap},
+	ffi::CString,
+	os::raw::{c_int, c_void},
+	ptr,
+	rc::Rc,
+	time::{Duration, Instant},
+};
+
+use ffmpeg::{
+	ffi,
+	format::{context::Input, stream::Stream},
+	media, Dictionary, Rational,
+};
+
+use crate::{analysis, compat, raw};
+
+// How to reach, and when to give up on, a remote or untrusted input. The
+// timeout and byte limit cover the whole probe, including the extra reads done
+// on the side to parse what FFmpeg doesn't.
+#[derive(Clone, Debug, Default)]
+pub struct ProbeOptions {
+	pub user_agent: Option<String>,
+	pub headers: Vec<(String, String)>,
+	// For any single read, as opposed to `timeout`.
+	pub rw_timeout: Option<Duration>,
+	pub reconnect: bool,
+	// Passed through as is, e.g. `latency` for srt or `tls_verify` for https.
+	pub protocol: BTreeMap<String, String>,
+	pub max_bytes: Option<u64>,
+	pub timeout: Option<Duration>,
+	// Inputs declaring more streams than this fail to open.
+	pub max_streams: Option<usize>,
+	// Frames decoded per stream while probing, and by analyses run with
+	// `ProbeOptions::analysis`.
+	pub max_frames: Option<usize>,
+	// Leaves codec parameters to what the container declares, no decoder
+	// ever sees the input. Always on with the `headers-only` feature.
+	pub disable_decoders: bool,
+	pub streams: StreamSelection,
+	pub raw: Option<RawHints>,
+}
+
+// What a headerless input can't say about itself. Setting any of this forces
+// the demuxer, from `format` or the extension, instead of probing for one.
+#[derive(Clone, Debug, Default)]
+pub struct RawHints {
+	// A demuxer name, e.g. `h264`, `aac` or `s16le`.
+	pub format: Option<String>,
+	// For raw video and the `h264` and `hevc` demuxers, which assume 25 fps.
+	pub frame_rate: Option<Rational>,
+	// Raw video only.
+	pub width: Option<u32>,
+	pub height: Option<u32>,
+	pub pixel_format: Option<String>,
+	// Headerless PCM, which is 44.1 kHz mono unless told otherwise.
+	pub sample_rate: Option<u32>,
+	pub channels: Option<u32>,
+}
+
+impl RawHints {
+	// The demuxer for the raw formats FFmpeg can't, or doesn't reliably,
+	// probe for.
+	pub fn demuxer(&self, url: &str) -> Option<String> {
+		if let Some(format) = &self.format {
+			return Some(format.clone());
+		}
+
+		let extension = url.rsplit(['/', '\\'].as_ref()).next()?.rsplit_once('.')?.1.to_lowercase();
+		let name = match extension.as_str() {
+			"h264" | "264" | "avc" | "jsv" => "h264",
+			"h265" | "265" | "hevc" => "hevc",
+			"aac" | "adts" => "aac",
+			"ac3" => "ac3",
+			"eac3" | "ec3" => "eac3",
+			"yuv" | "rgb" => "rawvideo",
+			"pcm" | "raw" | "s16" => "s16le",
+			"f32" => "f32le",
+			"u8" => "u8",
+			_ => return None,
+		};
+
+		Some(name.into())
+	}
+
+	fn dictionary(&self, dictionary: &mut Dictionary) {
+		if let Some(rate) = self.frame_rate {
+			dictionary.set("framerate", &format!("{}/{}", rate.numerator(), rate.denominator()));
+		}
+
+		if let (Some(width), Some(height)) = (self.width, self.height) {
+			dictionary.set("video_size", &format!("{}x{}", width, height));
+		}
+
+		if let Some(format) = &self.pixel_format {
+			dictionary.set("pixel_format", format);
+		}
+
+		if let Some(rate) = self.sample_rate {
+			dictionary.set("sample_rate", &rate.to_string());
+		}
+
+		#[cfg(not(ffmpeg_6))]
+		if let Some(channels) = self.channels {
+			dictionary.set("channels", &channels.to_string());
+		}
+
+		#[cfg(ffmpeg_6)]
+		if let Some(channels) = self.channels {
+			dictionary.set("ch_layout", &format!("{}c", channels));
+		}
+	}
+}
+
+// Streams left out are discarded before FFmpeg looks for their parameters,
+// and missing from the result. Empty lists select everything.
+#[derive(Clone, Debug, Default)]
+pub struct StreamSelection {
+	pub kinds: Vec<media::Type>,
+	pub indices: Vec<usize>,
+}
+
+impl StreamSelection {
+	pub fn contains(&self, stream: &Stream) -> bool {
+		(self.kinds.is_empty() || self.kinds.contains(&compat::medium(stream)))
+			&& (self.indices.is_empty() || self.indices.contains(&stream.index()))
+	}
+}
+
+impl ProbeOptions {
+	fn dictionary(&self) -> Dictionary<'static> {
+		let mut dictionary = Dictionary::new();
+
+		if let Some(user_agent) = &self.user_agent {
+			dictionary.set("user_agent", user_agent);
+		}
+
+		if !self.headers.is_empty() {
+			let headers: String =
+				self.headers.iter().map(|(key, value)| format!("{}: {}\r\n", key, value)).collect();
+			dictionary.set("headers", &headers);
+		}
+
+		if let Some(timeout) = self.rw_timeout {
+			dictionary.set("rw_timeout", &timeout.as_micros().to_string());
+		}
+
+		if self.reconnect {
+			dictionary.set("reconnect", "1");
+			dictionary.set("reconnect_streamed", "1");
+		}
+
+		// Format options ride along with the protocol ones, so FFmpeg stops
+		// on its own before the interrupt has to.
+		if let Some(max) = self.max_bytes {
+			dictionary.set("probesize", &max.max(32).to_string());
+		}
+
+		if let Some(max) = self.max_streams {
+			dictionary.set("max_streams", &max.to_string());
+		}
+
+		if let Some(max) = self.max_frames {
+			dictionary.set("fpsprobesize", &max.to_string());
+		}
+
+		if let Some(raw) = &self.raw {
+			raw.dictionary(&mut dictionary);
+		}
+
+		for (key, value) in &self.protocol {
+			dictionary.set(key, value);
+		}
+
+		dictionary
+	}
+
+	pub fn analysis(&self) -> analysis::Options {
+		analysis::Options {
+			max_frames: self.max_frames,
+			..Default::default()
+		}
+	}
+}
+
+pub(crate) struct Limits {
+	options: ProbeOptions,
+	deadline: Option<Instant>,
+	// Bytes read through `Avio`, the main input counts its own.
+	read: Cell<u64>,
+	context: Cell<*mut ffi::AVFormatContext>,
+}
+
+thread_local! {
+	static CURRENT: RefCell<Option<Rc<Limits>>> = RefCell::new(None);
+}
+
+impl Limits {
+	fn new(options: &ProbeOptions) -> Self {
+		Limits {
+			options: options.clone(),
+			deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+			read: Cell::new(0),
+			context: Cell::new(ptr::null_mut()),
+		}
+	}
+
+	fn exceeded(&self) -> bool {
+		if self.deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+			return true;
+		}
+
+		let context = self.context.get();
+		let input = unsafe {
+			if context.is_null() || (*context).pb.is_null() {
+				0
+			}
+			else {
+				(*(*context).pb).bytes_read.max(0) as u64
+			}
+		};
+
+		self.options.max_bytes.map_or(false, |max| self.read.get() + input > max)
+	}
+
+	// Returns false once the limit is reached, so the caller fails the read.
+	pub fn consume(&self, bytes: usize) -> bool {
+		self.read.set(self.read.get() + bytes as u64);
+		!self.exceeded()
+	}
+
+	pub fn dictionary(&self) -> Dictionary<'static> {
+		self.options.dictionary()
+	}
+
+	pub fn interrupt(&self) -> ffi::AVIOInterruptCB {
+		ffi::AVIOInterruptCB {
+			callback: Some(interrupt),
+			opaque: self as *const Limits as *mut c_void,
+		}
+	}
+}
+
+extern "C" fn interrupt(opaque: *mut c_void) -> c_int {
+	let limits = unsafe { &*(opaque as *const Limits) };
+	limits.exceeded() as c_int
+}
+
+// The limits of the probe running on this thread, if any.
+pub(crate) fn current() -> Option<Rc<Limits>> {
+	CURRENT.with(|current| current.borrow().clone())
+}
+
+pub(crate) fn headers_only() -> bool {
+	cfg!(feature = "headers-only")
+		|| current().map_or(false, |limits| limits.options.disable_decoders)
+}
+
+pub(crate) fn selected(stream: &Stream) -> bool {
+	current().map_or(true, |limits| limits.options.streams.contains(stream))
+}
+
+// Opens `url` under `options` and runs `f` with the limits in place, for the
+// input itself and anything reopened through `Avio`.
+pub(crate) fn scoped<T, F>(url: &str, options: &ProbeOptions, f: F) -> ffmpeg::Result<T>
+where
+	F: FnOnce(&mut Input) -> ffmpeg::Result<T>,
+{
+	let limits = Rc::new(Limits::new(options));
+	let previous = CURRENT.with(|current| current.replace(Some(limits.clone())));
+	let _scope = Scope { limits: limits.clone(), previous };
+
+	open(url, &limits, true).and_then(|mut input| f(&mut input))
+}
+
+// Puts back the limits of the enclosing probe, even when `f` panics.
+struct Scope {
+	limits: Rc<Limits>,
+	previous: Option<Rc<Limits>>,
+}
+
+impl Drop for Scope {
+	fn drop(&mut self) {
+		self.limits.context.set(ptr::null_mut());
+		CURRENT.with(|current| current.replace(self.previous.take()));
+	}
+}
+
+// The first packets of every stream, read once from a second handle so the
+// caller's input doesn't move, and only when a parser asks for them.
+pub(crate) struct Packets<'a> {
+	input: &'a Input,
+	read: RefCell<Option<HashMap<usize, Vec<Vec<u8>>>>>,
+}
+
+impl<'a> Packets<'a> {
+	pub fn new(input: &'a Input) -> Self {
+		Packets {
+			input,
+			read: RefCell::new(None),
+		}
+	}
+
+	pub fn input(&self) -> &'a Input {
+		self.input
+	}
+
+	// At most `PACKETS` of them.
+	pub fn stream(&self, index: usize) -> Ref<'_, [Vec<u8>]> {
+		if self.read.borrow().is_none() {
+			self.read.replace(Some(read(self.input)));
+		}
+
+		Ref::map(self.read.borrow(), |read| {
+			read.as_ref().and_then(|read| read.get(&index)).map_or(&[][..], Vec::as_slice)
+		})
+	}
+}
+
+// Enough for the parsers looking at the start of a stream.
+const PACKETS: usize = 64;
+
+fn read(input: &Input) -> HashMap<usize, Vec<Vec<u8>>> {
+	let mut packets = HashMap::new();
+
+	// Streams in formats without a header only show up on the second handle
+	// once their first packet does.
+	let streams = input.streams().filter(selected).count();
+
+	// The stream parameters are known already, there's no need to have FFmpeg
+	// look for them again.
+	let url = match raw::url(input) {
+		Some(url) => url,
+		None => return packets,
+	};
+
+	// Outlives the input, its interrupt callback points at it.
+	let limits = current().unwrap_or_else(|| Rc::new(Limits::new(&ProbeOptions::default())));
+	let mut input = match open(&url, &limits, false) {
+		Ok(input) => input,
+		Err(_) => return packets,
+	};
+
+	// Streams that never show a packet would have the whole input read
+	// otherwise.
+	for (stream, packet) in input.packets().take(PACKETS * streams.max(1) * 4) {
+		let list = packets.entry(stream.index()).or_default();
+
+		if list.len() < PACKETS {
+			if let Some(data) = packet.data() {
+				list.push(data.to_vec());
+			}
+		}
+
+		if packets.len() >= streams && packets.values().all(|list| list.len() >= PACKETS) {
+			break;
+		}
+	}
+
+	packets
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(limits)))]
+fn open(url: &str, limits: &Limits, analyze: bool) -> ffmpeg::Result<Input> {
+	let demuxer = limits.options.raw.as_ref().and_then(|raw| raw.demuxer(url));
+	let demuxer = demuxer.map(CString::new).transpose().map_err(|_| ffmpeg::Error::InvalidData)?;
+	let url = CString::new(url).map_err(|_| ffmpeg::Error::InvalidData)?;
+
+	unsafe {
+		let format = match &demuxer {
+			Some(name) => match ffi::av_find_input_format(name.as_ptr()) {
+				format if format.is_null() => return Err(ffmpeg::Error::DemuxerNotFound),
+				format => format as *const ffi::AVInputFormat,
+			},
+			None => ptr::null(),
+		};
+
+		let mut context = ffi::avformat_alloc_context();
+		if context.is_null() {
+			return Err(ffmpeg::Error::from(ffi::AVERROR(libc::ENOMEM)));
+		}
+
+		(*context).interrupt_callback = limits.interrupt();
+
+		// The first input opened is the one the byte limit watches.
+		let main = limits.context.get().is_null();
+		if main {
+			limits.context.set(context);
+		}
+
+		// Whatever the protocol didn't take is left in the dictionary, which
+		// is not an error here.
+		let mut dictionary = limits.dictionary().disown();
+		let result =
+			ffi::avformat_open_input(&mut context, url.as_ptr(), format as _, &mut dictionary);
+		Dictionary::own(dictionary);
+
+		if result < 0 {
+			if main {
+				limits.context.set(ptr::null_mut());
+			}
+
+			return Err(ffmpeg::Error::from(result));
+		}
+
+		let input = Input::wrap(context);
+		for stream in input.streams() {
+			if !limits.options.streams.contains(&stream) {
+				(*(stream.as_ptr() as *mut ffi::AVStream)).discard = ffi::AVDiscard::AVDISCARD_ALL;
+			}
+		}
+
+		if !analyze || limits.options.disable_decoders || cfg!(feature = "headers-only") {
+			return Ok(input);
+		}
+
+		match ffi::avformat_find_stream_info(context, ptr::null_mut()) {
+			error if error < 0 => {
+				if main {
+					limits.context.set(ptr::null_mut());
+				}
+
+				Err(ffmpeg::Error::from(error))
+			}
+
+			_ => Ok(input),
+		}
+	}
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Metadata;
+
+const COLOR: &[&str] = &[
+	"color_space",
+	"color_range",
+	"color_primaries",
+	"color_transfer_characteristic",
+	"chroma_location",
+];
+
+// A reduced view of `Metadata` for handing out, e.g. to API clients that
+// shouldn't see internal tags.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Projection {
+	// Top level fields to keep by their serialized name, `None` keeps them
+	// all. `schema_version` is always kept.
+	pub fields: Option<Vec<String>>,
+	// The tag maps of the file and its chapters.
+	pub details: bool,
+	// Color properties of video streams.
+	pub color: bool,
+}
+
+impl Default for Projection {
+	fn default() -> Self {
+		Projection {
+			fields: None,
+			details: true,
+			color: true,
+		}
+	}
+}
+
+impl Projection {
+	pub fn only(fields: &[&str]) -> Self {
+		Projection {
+			fields: Some(fields.iter().map(|&field| field.into()).collect()),
+			..Default::default()
+		}
+	}
+
+	pub fn format_only() -> Self {
+		Projection::only(&["format"])
+	}
+
+	pub fn without_details(self) -> Self {
+		Projection {
+			details: false,
+			..self
+		}
+	}
+
+	pub fn without_color(self) -> Self {
+		Projection { color: false, ..self }
+	}
+
+	pub fn apply(&self, metadata: &Metadata) -> serde_json::Result<Value> {
+		let mut document = serde_json::to_value(metadata)?;
+		let object = match document.as_object_mut() {
+			Some(object) => object,
+			None => return Ok(document),
+		};
+
+		if let Some(fields) = &self.fields {
+			object.retain(|key, _| key == "schema_version" || fields.iter().any(|field| field == key));
+		}
+
+		if !self.details {
+			object.remove("details");
+
+			let chapters = object.get_mut("chapters").and_then(Value::as_array_mut);
+			for chapter in chapters.into_iter().flatten().filter_map(Value::as_object_mut) {
+				chapter.remove("details");
+			}
+		}
+
+		if !self.color {
+			let streams = object.get_mut("streams").and_then(Value::as_array_mut);
+			for stream in streams.into_iter().flatten() {
+				let video = stream.get_mut("content").and_then(|content| content.get_mut("video"));
+
+				if let Some(video) = video.and_then(Value::as_object_mut) {
+					video.retain(|key, _| !COLOR.contains(&key.as_str()));
+				}
+			}
+		}
+
+		Ok(document)
+	}
+}
@@ -0,0 +1,162 @@
+use ffmpeg::{codec, format::context::Input, media};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	analysis,
+	bitstream::{nal, sei},
+	compat, raw,
+};
+
+// Encoder strings live in the first access unit, looking further is a waste.
+const PACKETS: usize = 8;
+
+const FORMAT: &[(&str, ProvenanceSource)] = &[
+	("encoder", ProvenanceSource::Encoder),
+	("encoded_by", ProvenanceSource::Encoder),
+	("writing_application", ProvenanceSource::Muxer),
+	("muxing_application", ProvenanceSource::Muxer),
+	("software", ProvenanceSource::Software),
+	("com.apple.quicktime.software", ProvenanceSource::Software),
+	("creation_tool", ProvenanceSource::Software),
+	("com.apple.quicktime.make", ProvenanceSource::Device),
+	("com.apple.quicktime.model", ProvenanceSource::Device),
+	("com.android.manufacturer", ProvenanceSource::Device),
+	("com.android.model", ProvenanceSource::Device),
+	("major_brand", ProvenanceSource::Brand),
+	("minor_version", ProvenanceSource::Brand),
+	("compatible_brands", ProvenanceSource::Brand),
+];
+
+const STREAM: &[(&str, ProvenanceSource)] = &[
+	("encoder", ProvenanceSource::Encoder),
+	("handler_name", ProvenanceSource::Handler),
+	("vendor_id", ProvenanceSource::Vendor),
+	("_STATISTICS_WRITING_APP", ProvenanceSource::Muxer),
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Provenance {
+	pub entries: Vec<ProvenanceEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProvenanceEntry {
+	pub source: ProvenanceSource,
+	pub stream: Option<usize>,
+	pub key: String,
+	pub value: String,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ProvenanceSource {
+	Encoder,
+	Muxer,
+	Software,
+	Device,
+	Brand,
+	Handler,
+	Vendor,
+	Sei,
+}
+
+impl Provenance {
+	pub fn new(input: &Input) -> Self {
+		let mut provenance = Provenance::default();
+		let format = input.metadata();
+
+		for (key, source) in FORMAT {
+			if let Some(value) = format.get(key) {
+				provenance.push(*source, None, key, value);
+			}
+		}
+
+		for stream in input.streams() {
+			let metadata = stream.metadata();
+
+			for (key, source) in STREAM {
+				if let Some(value) = metadata.get(key) {
+					provenance.push(*source, Some(stream.index()), key, value);
+				}
+			}
+		}
+
+		provenance
+	}
+
+	pub fn encoders(&self) -> impl Iterator<Item = &str> {
+		self.entries
+			.iter()
+			.filter(|entry| matches!(entry.source, ProvenanceSource::Encoder | ProvenanceSource::Sei))
+			.map(|entry| entry.value.as_str())
+	}
+
+	// Looks for the unregistered user data SEI most encoders use to sign
+	// their output, x264 and x265 also dump their settings there.
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(&mut self, input: &mut Input, options: &analysis::Options) -> ffmpeg::Result<()> {
+		let videos = input
+			.streams()
+			.filter(|stream| compat::medium(stream) == media::Type::Video)
+			.map(|stream| {
+				let parameters = stream.parameters();
+				let hevc = parameters.id() == codec::Id::HEVC;
+
+				(stream.index(), parameters.id(), nal::length_size(raw::extradata(&parameters), hevc))
+			})
+			.collect::<Vec<_>>();
+
+		let mut options = options.clone();
+		options.max_frames = Some(options.max_frames.map_or(PACKETS, |max| max.min(PACKETS)));
+
+		for (index, id, length) in videos {
+			if id != codec::Id::H264 && id != codec::Id::HEVC {
+				continue;
+			}
+
+			let mut found = Vec::new();
+			analysis::packets(input, index, &options, |packet, _| {
+				for payload in packet
+					.data()
+					.map(|data| sei::payloads(data, length, id == codec::Id::HEVC))
+					.unwrap_or_default()
+				{
+					for (kind, message) in sei::messages(&payload) {
+						if kind == 5 && message.len() > 16 {
+							let text = String::from_utf8_lossy(&message[16..]);
+							let text = text.trim_end_matches('\0').trim();
+
+							if !text.is_empty() && text.chars().all(|c| !c.is_control()) {
+								found.push(text.to_owned());
+							}
+						}
+					}
+				}
+
+				Ok(())
+			})?;
+
+			for text in found {
+				self.push(ProvenanceSource::Sei, Some(index), "user_data_unregistered", &text);
+			}
+		}
+
+		Ok(())
+	}
+
+	fn push(&mut self, source: ProvenanceSource, stream: Option<usize>, key: &str, value: &str) {
+		let entry = ProvenanceEntry {
+			source,
+			stream,
+			key: key.into(),
+			value: value.into(),
+		};
+
+		if !value.trim().is_empty() && !self.entries.contains(&entry) {
+			self.entries.push(entry);
+		}
+	}
+}
@@ -0,0 +1,160 @@
+use pyo3::{exceptions::PyOSError, prelude::*};
+
+use crate::{Content, Metadata, Stream};
+
+fn error(error: ffmpeg::Error) -> PyErr {
+	PyOSError::new_err(error.to_string())
+}
+
+fn load(path: &str) -> PyResult<Metadata> {
+	Metadata::probe(path).map_err(error)
+}
+
+#[pyclass(name = "Metadata")]
+#[derive(Clone)]
+struct PyMetadata {
+	inner: Metadata,
+}
+
+#[pymethods]
+impl PyMetadata {
+	#[getter]
+	fn format(&self) -> &str {
+		&self.inner.format.name
+	}
+
+	#[getter]
+	fn duration(&self) -> Option<f64> {
+		self.inner.duration_seconds()
+	}
+
+	#[getter]
+	fn bit_rate(&self) -> usize {
+		self.inner.bit_rate
+	}
+
+	#[getter]
+	fn streams(&self) -> Vec<PyStream> {
+		self.inner.streams.iter().cloned().map(|inner| PyStream { inner }).collect()
+	}
+
+	#[getter]
+	fn tags(&self) -> std::collections::HashMap<String, String> {
+		self.inner.details.clone()
+	}
+
+	fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+		pythonize::pythonize(py, &self.inner).map_err(PyErr::from)
+	}
+
+	fn __str__(&self) -> String {
+		self.inner.to_string()
+	}
+
+	fn __repr__(&self) -> String {
+		format!("<Metadata format={:?} streams={}>", self.inner.format.name, self.inner.streams.len())
+	}
+}
+
+#[pyclass(name = "Stream")]
+#[derive(Clone)]
+struct PyStream {
+	inner: Stream,
+}
+
+#[pymethods]
+impl PyStream {
+	#[getter]
+	fn index(&self) -> usize {
+		self.inner.index
+	}
+
+	#[getter]
+	fn kind(&self) -> &'static str {
+		self.inner.content.kind()
+	}
+
+	#[getter]
+	fn codec(&self) -> Option<&str> {
+		self.inner.content.codec().map(|codec| codec.name.as_str())
+	}
+
+	#[getter]
+	fn duration(&self) -> Option<f64> {
+		self.inner.duration_seconds()
+	}
+
+	#[getter]
+	fn language(&self) -> Option<&str> {
+		self.inner.language.as_ref().map(|language| language.code.as_str())
+	}
+
+	#[getter]
+	fn fps(&self) -> Option<f64> {
+		self.inner.fps()
+	}
+
+	#[getter]
+	fn width(&self) -> Option<u32> {
+		match &self.inner.content {
+			Content::Video(video) => Some(video.width),
+			_ => None,
+		}
+	}
+
+	#[getter]
+	fn height(&self) -> Option<u32> {
+		match &self.inner.content {
+			Content::Video(video) => Some(video.height),
+			_ => None,
+		}
+	}
+
+	#[getter]
+	fn sample_rate(&self) -> Option<u32> {
+		match &self.inner.content {
+			Content::Audio(audio) => Some(audio.sample_rate),
+			_ => None,
+		}
+	}
+
+	#[getter]
+	fn channels(&self) -> Option<u16> {
+		match &self.inner.content {
+			Content::Audio(audio) => Some(audio.channels),
+			_ => None,
+		}
+	}
+
+	fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+		pythonize::pythonize(py, &self.inner).map_err(PyErr::from)
+	}
+
+	fn __repr__(&self) -> String {
+		format!("<Stream index={} kind={:?}>", self.inner.index, self.kind())
+	}
+}
+
+/// Probes the file or URL at `path` and returns the metadata as a `dict`.
+#[pyfunction]
+fn probe(py: Python, path: &str) -> PyResult<PyObject> {
+	let metadata = py.allow_threads(|| load(path))?;
+	pythonize::pythonize(py, &metadata).map_err(PyErr::from)
+}
+
+/// Probes the file or URL at `path` and returns a typed `Metadata`.
+#[pyfunction]
+fn open(py: Python, path: &str) -> PyResult<PyMetadata> {
+	let inner = py.allow_threads(|| load(path))?;
+	Ok(PyMetadata { inner })
+}
+
+#[pymodule]
+fn avmetadata(_py: Python, module: &PyModule) -> PyResult<()> {
+	module.add_function(wrap_pyfunction!(probe, module)?)?;
+	module.add_function(wrap_pyfunction!(open, module)?)?;
+	module.add_class::<PyMetadata>()?;
+	module.add_class::<PyStream>()?;
+
+	Ok(())
+}
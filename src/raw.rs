@@ -0,0 +1,202 @@
+use std::{ffi::CStr, ptr, slice};
+use ffmpeg::{
+	codec, ffi,
+	format::{
+		stream::{Disposition, StreamMut},
+		Pixel,
+	},
+	media,
+};
+
+pub fn extradata(parameters: &codec::Parameters) -> &[u8] {
+	unsafe {
+		let parameters = parameters.as_ptr();
+
+		if (*parameters).extradata.is_null() || (*parameters).extradata_size <= 0 {
+			&[]
+		}
+		else {
+			slice::from_raw_parts((*parameters).extradata, (*parameters).extradata_size as usize)
+		}
+	}
+}
+
+pub fn field_order(parameters: &codec::Parameters) -> ffi::AVFieldOrder {
+	unsafe { (*parameters.as_ptr()).field_order }
+}
+
+pub fn profile(parameters: &codec::Parameters) -> i32 {
+	unsafe { (*parameters.as_ptr()).profile }
+}
+
+pub fn profile_name(id: codec::Id, profile: i32) -> Option<String> {
+	unsafe {
+		let name = ffi::avcodec_profile_name(id.into(), profile);
+
+		if name.is_null() {
+			None
+		}
+		else {
+			Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+		}
+	}
+}
+
+pub fn codec_description(id: codec::Id) -> Option<String> {
+	unsafe {
+		let descriptor = ffi::avcodec_descriptor_get(id.into());
+
+		if descriptor.is_null() || (*descriptor).long_name.is_null() {
+			None
+		}
+		else {
+			Some(CStr::from_ptr((*descriptor).long_name).to_string_lossy().into_owned())
+		}
+	}
+}
+
+pub fn side_data_name(kind: ffi::AVPacketSideDataType) -> String {
+	unsafe {
+		let name = ffi::av_packet_side_data_name(kind);
+
+		if name.is_null() {
+			format!("{:?}", kind)
+		}
+		else {
+			CStr::from_ptr(name).to_string_lossy().into_owned()
+		}
+	}
+}
+
+pub fn bits_per_raw_sample(parameters: &codec::Parameters) -> Option<u8> {
+	match unsafe { (*parameters.as_ptr()).bits_per_raw_sample } {
+		bits if bits > 0 => Some(bits as u8),
+		_ => None,
+	}
+}
+
+pub fn depth(format: Pixel) -> Option<u8> {
+	unsafe {
+		let descriptor = ffi::av_pix_fmt_desc_get(format.into());
+
+		if descriptor.is_null() || (*descriptor).nb_components == 0 {
+			None
+		}
+		else {
+			Some((*descriptor).comp[0].depth as u8)
+		}
+	}
+}
+
+pub fn sample_rate(parameters: &codec::Parameters) -> u32 {
+	unsafe { (*parameters.as_ptr()).sample_rate.max(0) as u32 }
+}
+
+pub fn bit_rate(parameters: &codec::Parameters) -> i64 {
+	unsafe { (*parameters.as_ptr()).bit_rate }
+}
+
+pub fn initial_padding(parameters: &codec::Parameters) -> i32 {
+	unsafe { (*parameters.as_ptr()).initial_padding }
+}
+
+pub fn trailing_padding(parameters: &codec::Parameters) -> i32 {
+	unsafe { (*parameters.as_ptr()).trailing_padding }
+}
+
+pub fn seek_preroll(parameters: &codec::Parameters) -> i32 {
+	unsafe { (*parameters.as_ptr()).seek_preroll }
+}
+
+pub fn codec_tag(parameters: &codec::Parameters) -> [u8; 4] {
+	unsafe { (*parameters.as_ptr()).codec_tag.to_le_bytes() }
+}
+
+pub fn url(input: &ffmpeg::format::context::Input) -> Option<String> {
+	unsafe {
+		let url = (*input.as_ptr()).url;
+
+		if url.is_null() {
+			None
+		}
+		else {
+			Some(CStr::from_ptr(url).to_string_lossy().into_owned())
+		}
+	}
+}
+
+pub fn duration_estimation(
+	input: &ffmpeg::format::context::Input,
+) -> ffi::AVDurationEstimationMethod {
+	unsafe { (*input.as_ptr()).duration_estimation_method }
+}
+
+// The size of the file behind the input, when the protocol knows it.
+pub fn size(input: &ffmpeg::format::context::Input) -> Option<u64> {
+	unsafe {
+		let pb = (*input.as_ptr()).pb;
+
+		if pb.is_null() {
+			None
+		}
+		else {
+			Some(ffi::avio_size(pb)).filter(|&size| size > 0).map(|size| size as u64)
+		}
+	}
+}
+
+// Not every demuxer can seek by byte offset, and those that can resync on
+// the next packet they find.
+pub fn seek_bytes(input: &mut ffmpeg::format::context::Input, position: u64) -> bool {
+	unsafe {
+		let position = position as i64;
+		let flags = ffi::AVSEEK_FLAG_BYTE as i32;
+
+		ffi::avformat_seek_file(input.as_mut_ptr(), -1, i64::MIN, position, i64::MAX, flags) >= 0
+	}
+}
+
+pub fn set_codec(stream: &mut StreamMut, medium: media::Type, id: codec::Id) {
+	unsafe {
+		let parameters = (*stream.as_mut_ptr()).codecpar;
+
+		(*parameters).codec_type = medium.into();
+		(*parameters).codec_id = id.into();
+	}
+}
+
+// Muxers pick their own tag, the one from the source container is often not
+// valid in the destination.
+pub fn clear_codec_tag(stream: &mut StreamMut) {
+	unsafe {
+		(*(*stream.as_mut_ptr()).codecpar).codec_tag = 0;
+	}
+}
+
+pub fn set_disposition(stream: &mut StreamMut, disposition: Disposition) {
+	unsafe {
+		(*stream.as_mut_ptr()).disposition = disposition.bits();
+	}
+}
+
+pub fn set_extradata(stream: &mut StreamMut, data: &[u8]) {
+	unsafe {
+		let parameters = (*stream.as_mut_ptr()).codecpar;
+		let size = data.len() + ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize;
+		let extradata = ffi::av_mallocz(size) as *mut u8;
+
+		ffi::av_freep(&mut (*parameters).extradata as *mut *mut u8 as *mut _);
+		(*parameters).extradata_size = 0;
+
+		if !extradata.is_null() {
+			ptr::copy_nonoverlapping(data.as_ptr(), extradata, data.len());
+			(*parameters).extradata = extradata;
+			(*parameters).extradata_size = data.len() as i32;
+		}
+	}
+}
+
+#[cfg(feature = "hash")]
+pub fn linesize(format: Pixel, width: u32, plane: usize) -> usize {
+	unsafe { ffi::av_image_get_linesize(format.into(), width as i32, plane as i32).max(0) as usize }
+}
@@ -0,0 +1,342 @@
+use std::{collections::HashMap, mem};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EditionChapter, ItunesValue, MatroskaTag, Metadata, SimpleTag, Warning};
+
+const MASK: &str = "[redacted]";
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Redaction {
+	Keep,
+	Remove,
+	// Keeps what's harmless, see the policy for what that is per category.
+	Mask,
+}
+
+// What to strip from the metadata of files that get republished.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RedactionPolicy {
+	// GPS coordinates, masked down to a tenth of a degree (about 10 km).
+	pub location: Redaction,
+	// Make, model, lens and serial numbers of the recording device, masked
+	// entirely.
+	pub device: Redaction,
+	// Encoder and software tags, masked by dropping paths, URLs and
+	// `user@host` names so the tool name is left. FFmpeg's log messages get
+	// the same treatment, they quote the input URL and the paths it refers to.
+	pub software: Redaction,
+	// Creation and modification times, masked down to the day.
+	pub timestamps: Redaction,
+	// Further tag names to remove, case insensitive.
+	pub keys: Vec<String>,
+}
+
+impl Default for RedactionPolicy {
+	fn default() -> Self {
+		RedactionPolicy {
+			location: Redaction::Remove,
+			device: Redaction::Remove,
+			software: Redaction::Mask,
+			timestamps: Redaction::Mask,
+			keys: Vec::new(),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Kind {
+	Location,
+	Device,
+	Software,
+	Timestamp,
+}
+
+impl RedactionPolicy {
+	pub fn apply(&self, metadata: &mut Metadata) {
+		self.tags(&mut metadata.details);
+
+		for chapter in &mut metadata.chapters {
+			self.tags(&mut chapter.details);
+		}
+
+		let entries = mem::take(&mut metadata.provenance.entries);
+		metadata.provenance.entries = entries
+			.into_iter()
+			.filter_map(|mut entry| {
+				let keep = self.redact(&entry.key, &mut entry.value);
+				keep.then(|| entry)
+			})
+			.collect();
+
+		match self.location {
+			Redaction::Keep => (),
+			Redaction::Remove => metadata.location = None,
+			Redaction::Mask => {
+				if let Some(location) = &mut metadata.location {
+					location.latitude = (location.latitude * 10.0).round() / 10.0;
+					location.longitude = (location.longitude * 10.0).round() / 10.0;
+					location.altitude = None;
+					location.raw = mask(Kind::Location, &location.raw);
+				}
+			}
+		}
+
+		if let Some(exif) = &mut metadata.exif {
+			self.field(Kind::Device, &mut exif.make);
+			self.field(Kind::Device, &mut exif.model);
+			self.field(Kind::Device, &mut exif.lens);
+			self.field(Kind::Software, &mut exif.software);
+			self.field(Kind::Timestamp, &mut exif.date_time);
+		}
+
+		let bext = metadata.broadcast.as_mut().and_then(|broadcast| broadcast.bext.as_mut());
+		if let Some(bext) = bext {
+			// The reference and UMID usually embed the recorder's serial number.
+			self.field(Kind::Device, &mut bext.originator);
+			self.field(Kind::Device, &mut bext.originator_reference);
+			self.field(Kind::Device, &mut bext.umid);
+			self.field(Kind::Timestamp, &mut bext.origination_date);
+
+			if self.timestamps != Redaction::Keep {
+				bext.origination_time = None;
+			}
+		}
+
+		if let Some(itunes) = &mut metadata.itunes {
+			let items = mem::take(&mut itunes.items);
+			itunes.items = items
+				.into_iter()
+				.filter_map(|mut item| {
+					let key = &item.key;
+					let keep = item.values.iter_mut().all(|value| match value {
+						ItunesValue::Text(text) => self.redact(key, text),
+						_ => self.rule(key).is_none(),
+					});
+
+					keep.then(|| item)
+				})
+				.collect();
+		}
+
+		if let Some(matroska) = &mut metadata.matroska {
+			self.matroska_tags(&mut matroska.tags);
+
+			for edition in &mut matroska.editions {
+				self.matroska_tags(&mut edition.tags);
+				self.edition_chapters(&mut edition.chapters);
+			}
+		}
+
+		if let Some(ogg) = &mut metadata.ogg {
+			for link in &mut ogg.links {
+				self.tags(&mut link.comments);
+				self.field(Kind::Software, &mut link.vendor);
+			}
+		}
+
+		// Private frames are opaque, what's in them is up to the tool or device
+		// that wrote them.
+		if let Some(id3) = &mut metadata.id3 {
+			let keep = self.device == Redaction::Keep;
+			id3.private.retain(|frame| keep && self.rule(&frame.owner).is_none());
+		}
+
+		let warnings = mem::take(&mut metadata.warnings);
+		metadata.warnings = warnings
+			.into_iter()
+			.filter_map(|mut warning| {
+				if let Warning::Log(log) = &mut warning {
+					match self.software {
+						Redaction::Keep => (),
+						Redaction::Remove if log.message.split_whitespace().any(private) => {
+							return None;
+						}
+						_ => log.message = scrub(&log.message),
+					}
+				}
+
+				Some(warning)
+			})
+			.collect();
+
+		#[cfg(feature = "chrono")]
+		{
+			metadata.created = self.time(metadata.created);
+			metadata.modified = self.time(metadata.modified);
+		}
+	}
+
+	#[cfg(feature = "chrono")]
+	fn time(
+		&self,
+		time: Option<chrono::DateTime<chrono::Utc>>,
+	) -> Option<chrono::DateTime<chrono::Utc>> {
+		use chrono::Timelike;
+
+		match self.timestamps {
+			Redaction::Keep => time,
+			Redaction::Remove => None,
+			Redaction::Mask => {
+				time?.with_hour(0)?.with_minute(0)?.with_second(0)?.with_nanosecond(0)
+			}
+		}
+	}
+
+	fn tags(&self, tags: &mut HashMap<String, String>) {
+		tags.retain(|key, value| self.redact(key, value));
+	}
+
+	fn matroska_tags(&self, tags: &mut [MatroskaTag]) {
+		for tag in tags {
+			tag.tags = self.simple_tags(mem::take(&mut tag.tags));
+		}
+	}
+
+	fn edition_chapters(&self, chapters: &mut [EditionChapter]) {
+		for chapter in chapters {
+			self.matroska_tags(&mut chapter.tags);
+			self.edition_chapters(&mut chapter.chapters);
+		}
+	}
+
+	fn simple_tags(&self, tags: Vec<SimpleTag>) -> Vec<SimpleTag> {
+		tags
+			.into_iter()
+			.filter_map(|mut tag| {
+				tag.children = self.simple_tags(mem::take(&mut tag.children));
+
+				let keep = match &mut tag.value {
+					Some(value) => self.redact(&tag.name, value),
+					None => !matches!(self.rule(&tag.name), Some((_, Redaction::Remove))),
+				};
+
+				keep.then(|| tag)
+			})
+			.collect()
+	}
+
+	fn field(&self, kind: Kind, value: &mut Option<String>) {
+		match self.redaction(kind) {
+			Redaction::Keep => (),
+			Redaction::Remove => *value = None,
+			Redaction::Mask => *value = value.as_deref().map(|value| mask(kind, value)),
+		}
+	}
+
+	// Masks `value` in place, false when the tag should go entirely.
+	fn redact(&self, key: &str, value: &mut String) -> bool {
+		match self.rule(key) {
+			None => true,
+			Some((Some(kind), Redaction::Mask)) => {
+				*value = mask(kind, value);
+				true
+			}
+			Some(_) => false,
+		}
+	}
+
+	// The category of a tag and what to do with it, `None` for tags left
+	// alone. Tags listed in `keys` have no category.
+	fn rule(&self, key: &str) -> Option<(Option<Kind>, Redaction)> {
+		if self.keys.iter().any(|listed| listed.eq_ignore_ascii_case(key)) {
+			return Some((None, Redaction::Remove));
+		}
+
+		let kind = kind(&key.to_lowercase())?;
+		let redaction = self.redaction(kind);
+
+		Some((Some(kind), redaction)).filter(|_| redaction != Redaction::Keep)
+	}
+
+	fn redaction(&self, kind: Kind) -> Redaction {
+		match kind {
+			Kind::Location => self.location,
+			Kind::Device => self.device,
+			Kind::Software => self.software,
+			Kind::Timestamp => self.timestamps,
+		}
+	}
+}
+
+// Tag names across containers: FFmpeg's generic ones, QuickTime and Android
+// keys, iTunes atoms and Matroska simple tags, all lowercased.
+fn kind(key: &str) -> Option<Kind> {
+	let any = |words: &[&str]| words.iter().any(|word| key.contains(word));
+
+	if any(&["location", "gps", "©xyz"]) {
+		Some(Kind::Location)
+	}
+	else if any(&[
+		"make", "model", "serial", "manufacturer", "lens", "camera", "device", "©mak", "©mod",
+	]) {
+		Some(Kind::Device)
+	}
+	else if any(&[
+		"encoder", "encoded_by", "encoding_tool", "software", "writing_app", "©too", "©swr",
+	]) {
+		Some(Kind::Software)
+	}
+	else if any(&["date", "creation_time", "timestamp", "©day"]) {
+		Some(Kind::Timestamp)
+	}
+	else {
+		None
+	}
+}
+
+fn mask(kind: Kind, value: &str) -> String {
+	match kind {
+		Kind::Location => coarsen(value),
+		Kind::Device => MASK.into(),
+		Kind::Software => {
+			value.split_whitespace().filter(|word| !private(word)).collect::<Vec<_>>().join(" ")
+		}
+		// `2021-03-04T12:34:56Z`, `2021:03:04 12:34:56` and the like.
+		Kind::Timestamp => match value.get(..10) {
+			Some(day) if day.chars().filter(char::is_ascii_digit).count() == 8 => day.into(),
+			_ => MASK.into(),
+		},
+	}
+}
+
+// Paths, URLs and `user@host` names, possibly quoted.
+fn private(word: &str) -> bool {
+	let word = word.trim_start_matches(['\'', '"', '('].as_ref());
+
+	word.contains('@')
+		|| word.contains('\\')
+		|| word.contains("://")
+		|| word.starts_with(['/', '~'].as_ref())
+}
+
+// Masks the private words of a sentence, keeping the rest of it readable.
+fn scrub(message: &str) -> String {
+	message
+		.split(' ')
+		.map(|word| if private(word) { MASK } else { word })
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+// Keeps a single decimal of every number, for ISO 6709 strings and their
+// relatives.
+fn coarsen(value: &str) -> String {
+	let mut decimals = None;
+
+	value
+		.chars()
+		.filter(|&c| {
+			decimals = match (c, decimals) {
+				('.', _) => Some(0),
+				(c, Some(count)) if c.is_ascii_digit() => Some(count + 1),
+				_ => None,
+			};
+
+			decimals.map_or(true, |count| count <= 1)
+		})
+		.collect()
+}
@@ -0,0 +1,187 @@
+use std::{
+	ffi::{CStr, CString},
+	os::raw::c_int,
+};
+
+use ffmpeg::{codec, ffi};
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, Metadata};
+
+// The tags the MP4 muxer writes as iTunes atoms, anything else is dropped
+// unless `-movflags use_metadata_tags` is given.
+const MP4_TAGS: &[&str] = &[
+	"album", "album_artist", "artist", "author", "category", "comment", "compilation", "composer",
+	"copyright", "date", "description", "disc", "encoding_tool", "episode_id", "episode_sort",
+	"gapless_playback", "genre", "grouping", "hd_video", "keywords", "lyrics", "media_type",
+	"network", "podcast", "season_number", "show", "sort_album", "sort_album_artist",
+	"sort_artist", "sort_composer", "sort_name", "sort_show", "synopsis", "title", "track",
+];
+
+// Written by every muxer on its own, losing the input's is expected.
+const REGENERATED: &[&str] =
+	&["compatible_brands", "creation_time", "encoder", "major_brand", "minor_version"];
+
+const CHAPTERS: &[&str] =
+	&["ffmetadata", "ipod", "matroska", "mov", "mp3", "mp4", "ogg", "opus", "webm"];
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum RemuxAction {
+	Copy,
+	Transcode,
+	Drop,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StreamPlan {
+	pub index: usize,
+	pub kind: String,
+	pub codec: Option<String>,
+	pub action: RemuxAction,
+	// The muxer's default encoder for the kind of stream.
+	pub target: Option<String>,
+	pub reason: Option<String>,
+}
+
+// What converting to another container would do, according to the muxer's
+// own codec list.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RemuxPreview {
+	pub format: String,
+	pub streams: Vec<StreamPlan>,
+	pub lost_tags: Vec<String>,
+	pub lost_chapters: usize,
+}
+
+impl RemuxPreview {
+	// Takes a muxer name, `mkv` being accepted for `matroska`.
+	pub fn new(metadata: &Metadata, format: &str) -> ffmpeg::Result<Self> {
+		let name = if format == "mkv" { "matroska" } else { format };
+		let short = CString::new(name).map_err(|_| ffmpeg::Error::InvalidData)?;
+		let output =
+			unsafe { ffi::av_guess_format(short.as_ptr(), std::ptr::null(), std::ptr::null()) };
+
+		if output.is_null() {
+			return Err(ffmpeg::Error::MuxerNotFound);
+		}
+
+		let matroska = matches!(name, "matroska" | "webm");
+		let default = |id: ffi::AVCodecID| {
+			Some(id).filter(|&id| id != ffi::AVCodecID::AV_CODEC_ID_NONE).and_then(|id| unsafe {
+				let descriptor = ffi::avcodec_descriptor_get(id);
+				Some(descriptor)
+					.filter(|descriptor| !descriptor.is_null())
+					.map(|descriptor| CStr::from_ptr((*descriptor).name).to_string_lossy().into())
+			})
+		};
+
+		let streams = metadata
+			.streams
+			.iter()
+			.map(|stream| {
+				let mut plan = StreamPlan {
+					index: stream.index,
+					kind: stream.content.kind().into(),
+					codec: stream.content.codec().map(|codec| codec.name.clone()),
+					action: RemuxAction::Copy,
+					target: None,
+					reason: None,
+				};
+
+				let target = unsafe {
+					match &stream.content {
+						Content::Audio(_) => (*output).audio_codec,
+						Content::Video(_) => (*output).video_codec,
+						Content::Subtitle(_) => (*output).subtitle_codec,
+						_ => ffi::AVCodecID::AV_CODEC_ID_NONE,
+					}
+				};
+
+				match (&stream.content, stream.content.codec()) {
+					(Content::Attachment(_), _) if matroska => (),
+
+					(Content::Attachment(_), _) | (Content::Data(_), _) | (Content::Unknown(_), _) => {
+						plan.action = RemuxAction::Drop;
+						plan.reason = Some(format!("{} streams are not carried over", plan.kind));
+					}
+
+					(_, Some(codec)) => match query(output, codec.id) {
+						1 => (),
+
+						0 if bitmap(codec.id) && target != ffi::AVCodecID::AV_CODEC_ID_NONE => {
+							plan.action = RemuxAction::Drop;
+							plan.reason = Some("bitmap subtitles can't be converted to text".into());
+						}
+
+						0 => {
+							plan.target = default(target);
+							plan.action = if plan.target.is_some() {
+								RemuxAction::Transcode
+							}
+							else {
+								RemuxAction::Drop
+							};
+							plan.reason = Some(format!("{} is not allowed in {}", codec.name, name));
+						}
+
+						_ => {
+							plan.reason = Some(format!("{} doesn't list what it accepts", name));
+						}
+					},
+
+					(_, None) => {
+						plan.action = RemuxAction::Drop;
+						plan.reason = Some("unknown codec".into());
+					}
+				}
+
+				plan
+			})
+			.collect();
+
+		let mut lost_tags = if matches!(name, "mp4" | "mov" | "ipod") {
+			metadata
+				.details
+				.keys()
+				.map(|key| key.to_lowercase())
+				.filter(|key| !MP4_TAGS.contains(&key.as_str()))
+				.filter(|key| !REGENERATED.contains(&key.as_str()))
+				.collect::<Vec<_>>()
+		}
+		else {
+			Vec::new()
+		};
+
+		lost_tags.sort();
+
+		Ok(RemuxPreview {
+			format: name.into(),
+			streams,
+			lost_tags,
+			lost_chapters: if CHAPTERS.contains(&name) { 0 } else { metadata.chapters.len() },
+		})
+	}
+
+	pub fn is_lossless(&self) -> bool {
+		self.streams.iter().all(|stream| stream.action == RemuxAction::Copy)
+			&& self.lost_tags.is_empty()
+			&& self.lost_chapters == 0
+	}
+}
+
+// One when the muxer takes the codec, zero when it doesn't and negative when
+// it can't tell.
+fn query(output: *const ffi::AVOutputFormat, id: codec::Id) -> c_int {
+	unsafe { ffi::avformat_query_codec(output, id.into(), ffi::FF_COMPLIANCE_NORMAL as c_int) }
+}
+
+fn bitmap(id: codec::Id) -> bool {
+	unsafe {
+		let descriptor = ffi::avcodec_descriptor_get(id.into());
+		!descriptor.is_null() && (*descriptor).props & ffi::AV_CODEC_PROP_BITMAP_SUB as c_int != 0
+	}
+}
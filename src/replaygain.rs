@@ -0,0 +1,129 @@
+use ffmpeg::format::{context::Input, stream::Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::tags::itunes;
+
+// Opus R128 gains are relative to -23 LUFS, ReplayGain to -18.
+const R128_OFFSET: f64 = 5.0;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ReplayGain {
+	pub source: ReplayGainSource,
+	pub track: Option<Gain>,
+	pub album: Option<Gain>,
+	// Loudness in dB SPL the gains were computed against, when not the usual 89.
+	pub reference: Option<f64>,
+}
+
+// Gains in dB to apply on playback, peaks as linear sample values where 1.0
+// is full scale.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Gain {
+	pub gain: f64,
+	pub peak: Option<f64>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplayGainSource {
+	ReplayGain,
+	R128,
+	SoundCheck,
+}
+
+impl Gain {
+	pub fn scale(&self) -> f64 {
+		10f64.powf(self.gain / 20.0)
+	}
+
+	pub fn peak_db(&self) -> Option<f64> {
+		self.peak.filter(|&peak| peak > 0.0).map(|peak| 20.0 * peak.log10())
+	}
+
+	// Scale that applies the gain without pushing the peak past full scale.
+	pub fn safe_scale(&self) -> f64 {
+		match self.peak.filter(|&peak| peak > 0.0) {
+			Some(peak) => self.scale().min(1.0 / peak),
+			None => self.scale(),
+		}
+	}
+}
+
+impl ReplayGain {
+	// Vorbis comments end up on the stream for Ogg and on the container for
+	// everything else, ID3 TXXX and MP4 freeform atoms are keyed by their
+	// description. Lookups ignore case.
+	pub fn find(input: &Input, stream: &Stream) -> Option<Self> {
+		let metadata = stream.metadata();
+		let global = input.metadata();
+		let get = |key: &str| metadata.get(key).or_else(|| global.get(key)).map(str::to_owned);
+
+		let gain = |gain: &str, peak: &str| {
+			Some(Gain {
+				gain: decibels(&get(gain)?)?,
+				peak: get(peak).and_then(|peak| peak.trim().parse().ok()),
+			})
+		};
+
+		let track = gain("REPLAYGAIN_TRACK_GAIN", "REPLAYGAIN_TRACK_PEAK");
+		let album = gain("REPLAYGAIN_ALBUM_GAIN", "REPLAYGAIN_ALBUM_PEAK");
+
+		if track.is_some() || album.is_some() {
+			return Some(ReplayGain {
+				source: ReplayGainSource::ReplayGain,
+				track,
+				album,
+				reference: get("REPLAYGAIN_REFERENCE_LOUDNESS").and_then(|value| decibels(&value)),
+			});
+		}
+
+		// Q7.8 fixed point, with no peak information.
+		let r128 = |key: &str| {
+			let value: i16 = get(key)?.trim().parse().ok()?;
+
+			Some(Gain {
+				gain: f64::from(value) / 256.0 + R128_OFFSET,
+				peak: None,
+			})
+		};
+
+		let track = r128("R128_TRACK_GAIN");
+		let album = r128("R128_ALBUM_GAIN");
+
+		if track.is_some() || album.is_some() {
+			return Some(ReplayGain {
+				source: ReplayGainSource::R128,
+				track,
+				album,
+				reference: None,
+			});
+		}
+
+		let norm = get("iTunNORM").as_deref().and_then(itunes::norm)?;
+
+		Some(ReplayGain {
+			source: ReplayGainSource::SoundCheck,
+			track: Some(Gain {
+				gain: norm.gain,
+				peak: Some(norm.peak),
+			}),
+			album: None,
+			reference: None,
+		})
+	}
+}
+
+// Values are written as `-6.54 dB`, with the unit in any case or missing.
+fn decibels(value: &str) -> Option<f64> {
+	let value = value.trim();
+	let value = value
+		.strip_suffix("dB")
+		.or_else(|| value.strip_suffix("db"))
+		.or_else(|| value.strip_suffix("DB"))
+		.unwrap_or(value);
+
+	value.trim().trim_start_matches('+').parse().ok()
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use ffmpeg::ChannelLayout;
+
+use crate::{Audio, Content, Metadata, Stream, Subtitle, Video};
+
+// Width of the label column, chosen to fit the longest label.
+const LABEL: usize = 18;
+
+impl fmt::Display for Metadata {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "General")?;
+		field(f, "Format", format_args!("{} ({})", self.format.name, self.format.description))?;
+
+		if let Some(duration) = self.duration_seconds() {
+			field(f, "Duration", Time(duration))?;
+		}
+
+		if self.elementary.is_some() {
+			field(f, "Structure", "Elementary stream, duration estimated")?;
+		}
+
+		if self.bit_rate > 0 {
+			field(f, "Overall bit rate", BitRate(self.bit_rate))?;
+		}
+
+		field(f, "Streams", self.streams.len())?;
+
+		if let Some(title) = self.details.get("title") {
+			field(f, "Title", title)?;
+		}
+
+		if let Some(timecode) = &self.timecode {
+			field(f, "Timecode", &timecode.value)?;
+		}
+
+		for stream in &self.streams {
+			writeln!(f)?;
+			write!(f, "{}", stream)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for Stream {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let kind = match &self.content {
+			Content::Unknown(_) => "Unknown",
+			Content::Audio(_) => "Audio",
+			Content::Video(_) => "Video",
+			Content::Data(_) => "Data",
+			Content::Subtitle(_) => "Subtitle",
+			Content::Attachment(_) => "Attachment",
+		};
+
+		writeln!(f, "{} #{}", kind, self.index)?;
+
+		if self.is_empty {
+			field(f, "Packets", "none")?;
+		}
+
+		match &self.content {
+			Content::Video(video) => self.video(f, video)?,
+			Content::Audio(audio) => self.audio(f, audio)?,
+			Content::Subtitle(Subtitle { codec }) => field(f, "Codec", &codec.description)?,
+			_ => (),
+		}
+
+		if let Some(duration) = self.duration_seconds() {
+			field(f, "Duration", Time(duration))?;
+		}
+
+		if let Some(language) = &self.language {
+			match &language.name {
+				Some(name) => field(f, "Language", format_args!("{} ({})", name, language.code))?,
+				None => field(f, "Language", &language.code)?,
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Stream {
+	fn video(&self, f: &mut fmt::Formatter, video: &Video) -> fmt::Result {
+		field(f, "Codec", Codec(&video.codec))?;
+
+		match video.display_aspect_ratio() {
+			Some(dar) => field(
+				f,
+				"Resolution",
+				format_args!(
+					"{}x{} ({}:{})",
+					video.width,
+					video.height,
+					dar.numerator(),
+					dar.denominator()
+				),
+			)?,
+
+			None => field(f, "Resolution", format_args!("{}x{}", video.width, video.height))?,
+		}
+
+		if let Some(fps) = self.fps() {
+			field(f, "Frame rate", format_args!("{:.3} fps", fps))?;
+		}
+
+		if let Some(depth) = video.bit_depth {
+			field(f, "Bit depth", format_args!("{} bits", depth))?;
+		}
+
+		field(f, "Pixel format", format_args!("{:?}", video.format))?;
+		field(
+			f,
+			"Color",
+			format_args!(
+				"{:?} / {:?} / {:?} / {:?}",
+				video.color_space,
+				video.color_range,
+				video.color_primaries,
+				video.color_transfer_characteristic
+			),
+		)?;
+
+		if video.bit_rate > 0 {
+			field(f, "Bit rate", BitRate(video.bit_rate))?;
+		}
+
+		Ok(())
+	}
+
+	fn audio(&self, f: &mut fmt::Formatter, audio: &Audio) -> fmt::Result {
+		field(f, "Codec", Codec(&audio.codec))?;
+		field(
+			f,
+			"Channels",
+			format_args!("{} ({})", audio.channels, layout(audio.channel_layout, audio.channels)),
+		)?;
+		field(f, "Sample rate", format_args!("{} Hz", audio.sample_rate))?;
+
+		if let Some(depth) = audio.bit_depth {
+			field(f, "Bit depth", format_args!("{} bits", depth))?;
+		}
+
+		if audio.bit_rate > 0 {
+			field(f, "Bit rate", BitRate(audio.bit_rate))?;
+		}
+
+		Ok(())
+	}
+}
+
+fn field<T: fmt::Display>(f: &mut fmt::Formatter, label: &str, value: T) -> fmt::Result {
+	writeln!(f, "{:width$}: {}", label, value, width = LABEL)
+}
+
+fn layout(layout: ChannelLayout, channels: u16) -> String {
+	let name = match layout {
+		l if l == ChannelLayout::MONO => "mono",
+		l if l == ChannelLayout::STEREO => "stereo",
+		l if l == ChannelLayout::_2POINT1 => "2.1",
+		l if l == ChannelLayout::SURROUND => "3.0",
+		l if l == ChannelLayout::QUAD => "quad",
+		l if l == ChannelLayout::_5POINT0 => "5.0",
+		l if l == ChannelLayout::_5POINT1 => "5.1",
+		l if l == ChannelLayout::_5POINT1_BACK => "5.1",
+		l if l == ChannelLayout::_6POINT1 => "6.1",
+		l if l == ChannelLayout::_7POINT1 => "7.1",
+		_ => return format!("{} channels", channels),
+	};
+
+	name.into()
+}
+
+struct Codec<'a>(&'a crate::Codec);
+
+impl fmt::Display for Codec<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match &self.0.profile {
+			Some(profile) => write!(f, "{} ({})", self.0.description, profile),
+			None => write!(f, "{}", self.0.description),
+		}
+	}
+}
+
+struct Time(f64);
+
+impl fmt::Display for Time {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let millis = (self.0.max(0.0) * 1000.0).round() as u64;
+
+		write!(
+			f,
+			"{:02}:{:02}:{:02}.{:03}",
+			millis / 3_600_000,
+			(millis / 60_000) % 60,
+			(millis / 1000) % 60,
+			millis % 1000
+		)
+	}
+}
+
+struct BitRate(usize);
+
+impl fmt::Display for BitRate {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.0 >= 1_000_000 {
+			write!(f, "{:.1} Mb/s", self.0 as f64 / 1_000_000.0)
+		}
+		else {
+			write!(f, "{:.0} kb/s", self.0 as f64 / 1000.0)
+		}
+	}
+}
@@ -0,0 +1,209 @@
+use std::{
+	collections::HashSet,
+	fs,
+	os::raw::c_int,
+	path::{Path, PathBuf},
+	sync::{mpsc, Arc, Mutex},
+	thread,
+};
+
+use ffmpeg::ffi;
+
+use crate::{identify, Metadata};
+
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use self::watch::{watch, Watch, WatchEvent};
+
+const SNIFF_SCORE: c_int = ffi::AVPROBE_SCORE_EXTENSION as c_int;
+
+pub const EXTENSIONS: &[&str] = &[
+	"3g2", "3gp", "aac", "ac3", "aif", "aifc", "aiff", "alac", "ape", "asf", "avi", "avif", "caf",
+	"dts", "eac3", "f4v", "flac", "flv", "heic", "heif", "it", "m2ts", "m4a", "m4b", "m4v", "mka",
+	"mkv", "mod", "mov", "mp2", "mp3", "mp4", "mpc", "mpeg", "mpg", "mts", "mxf", "oga", "ogg",
+	"ogv", "opus", "s3m", "spx", "ts", "tta", "vob", "w64", "wav", "webm", "wma", "wmv", "wv",
+	"xm",
+];
+
+#[derive(Clone, Debug)]
+pub struct ScanOptions {
+	// Files with any of these extensions are probed, compared ignoring case.
+	pub extensions: Vec<String>,
+	// Also probes files FFmpeg recognizes from their contents, whatever their
+	// extension.
+	pub sniff: bool,
+	// Wildcard patterns (`*` and `?`) on the path relative to the root, a
+	// file must match one of `include` when any is given and none of
+	// `exclude`.
+	pub include: Vec<String>,
+	pub exclude: Vec<String>,
+	pub follow_symlinks: bool,
+	pub max_depth: Option<usize>,
+	pub threads: usize,
+}
+
+impl Default for ScanOptions {
+	fn default() -> Self {
+		ScanOptions {
+			extensions: EXTENSIONS.iter().map(|&e| e.to_owned()).collect(),
+			sniff: false,
+			include: Vec::new(),
+			exclude: Vec::new(),
+			follow_symlinks: false,
+			max_depth: None,
+			threads: thread::available_parallelism().map_or(4, |n| n.get()),
+		}
+	}
+}
+
+// Results in the order probes finish, which is not the order files were
+// found in. Dropping it stops the scan after the probes in flight.
+pub struct Scan {
+	results: mpsc::Receiver<(PathBuf, ffmpeg::Result<Metadata>)>,
+}
+
+impl Iterator for Scan {
+	type Item = (PathBuf, ffmpeg::Result<Metadata>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.results.recv().ok()
+	}
+}
+
+pub fn scan<P: AsRef<Path>>(root: P, options: &ScanOptions) -> Scan {
+	let root = root.as_ref().to_path_buf();
+	let options = Arc::new(options.clone());
+	let (paths, queue) = mpsc::sync_channel::<PathBuf>(options.threads.max(1) * 4);
+	let (sender, results) = mpsc::channel();
+	let queue = Arc::new(Mutex::new(queue));
+
+	for _ in 0..options.threads.max(1) {
+		let queue = queue.clone();
+		let sender = sender.clone();
+
+		thread::spawn(move || loop {
+			let path = match queue.lock().ok().and_then(|queue| queue.recv().ok()) {
+				Some(path) => path,
+				None => break,
+			};
+
+			let result = ffmpeg::format::input(&path).and_then(|input| Metadata::new(&input));
+			if sender.send((path, result)).is_err() {
+				break;
+			}
+		});
+	}
+
+	thread::spawn(move || {
+		let mut visited = HashSet::new();
+		walk(&root, &root, 0, &options, &mut visited, &mut |path| paths.send(path).is_ok());
+	});
+
+	Scan { results }
+}
+
+// Stops, returning false, once `found` does.
+fn walk(
+	root: &Path,
+	directory: &Path,
+	depth: usize,
+	options: &ScanOptions,
+	visited: &mut HashSet<PathBuf>,
+	found: &mut dyn FnMut(PathBuf) -> bool,
+) -> bool {
+	// Symbolic links can loop back up the tree.
+	if options.follow_symlinks {
+		match fs::canonicalize(directory) {
+			Ok(canonical) if visited.insert(canonical) => (),
+			_ => return true,
+		}
+	}
+
+	let mut entries = match fs::read_dir(directory) {
+		Ok(entries) => entries.filter_map(Result::ok).map(|e| e.path()).collect::<Vec<_>>(),
+		Err(_) => return true,
+	};
+
+	entries.sort();
+	for path in entries {
+		let metadata = if options.follow_symlinks {
+			fs::metadata(&path)
+		}
+		else {
+			fs::symlink_metadata(&path)
+		};
+
+		let metadata = match metadata {
+			Ok(metadata) => metadata,
+			Err(_) => continue,
+		};
+
+		if metadata.is_dir() {
+			if options.max_depth.map_or(true, |max| depth < max)
+				&& !walk(root, &path, depth + 1, options, visited, found)
+			{
+				return false;
+			}
+		}
+		else if metadata.is_file() && selected(root, &path, options) && !found(path) {
+			return false;
+		}
+	}
+
+	true
+}
+
+fn selected(root: &Path, path: &Path, options: &ScanOptions) -> bool {
+	let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+	let relative = relative.replace('\\', "/");
+
+	if !options.include.is_empty() && !options.include.iter().any(|p| matches(p, &relative)) {
+		return false;
+	}
+
+	if options.exclude.iter().any(|pattern| matches(pattern, &relative)) {
+		return false;
+	}
+
+	let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+	let known = extension.map_or(false, |extension| {
+		options.extensions.iter().any(|e| e.eq_ignore_ascii_case(&extension))
+	});
+
+	known || (options.sniff && sniff(path))
+}
+
+// `*` matches any run of characters, `/` included, `?` any one character.
+fn matches(pattern: &str, text: &str) -> bool {
+	let pattern = pattern.chars().collect::<Vec<_>>();
+	let text = text.chars().collect::<Vec<_>>();
+	let (mut p, mut t) = (0, 0);
+	let mut backtrack = None;
+
+	while t < text.len() {
+		if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+			p += 1;
+			t += 1;
+		}
+		else if p < pattern.len() && pattern[p] == '*' {
+			backtrack = Some((p, t));
+			p += 1;
+		}
+		else if let Some((star, position)) = backtrack {
+			p = star + 1;
+			t = position + 1;
+			backtrack = Some((star, position + 1));
+		}
+		else {
+			return false;
+		}
+	}
+
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Anything FFmpeg is less sure about than a matching extension is not media.
+fn sniff(path: &Path) -> bool {
+	identify(path).ok().flatten().map_or(false, |guess| guess.score > SNIFF_SCORE)
+}
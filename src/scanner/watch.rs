@@ -0,0 +1,106 @@
+use std::{
+	path::{Path, PathBuf},
+	sync::mpsc,
+	thread,
+	time::Duration,
+};
+
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{selected, walk, ScanOptions};
+use crate::Metadata;
+
+#[derive(Debug)]
+pub enum WatchEvent {
+	// Added or modified, renames show up as a removal and an addition.
+	Probed(PathBuf, ffmpeg::Result<Metadata>),
+	Removed(PathBuf),
+}
+
+// Events as files settle, `delay` after their last change. Dropping it stops
+// watching.
+pub struct Watch {
+	_watcher: RecommendedWatcher,
+	events: mpsc::Receiver<WatchEvent>,
+}
+
+impl Iterator for Watch {
+	type Item = WatchEvent;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.events.recv().ok()
+	}
+}
+
+// Only reports changes from now on, `scan` the root first for what's there.
+pub fn watch<P: AsRef<Path>>(
+	root: P,
+	options: &ScanOptions,
+	delay: Duration,
+) -> notify::Result<Watch> {
+	let root = root.as_ref().to_path_buf();
+	let options = options.clone();
+	let (notifications, changes) = mpsc::channel();
+	let (sender, events) = mpsc::channel();
+
+	let mut watcher = notify::watcher(notifications, delay)?;
+	watcher.watch(&root, RecursiveMode::Recursive)?;
+
+	thread::spawn(move || {
+		for change in changes {
+			let (removed, changed) = match change {
+				DebouncedEvent::Create(path) | DebouncedEvent::Write(path) => (None, Some(path)),
+				DebouncedEvent::Remove(path) => (Some(path), None),
+				DebouncedEvent::Rename(from, to) => (Some(from), Some(to)),
+				_ => continue,
+			};
+
+			if let Some(path) = removed {
+				if sender.send(WatchEvent::Removed(path)).is_err() {
+					return;
+				}
+			}
+
+			if let Some(path) = changed {
+				if !probe(&root, path, &options, &sender) {
+					return;
+				}
+			}
+		}
+	});
+
+	Ok(Watch {
+		_watcher: watcher,
+		events,
+	})
+}
+
+// New directories are walked like the scanner does, since nothing was told
+// about the files already in them.
+fn probe(
+	root: &Path,
+	path: PathBuf,
+	options: &ScanOptions,
+	sender: &mpsc::Sender<WatchEvent>,
+) -> bool {
+	let paths = if path.is_dir() {
+		let mut found = Vec::new();
+		walk(root, &path, 0, options, &mut Default::default(), &mut |path| {
+			found.push(path);
+			true
+		});
+
+		found
+	}
+	else if path.is_file() && selected(root, &path, options) {
+		vec![path]
+	}
+	else {
+		Vec::new()
+	};
+
+	paths.into_iter().all(|path| {
+		let result = ffmpeg::format::input(&path).and_then(|input| Metadata::new(&input));
+		sender.send(WatchEvent::Probed(path, result)).is_ok()
+	})
+}
@@ -0,0 +1,35 @@
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+
+use crate::Metadata;
+
+pub fn schema() -> RootSchema {
+	schema_for!(Metadata)
+}
+
+// Stand-ins describing how the FFmpeg types serialize, since they can't
+// derive the schema themselves.
+
+#[derive(JsonSchema)]
+pub struct Rational(pub i32, pub i32);
+
+#[derive(JsonSchema)]
+pub struct Flags {
+	pub bits: u64,
+}
+
+#[derive(JsonSchema)]
+pub enum Sample {
+	None,
+	U8(SampleType),
+	I16(SampleType),
+	I32(SampleType),
+	I64(SampleType),
+	F32(SampleType),
+	F64(SampleType),
+}
+
+#[derive(JsonSchema)]
+pub enum SampleType {
+	Packed,
+	Planar,
+}
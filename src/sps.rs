@@ -0,0 +1,90 @@
+use ffmpeg::{codec, format::stream::Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	bitstream::{nal, sps},
+	raw,
+};
+
+// What the sequence parameter set says, rather than what the container
+// claims or FFmpeg derives.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SequenceParameters {
+	pub profile_idc: u8,
+	pub level_idc: u8,
+	// HEVC only.
+	pub tier: Option<HevcTier>,
+	pub chroma_format: ChromaFormat,
+	pub bit_depth_luma: u8,
+	pub bit_depth_chroma: u8,
+	// After cropping.
+	pub width: u32,
+	pub height: u32,
+	pub interlaced: bool,
+	// max_num_ref_frames for H.264, the largest sps_max_dec_pic_buffering
+	// minus the current picture for HEVC.
+	pub max_ref_frames: u32,
+	// How many frames of this size the level fits in the decoded picture
+	// buffer, H.264 only.
+	pub max_dpb_frames: Option<u32>,
+	// From the VUI for H.264 and the VPS for HEVC.
+	pub timing: Option<VuiTiming>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VuiTiming {
+	pub num_units_in_tick: u32,
+	pub time_scale: u32,
+	pub fixed_frame_rate: Option<bool>,
+	pub frame_rate: Option<f64>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum HevcTier {
+	Main,
+	High,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ChromaFormat {
+	Monochrome,
+	Yuv420,
+	Yuv422,
+	Yuv444,
+}
+
+impl SequenceParameters {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(stream: &Stream) -> Option<Self> {
+		let parameters = stream.parameters();
+		let hevc = match parameters.id() {
+			codec::Id::H264 => false,
+			codec::Id::HEVC => true,
+			_ => return None,
+		};
+
+		let units = nal::parameter_sets(raw::extradata(&parameters), hevc);
+
+		if hevc {
+			let kind = |unit: &[u8]| unit.first().map(|b| (b >> 1) & 0x3f);
+			let vps = units.iter().find(|unit| kind(unit) == Some(32));
+
+			units
+				.iter()
+				.filter(|unit| kind(unit) == Some(33))
+				.find_map(|unit| sps::hevc(unit, vps.copied()))
+		}
+		else {
+			units
+				.iter()
+				.filter(|unit| unit.first().map(|b| b & 0x1f) == Some(7))
+				.find_map(|unit| sps::h264(unit))
+		}
+	}
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{validate, Content, Metadata, Origin};
+
+// Aggregates over a whole library scan. Maps are keyed by name so they come
+// out sorted and serialize as plain objects.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Summary {
+	pub files: usize,
+	pub streams: usize,
+	// Seconds, over the files that have a duration at all.
+	pub duration: f64,
+	pub formats: BTreeMap<String, usize>,
+	// Keyed by stream kind then codec name, counting streams.
+	pub codecs: BTreeMap<String, BTreeMap<String, usize>>,
+	// Video streams by `WIDTHxHEIGHT`.
+	pub resolutions: BTreeMap<String, usize>,
+	pub issues: Issues,
+}
+
+// Files, not streams, with each kind of problem that shows up without
+// reading the media.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Issues {
+	pub total: usize,
+	pub no_streams: usize,
+	pub no_duration: usize,
+	pub estimated_duration: usize,
+	pub unknown_streams: usize,
+	pub chapters: usize,
+	pub encrypted: usize,
+}
+
+impl From<&[Metadata]> for Summary {
+	fn from(files: &[Metadata]) -> Self {
+		let mut summary = Summary::default();
+
+		for metadata in files {
+			summary.add(metadata);
+		}
+
+		summary
+	}
+}
+
+impl Summary {
+	pub fn add(&mut self, metadata: &Metadata) {
+		self.files += 1;
+		self.streams += metadata.streams.len();
+		self.duration += metadata.duration_seconds().unwrap_or(0.0);
+		*self.formats.entry(metadata.format.name.clone()).or_default() += 1;
+
+		for stream in &metadata.streams {
+			if let Some(codec) = stream.content.codec() {
+				*self
+					.codecs
+					.entry(stream.content.kind().to_owned())
+					.or_default()
+					.entry(codec.name.clone())
+					.or_default() += 1;
+			}
+
+			if let Content::Video(video) = &stream.content {
+				let resolution = format!("{}x{}", video.width, video.height);
+				*self.resolutions.entry(resolution).or_default() += 1;
+			}
+		}
+
+		let issues = &mut self.issues;
+		let flags = [
+			(&mut issues.no_streams, metadata.streams.is_empty()),
+			(&mut issues.no_duration, metadata.duration.is_none()),
+			(&mut issues.estimated_duration, metadata.origins.duration == Some(Origin::Estimated)),
+			(
+				&mut issues.unknown_streams,
+				metadata.streams.iter().any(|stream| matches!(stream.content, Content::Unknown(_))),
+			),
+			(&mut issues.chapters, !validate::Chapters::check(metadata).is_valid()),
+			(&mut issues.encrypted, metadata.is_encrypted()),
+		];
+
+		let mut any = false;
+		for (count, flagged) in flags {
+			if flagged {
+				*count += 1;
+				any = true;
+			}
+		}
+
+		if any {
+			issues.total += 1;
+		}
+	}
+}
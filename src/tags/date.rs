@@ -0,0 +1,146 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use ffmpeg::format::context::Input;
+
+// Seconds between the QuickTime epoch (1904) and the UNIX one.
+const QUICKTIME_EPOCH: i64 = 2_082_844_800;
+
+const CREATED: &[&str] = &[
+	"creation_time",
+	"com.apple.quicktime.creationdate",
+	"date_recorded",
+	"DateTimeOriginal",
+	"date",
+	"TDRC",
+	"TYER",
+	"year",
+];
+
+const MODIFIED: &[&str] = &[
+	"modification_time",
+	"modification_date",
+	"com.apple.quicktime.modificationdate",
+	"date_modified",
+	"DateTime",
+	"TDTG",
+];
+
+pub fn created(input: &Input) -> Option<DateTime<Utc>> {
+	find(input, CREATED)
+}
+
+pub fn modified(input: &Input) -> Option<DateTime<Utc>> {
+	find(input, MODIFIED)
+}
+
+fn find(input: &Input, keys: &[&str]) -> Option<DateTime<Utc>> {
+	let format = input.metadata();
+
+	keys.iter().find_map(|key| {
+		format.get(key).and_then(parse).or_else(|| {
+			input
+				.streams()
+				.find_map(|stream| stream.metadata().get(key).and_then(parse))
+		})
+	})
+}
+
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+	let value = value.trim().trim_end_matches('\0');
+
+	if let Ok(seconds) = value.parse::<i64>() {
+		return match value.len() {
+			// Bare years are what ID3v2.3 and most Vorbis comments carry.
+			4 => date(seconds as i32, 1, 1),
+			_ if seconds >= QUICKTIME_EPOCH => Utc.timestamp_opt(seconds - QUICKTIME_EPOCH, 0).single(),
+			_ => Utc.timestamp_opt(seconds, 0).single(),
+		};
+	}
+
+	if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+		return Some(time.with_timezone(&Utc));
+	}
+
+	for format in &["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%d %H:%M:%S%z"] {
+		if let Ok(time) = DateTime::parse_from_str(value, format) {
+			return Some(time.with_timezone(&Utc));
+		}
+	}
+
+	// Zone-less timestamps are assumed to be UTC, which is what the vast
+	// majority of muxers write.
+	for format in &[
+		"%Y-%m-%dT%H:%M:%S%.f",
+		"%Y-%m-%dT%H:%M:%S",
+		"%Y-%m-%d %H:%M:%S%.f",
+		"%Y-%m-%d %H:%M:%S",
+		"%Y:%m:%d %H:%M:%S",
+		"%Y-%m-%dT%H:%M",
+		"%Y-%m-%d %H:%M",
+	] {
+		if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+			return Some(Utc.from_utc_datetime(&time));
+		}
+	}
+
+	for format in &["%Y-%m-%d", "%Y:%m:%d", "%Y/%m/%d"] {
+		if let Ok(day) = NaiveDate::parse_from_str(value, format) {
+			return Some(Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0)?));
+		}
+	}
+
+	// ID3v2.4 allows truncated timestamps down to the month.
+	let mut parts = value.splitn(2, '-');
+	let year = parts.next()?.parse().ok()?;
+	let month = parts.next()?.parse().ok()?;
+
+	date(year, month, 1)
+}
+
+fn date(year: i32, month: u32, day: u32) -> Option<DateTime<Utc>> {
+	Some(Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(0, 0, 0)?))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn utc(value: &str) -> Option<String> {
+		parse(value).map(|time| time.to_rfc3339())
+	}
+
+	#[test]
+	fn iso_8601() {
+		// FFmpeg's `creation_time`, and what phones write.
+		assert_eq!(utc("2021-03-04T12:34:56.000000Z").as_deref(), Some("2021-03-04T12:34:56+00:00"));
+		assert_eq!(utc("2019-06-01T14:22:33+0200").as_deref(), Some("2019-06-01T12:22:33+00:00"));
+		assert_eq!(utc("2019-06-01 14:22:33").as_deref(), Some("2019-06-01T14:22:33+00:00"));
+		assert_eq!(utc("2019-06-01T14:22").as_deref(), Some("2019-06-01T14:22:00+00:00"));
+	}
+
+	#[test]
+	fn exif() {
+		assert_eq!(utc("2021:03:04 12:34:56\0").as_deref(), Some("2021-03-04T12:34:56+00:00"));
+		assert_eq!(utc("2021:03:04").as_deref(), Some("2021-03-04T00:00:00+00:00"));
+	}
+
+	#[test]
+	fn truncated() {
+		assert_eq!(utc("1999").as_deref(), Some("1999-01-01T00:00:00+00:00"));
+		assert_eq!(utc("1999-07").as_deref(), Some("1999-07-01T00:00:00+00:00"));
+		assert_eq!(utc("1999/07/14").as_deref(), Some("1999-07-14T00:00:00+00:00"));
+	}
+
+	#[test]
+	fn seconds() {
+		// Past 2036 as UNIX time means it's counted from 1904.
+		assert_eq!(utc("3700000000").as_deref(), Some("2021-03-31T01:46:40+00:00"));
+		assert_eq!(utc("1617155200").as_deref(), Some("2021-03-31T01:46:40+00:00"));
+	}
+
+	#[test]
+	fn invalid() {
+		for value in &["", "yesterday", "1999-13", "2021-02-30", "12:34:56"] {
+			assert_eq!(utc(value), None, "{:?}", value);
+		}
+	}
+}
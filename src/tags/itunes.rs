@@ -0,0 +1,60 @@
+use ffmpeg::format::{context::Input, stream::Stream};
+
+// The iTunSMPB tag is a list of hexadecimal fields, of which the second is
+// the encoder delay, the third the padding and the fourth the sample count.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Smpb {
+	pub delay: u32,
+	pub padding: u32,
+	pub samples: u64,
+}
+
+pub fn smpb(value: &str) -> Option<Smpb> {
+	let mut fields = value.split_whitespace().skip(1);
+
+	Some(Smpb {
+		delay: u32::from_str_radix(fields.next()?, 16).ok()?,
+		padding: u32::from_str_radix(fields.next()?, 16).ok()?,
+		samples: u64::from_str_radix(fields.next()?, 16).ok()?,
+	})
+}
+
+// MP4 keeps the tag on the track, MP3 and the like on the file. A track tag
+// that doesn't parse falls back to the file one rather than hiding it.
+pub fn find_smpb(input: &Input, stream: &Stream) -> Option<Smpb> {
+	stream
+		.metadata()
+		.get("iTunSMPB")
+		.and_then(smpb)
+		.or_else(|| input.metadata().get("iTunSMPB").and_then(smpb))
+}
+
+// iTunNORM (SoundCheck) holds ten hexadecimal fields in pairs for the left and
+// right channels: the first pair is the loudness relative to 1/1000 W, the
+// seventh the peak sample value on a 16 bit scale.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Norm {
+	pub gain: f64,
+	pub peak: f64,
+}
+
+pub fn norm(value: &str) -> Option<Norm> {
+	let fields = value
+		.split_whitespace()
+		.map(|field| u32::from_str_radix(field, 16).ok())
+		.collect::<Option<Vec<_>>>()?;
+
+	if fields.len() < 10 {
+		return None;
+	}
+
+	let loudness = fields[0].max(fields[1]);
+	if loudness == 0 {
+		return None;
+	}
+
+	Some(Norm {
+		gain: -10.0 * (f64::from(loudness) / 1000.0).log10(),
+		peak: f64::from(fields[6].max(fields[7])) / 32768.0,
+	})
+}
@@ -0,0 +1,3 @@
+#[cfg(feature = "chrono")]
+pub mod date;
+pub mod itunes;
@@ -0,0 +1,245 @@
+use ffmpeg::{
+	codec, encoder,
+	format::{context::Input, Pixel},
+	frame, media,
+	software::scaling::{self, Flags},
+	Packet,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{self, picture::Gray, Ticker};
+
+// Pictures darker than this on average are considered black and never picked
+// as representative.
+const BLACK: f64 = 24.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageFormat {
+	Rgb,
+	Rgba,
+	Jpeg,
+	Png,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ThumbnailOptions {
+	pub stream: Option<usize>,
+	// Picks the most detailed non-black picture among a few candidates spread
+	// across the stream when missing.
+	pub time: Option<f64>,
+	pub candidates: usize,
+	pub max_width: Option<u32>,
+	pub format: ImageFormat,
+}
+
+impl Default for ThumbnailOptions {
+	fn default() -> Self {
+		ThumbnailOptions {
+			stream: None,
+			time: None,
+			candidates: 5,
+			max_width: None,
+			format: ImageFormat::Jpeg,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Image {
+	pub time: f64,
+	pub width: u32,
+	pub height: u32,
+	pub format: ImageFormat,
+	pub data: Vec<u8>,
+}
+
+// Candidates are picked within the interval of `options` when there is one.
+pub fn extract_thumbnail(
+	input: &mut Input,
+	options: &analysis::Options,
+	thumbnail: &ThumbnailOptions,
+) -> ffmpeg::Result<Image> {
+	let index = match thumbnail.stream {
+		Some(index) => index,
+		None => input
+			.streams()
+			.best(media::Type::Video)
+			.ok_or(ffmpeg::Error::StreamNotFound)?
+			.index(),
+	};
+
+	let (start, duration) = {
+		let stream = analysis::stream(input, index)?;
+
+		let start = Some(stream.start_time())
+			.filter(|&ts| ts != ffmpeg::ffi::AV_NOPTS_VALUE)
+			.map_or(0.0, |ts| analysis::seconds(ts, stream.time_base()));
+
+		let duration = if stream.duration() > 0 {
+			analysis::seconds(stream.duration(), stream.time_base())
+		}
+		else {
+			input.duration().max(0) as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE)
+		};
+
+		(start, duration)
+	};
+
+	// Times stay relative to the start of the stream.
+	let (from, length) = match options.interval {
+		Some(interval) if duration > 0.0 => {
+			let offset = analysis::start_time(input) - start;
+			let from = (offset + interval.start).max(0.0);
+			let to = (offset + interval.end).min(duration);
+
+			(from, (to - from).max(0.0))
+		}
+		_ => (0.0, duration),
+	};
+
+	let mut ticker = options.ticker(input);
+
+	let (time, frame) = match thumbnail.time {
+		Some(time) => (time, analysis::video_at(input, index, start + time, &mut ticker)?),
+		None => representative(input, index, start, (from, length), thumbnail.candidates, &mut ticker)?,
+	};
+
+	let frame = frame.ok_or(ffmpeg::Error::StreamNotFound)?;
+	let (width, height) = size(&frame, thumbnail.max_width);
+
+	let pixel = match thumbnail.format {
+		ImageFormat::Rgb | ImageFormat::Png => Pixel::RGB24,
+		ImageFormat::Rgba => Pixel::RGBA,
+		ImageFormat::Jpeg => Pixel::YUVJ420P,
+	};
+
+	let mut scaler = scaling::Context::get(
+		frame.format(),
+		frame.width(),
+		frame.height(),
+		pixel,
+		width,
+		height,
+		Flags::BICUBIC,
+	)?;
+
+	let mut output = frame::Video::empty();
+	scaler.run(&frame, &mut output)?;
+
+	let data = match thumbnail.format {
+		ImageFormat::Rgb => packed(&output, 3),
+		ImageFormat::Rgba => packed(&output, 4),
+		ImageFormat::Png => encode(&mut output, codec::Id::PNG)?,
+		ImageFormat::Jpeg => encode(&mut output, codec::Id::MJPEG)?,
+	};
+
+	Ok(Image {
+		time,
+		width,
+		height,
+		format: thumbnail.format,
+		data,
+	})
+}
+
+// Candidates come from the `length` seconds past `from`, relative to `start`.
+fn representative(
+	input: &mut Input,
+	index: usize,
+	start: f64,
+	(from, length): (f64, f64),
+	candidates: usize,
+	ticker: &mut Ticker,
+) -> ffmpeg::Result<(f64, Option<frame::Video>)> {
+	if length <= 0.0 || candidates == 0 {
+		return Ok((from, analysis::video_at(input, index, start + from, ticker)?));
+	}
+
+	let mut gray = Gray::new(160);
+	let mut best = None::<(f64, f64, frame::Video)>;
+	let mut fallback = None;
+
+	for i in 0..candidates {
+		let time = from + length * (i as f64 + 1.0) / (candidates as f64 + 1.0);
+		let frame = match analysis::video_at(input, index, start + time, ticker)? {
+			Some(frame) => frame,
+			None => continue,
+		};
+
+		let pixels = gray.convert(&frame)?.pixels().map(f64::from).collect::<Vec<_>>();
+		let count = pixels.len().max(1) as f64;
+		let mean = pixels.iter().sum::<f64>() / count;
+		let variance = pixels.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / count;
+
+		if mean < BLACK {
+			fallback.get_or_insert((time, frame));
+			continue;
+		}
+
+		if best.as_ref().map_or(true, |(_, score, _)| variance > *score) {
+			best = Some((time, variance, frame));
+		}
+	}
+
+	Ok(match best {
+		Some((time, _, frame)) => (time, Some(frame)),
+		None => fallback.map_or((0.0, None), |(time, frame)| (time, Some(frame))),
+	})
+}
+
+// Square pixels of the display aspect ratio, optionally bounded in width.
+fn size(frame: &frame::Video, max: Option<u32>) -> (u32, u32) {
+	let ratio = frame.aspect_ratio();
+	let mut width = frame.width();
+	let height = frame.height();
+
+	if ratio.numerator() > 0 && ratio.denominator() > 0 {
+		width = (u64::from(width) * ratio.numerator() as u64 / ratio.denominator() as u64) as u32;
+	}
+
+	match max {
+		Some(max) if width > max => {
+			let scaled = (u64::from(height) * u64::from(max) / u64::from(width.max(1))) as u32;
+			(max, scaled.max(2) & !1)
+		}
+
+		_ => (width.max(2) & !1, height.max(2) & !1),
+	}
+}
+
+fn packed(frame: &frame::Video, bytes: usize) -> Vec<u8> {
+	let stride = frame.stride(0);
+	let row = frame.width() as usize * bytes;
+
+	frame
+		.data(0)
+		.chunks(stride)
+		.take(frame.height() as usize)
+		.flat_map(|line| &line[..row])
+		.copied()
+		.collect()
+}
+
+fn encode(frame: &mut frame::Video, id: codec::Id) -> ffmpeg::Result<Vec<u8>> {
+	let codec = encoder::find(id).ok_or(ffmpeg::Error::EncoderNotFound)?;
+
+	let mut context = codec::Context::new().encoder().video()?;
+	context.set_width(frame.width());
+	context.set_height(frame.height());
+	context.set_format(frame.format());
+	context.set_time_base((1, 1));
+
+	let mut encoder = context.open_as(codec)?;
+	frame.set_pts(Some(0));
+	encoder.send_frame(frame)?;
+	encoder.send_eof()?;
+
+	let mut packet = Packet::empty();
+	encoder.receive_packet(&mut packet)?;
+
+	Ok(packet.data().unwrap_or_default().to_vec())
+}
@@ -0,0 +1,184 @@
+use std::{convert::TryInto, slice};
+use ffmpeg::{ffi, format::context::Input, frame, media, Rational};
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis, compat, raw};
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Timecode {
+	pub value: String,
+	pub hours: u8,
+	pub minutes: u8,
+	pub seconds: u8,
+	pub frames: u8,
+	#[cfg_attr(feature = "schemars", schemars(with = "Option<crate::schema::Rational>"))]
+	pub frame_rate: Option<Rational>,
+	pub drop_frame: bool,
+	pub source: TimecodeSource,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum TimecodeSource {
+	Format,
+	Stream,
+	Track,
+	S12m,
+}
+
+impl Timecode {
+	pub fn parse(value: &str, frame_rate: Option<Rational>, source: TimecodeSource) -> Option<Self> {
+		let value = value.trim();
+		let fields = value
+			.split(|c| c == ':' || c == ';' || c == '.' || c == ',')
+			.map(|field| field.parse::<u8>().ok())
+			.collect::<Option<Vec<_>>>()?;
+
+		if fields.len() != 4 || fields[1] > 59 || fields[2] > 59 {
+			return None;
+		}
+
+		// Drop frame timecodes use a different separator before the frames.
+		let drop_frame = value.contains(';') || value.contains('.') || value.contains(',');
+
+		Some(Timecode::new(
+			fields[0], fields[1], fields[2], fields[3], frame_rate, drop_frame, source,
+		))
+	}
+
+	pub fn from_s12m(value: u32, frame_rate: Option<Rational>) -> Self {
+		let bcd = |value: u32| ((value >> 4) * 10 + (value & 0xf)) as u8;
+
+		Timecode::new(
+			bcd(value & 0x3f),
+			bcd((value >> 8) & 0x7f),
+			bcd((value >> 16) & 0x7f),
+			bcd((value >> 24) & 0x3f),
+			frame_rate,
+			value & (1 << 30) != 0,
+			TimecodeSource::S12m,
+		)
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn find(input: &Input) -> Option<Self> {
+		let rate = |rate: Rational| if rate.numerator() > 0 { Some(rate) } else { None };
+		let video = input
+			.streams()
+			.best(media::Type::Video)
+			.and_then(|stream| rate(stream.avg_frame_rate()));
+
+		// The dedicated timecode track is the authoritative source for MOV, the
+		// copies on other streams and the container are derived from it.
+		for stream in input.streams() {
+			if compat::medium(&stream) == media::Type::Data
+				&& &raw::codec_tag(&stream.parameters()) == b"tmcd"
+			{
+				if let Some(value) = stream.metadata().get("timecode") {
+					let frame_rate = rate(stream.avg_frame_rate()).or(video);
+
+					if let Some(timecode) = Timecode::parse(value, frame_rate, TimecodeSource::Track) {
+						return Some(timecode);
+					}
+				}
+			}
+		}
+
+		if let Some(value) = input.metadata().get("timecode") {
+			if let Some(timecode) = Timecode::parse(value, video, TimecodeSource::Format) {
+				return Some(timecode);
+			}
+		}
+
+		input.streams().find_map(|stream| {
+			let frame_rate = rate(stream.avg_frame_rate()).or(video);
+
+			stream
+				.metadata()
+				.get("timecode")
+				.and_then(|value| Timecode::parse(value, frame_rate, TimecodeSource::Stream))
+		})
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn analyze(
+		input: &mut Input,
+		index: usize,
+		options: &analysis::Options,
+	) -> ffmpeg::Result<Option<Self>> {
+		let frame_rate = {
+			let stream = analysis::stream(input, index)?;
+
+			if compat::medium(&stream) != media::Type::Video {
+				return Err(ffmpeg::Error::InvalidData);
+			}
+
+			Some(stream.avg_frame_rate()).filter(|rate| rate.numerator() > 0)
+		};
+
+		// The first decoded picture carries the starting timecode, no need to
+		// go any further.
+		let mut options = options.clone();
+		options.max_frames = Some(1);
+
+		let mut timecode = None;
+		analysis::video(input, index, &options, |frame, _| {
+			timecode = s12m(frame).map(|value| Timecode::from_s12m(value, frame_rate));
+			Ok(())
+		})?;
+
+		Ok(timecode)
+	}
+
+	fn new(
+		hours: u8,
+		minutes: u8,
+		seconds: u8,
+		frames: u8,
+		frame_rate: Option<Rational>,
+		drop_frame: bool,
+		source: TimecodeSource,
+	) -> Self {
+		Timecode {
+			value: format!(
+				"{:02}:{:02}:{:02}{}{:02}",
+				hours,
+				minutes,
+				seconds,
+				if drop_frame { ';' } else { ':' },
+				frames
+			),
+			hours,
+			minutes,
+			seconds,
+			frames,
+			frame_rate,
+			drop_frame,
+			source,
+		}
+	}
+}
+
+fn s12m(frame: &frame::Video) -> Option<u32> {
+	let data = unsafe {
+		let data = ffi::av_frame_get_side_data(
+			frame.as_ptr(),
+			ffi::AVFrameSideDataType::AV_FRAME_DATA_S12M_TIMECODE,
+		);
+
+		if data.is_null() {
+			return None;
+		}
+
+		slice::from_raw_parts((*data).data, (*data).size as usize)
+	};
+
+	// A count followed by up to three timecodes, only the first matters.
+	if data.len() < 8 || u32::from_ne_bytes(data[..4].try_into().ok()?) == 0 {
+		return None;
+	}
+
+	Some(u32::from_ne_bytes(data[4..8].try_into().ok()?))
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Metadata;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Chapters {
+	pub chapters: usize,
+	pub findings: Vec<Finding>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Finding {
+	pub chapter: usize,
+	pub issue: Issue,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Issue {
+	NotMonotonic { start: f64, previous: f64 },
+	Overlap { start: f64, previous: f64 },
+	Empty { start: f64, end: f64 },
+	BeyondDuration { end: f64, duration: f64 },
+	Untitled,
+}
+
+impl Chapters {
+	pub fn check(metadata: &Metadata) -> Self {
+		let duration = metadata.duration_seconds();
+		let mut findings = Vec::new();
+		let mut previous = None::<(f64, f64)>;
+
+		for (chapter, entry) in metadata.chapters.iter().enumerate() {
+			let mut report = |issue| findings.push(Finding { chapter, issue });
+			let (start, end) = (entry.start_seconds(), entry.end_seconds());
+
+			if end <= start {
+				report(Issue::Empty { start, end });
+			}
+
+			if let Some((from, to)) = previous {
+				if start < from {
+					report(Issue::NotMonotonic {
+						start,
+						previous: from,
+					});
+				}
+				else if start < to {
+					report(Issue::Overlap { start, previous: to });
+				}
+			}
+
+			// Muxers round the last chapter end to their own time base, so allow
+			// a little slack before calling it out.
+			if let Some(duration) = duration {
+				if end > duration + 0.5 {
+					report(Issue::BeyondDuration { end, duration });
+				}
+			}
+
+			if entry.title.as_deref().map_or(true, |title| title.trim().is_empty()) {
+				report(Issue::Untitled);
+			}
+
+			previous = Some((start, end));
+		}
+
+		Chapters {
+			chapters: metadata.chapters.len(),
+			findings,
+		}
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.findings.is_empty()
+	}
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use ffmpeg::{decoder, format::context::Input, frame, media, Packet};
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis, compat, Metadata};
+
+// Read errors other than corrupt data tend to repeat forever at the point the
+// file got cut.
+const MAX_READ_ERRORS: usize = 16;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ValidationOptions {
+	pub decode: bool,
+	// Tolerance in seconds between the declared and observed end of a stream.
+	pub truncation: f64,
+	// Only scans part of the input, which can't tell whether it's truncated.
+	pub interval: Option<analysis::Interval>,
+	#[serde(skip)]
+	pub progress: Option<analysis::ProgressCallback>,
+	#[serde(skip)]
+	pub cancel: Option<analysis::CancellationToken>,
+}
+
+impl Default for ValidationOptions {
+	fn default() -> Self {
+		ValidationOptions {
+			decode: false,
+			truncation: 1.0,
+			interval: None,
+			progress: None,
+			cancel: None,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ValidationReport {
+	pub read_errors: usize,
+	pub truncated: bool,
+	pub streams: Vec<StreamReport>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StreamReport {
+	pub index: usize,
+	pub packets: usize,
+	pub corrupt: usize,
+	pub decode_errors: usize,
+	pub discontinuities: usize,
+	pub gaps: usize,
+	pub first_error: Option<f64>,
+	pub end: Option<f64>,
+	pub truncated: bool,
+}
+
+#[derive(Default)]
+struct State {
+	report: StreamReport,
+	dts: Option<i64>,
+	duration: i64,
+}
+
+impl StreamReport {
+	fn error(&mut self, time: Option<f64>) {
+		if self.first_error.is_none() {
+			self.first_error = time;
+		}
+	}
+
+	pub fn is_intact(&self) -> bool {
+		self.corrupt == 0
+			&& self.decode_errors == 0
+			&& self.discontinuities == 0
+			&& self.gaps == 0
+			&& !self.truncated
+	}
+}
+
+impl ValidationReport {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+	pub fn scan(
+		metadata: &Metadata,
+		input: &mut Input,
+		options: &ValidationOptions,
+	) -> ffmpeg::Result<Self> {
+		let time_bases = input.streams().map(|s| s.time_base()).collect::<Vec<_>>();
+		let mut states = (0..time_bases.len())
+			.map(|index| State {
+				report: StreamReport {
+					index,
+					..Default::default()
+				},
+				..Default::default()
+			})
+			.collect::<Vec<_>>();
+
+		let mut decoders = HashMap::new();
+		if options.decode {
+			for stream in input.streams() {
+				if matches!(compat::medium(&stream), media::Type::Audio | media::Type::Video) {
+					decoders.insert(stream.index(), compat::decoder(&stream)?.open()?);
+				}
+			}
+		}
+
+		let window = analysis::window(input, options.interval)?;
+		let mut ticker = analysis::Ticker::new(
+			input,
+			options.interval,
+			options.progress.as_ref(),
+			options.cancel.as_ref(),
+		);
+		let mut frame = unsafe { frame::Frame::empty() };
+		let mut read_errors = 0;
+		let mut failures = 0;
+
+		loop {
+			let mut packet = Packet::empty();
+
+			match packet.read(input) {
+				Ok(()) => failures = 0,
+				Err(ffmpeg::Error::Eof) => break,
+
+				Err(_) => {
+					read_errors += 1;
+					failures += 1;
+
+					if failures >= MAX_READ_ERRORS {
+						break;
+					}
+
+					continue;
+				}
+			}
+
+			let index = packet.stream();
+			let time_base = time_bases[index];
+			ticker.packet(&packet, time_base)?;
+
+			let position = analysis::time(&packet, time_base);
+			if window.before(position) {
+				continue;
+			}
+
+			if window.after(position) {
+				break;
+			}
+			let state = &mut states[index];
+			let time = packet
+				.dts()
+				.or_else(|| packet.pts())
+				.map(|ts| analysis::seconds(ts, time_base));
+
+			state.report.packets += 1;
+
+			if packet.is_corrupt() {
+				state.report.corrupt += 1;
+				state.report.error(time);
+			}
+
+			if let (Some(dts), Some(previous)) = (packet.dts(), state.dts) {
+				if dts < previous {
+					state.report.discontinuities += 1;
+					state.report.error(time);
+				}
+				// More than a couple of packets worth of nothing is lost data,
+				// sparse streams with no duration information can't be judged.
+				else if state.duration > 0 && dts - previous > state.duration * 3 {
+					state.report.gaps += 1;
+					state.report.error(time);
+				}
+			}
+
+			if let Some(dts) = packet.dts() {
+				state.dts = Some(dts);
+
+				let end = analysis::seconds(dts + packet.duration().max(0), time_base);
+				state.report.end = Some(state.report.end.map_or(end, |e: f64| e.max(end)));
+			}
+
+			state.duration = packet.duration();
+
+			if let Some(decoder) = decoders.get_mut(&index) {
+				decode(decoder, Some(&packet), &mut frame, &mut state.report, time);
+			}
+		}
+
+		for (&index, decoder) in decoders.iter_mut() {
+			let report = &mut states[index].report;
+			let time = report.end;
+
+			decode(decoder, None, &mut frame, report, time);
+		}
+
+		let mut truncated = false;
+		let mut streams = states.into_iter().map(|state| state.report).collect::<Vec<_>>();
+
+		for report in &mut streams {
+			let declared = metadata
+				.streams
+				.get(report.index)
+				.and_then(|stream| stream.duration_seconds())
+				.filter(|&duration| duration > 0.0);
+
+			let start = metadata
+				.streams
+				.get(report.index)
+				.and_then(|stream| stream.start_time_seconds())
+				.unwrap_or(0.0);
+
+			if let (Some(declared), Some(end), None) = (declared, report.end, options.interval) {
+				report.truncated = end - start + options.truncation < declared;
+			}
+
+			truncated |= report.truncated;
+		}
+
+		// A read error at the very end is how a cut download looks like.
+		truncated |= failures >= MAX_READ_ERRORS && options.interval.is_none();
+
+		Ok(ValidationReport {
+			read_errors,
+			truncated,
+			streams,
+		})
+	}
+
+	pub fn is_intact(&self) -> bool {
+		self.read_errors == 0 && !self.truncated && self.streams.iter().all(StreamReport::is_intact)
+	}
+}
+
+fn decode(
+	decoder: &mut decoder::Opened,
+	packet: Option<&Packet>,
+	frame: &mut frame::Frame,
+	report: &mut StreamReport,
+	time: Option<f64>,
+) {
+	let sent = match packet {
+		Some(packet) => decoder.send_packet(packet),
+		None => decoder.send_eof(),
+	};
+
+	if sent.is_err() {
+		report.decode_errors += 1;
+		report.error(time);
+	}
+
+	loop {
+		match decoder.receive_frame(frame) {
+			Ok(()) => (),
+			Err(ffmpeg::Error::Eof) => break,
+			Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => break,
+
+			Err(_) => {
+				report.decode_errors += 1;
+				report.error(time);
+				break;
+			}
+		}
+	}
+}
@@ -0,0 +1,20 @@
+pub mod webm;
+pub use self::webm::Webm;
+
+pub mod subtitles;
+pub use self::subtitles::{SubtitleThresholds, Subtitles};
+
+pub mod chapters;
+pub use self::chapters::Chapters;
+
+pub mod profile;
+pub use self::profile::{AudioConstraints, Profile, VideoConstraints};
+
+pub mod integrity;
+pub use self::integrity::{ValidationOptions, ValidationReport};
+
+pub mod side_data;
+pub use self::side_data::SideDataAudit;
+
+pub mod timing;
+pub use self::timing::{Timing, TimingThresholds};
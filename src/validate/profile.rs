@@ -0,0 +1,258 @@
+use ffmpeg::codec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, Metadata};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Profile {
+	pub name: String,
+	pub containers: Vec<String>,
+	pub max_bit_rate: Option<usize>,
+	// A missing set of constraints means the kind of stream isn't allowed at
+	// all by the target.
+	pub video: Option<VideoConstraints>,
+	pub audio: Option<AudioConstraints>,
+	#[cfg_attr(feature = "schemars", schemars(with = "Option<Vec<String>>"))]
+	pub subtitles: Option<Vec<codec::Id>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VideoConstraints {
+	#[cfg_attr(feature = "schemars", schemars(with = "Vec<String>"))]
+	pub codecs: Vec<codec::Id>,
+	pub max_width: Option<u32>,
+	pub max_height: Option<u32>,
+	pub max_fps: Option<f64>,
+	pub max_bit_rate: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AudioConstraints {
+	#[cfg_attr(feature = "schemars", schemars(with = "Vec<String>"))]
+	pub codecs: Vec<codec::Id>,
+	pub max_channels: Option<u16>,
+	pub sample_rates: Vec<u32>,
+	pub max_bit_rate: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Violation {
+	Container { name: String },
+	BitRate { stream: Option<usize>, bit_rate: usize },
+	Stream { stream: usize, kind: String },
+	Codec { stream: usize, codec: String },
+	Resolution { stream: usize, width: u32, height: u32 },
+	FrameRate { stream: usize, fps: f64 },
+	Channels { stream: usize, channels: u16 },
+	SampleRate { stream: usize, sample_rate: u32 },
+}
+
+impl Violation {
+	pub fn stream(&self) -> Option<usize> {
+		match *self {
+			Violation::Container { .. } => None,
+			Violation::BitRate { stream, .. } => stream,
+			Violation::Stream { stream, .. }
+			| Violation::Codec { stream, .. }
+			| Violation::Resolution { stream, .. }
+			| Violation::FrameRate { stream, .. }
+			| Violation::Channels { stream, .. }
+			| Violation::SampleRate { stream, .. } => Some(stream),
+		}
+	}
+
+	// A wrong container or a stream the target can't carry are fixed by
+	// remuxing, or dropping the stream, everything else needs an encode.
+	pub fn needs_encode(&self) -> bool {
+		!matches!(self, Violation::Container { .. } | Violation::Stream { .. })
+	}
+}
+
+impl Profile {
+	// Progressive H.264/AAC MP4 playable by every browser.
+	pub fn web() -> Self {
+		Profile {
+			name: "web".into(),
+			containers: vec!["mp4".into()],
+			max_bit_rate: None,
+			video: Some(VideoConstraints {
+				codecs: vec![codec::Id::H264],
+				max_width: Some(1920),
+				max_height: Some(1080),
+				max_fps: Some(60.0),
+				max_bit_rate: None,
+			}),
+			audio: Some(AudioConstraints {
+				codecs: vec![codec::Id::AAC],
+				max_channels: Some(2),
+				sample_rates: vec![44_100, 48_000],
+				max_bit_rate: None,
+			}),
+			subtitles: Some(vec![codec::Id::MOV_TEXT]),
+		}
+	}
+
+	// MPEG-TS segments as described by the HLS authoring specification,
+	// subtitles are delivered as separate WebVTT renditions.
+	pub fn hls() -> Self {
+		Profile {
+			name: "hls".into(),
+			containers: vec!["mpegts".into()],
+			max_bit_rate: None,
+			video: Some(VideoConstraints {
+				codecs: vec![codec::Id::H264, codec::Id::HEVC],
+				..Default::default()
+			}),
+			audio: Some(AudioConstraints {
+				codecs: vec![codec::Id::AAC, codec::Id::AC3, codec::Id::EAC3],
+				max_channels: None,
+				sample_rates: vec![32_000, 44_100, 48_000],
+				max_bit_rate: None,
+			}),
+			subtitles: None,
+		}
+	}
+
+	pub fn check(&self, metadata: &Metadata) -> Vec<Violation> {
+		let mut violations = Vec::new();
+
+		let container = self.containers.iter().any(|name| {
+			metadata.format.name == *name
+				|| metadata.format.aliases.iter().any(|alias| alias == name)
+		});
+
+		if !self.containers.is_empty() && !container {
+			violations.push(Violation::Container {
+				name: metadata.format.name.clone(),
+			});
+		}
+
+		if let Some(max) = self.max_bit_rate {
+			if metadata.bit_rate > max {
+				violations.push(Violation::BitRate {
+					stream: None,
+					bit_rate: metadata.bit_rate,
+				});
+			}
+		}
+
+		for stream in &metadata.streams {
+			let index = stream.index;
+			let kind = || Violation::Stream {
+				stream: index,
+				kind: stream.content.kind().into(),
+			};
+
+			match &stream.content {
+				Content::Video(video) => {
+					let constraints = match &self.video {
+						Some(constraints) => constraints,
+						None => {
+							violations.push(kind());
+							continue;
+						}
+					};
+
+					if !constraints.codecs.is_empty()
+						&& !constraints.codecs.contains(&video.codec.id)
+					{
+						violations.push(Violation::Codec {
+							stream: index,
+							codec: video.codec.name.clone(),
+						});
+					}
+
+					if constraints.max_width.map_or(false, |max| video.width > max)
+						|| constraints.max_height.map_or(false, |max| video.height > max)
+					{
+						violations.push(Violation::Resolution {
+							stream: index,
+							width: video.width,
+							height: video.height,
+						});
+					}
+
+					if let (Some(max), Some(fps)) = (constraints.max_fps, stream.fps()) {
+						// Allow for NTSC rates, 59.94 is fine for a 60 fps target.
+						if fps > max + 0.01 {
+							violations.push(Violation::FrameRate { stream: index, fps });
+						}
+					}
+
+					if constraints.max_bit_rate.map_or(false, |max| video.bit_rate > max) {
+						violations.push(Violation::BitRate {
+							stream: Some(index),
+							bit_rate: video.bit_rate,
+						});
+					}
+				}
+
+				Content::Audio(audio) => {
+					let constraints = match &self.audio {
+						Some(constraints) => constraints,
+						None => {
+							violations.push(kind());
+							continue;
+						}
+					};
+
+					if !constraints.codecs.is_empty()
+						&& !constraints.codecs.contains(&audio.codec.id)
+					{
+						violations.push(Violation::Codec {
+							stream: index,
+							codec: audio.codec.name.clone(),
+						});
+					}
+
+					if constraints.max_channels.map_or(false, |max| audio.channels > max) {
+						violations.push(Violation::Channels {
+							stream: index,
+							channels: audio.channels,
+						});
+					}
+
+					if !constraints.sample_rates.is_empty()
+						&& !constraints.sample_rates.contains(&audio.sample_rate)
+					{
+						violations.push(Violation::SampleRate {
+							stream: index,
+							sample_rate: audio.sample_rate,
+						});
+					}
+
+					if constraints.max_bit_rate.map_or(false, |max| audio.bit_rate > max) {
+						violations.push(Violation::BitRate {
+							stream: Some(index),
+							bit_rate: audio.bit_rate,
+						});
+					}
+				}
+
+				Content::Subtitle(subtitle) => match &self.subtitles {
+					Some(codecs) => {
+						if !codecs.is_empty() && !codecs.contains(&subtitle.codec.id) {
+							violations.push(Violation::Codec {
+								stream: index,
+								codec: subtitle.codec.name.clone(),
+							});
+						}
+					}
+
+					None => violations.push(kind()),
+				},
+
+				// Data and attachments are dropped by any remux targeting a
+				// delivery format, they don't get in the way.
+				_ => (),
+			}
+		}
+
+		violations
+	}
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{diff, Metadata, SideData};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SideDataAudit {
+	pub dropped: Vec<Dropped>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Dropped {
+	SideData { stream: usize, kind: SideData },
+	Tag { key: String },
+}
+
+impl SideDataAudit {
+	// Streams are paired the same way `Metadata::diff` does, indices are the
+	// ones from the source.
+	pub fn check(source: &Metadata, output: &Metadata) -> Self {
+		let mut dropped = Vec::new();
+
+		for kind in diff::KINDS {
+			let before = diff::kind_of(source, kind);
+			let after = diff::kind_of(output, kind);
+
+			for (index, stream) in before.iter().enumerate() {
+				let kept = after.get(index).map(|s| s.side_data.as_slice()).unwrap_or_default();
+
+				for data in &stream.side_data {
+					if !kept.contains(data) {
+						dropped.push(Dropped::SideData {
+							stream: stream.index,
+							kind: data.clone(),
+						});
+					}
+				}
+			}
+		}
+
+		// Loudness information most often lives in tags rather than side data,
+		// and muxers drop the ones they don't know about.
+		for key in source.details.keys().filter(|key| loudness(key)) {
+			if !output.details.keys().any(|other| other.eq_ignore_ascii_case(key)) {
+				dropped.push(Dropped::Tag { key: key.clone() });
+			}
+		}
+
+		SideDataAudit { dropped }
+	}
+
+	pub fn is_preserved(&self) -> bool {
+		self.dropped.is_empty()
+	}
+}
+
+fn loudness(key: &str) -> bool {
+	let key = key.to_ascii_lowercase();
+
+	key.starts_with("replaygain_") || key == "itunnorm" || key.starts_with("r128_")
+}
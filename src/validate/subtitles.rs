@@ -0,0 +1,149 @@
+use ffmpeg::{
+	ffi::{AV_NOPTS_VALUE, AV_TIME_BASE},
+	format::context::Input,
+	media,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis, compat};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubtitleThresholds {
+	pub min_display: f64,
+	pub max_display: f64,
+}
+
+impl Default for SubtitleThresholds {
+	fn default() -> Self {
+		SubtitleThresholds {
+			min_display: 0.3,
+			max_display: 30.0,
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Subtitles {
+	pub events: usize,
+	pub findings: Vec<Finding>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Finding {
+	pub event: usize,
+	pub time: f64,
+	pub issue: Issue,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Issue {
+	Overlap { previous: f64 },
+	// `duration` is where the presentation ends, start time included.
+	BeyondDuration { end: f64, duration: f64 },
+	NegativeDuration { duration: f64 },
+	TooShort { duration: f64 },
+	TooLong { duration: f64 },
+}
+
+impl Subtitles {
+	pub fn check(
+		input: &mut Input,
+		index: usize,
+		options: &analysis::Options,
+		thresholds: &SubtitleThresholds,
+	) -> ffmpeg::Result<Self> {
+		if compat::medium(&analysis::stream(input, index)?) != media::Type::Subtitle {
+			return Err(ffmpeg::Error::InvalidData);
+		}
+
+		let duration = end(input);
+		let mut events = 0;
+		let mut end = None::<f64>;
+		let mut findings = Vec::new();
+
+		analysis::packets(input, index, options, |packet, time_base| {
+			let start = match packet.pts().or_else(|| packet.dts()) {
+				Some(pts) => analysis::seconds(pts, time_base),
+				None => return Ok(()),
+			};
+
+			let event = events;
+			events += 1;
+
+			let mut report = |issue| findings.push(Finding { event, time: start, issue });
+			let length = analysis::seconds(packet.duration(), time_base);
+
+			// Bitmap formats leave the duration unset and clear the screen with
+			// an empty event instead, there's nothing to check on those.
+			if packet.duration() == 0 {
+				end = Some(end.map_or(start, |end| end.max(start)));
+				return Ok(());
+			}
+
+			if length < 0.0 {
+				report(Issue::NegativeDuration { duration: length });
+			}
+			else if length < thresholds.min_display {
+				report(Issue::TooShort { duration: length });
+			}
+			else if length > thresholds.max_display {
+				report(Issue::TooLong { duration: length });
+			}
+
+			if let Some(previous) = end {
+				if start < previous {
+					report(Issue::Overlap { previous });
+				}
+			}
+
+			let stop = start + length.max(0.0);
+			if let Some(duration) = duration {
+				if stop > duration {
+					report(Issue::BeyondDuration { end: stop, duration });
+				}
+			}
+
+			end = Some(end.map_or(stop, |end| end.max(stop)));
+
+			Ok(())
+		})?;
+
+		Ok(Subtitles { events, findings })
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.findings.is_empty()
+	}
+}
+
+// Subtitles are checked against the picture they're shown on, the container
+// duration is just a fallback for audio-only or subtitle-only files. Event
+// timestamps count from the start time, which MPEG-TS puts well past zero.
+fn end(input: &Input) -> Option<f64> {
+	let start = |start: i64| if start == AV_NOPTS_VALUE { 0 } else { start };
+
+	let video = input.streams().best(media::Type::Video).and_then(|stream| {
+		if stream.duration() > 0 {
+			let end = start(stream.start_time()) + stream.duration();
+			Some(analysis::seconds(end, stream.time_base()))
+		}
+		else {
+			None
+		}
+	});
+
+	video.or_else(|| {
+		if input.duration() > 0 {
+			let end = start(input.start_time()) + input.duration();
+			Some(end as f64 / f64::from(AV_TIME_BASE))
+		}
+		else {
+			None
+		}
+	})
+}
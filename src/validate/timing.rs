@@ -0,0 +1,126 @@
+use ffmpeg::format::stream::Disposition;
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, Metadata, Stream};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TimingThresholds {
+	// How much later than the earliest stream a stream may start.
+	pub start_offset: f64,
+	// A stream duration may differ from the container one by whichever is
+	// larger, in seconds or as a fraction of the container duration.
+	pub duration_mismatch: f64,
+	pub duration_fraction: f64,
+}
+
+impl Default for TimingThresholds {
+	fn default() -> Self {
+		TimingThresholds {
+			start_offset: 1.0,
+			duration_mismatch: 1.0,
+			duration_fraction: 0.01,
+		}
+	}
+}
+
+// A quick verdict on the timing from what the demuxer reports, without
+// reading any packets.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Timing {
+	// The earliest start among the timed streams, in seconds.
+	pub start: Option<f64>,
+	pub findings: Vec<Finding>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Finding {
+	// `None` for the container itself.
+	pub stream: Option<usize>,
+	pub issue: Issue,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Issue {
+	StartOffset { start: f64, earliest: f64 },
+	NegativeStart { start: f64 },
+	DurationMismatch { duration: f64, container: f64 },
+	MissingStart,
+	MissingDuration,
+}
+
+impl Timing {
+	pub fn check(metadata: &Metadata, thresholds: &TimingThresholds) -> Self {
+		let mut findings = Vec::new();
+		let container = metadata.duration_seconds();
+
+		if container.is_none() {
+			findings.push(Finding {
+				stream: None,
+				issue: Issue::MissingDuration,
+			});
+		}
+
+		// Subtitles and data start whenever they have something to say, cover
+		// art has no timeline at all.
+		let streams = metadata.streams.iter().filter(|stream| timed(stream)).collect::<Vec<_>>();
+		let start = streams
+			.iter()
+			.filter_map(|stream| stream.start_time_seconds())
+			.fold(None, |earliest: Option<f64>, start| {
+				Some(earliest.map_or(start, |earliest| earliest.min(start)))
+			});
+
+		for stream in streams {
+			let mut report = |issue| {
+				findings.push(Finding {
+					stream: Some(stream.index),
+					issue,
+				})
+			};
+
+			match stream.start_time_seconds() {
+				Some(begin) => {
+					if begin < 0.0 {
+						report(Issue::NegativeStart { start: begin });
+					}
+
+					let late = start.filter(|&earliest| begin - earliest > thresholds.start_offset);
+					if let Some(earliest) = late {
+						report(Issue::StartOffset { start: begin, earliest });
+					}
+				}
+				None => report(Issue::MissingStart),
+			}
+
+			match (stream.duration_seconds(), container) {
+				(Some(duration), Some(container)) => {
+					let allowed = thresholds.duration_mismatch.max(container * thresholds.duration_fraction);
+
+					if (duration - container).abs() > allowed {
+						report(Issue::DurationMismatch { duration, container });
+					}
+				}
+				// Most containers only keep the duration at the top level, that's
+				// only worth calling out when it's missing there too.
+				(None, None) => report(Issue::MissingDuration),
+				_ => {}
+			}
+		}
+
+		Timing { start, findings }
+	}
+
+	pub fn is_sane(&self) -> bool {
+		self.findings.is_empty()
+	}
+}
+
+fn timed(stream: &Stream) -> bool {
+	matches!(stream.content, Content::Audio(_) | Content::Video(_))
+		&& !stream.disposition.contains(Disposition::ATTACHED_PIC)
+}
@@ -0,0 +1,75 @@
+use ffmpeg::codec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, Metadata};
+
+const VIDEO: &[codec::Id] = &[codec::Id::VP8, codec::Id::VP9, codec::Id::AV1];
+const AUDIO: &[codec::Id] = &[codec::Id::VORBIS, codec::Id::OPUS];
+const SUBTITLE: &[codec::Id] = &[codec::Id::WEBVTT];
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Webm {
+	pub container: bool,
+	pub violations: Vec<Violation>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Violation {
+	Container { name: String },
+	Codec { stream: usize, codec: String },
+	Stream { stream: usize },
+}
+
+impl Webm {
+	// Browsers only accept a small subset of what Matroska can carry, and
+	// FFmpeg happily demuxes anything with a .webm extension regardless.
+	pub fn check(metadata: &Metadata) -> Self {
+		let container = metadata.format.name == "matroska"
+			|| metadata.format.name == "webm"
+			|| metadata.format.aliases.iter().any(|alias| alias == "webm");
+
+		let mut violations = Vec::new();
+
+		if !container {
+			violations.push(Violation::Container {
+				name: metadata.format.name.clone(),
+			});
+		}
+
+		for stream in &metadata.streams {
+			let (codec, allowed) = match &stream.content {
+				Content::Video(video) => (&video.codec, VIDEO),
+				Content::Audio(audio) => (&audio.codec, AUDIO),
+				Content::Subtitle(subtitle) => (&subtitle.codec, SUBTITLE),
+
+				// Attachments and data tracks have no place in WebM.
+				_ => {
+					violations.push(Violation::Stream {
+						stream: stream.index,
+					});
+
+					continue;
+				}
+			};
+
+			if !allowed.contains(&codec.id) {
+				violations.push(Violation::Codec {
+					stream: stream.index,
+					codec: codec.name.clone(),
+				});
+			}
+		}
+
+		Webm {
+			container,
+			violations,
+		}
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.violations.is_empty()
+	}
+}
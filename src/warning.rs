@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{EditListIssue, LogMessage};
+
+// Things that don't stop probing but that downstream tools trip over.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Warning {
+	// Declared in the headers but without a single packet, most muxers refuse
+	// to write those.
+	EmptyStream { stream: usize, kind: String },
+	// Fields left out because the input was read as a pipe, they need a seek
+	// to the end to be known.
+	Unseekable { unavailable: Vec<String> },
+	// An edit list players are known to apply differently from one another.
+	EditList { stream: usize, issues: Vec<EditListIssue> },
+	// What FFmpeg itself complained about while opening and probing.
+	Log(LogMessage),
+}